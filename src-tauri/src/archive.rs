@@ -0,0 +1,397 @@
+use std::fs::{self, File};
+use std::io::{Read, Write};
+use std::path::{Component, Path, PathBuf};
+use tokio_util::sync::CancellationToken;
+use zip::ZipArchive;
+
+use tracing::{debug, info, warn};
+
+use crate::disk_space;
+use crate::error::LauncherError;
+use crate::launch_progress::DownloadTracker;
+
+// Cancellation is checked between chunks, so a large file doesn't hold up a cancel
+const CHUNK_SIZE: usize = 64 * 1024;
+// File type bits of a unix mode, and the value marking a symlink
+const S_IFMT: u32 = 0o170000;
+const S_IFLNK: u32 = 0o120000;
+
+// Extracts entry by entry, reporting the uncompressed bytes written to the tracker. On
+// failure or cancellation, whatever this call created is removed again.
+// The archives come from the network and end up loaded into the game, so entries that
+// would land outside extract_path, symlinks and entries larger than the archive says are
+// all refused
+pub fn extract_zip(
+    zip_path: &str,
+    extract_path: &str,
+    mut tracker: Option<&mut DownloadTracker<'_>>,
+    cancel: &CancellationToken,
+) -> Result<(), String> {
+    let mut created = Vec::new();
+    let result = extract_entries(
+        zip_path,
+        Path::new(extract_path),
+        tracker.as_deref_mut(),
+        cancel,
+        &mut created,
+    );
+
+    match &result {
+        Ok(count) => {
+            info!("Extracted {} entries to {}", count, extract_path);
+            if let Some(tracker) = tracker {
+                tracker.finish();
+            }
+        }
+        Err(e) => {
+            warn!(
+                "Extracting {} failed, removing partial output: {}",
+                zip_path, e
+            );
+            remove_created(&created);
+        }
+    }
+    result.map(|_| ())
+}
+
+fn extract_entries(
+    zip_path: &str,
+    extract_path: &Path,
+    mut tracker: Option<&mut DownloadTracker<'_>>,
+    cancel: &CancellationToken,
+    created: &mut Vec<PathBuf>,
+) -> Result<usize, String> {
+    let file = File::open(zip_path).map_err(|e| format!("Failed to open zip file: {}", e))?;
+    let mut archive =
+        ZipArchive::new(file).map_err(|e| format!("Failed to read zip archive: {}", e))?;
+
+    let needed: u64 = (0..archive.len())
+        .filter_map(|i| archive.by_index(i).ok().map(|entry| entry.size()))
+        .sum();
+    disk_space::ensure_free_space(extract_path, needed, "extracting the archive")?;
+    if let Some(tracker) = tracker.as_deref_mut() {
+        tracker.start(Some(needed));
+    }
+
+    create_dirs(extract_path, created)?;
+    let root = extract_path
+        .canonicalize()
+        .map_err(|e| format!("Failed to resolve {}: {}", extract_path.display(), e))?;
+    let mut buffer = vec![0u8; CHUNK_SIZE];
+    let mut files = 0;
+    let mut total_written = 0u64;
+    for i in 0..archive.len() {
+        let mut entry = archive
+            .by_index(i)
+            .map_err(|e| format!("Failed to read zip entry {}: {}", i, e))?;
+        let relative = safe_relative_path(entry.name())?;
+        if entry
+            .unix_mode()
+            .is_some_and(|mode| mode & S_IFMT == S_IFLNK)
+        {
+            return Err(format!("Archive contains a symlink: {}", entry.name()));
+        }
+        let target = root.join(&relative);
+
+        if entry.is_dir() {
+            create_dirs_inside(&root, &target, created)?;
+            continue;
+        }
+        if let Some(parent) = target.parent() {
+            create_dirs_inside(&root, parent, created)?;
+        }
+
+        debug!("Extracting {}", relative.display());
+        match fs::symlink_metadata(&target) {
+            // Writing through a link placed there earlier would land outside extract_path
+            Ok(metadata) if metadata.file_type().is_symlink() || metadata.is_dir() => {
+                return Err(format!(
+                    "Refusing to overwrite {} from the archive",
+                    target.display()
+                ));
+            }
+            Ok(_) => {}
+            Err(_) => created.push(target.clone()),
+        }
+        let mut output = File::create(&target)
+            .map_err(|e| format!("Failed to create {}: {}", target.display(), e))?;
+        let expected = entry.size();
+        let mut written = 0u64;
+        loop {
+            if cancel.is_cancelled() {
+                return Err(LauncherError::Cancelled.to_string());
+            }
+            let read = entry
+                .read(&mut buffer)
+                .map_err(|e| format!("Failed to read {} from archive: {}", entry.name(), e))?;
+            if read == 0 {
+                break;
+            }
+            written += read as u64;
+            // The declared size is what the disk space check went by
+            if written > expected {
+                return Err(format!(
+                    "{} is larger than the archive says ({} bytes)",
+                    entry.name(),
+                    expected
+                ));
+            }
+            output
+                .write_all(&buffer[..read])
+                .map_err(|e| format!("Failed to write {}: {}", target.display(), e))?;
+            if let Some(tracker) = tracker.as_deref_mut() {
+                tracker.advance(read as u64);
+            }
+        }
+        if written != expected {
+            return Err(format!(
+                "{} is truncated, got {} of {} bytes",
+                entry.name(),
+                written,
+                expected
+            ));
+        }
+        files += 1;
+        total_written += written;
+
+        #[cfg(unix)]
+        if let Some(mode) = entry.unix_mode() {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(&target, fs::Permissions::from_mode(mode))
+                .map_err(|e| format!("Failed to set permissions on {}: {}", target.display(), e))?;
+        }
+    }
+    if total_written != needed {
+        return Err(format!(
+            "Extracted {} bytes in {} files, the archive lists {} bytes",
+            total_written, files, needed
+        ));
+    }
+    Ok(files)
+}
+
+// Only plain relative paths, checked on the raw name rather than trusting the zip crate's
+// own sanitising: no absolute paths, drive letters, .. or alternate data streams
+pub(crate) fn safe_relative_path(name: &str) -> Result<PathBuf, String> {
+    let unsafe_path = || format!("Unsafe file path in archive: {}", name);
+    let normalized = name.replace('\\', "/");
+    if normalized.starts_with('/') || normalized.contains(':') || normalized.contains('\0') {
+        return Err(unsafe_path());
+    }
+
+    let mut path = PathBuf::new();
+    for component in Path::new(&normalized).components() {
+        match component {
+            Component::Normal(part) => path.push(part),
+            Component::CurDir => {}
+            Component::ParentDir | Component::RootDir | Component::Prefix(_) => {
+                return Err(unsafe_path())
+            }
+        }
+    }
+    if path.as_os_str().is_empty() {
+        return Err(unsafe_path());
+    }
+    Ok(path)
+}
+
+// Resolves links that were already on disk, the entry's path alone can't show those
+fn ensure_inside(root: &Path, path: &Path) -> Result<(), String> {
+    let resolved = path
+        .canonicalize()
+        .map_err(|e| format!("Failed to resolve {}: {}", path.display(), e))?;
+    if resolved.starts_with(root) {
+        Ok(())
+    } else {
+        Err(format!(
+            "{} resolves outside of {}",
+            path.display(),
+            root.display()
+        ))
+    }
+}
+
+// The nearest directory that already exists is resolved first, so a link on disk can't
+// redirect the new directories somewhere else
+fn create_dirs_inside(root: &Path, dir: &Path, created: &mut Vec<PathBuf>) -> Result<(), String> {
+    let existing = dir.ancestors().find(|dir| dir.exists()).unwrap_or(root);
+    ensure_inside(root, existing)?;
+    create_dirs(dir, created)?;
+    ensure_inside(root, dir)
+}
+
+// Records each directory it had to create, parents first
+fn create_dirs(path: &Path, created: &mut Vec<PathBuf>) -> Result<(), String> {
+    let missing: Vec<&Path> = path.ancestors().take_while(|dir| !dir.exists()).collect();
+    for dir in missing.into_iter().rev() {
+        fs::create_dir(dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+        created.push(dir.to_path_buf());
+    }
+    Ok(())
+}
+
+// Newest first, so files go before the directories they were created in
+fn remove_created(created: &[PathBuf]) {
+    for path in created.iter().rev() {
+        let result = if path.is_dir() {
+            fs::remove_dir(path)
+        } else {
+            fs::remove_file(path)
+        };
+        if let Err(e) = result {
+            warn!("Failed to remove {}: {}", path.display(), e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use zip::write::FileOptions;
+    use zip::ZipWriter;
+
+    fn write_zip(path: &Path, build: impl FnOnce(&mut ZipWriter<File>)) {
+        let mut writer = ZipWriter::new(File::create(path).unwrap());
+        build(&mut writer);
+        writer.finish().unwrap();
+    }
+
+    fn add_file(writer: &mut ZipWriter<File>, name: &str, contents: &[u8]) {
+        writer.start_file(name, FileOptions::default()).unwrap();
+        writer.write_all(contents).unwrap();
+    }
+
+    fn extract(zip_path: &Path, target: &Path) -> Result<(), String> {
+        extract_zip(
+            &zip_path.to_string_lossy(),
+            &target.to_string_lossy(),
+            None,
+            &CancellationToken::new(),
+        )
+    }
+
+    #[test]
+    fn relative_paths_are_kept() {
+        assert_eq!(
+            safe_relative_path("dir/file.txt").unwrap(),
+            Path::new("dir").join("file.txt")
+        );
+        assert_eq!(
+            safe_relative_path("./dir\\sub\\file.txt").unwrap(),
+            Path::new("dir").join("sub").join("file.txt")
+        );
+    }
+
+    #[test]
+    fn paths_leaving_the_target_are_refused() {
+        for name in [
+            "../file.txt",
+            "dir/../../file.txt",
+            "..\\file.txt",
+            "/etc/passwd",
+            "\\Windows\\file.txt",
+            "C:\\Windows\\file.txt",
+            "C:file.txt",
+            "c:/file.txt",
+            "dir/file.txt:stream",
+            "dir/fi\0le.txt",
+            "",
+            "./",
+        ] {
+            assert!(safe_relative_path(name).is_err(), "{:?} was accepted", name);
+        }
+    }
+
+    #[test]
+    fn extracts_into_the_target() {
+        let dir = tempfile::tempdir().unwrap();
+        let zip_path = dir.path().join("test.zip");
+        write_zip(&zip_path, |writer| {
+            add_file(writer, "top.txt", b"top");
+            add_file(writer, "nested/dir/inner.txt", b"inner");
+        });
+
+        let target = dir.path().join("out");
+        extract(&zip_path, &target).unwrap();
+        assert_eq!(fs::read(target.join("top.txt")).unwrap(), b"top");
+        assert_eq!(
+            fs::read(target.join("nested/dir/inner.txt")).unwrap(),
+            b"inner"
+        );
+    }
+
+    #[test]
+    fn parent_dir_entries_are_refused_and_cleaned_up() {
+        let dir = tempfile::tempdir().unwrap();
+        let zip_path = dir.path().join("test.zip");
+        write_zip(&zip_path, |writer| {
+            add_file(writer, "fine.txt", b"fine");
+            add_file(writer, "../escaped.txt", b"escaped");
+        });
+
+        let target = dir.path().join("out");
+        let error = extract(&zip_path, &target).unwrap_err();
+        assert!(
+            error.starts_with("Unsafe file path in archive"),
+            "{}",
+            error
+        );
+        assert!(!dir.path().join("escaped.txt").exists());
+        // Everything this call created is gone again, the target included
+        assert!(!target.exists());
+    }
+
+    #[test]
+    fn symlink_entries_are_refused() {
+        let dir = tempfile::tempdir().unwrap();
+        let zip_path = dir.path().join("test.zip");
+        write_zip(&zip_path, |writer| {
+            writer
+                .add_symlink("link", "/etc", FileOptions::default())
+                .unwrap();
+            add_file(writer, "link/passwd", b"escaped");
+        });
+
+        let target = dir.path().join("out");
+        let error = extract(&zip_path, &target).unwrap_err();
+        assert_eq!(error, "Archive contains a symlink: link");
+        assert!(!target.join("link").exists());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn existing_symlinked_dirs_are_not_followed() {
+        let dir = tempfile::tempdir().unwrap();
+        let outside = dir.path().join("outside");
+        let target = dir.path().join("out");
+        fs::create_dir(&outside).unwrap();
+        fs::create_dir(&target).unwrap();
+        std::os::unix::fs::symlink(&outside, target.join("link")).unwrap();
+
+        let root = target.canonicalize().unwrap();
+        let mut created = Vec::new();
+        let error = create_dirs_inside(&root, &root.join("link/sub"), &mut created).unwrap_err();
+        assert!(error.contains("resolves outside of"), "{}", error);
+        assert!(!outside.join("sub").exists());
+
+        let zip_path = dir.path().join("test.zip");
+        write_zip(&zip_path, |writer| {
+            add_file(writer, "link/file.txt", b"escaped");
+        });
+        let error = extract(&zip_path, &target).unwrap_err();
+        assert!(error.contains("resolves outside of"), "{}", error);
+        assert!(!outside.join("file.txt").exists());
+        // The link was there before, only what the extraction created is removed
+        assert!(target.join("link").is_symlink());
+    }
+
+    #[test]
+    fn missing_dirs_are_created_and_recorded() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().canonicalize().unwrap();
+        let mut created = Vec::new();
+        create_dirs_inside(&root, &root.join("a/b"), &mut created).unwrap();
+        assert_eq!(created, [root.join("a"), root.join("a/b")]);
+        assert!(root.join("a/b").is_dir());
+    }
+}
@@ -0,0 +1,239 @@
+use crate::encryption::{blowfish_encrypt, to_mangled_se_base64};
+use crate::ffxiv::LaunchConfig;
+
+// Checksum character appended to the encrypted blob, indexed by bits 16..20 of the key
+const CHECKSUM_TABLE: [char; 16] = [
+    'f', 'X', '1', 'p', 'G', 't', 'd', 'S', '5', 'C', 'A', 'P', '4', '_', 'V', 'L',
+];
+
+// Set by the launcher itself, or able to point the client at a different server
+const BLOCKED_ARGUMENTS: &[&str] = &[
+    "DEV.TestSID",
+    "DEV.MaxEntitledExpansionID",
+    "DEV.DataPathType",
+    "DEV.UseSqPack",
+    "DEV.LobbyHost",
+    "DEV.LobbyPort",
+    "DEV.GMServerHost",
+    "DEV.SaveDataBankHost",
+    "DEV.SaveDataBankPort",
+    "SYS.Region",
+    "language",
+    "IsSteam",
+    "T",
+];
+
+#[derive(Debug, Default, Clone)]
+pub struct ArgumentBuilder {
+    arguments: Vec<(String, String)>,
+}
+
+impl ArgumentBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn append(mut self, key: &str, value: impl ToString) -> Self {
+        self.arguments.push((key.to_string(), value.to_string()));
+        self
+    }
+
+    // Plain "key=value key=value" form, readable by anything that can see the command line
+    pub fn build(&self) -> String {
+        self.arguments
+            .iter()
+            .map(|(key, value)| format!("{}={}", key, value))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    // SqexArg form the official launcher uses, keyed off the current tick count
+    pub fn build_encrypted(&self) -> String {
+        let ticks = get_tick_count();
+        let key = ticks & 0xFFFF_0000;
+        self.build_encrypted_with_key(ticks, key)
+    }
+
+    fn build_encrypted_with_key(&self, ticks: u32, key: u32) -> String {
+        // The game expects the tick count the key was derived from as the first argument
+        let arguments = std::iter::once(("T".to_string(), ticks.to_string()))
+            .chain(self.arguments.iter().cloned())
+            .map(|(name, value)| format!(" /{} ={}", escape_value(&name), escape_value(&value)))
+            .collect::<String>();
+
+        let ciphertext = blowfish_encrypt(&key_bytes(key), arguments.as_bytes());
+        let checksum = CHECKSUM_TABLE[((key & 0x000F_0000) >> 16) as usize];
+
+        format!(
+            "//**sqex0003{}{}**//",
+            to_mangled_se_base64(&ciphertext),
+            checksum
+        )
+    }
+}
+
+// Parses user supplied "Key=Value" pairs, values with spaces can be wrapped in double quotes
+pub fn parse_additional_arguments(input: &str) -> Result<Vec<(String, String)>, String> {
+    let mut tokens = Vec::new();
+    let mut current = String::new();
+    let mut in_quotes = false;
+
+    for c in input.chars() {
+        match c {
+            '"' => in_quotes = !in_quotes,
+            c if c.is_whitespace() && !in_quotes => {
+                if !current.is_empty() {
+                    tokens.push(std::mem::take(&mut current));
+                }
+            }
+            c => current.push(c),
+        }
+    }
+    if in_quotes {
+        return Err("Unterminated quote in additional launch arguments".to_string());
+    }
+    if !current.is_empty() {
+        tokens.push(current);
+    }
+
+    tokens
+        .into_iter()
+        .map(|token| {
+            let (key, value) = token.split_once('=').ok_or_else(|| {
+                format!("Launch argument \"{}\" must be in Key=Value form", token)
+            })?;
+
+            let valid_key = !key.is_empty()
+                && key
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '.' || c == '_');
+            if !valid_key {
+                return Err(format!("Invalid launch argument name \"{}\"", key));
+            }
+
+            // Numbered variants like DEV.LobbyHost01 are covered by their prefix
+            if BLOCKED_ARGUMENTS.iter().any(|blocked| {
+                key == *blocked || (blocked.starts_with("DEV.") && key.starts_with(blocked))
+            }) {
+                return Err(format!("Launch argument {} can't be overridden", key));
+            }
+
+            Ok((key.to_string(), value.to_string()))
+        })
+        .collect()
+}
+
+// Parses the additional arguments and fills in the placeholders in their values. Keys
+// can't hold placeholders, the name check already refuses braces
+pub fn expand_additional_arguments(
+    input: &str,
+    vars: &TemplateVars,
+) -> Result<Vec<(String, String)>, String> {
+    parse_additional_arguments(input)?
+        .into_iter()
+        .map(|(key, value)| Ok((key, vars.expand(&value)?)))
+        .collect()
+}
+
+// Values for the {name} placeholders in additional launch arguments and external tool
+// arguments, filled in at launch time
+#[derive(Debug, Default, Clone)]
+pub struct TemplateVars {
+    pub game_path: String,
+    pub language: u32,
+    pub sid_present: bool,
+    // Only known once the game process exists
+    pub pid: Option<u32>,
+}
+
+impl TemplateVars {
+    pub fn new(config: &LaunchConfig) -> Self {
+        Self {
+            game_path: config.game_path.clone(),
+            language: config.language,
+            ..Self::default()
+        }
+    }
+
+    fn value(&self, name: &str) -> Result<String, String> {
+        match name {
+            "game_path" => Ok(self.game_path.clone()),
+            "language" => Ok(self.language.to_string()),
+            "sid_present" => Ok(self.sid_present.to_string()),
+            "pid" => self.pid.map(|pid| pid.to_string()).ok_or_else(|| {
+                "{pid} is only available to tools started after the game".to_string()
+            }),
+            _ => Err(format!("Unknown placeholder {{{}}}", name)),
+        }
+    }
+
+    // "{{" and "}}" stand for literal braces
+    pub fn expand(&self, input: &str) -> Result<String, String> {
+        let mut output = String::with_capacity(input.len());
+        let mut chars = input.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '{' if chars.peek() == Some(&'{') => {
+                    chars.next();
+                    output.push('{');
+                }
+                '}' if chars.peek() == Some(&'}') => {
+                    chars.next();
+                    output.push('}');
+                }
+                '{' => {
+                    let mut name = String::new();
+                    loop {
+                        match chars.next() {
+                            Some('}') => break,
+                            Some(c) => name.push(c),
+                            None => {
+                                return Err(format!("Unterminated placeholder in \"{}\"", input))
+                            }
+                        }
+                    }
+                    output.push_str(&self.value(&name)?);
+                }
+                c => output.push(c),
+            }
+        }
+        Ok(output)
+    }
+}
+
+// Spaces are doubled so the game can tell them apart from argument separators
+fn escape_value(value: &str) -> String {
+    value.replace(' ', "  ")
+}
+
+fn key_bytes(key: u32) -> Vec<u8> {
+    format!("{:08x}", key).into_bytes()
+}
+
+#[cfg(windows)]
+fn get_tick_count() -> u32 {
+    unsafe { windows::Win32::System::SystemInformation::GetTickCount() }
+}
+
+// Wine's GetTickCount counts CLOCK_MONOTONIC, which is what the game derives the key from
+#[cfg(target_os = "linux")]
+fn get_tick_count() -> u32 {
+    let mut now = libc::timespec {
+        tv_sec: 0,
+        tv_nsec: 0,
+    };
+    unsafe {
+        libc::clock_gettime(libc::CLOCK_MONOTONIC, &mut now);
+    }
+    (now.tv_sec as u64 * 1000 + now.tv_nsec as u64 / 1_000_000) as u32
+}
+
+#[cfg(not(any(windows, target_os = "linux")))]
+fn get_tick_count() -> u32 {
+    use std::time::{SystemTime, UNIX_EPOCH};
+
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u32)
+        .unwrap_or(0)
+}
@@ -0,0 +1,191 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
+use tokio_util::sync::CancellationToken;
+
+use tracing::{info, warn};
+
+use crate::argument_builder::ArgumentBuilder;
+use crate::companions;
+use crate::error::LauncherError;
+use crate::ffxiv::{self, with_cancel, LaunchConfig};
+use crate::game_config::ScreenMode;
+
+// Relative to the benchmark folder, the same layout as a game install
+const BENCHMARK_EXE: &str = "game/ffxiv_dx11.exe";
+// The benchmark launcher's settings, the client writes the score of a finished run into it
+const RESULT_FILE: &str = "ffxivbenchmarklauncher.ini";
+const SCORE_PREFIX: &str = "SCORE";
+const EXIT_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+// A benchmark profile starts the official benchmark instead of logging in to the game
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ProfileType {
+    #[default]
+    Game,
+    Benchmark,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BenchmarkConfig {
+    // The extracted benchmark folder, the one that holds game and the benchmark launcher
+    pub path: String,
+    pub screen_mode: ScreenMode,
+    pub screen_width: u32,
+    pub screen_height: u32,
+}
+
+impl Default for BenchmarkConfig {
+    fn default() -> Self {
+        Self {
+            path: String::new(),
+            screen_mode: ScreenMode::Windowed,
+            screen_width: 1920,
+            screen_height: 1080,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct BenchmarkResult {
+    pub score: Option<u64>,
+    // Every SCORE entry the run wrote, e.g. average and minimum frame rate and load times
+    pub details: BTreeMap<String, String>,
+    pub duration_ms: u64,
+}
+
+// Plain SYS.* arguments, the benchmark takes no session id and nothing encrypted
+fn benchmark_arguments(config: &LaunchConfig) -> String {
+    let benchmark = &config.benchmark;
+    ArgumentBuilder::new()
+        .append("SYS.Language", config.language)
+        .append("SYS.ScreenMode", benchmark.screen_mode.value())
+        .append("SYS.ScreenWidth", benchmark.screen_width)
+        .append("SYS.ScreenHeight", benchmark.screen_height)
+        .append("SYS.Fps", 0)
+        .build()
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+// Runs the benchmark to the end and reads the score it saved. Cancelling stops waiting,
+// the benchmark window is left for the user to close
+pub async fn run(
+    config: &LaunchConfig,
+    cancel: &CancellationToken,
+) -> Result<BenchmarkResult, LauncherError> {
+    let root = PathBuf::from(&config.benchmark.path);
+    let executable = root.join(BENCHMARK_EXE);
+    if config.benchmark.path.trim().is_empty() || !executable.is_file() {
+        return Err(LauncherError::Config(format!(
+            "Benchmark not found at {}, pick the extracted benchmark folder",
+            executable.display()
+        )));
+    }
+    let result_file = root.join(RESULT_FILE);
+    let previous_result = modified(&result_file);
+
+    let args = benchmark_arguments(config);
+    info!("Starting benchmark {} {}", executable.display(), args);
+    let started = Instant::now();
+    let pid = spawn(config, &executable, &args)
+        .map_err(|e| LauncherError::Process(format!("Failed to start the benchmark: {}", e)))?;
+
+    with_cancel(cancel, async {
+        while companions::is_running(pid) {
+            tokio::time::sleep(EXIT_POLL_INTERVAL).await;
+        }
+    })
+    .await?;
+    let duration_ms = started.elapsed().as_millis() as u64;
+    info!("Benchmark {} exited after {}ms", pid, duration_ms);
+
+    // Closing the benchmark before it finishes leaves the last run's score in place
+    if modified(&result_file) == previous_result {
+        return Err(LauncherError::Process(
+            "The benchmark exited without saving a score".to_string(),
+        ));
+    }
+    let contents = fs::read_to_string(&result_file).map_err(|e| {
+        LauncherError::Process(format!("Failed to read {}: {}", result_file.display(), e))
+    })?;
+    let details = parse_scores(&contents);
+    let score = details
+        .get(SCORE_PREFIX)
+        .and_then(|score| score.parse().ok());
+    if score.is_none() {
+        warn!("No score found in {}", result_file.display());
+    }
+    Ok(BenchmarkResult {
+        score,
+        details,
+        duration_ms,
+    })
+}
+
+// KEY=VALUE lines, sections and anything that isn't a score are skipped
+fn parse_scores(contents: &str) -> BTreeMap<String, String> {
+    contents
+        .lines()
+        .filter_map(|line| line.split_once('='))
+        .map(|(key, value)| (key.trim(), value.trim()))
+        .filter(|(key, _)| key.starts_with(SCORE_PREFIX))
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .collect()
+}
+
+#[cfg(windows)]
+fn spawn(_config: &LaunchConfig, executable: &Path, args: &str) -> Result<u32, String> {
+    let mut command = std::process::Command::new(executable);
+    command.args(args.split_whitespace());
+    if let Some(dir) = executable.parent() {
+        command.current_dir(dir);
+    }
+    let mut child = command.spawn().map_err(|e| e.to_string())?;
+    let pid = child.id();
+    // Reaped here, the exit is watched through the pid like the Wine runner's
+    std::thread::spawn(move || {
+        if let Err(e) = child.wait() {
+            warn!("Failed to wait for benchmark {}: {}", pid, e);
+        }
+    });
+    Ok(pid)
+}
+
+#[cfg(target_os = "linux")]
+fn spawn(config: &LaunchConfig, executable: &Path, args: &str) -> Result<u32, String> {
+    crate::platform::linux::spawn_game(
+        &config.wine,
+        &config.gamescope,
+        config.gamemode,
+        &executable.to_string_lossy(),
+        args,
+        &[],
+    )
+    .map(|game| game.pid)
+}
+
+#[cfg(not(any(windows, target_os = "linux")))]
+fn spawn(_config: &LaunchConfig, _executable: &Path, _args: &str) -> Result<u32, String> {
+    Err("Running the benchmark is not supported on this platform".to_string())
+}
+
+// Cancelled through cancel_launch like a game launch
+#[tauri::command]
+pub async fn launch_benchmark(config: LaunchConfig) -> Result<BenchmarkResult, LauncherError> {
+    if config.profile_type != ProfileType::Benchmark {
+        return Err(LauncherError::Config(
+            "This profile starts the game, not the benchmark".to_string(),
+        ));
+    }
+    let (launch_id, cancel) = ffxiv::begin_launch();
+    let result = run(&config, &cancel).await;
+    ffxiv::end_launch(launch_id);
+    result
+}
@@ -0,0 +1,324 @@
+use serde::Serialize;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Manager};
+use time::OffsetDateTime;
+use tokio_util::sync::CancellationToken;
+use walkdir::WalkDir;
+use zip::write::FileOptions;
+use zip::ZipWriter;
+
+use tracing::{info, warn};
+
+use crate::archive::extract_zip;
+use crate::game_config::game_user_dir;
+use crate::game_monitor;
+
+// In the app data folder, next to the stores
+const BACKUP_FOLDER: &str = "character_backups";
+// Hotbars, keybinds, macros and UI layout, one folder per character
+const CHARACTER_PREFIX: &str = "FFXIV_CHR";
+// Backups before every launch add up, the oldest go once there are more than this
+const KEEP_BACKUPS: usize = 20;
+
+#[derive(Debug, Serialize)]
+pub struct CharacterBackup {
+    pub name: String,
+    pub path: String,
+    pub bytes: u64,
+    // Unix timestamp in seconds
+    pub created_at: u64,
+}
+
+fn backup_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    app.path()
+        .app_data_dir()
+        .map(|dir| dir.join(BACKUP_FOLDER))
+        .map_err(|e| format!("Failed to find the app data folder: {}", e))
+}
+
+// Zips every FFXIV_CHR folder in the game's user folder, named by the UTC time and the
+// optional label
+pub(crate) fn create_backup(
+    app: &AppHandle,
+    user_dir: Option<String>,
+    label: Option<&str>,
+) -> Result<CharacterBackup, String> {
+    let source = game_user_dir(app, user_dir)?;
+    let target_dir = backup_dir(app)?;
+    back_up(
+        &source,
+        &target_dir,
+        &backup_name(OffsetDateTime::now_utc(), label),
+    )
+}
+
+fn backup_name(now: OffsetDateTime, label: Option<&str>) -> String {
+    format!(
+        "chr-{:04}{:02}{:02}-{:02}{:02}{:02}{}.zip",
+        now.year(),
+        now.month() as u8,
+        now.day(),
+        now.hour(),
+        now.minute(),
+        now.second(),
+        label.map(|label| format!("-{}", label)).unwrap_or_default()
+    )
+}
+
+fn back_up(source: &Path, target_dir: &Path, name: &str) -> Result<CharacterBackup, String> {
+    let characters: Vec<PathBuf> = fs::read_dir(source)
+        .map_err(|e| format!("Failed to read {}: {}", source.display(), e))?
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.path().is_dir())
+        .filter(|entry| {
+            entry
+                .file_name()
+                .to_string_lossy()
+                .starts_with(CHARACTER_PREFIX)
+        })
+        .map(|entry| entry.path())
+        .collect();
+    if characters.is_empty() {
+        return Err(format!("No character data found in {}", source.display()));
+    }
+
+    fs::create_dir_all(target_dir)
+        .map_err(|e| format!("Failed to create {}: {}", target_dir.display(), e))?;
+    let path = target_dir.join(name);
+
+    let result = write_backup(&path, source, &characters);
+    if let Err(e) = result {
+        let _ = fs::remove_file(&path);
+        return Err(e);
+    }
+    info!(
+        "Backed up {} characters to {}",
+        characters.len(),
+        path.display()
+    );
+    prune_backups(target_dir);
+    describe(&path).ok_or_else(|| format!("Failed to read {}", path.display()))
+}
+
+fn write_backup(path: &Path, source: &Path, characters: &[PathBuf]) -> Result<(), String> {
+    let file = fs::File::create(path)
+        .map_err(|e| format!("Failed to create {}: {}", path.display(), e))?;
+    let mut zip = ZipWriter::new(file);
+    for character in characters {
+        for entry in WalkDir::new(character)
+            .into_iter()
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.file_type().is_file())
+        {
+            let Ok(relative) = entry.path().strip_prefix(source) else {
+                continue;
+            };
+            let name = relative.to_string_lossy().replace('\\', "/");
+            let contents = fs::read(entry.path())
+                .map_err(|e| format!("Failed to read {}: {}", entry.path().display(), e))?;
+            zip.start_file(name.as_str(), FileOptions::default())
+                .and_then(|_| zip.write_all(&contents).map_err(Into::into))
+                .map_err(|e| format!("Failed to add {} to the backup: {}", name, e))?;
+        }
+    }
+    zip.finish()
+        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+    Ok(())
+}
+
+fn describe(path: &Path) -> Option<CharacterBackup> {
+    let metadata = fs::metadata(path).ok()?;
+    let created_at = metadata
+        .modified()
+        .ok()?
+        .duration_since(std::time::UNIX_EPOCH)
+        .ok()?
+        .as_secs();
+    Some(CharacterBackup {
+        name: path.file_name()?.to_string_lossy().to_string(),
+        path: path.to_string_lossy().to_string(),
+        bytes: metadata.len(),
+        created_at,
+    })
+}
+
+// Newest first
+fn list_backups(dir: &Path) -> Vec<CharacterBackup> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut backups: Vec<CharacterBackup> = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "zip"))
+        .filter_map(|path| describe(&path))
+        .collect();
+    backups.sort_by(|a, b| (b.created_at, &b.name).cmp(&(a.created_at, &a.name)));
+    backups
+}
+
+fn prune_backups(dir: &Path) {
+    for backup in list_backups(dir).into_iter().skip(KEEP_BACKUPS) {
+        match fs::remove_file(&backup.path) {
+            Ok(()) => info!("Removed old character backup {}", backup.name),
+            Err(e) => warn!("Failed to remove {}: {}", backup.path, e),
+        }
+    }
+}
+
+#[tauri::command]
+pub async fn backup_character_data(
+    app: AppHandle,
+    user_dir: Option<String>,
+) -> Result<CharacterBackup, String> {
+    crate::ffxiv::run_blocking(move || create_backup(&app, user_dir, None)).await
+}
+
+#[tauri::command]
+pub fn list_character_backups(app: AppHandle) -> Result<Vec<CharacterBackup>, String> {
+    Ok(list_backups(&backup_dir(&app)?))
+}
+
+// Only names from list_character_backups, nothing outside the backup folder
+fn check_backup_name(name: &str) -> Result<(), String> {
+    if name.contains(['/', '\\']) || !name.ends_with(".zip") {
+        return Err(format!("Invalid backup name: {}", name));
+    }
+    Ok(())
+}
+
+// The current data is backed up first so a restore can be undone, that backup is returned
+// unless it failed
+#[tauri::command]
+pub async fn restore_character_backup(
+    app: AppHandle,
+    user_dir: Option<String>,
+    name: String,
+) -> Result<Option<CharacterBackup>, String> {
+    if game_monitor::get_game_status().running {
+        return Err("Close the game before restoring character data".to_string());
+    }
+    check_backup_name(&name)?;
+    let backup = backup_dir(&app)?.join(&name);
+    if !backup.is_file() {
+        return Err(format!("Character backup {} not found", name));
+    }
+
+    crate::ffxiv::run_blocking(move || {
+        let target = game_user_dir(&app, user_dir.clone())?;
+        let undo = match create_backup(&app, user_dir, Some("before-restore")) {
+            Ok(undo) => Some(undo),
+            Err(e) => {
+                warn!("Failed to back up character data before restoring: {}", e);
+                None
+            }
+        };
+        extract_zip(
+            &backup.to_string_lossy(),
+            &target.to_string_lossy(),
+            None,
+            &CancellationToken::new(),
+        )?;
+        info!("Restored character data from {}", name);
+        Ok(undo)
+    })
+    .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn user_dir() -> tempfile::TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        let character = dir.path().join("FFXIV_CHR004000174A2B3C4D");
+        fs::create_dir_all(character.join("log")).unwrap();
+        fs::write(character.join("HOTBAR.DAT"), "hotbars").unwrap();
+        fs::write(character.join("log/00000000.log"), "chat").unwrap();
+        fs::create_dir(dir.path().join("screenshots")).unwrap();
+        fs::write(dir.path().join("FFXIV.cfg"), "settings").unwrap();
+        dir
+    }
+
+    #[test]
+    fn backup_names_carry_the_time_and_label() {
+        // 2026-03-04 05:06:07 UTC
+        let now = OffsetDateTime::from_unix_timestamp(1772600767).unwrap();
+        assert_eq!(backup_name(now, None), "chr-20260304-050607.zip");
+        assert_eq!(
+            backup_name(now, Some("before-restore")),
+            "chr-20260304-050607-before-restore.zip"
+        );
+    }
+
+    #[test]
+    fn only_plain_zip_names_are_restored() {
+        assert!(check_backup_name("chr-20260304-050607.zip").is_ok());
+        for name in [
+            "../chr.zip",
+            "sub/chr.zip",
+            "sub\\chr.zip",
+            "chr.tar",
+            "chr",
+        ] {
+            assert!(check_backup_name(name).is_err(), "{}", name);
+        }
+    }
+
+    #[test]
+    fn backs_up_only_character_folders_and_restores_them() {
+        let source = user_dir();
+        let target = tempfile::tempdir().unwrap();
+        let backup = back_up(source.path(), target.path(), "chr-test.zip").unwrap();
+        assert_eq!(backup.name, "chr-test.zip");
+        assert!(backup.bytes > 0);
+
+        let restored = tempfile::tempdir().unwrap();
+        extract_zip(
+            &backup.path,
+            &restored.path().to_string_lossy(),
+            None,
+            &CancellationToken::new(),
+        )
+        .unwrap();
+        let character = restored.path().join("FFXIV_CHR004000174A2B3C4D");
+        assert_eq!(
+            fs::read_to_string(character.join("HOTBAR.DAT")).unwrap(),
+            "hotbars"
+        );
+        assert_eq!(
+            fs::read_to_string(character.join("log/00000000.log")).unwrap(),
+            "chat"
+        );
+        assert!(!restored.path().join("screenshots").exists());
+        assert!(!restored.path().join("FFXIV.cfg").exists());
+    }
+
+    #[test]
+    fn backup_without_characters_fails_and_leaves_nothing() {
+        let source = tempfile::tempdir().unwrap();
+        let target = tempfile::tempdir().unwrap();
+        assert!(back_up(source.path(), target.path(), "chr-test.zip").is_err());
+        assert!(list_backups(target.path()).is_empty());
+    }
+
+    #[test]
+    fn keeps_only_the_newest_backups() {
+        let source = user_dir();
+        let target = tempfile::tempdir().unwrap();
+        for i in 0..KEEP_BACKUPS + 2 {
+            back_up(source.path(), target.path(), &format!("chr-{:02}.zip", i)).unwrap();
+        }
+        fs::write(target.path().join("notes.txt"), "").unwrap();
+
+        let backups = list_backups(target.path());
+        assert_eq!(backups.len(), KEEP_BACKUPS);
+        // Names follow the write order, so backups made within one second sort the same way
+        let newest = format!("chr-{:02}.zip", KEEP_BACKUPS + 1);
+        assert_eq!(backups[0].name, newest);
+        assert!(!target.path().join("chr-00.zip").exists());
+        assert!(!target.path().join("chr-01.zip").exists());
+    }
+}
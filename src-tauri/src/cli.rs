@@ -0,0 +1,222 @@
+use clap::{Parser, Subcommand};
+use std::fs;
+use std::io::Write;
+use std::path::PathBuf;
+use tokio_util::sync::CancellationToken;
+
+use tracing::{info, warn};
+
+use crate::benchmark::{self, ProfileType};
+use crate::ffxiv::{self, LaunchConfig};
+use crate::redact::{self, Secret};
+
+// Same folder Tauri resolves as the app config dir
+const APP_IDENTIFIER: &str = "com.pieckenst.rust.xivloader.app";
+
+#[derive(Parser)]
+#[command(name = "xivloader", version, about = "Final Fantasy XIV launcher")]
+struct Cli {
+    #[command(subcommand)]
+    command: CliCommand,
+}
+
+#[derive(Subcommand)]
+enum CliCommand {
+    /// Log in and start the game with a saved profile
+    Launch {
+        /// Profile name, loaded from the profiles folder
+        #[arg(long)]
+        profile: String,
+        /// One-time password for this login
+        #[arg(long)]
+        otp: Option<String>,
+        /// Start the game without Dalamud even if the profile enables it
+        #[arg(long)]
+        no_dalamud: bool,
+        /// Check the profile and environment without logging in
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// List saved profiles
+    Profiles,
+}
+
+// Runs a CLI command when arguments were given, returning the exit code.
+// None means no arguments, so the GUI should start as usual
+pub fn run_from_args() -> Option<i32> {
+    if std::env::args_os().len() <= 1 {
+        return None;
+    }
+
+    attach_console();
+    init_logging();
+    let cli = Cli::parse();
+
+    let runtime = match tokio::runtime::Runtime::new() {
+        Ok(runtime) => runtime,
+        Err(e) => {
+            eprintln!("Failed to start async runtime: {}", e);
+            return Some(1);
+        }
+    };
+
+    let result = match cli.command {
+        CliCommand::Launch {
+            profile,
+            otp,
+            no_dalamud,
+            dry_run,
+        } => runtime.block_on(launch(&profile, otp, no_dalamud, dry_run)),
+        CliCommand::Profiles => list_profiles(),
+    };
+
+    match result {
+        Ok(()) => Some(0),
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            Some(1)
+        }
+    }
+}
+
+async fn launch(
+    profile: &str,
+    otp: Option<String>,
+    no_dalamud: bool,
+    dry_run: bool,
+) -> Result<(), String> {
+    let mut config = load_profile(profile)?;
+    if let Some(otp) = otp {
+        config.otp = Some(Secret::from(otp));
+    }
+    if no_dalamud {
+        config.enable_dalamud = false;
+    }
+
+    if dry_run {
+        if config.profile_type == ProfileType::Benchmark {
+            return Err("Dry runs only check game profiles".to_string());
+        }
+        for line in ffxiv::dry_run(&config).await? {
+            println!("{}", line);
+        }
+        println!("Dry run passed, profile {} is ready to launch", profile);
+        return Ok(());
+    }
+
+    let cancel = CancellationToken::new();
+    let ctrl_c_cancel = cancel.clone();
+    tokio::spawn(async move {
+        if tokio::signal::ctrl_c().await.is_ok() {
+            warn!("Interrupted, cancelling launch");
+            ctrl_c_cancel.cancel();
+        }
+    });
+
+    if config.profile_type == ProfileType::Benchmark {
+        let result = benchmark::run(&config, &cancel)
+            .await
+            .map_err(|e| e.to_string())?;
+        for (key, value) in &result.details {
+            println!("{}: {}", key, value);
+        }
+        match result.score {
+            Some(score) => println!("Benchmark score: {}", score),
+            None => println!("The benchmark finished without a score"),
+        }
+        return Ok(());
+    }
+
+    let result = ffxiv::launch_headless(config, &cancel)
+        .await
+        .map_err(|e| e.to_string())?;
+    for warning in &result.warnings {
+        println!("Warning: {}", warning);
+    }
+    match result.pid {
+        Some(pid) => println!("Game started with PID {}", pid),
+        None => println!("Game started"),
+    }
+    Ok(())
+}
+
+fn list_profiles() -> Result<(), String> {
+    let dir = profile_dir()?;
+    let entries = match fs::read_dir(&dir) {
+        Ok(entries) => entries,
+        Err(_) => {
+            println!("No profiles found in {}", dir.display());
+            return Ok(());
+        }
+    };
+
+    for entry in entries.filter_map(|entry| entry.ok()) {
+        let path = entry.path();
+        if path.extension().is_some_and(|ext| ext == "json") {
+            if let Some(name) = path.file_stem() {
+                println!("{}", name.to_string_lossy());
+            }
+        }
+    }
+    Ok(())
+}
+
+fn profile_dir() -> Result<PathBuf, String> {
+    let config_dir = if cfg!(windows) {
+        std::env::var("APPDATA").map(PathBuf::from)
+    } else {
+        std::env::var("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .or_else(|_| std::env::var("HOME").map(|home| PathBuf::from(home).join(".config")))
+    }
+    .map_err(|_| "Could not determine the config directory".to_string())?;
+
+    Ok(config_dir.join(APP_IDENTIFIER).join("profiles"))
+}
+
+fn load_profile(name: &str) -> Result<LaunchConfig, String> {
+    let path = profile_dir()?.join(format!("{}.json", name));
+    info!("Loading profile from {}", path.display());
+
+    let contents = fs::read_to_string(&path)
+        .map_err(|e| format!("Failed to read profile {}: {}", path.display(), e))?;
+    serde_json::from_str(&contents).map_err(|e| format!("Invalid profile {}: {}", name, e))
+}
+
+// Release builds use the windows subsystem, so there's no console unless we borrow the parent's
+#[cfg(windows)]
+fn attach_console() {
+    use windows::Win32::System::Console::{AttachConsole, ATTACH_PARENT_PROCESS};
+
+    unsafe {
+        let _ = AttachConsole(ATTACH_PARENT_PROCESS);
+    }
+}
+
+#[cfg(not(windows))]
+fn attach_console() {}
+
+fn init_logging() {
+    use tracing_subscriber::EnvFilter;
+
+    let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info"));
+    tracing_subscriber::fmt()
+        .with_env_filter(filter)
+        .with_writer(|| ScrubbingWriter)
+        .init();
+}
+
+// The fmt layer writes each event in one go, so every write is a complete line to scrub
+struct ScrubbingWriter;
+
+impl Write for ScrubbingWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let text = String::from_utf8_lossy(buf);
+        std::io::stderr().write_all(redact::scrub(&text).as_bytes())?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        std::io::stderr().flush()
+    }
+}
@@ -0,0 +1,184 @@
+use serde::Serialize;
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+
+use crate::ffxiv::run_blocking;
+
+const DX11_EXE: &str = "game/ffxiv_dx11.exe";
+// The 32-bit DirectX 9 client, removed in 6.0 but still left behind by older installs
+const DX9_EXE: &str = "game/ffxiv.exe";
+const MACHINE_X64: u16 = 0x8664;
+const MACHINE_X86: u16 = 0x014c;
+// Signature at the start of VS_FIXEDFILEINFO inside the version resource
+const FIXED_FILE_INFO_SIGNATURE: [u8; 4] = 0xFEEF04BDu32.to_le_bytes();
+// The resource section holds icons too, anything past this isn't a game client
+const MAX_RESOURCE_SIZE: u32 = 64 * 1024 * 1024;
+
+#[derive(Debug, Serialize)]
+pub struct ClientCheck {
+    pub executable: String,
+    // "x64", "x86" or None when the file isn't a readable executable
+    pub architecture: Option<String>,
+    pub file_version: Option<String>,
+    // Each of these keeps the game from starting
+    pub errors: Vec<String>,
+    pub warnings: Vec<String>,
+}
+
+struct ExeInfo {
+    machine: u16,
+    file_version: Option<String>,
+}
+
+// The launcher only starts the 64-bit DirectX 11 client, the only one current installs have
+pub(crate) fn check_client(game_path: &str, dx11: bool) -> ClientCheck {
+    let root = Path::new(game_path);
+    let executable = root.join(DX11_EXE);
+    let mut check = ClientCheck {
+        executable: executable.to_string_lossy().to_string(),
+        architecture: None,
+        file_version: None,
+        errors: Vec::new(),
+        warnings: Vec::new(),
+    };
+
+    if !dx11 {
+        check.errors.push(
+            "The DirectX 9 client was removed in patch 6.0, turn on DirectX 11 in the game settings"
+                .to_string(),
+        );
+    }
+    if root.join(DX9_EXE).is_file() {
+        check.warnings.push(format!(
+            "{} is left over from the removed DirectX 9 client and can be deleted",
+            root.join(DX9_EXE).display()
+        ));
+    }
+
+    if !executable.is_file() {
+        check.errors.push(format!(
+            "Game executable not found at {}, repair the install or pick the folder that contains game and boot",
+            executable.display()
+        ));
+        return check;
+    }
+    match read_exe_info(&executable) {
+        Ok(info) => {
+            check.architecture = Some(
+                match info.machine {
+                    MACHINE_X64 => "x64",
+                    MACHINE_X86 => "x86",
+                    _ => "unknown",
+                }
+                .to_string(),
+            );
+            if info.machine != MACHINE_X64 {
+                check.errors.push(format!(
+                    "{} is not a 64-bit executable, repair the install to get the current client",
+                    executable.display()
+                ));
+            }
+            if info.file_version.is_none() {
+                check.warnings.push(format!(
+                    "{} has no version information, the file may be damaged",
+                    executable.display()
+                ));
+            }
+            check.file_version = info.file_version;
+        }
+        Err(e) => check.errors.push(format!(
+            "{} is not a valid executable, repair the install: {}",
+            executable.display(),
+            e
+        )),
+    }
+    check
+}
+
+fn read_exe_info(path: &Path) -> Result<ExeInfo, String> {
+    let mut file =
+        File::open(path).map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+    let mut dos_header = [0u8; 64];
+    file.read_exact(&mut dos_header)
+        .map_err(|e| format!("Failed to read the header: {}", e))?;
+    if &dos_header[..2] != b"MZ" {
+        return Err("missing the MZ header".to_string());
+    }
+    let pe_offset = u32_at(&dos_header, 0x3C) as u64;
+
+    // Signature and COFF file header
+    let mut headers = [0u8; 24];
+    file.seek(SeekFrom::Start(pe_offset))
+        .and_then(|_| file.read_exact(&mut headers))
+        .map_err(|e| format!("Failed to read the PE header: {}", e))?;
+    if &headers[..4] != b"PE\0\0" {
+        return Err("missing the PE header".to_string());
+    }
+    let machine = u16_at(&headers, 4);
+    let section_count = u16_at(&headers, 6) as usize;
+    let optional_header_size = u16_at(&headers, 20) as u64;
+
+    let mut sections = vec![0u8; section_count * 40];
+    file.seek(SeekFrom::Start(pe_offset + 24 + optional_header_size))
+        .and_then(|_| file.read_exact(&mut sections))
+        .map_err(|e| format!("Failed to read the section table: {}", e))?;
+    let resources = sections
+        .chunks_exact(40)
+        .find(|section| section.starts_with(b".rsrc\0"));
+
+    // Not finding the version is reported by the caller, only a broken file is an error
+    let file_version = match resources {
+        Some(section) => {
+            let size = u32_at(section, 16).min(MAX_RESOURCE_SIZE) as usize;
+            let offset = u32_at(section, 20) as u64;
+            let mut data = vec![0u8; size];
+            file.seek(SeekFrom::Start(offset))
+                .and_then(|_| file.read_exact(&mut data))
+                .map_err(|e| format!("Failed to read the resources: {}", e))?;
+            fixed_file_version(&data)
+        }
+        None => None,
+    };
+    Ok(ExeInfo {
+        machine,
+        file_version,
+    })
+}
+
+// dwFileVersionMS and dwFileVersionLS follow the signature and struct version
+fn fixed_file_version(data: &[u8]) -> Option<String> {
+    let start = data
+        .windows(4)
+        .position(|window| window == FIXED_FILE_INFO_SIGNATURE)?;
+    let info = data.get(start..start + 16)?;
+    let (ms, ls) = (u32_at(info, 8), u32_at(info, 12));
+    Some(format!(
+        "{}.{}.{}.{}",
+        ms >> 16,
+        ms & 0xFFFF,
+        ls >> 16,
+        ls & 0xFFFF
+    ))
+}
+
+fn u16_at(data: &[u8], offset: usize) -> u16 {
+    u16::from_le_bytes([data[offset], data[offset + 1]])
+}
+
+fn u32_at(data: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes([
+        data[offset],
+        data[offset + 1],
+        data[offset + 2],
+        data[offset + 3],
+    ])
+}
+
+#[tauri::command]
+pub async fn check_game_client(game_path: String, dx11: bool) -> Result<ClientCheck, String> {
+    if !Path::new(&game_path).join("game").is_dir() {
+        return Err(format!("No game install found at {}", game_path));
+    }
+    run_blocking(move || Ok(check_client(&game_path, dx11))).await
+}
@@ -0,0 +1,444 @@
+use hmac::{Hmac, Mac};
+use reqwest::header::{ETAG, IF_MATCH, IF_NONE_MATCH};
+use reqwest::{Client, Method, RequestBuilder, StatusCode, Url};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::time::Duration;
+use tauri::{AppHandle, State};
+use tauri_plugin_store::StoreExt;
+use time::OffsetDateTime;
+
+use tracing::{info, warn};
+
+use crate::data_export::{self, DataImport};
+use crate::ffxiv::run_blocking;
+use crate::http::HttpClient;
+use crate::redact::Secret;
+
+// Separate from the exported stores, what was last synced is specific to this machine
+const SYNC_STORE: &str = "sync.json";
+const SYNC_STATE_KEY: &str = "state";
+// The bundle carries plugin configs and can be a few megabytes
+const SYNC_TIMEOUT: Duration = Duration::from_secs(120);
+
+// Where the encrypted settings bundle is kept, the frontend keeps this with its settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum SyncBackend {
+    // url is the bundle file itself, e.g. https://cloud.example.com/remote.php/dav/files/me/xivloader.bin
+    Webdav {
+        url: String,
+        username: String,
+        password: Secret,
+    },
+    // Path-style requests, which every S3-compatible server accepts
+    S3 {
+        endpoint: String,
+        region: String,
+        bucket: String,
+        key: String,
+        access_key_id: String,
+        secret_access_key: Secret,
+    },
+}
+
+// The remote version and local data at the last push or pull
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct SyncState {
+    etag: String,
+    fingerprint: String,
+    // Unix timestamp in seconds
+    synced_at: u64,
+}
+
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SyncOutcome {
+    Pushed,
+    Pulled,
+    UpToDate,
+    // Both sides changed since the last sync, nothing was transferred. Sync again with
+    // force to pick a side
+    Conflict,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SyncResult {
+    pub outcome: SyncOutcome,
+    pub message: String,
+    pub last_synced_at: Option<u64>,
+    // What a pull replaced, the frontend reloads its settings and offers the plugins
+    pub import: Option<DataImport>,
+}
+
+#[tauri::command]
+pub async fn sync_push(
+    app: AppHandle,
+    http: State<'_, HttpClient>,
+    backend: SyncBackend,
+    dalamud_path: String,
+    password: Secret,
+    force: bool,
+) -> Result<SyncResult, String> {
+    let client = http.client();
+    let state = load_state(&app)?;
+    let remote = head(&client, &backend).await?;
+
+    if !force {
+        if let Some(remote) = &remote {
+            if state.as_ref().is_none_or(|state| state.etag != *remote) {
+                return Ok(conflict(
+                    &state,
+                    "The synced settings were changed on another machine since the last sync",
+                ));
+            }
+        }
+    }
+
+    let bundle_app = app.clone();
+    let (encrypted, fingerprint) = run_blocking(move || {
+        let (bundle, export) = data_export::build_bundle(&bundle_app, &dalamud_path)?;
+        let encrypted = data_export::encrypt(&bundle, password.expose())?;
+        Ok((encrypted, export.fingerprint))
+    })
+    .await?;
+    if !force
+        && state.as_ref().is_some_and(|state| {
+            state.fingerprint == fingerprint && remote.as_deref() == Some(state.etag.as_str())
+        })
+    {
+        return Ok(result(
+            SyncOutcome::UpToDate,
+            "Nothing changed since the last sync",
+            &state,
+        ));
+    }
+
+    // The precondition catches a push from another machine between the check and the upload
+    let precondition = if force { None } else { Some(remote.as_deref()) };
+    let etag = put(&client, &backend, encrypted, precondition).await?;
+    let etag = match etag {
+        Some(etag) => etag,
+        None => head(&client, &backend)
+            .await?
+            .ok_or_else(|| "The uploaded settings could not be found on the server".to_string())?,
+    };
+    let state = save_state(&app, etag, fingerprint)?;
+    info!("Pushed launcher data to the sync backend");
+    Ok(result(
+        SyncOutcome::Pushed,
+        "Settings uploaded",
+        &Some(state),
+    ))
+}
+
+#[tauri::command]
+pub async fn sync_pull(
+    app: AppHandle,
+    http: State<'_, HttpClient>,
+    backend: SyncBackend,
+    dalamud_path: String,
+    password: Secret,
+    force: bool,
+) -> Result<SyncResult, String> {
+    let client = http.client();
+    let state = load_state(&app)?;
+    let Some((encrypted, etag)) = get(&client, &backend).await? else {
+        return Err("There are no synced settings yet, push from a machine first".to_string());
+    };
+    if state.as_ref().is_some_and(|state| state.etag == etag) {
+        return Ok(result(SyncOutcome::UpToDate, "Already up to date", &state));
+    }
+
+    if !force {
+        let fingerprint_app = app.clone();
+        let path = dalamud_path.clone();
+        let local = run_blocking(move || {
+            data_export::build_bundle(&fingerprint_app, &path).map(|(_, export)| export.fingerprint)
+        })
+        .await?;
+        if state
+            .as_ref()
+            .is_none_or(|state| state.fingerprint != local)
+        {
+            return Ok(conflict(
+                &state,
+                "Settings on this machine and the synced ones both changed since the last sync",
+            ));
+        }
+    }
+
+    let import_app = app.clone();
+    let (import, fingerprint) = run_blocking(move || {
+        let bundle = data_export::decrypt(&encrypted, password.expose())?;
+        let import = data_export::apply_bundle(&import_app, &dalamud_path, &bundle)?;
+        // Fingerprint of the data as it is now on this machine, plugins aren't installed yet
+        let (_, export) = data_export::build_bundle(&import_app, &dalamud_path)?;
+        Ok((import, export.fingerprint))
+    })
+    .await?;
+    let state = save_state(&app, etag, fingerprint)?;
+    info!("Pulled launcher data from the sync backend");
+    Ok(SyncResult {
+        import: Some(import),
+        ..result(SyncOutcome::Pulled, "Settings downloaded", &Some(state))
+    })
+}
+
+fn result(outcome: SyncOutcome, message: &str, state: &Option<SyncState>) -> SyncResult {
+    SyncResult {
+        outcome,
+        message: message.to_string(),
+        last_synced_at: state.as_ref().map(|state| state.synced_at),
+        import: None,
+    }
+}
+
+fn conflict(state: &Option<SyncState>, message: &str) -> SyncResult {
+    warn!("Sync conflict: {}", message);
+    result(SyncOutcome::Conflict, message, state)
+}
+
+fn load_state(app: &AppHandle) -> Result<Option<SyncState>, String> {
+    let store = app
+        .store(SYNC_STORE)
+        .map_err(|e| format!("Failed to open sync store: {}", e))?;
+
+    match store.get(SYNC_STATE_KEY) {
+        Some(value) => serde_json::from_value(value)
+            .map(Some)
+            .map_err(|e| format!("Failed to parse sync state: {}", e)),
+        None => Ok(None),
+    }
+}
+
+fn save_state(app: &AppHandle, etag: String, fingerprint: String) -> Result<SyncState, String> {
+    let state = SyncState {
+        etag,
+        fingerprint,
+        synced_at: crate::playtime::unix_now(),
+    };
+    let store = app
+        .store(SYNC_STORE)
+        .map_err(|e| format!("Failed to open sync store: {}", e))?;
+    let value = serde_json::to_value(&state)
+        .map_err(|e| format!("Failed to serialize sync state: {}", e))?;
+    store.set(SYNC_STATE_KEY, value);
+    store
+        .save()
+        .map_err(|e| format!("Failed to save sync state: {}", e))?;
+    Ok(state)
+}
+
+// ETag of the remote bundle, None when there is none yet
+async fn head(client: &Client, backend: &SyncBackend) -> Result<Option<String>, String> {
+    let response = request(client, backend, Method::HEAD, &[])?
+        .send()
+        .await
+        .map_err(|e| format!("Failed to reach the sync server: {}", e))?;
+    match response.status() {
+        StatusCode::NOT_FOUND => Ok(None),
+        status if status.is_success() => Ok(Some(etag(&response)?)),
+        status => Err(format!("Sync server answered {}", status)),
+    }
+}
+
+async fn get(client: &Client, backend: &SyncBackend) -> Result<Option<(Vec<u8>, String)>, String> {
+    let response = request(client, backend, Method::GET, &[])?
+        .send()
+        .await
+        .map_err(|e| format!("Failed to reach the sync server: {}", e))?;
+    match response.status() {
+        StatusCode::NOT_FOUND => Ok(None),
+        status if status.is_success() => {
+            let etag = etag(&response)?;
+            let body = response
+                .bytes()
+                .await
+                .map_err(|e| format!("Failed to download synced settings: {}", e))?;
+            Ok(Some((body.to_vec(), etag)))
+        }
+        status => Err(format!("Sync server answered {}", status)),
+    }
+}
+
+// precondition is the ETag the remote is expected to still have, Some(None) when it is
+// expected to not exist yet. Returns the new ETag if the server sent one
+async fn put(
+    client: &Client,
+    backend: &SyncBackend,
+    body: Vec<u8>,
+    precondition: Option<Option<&str>>,
+) -> Result<Option<String>, String> {
+    let mut builder = request(client, backend, Method::PUT, &body)?;
+    builder = match precondition {
+        Some(Some(etag)) => builder.header(IF_MATCH, etag),
+        Some(None) => builder.header(IF_NONE_MATCH, "*"),
+        None => builder,
+    };
+    let response = builder
+        .body(body)
+        .send()
+        .await
+        .map_err(|e| format!("Failed to upload settings: {}", e))?;
+
+    match response.status() {
+        StatusCode::PRECONDITION_FAILED => Err(
+            "The synced settings changed while uploading, sync again to check for a conflict"
+                .to_string(),
+        ),
+        status if status.is_success() => Ok(etag(&response).ok()),
+        status => Err(format!("Sync server answered {} to the upload", status)),
+    }
+}
+
+fn etag(response: &reqwest::Response) -> Result<String, String> {
+    response
+        .headers()
+        .get(ETAG)
+        .and_then(|value| value.to_str().ok())
+        .map(|value| value.to_string())
+        .ok_or_else(|| "Sync server sent no ETag, conflicts can't be detected".to_string())
+}
+
+fn request(
+    client: &Client,
+    backend: &SyncBackend,
+    method: Method,
+    body: &[u8],
+) -> Result<RequestBuilder, String> {
+    match backend {
+        SyncBackend::Webdav {
+            url,
+            username,
+            password,
+        } => {
+            let url = Url::parse(url).map_err(|e| format!("Invalid WebDAV URL: {}", e))?;
+            Ok(client
+                .request(method, url)
+                .timeout(SYNC_TIMEOUT)
+                .basic_auth(username, Some(password.expose())))
+        }
+        SyncBackend::S3 {
+            endpoint,
+            region,
+            bucket,
+            key,
+            access_key_id,
+            secret_access_key,
+        } => {
+            let path = format!(
+                "/{}/{}",
+                uri_encode(bucket, false),
+                uri_encode(key.trim_start_matches('/'), true)
+            );
+            let url = Url::parse(&format!("{}{}", endpoint.trim_end_matches('/'), path))
+                .map_err(|e| format!("Invalid S3 endpoint: {}", e))?;
+            let signer = SigV4 {
+                region,
+                access_key_id,
+                secret_access_key: secret_access_key.expose(),
+            };
+            let headers = signer.sign(&method, &url, &path, body)?;
+
+            let mut builder = client.request(method, url).timeout(SYNC_TIMEOUT);
+            for (name, value) in headers {
+                builder = builder.header(name, value);
+            }
+            Ok(builder)
+        }
+    }
+}
+
+// AWS Signature Version 4 for a single S3 object request without query parameters
+struct SigV4<'a> {
+    region: &'a str,
+    access_key_id: &'a str,
+    secret_access_key: &'a str,
+}
+
+impl SigV4<'_> {
+    fn sign(
+        &self,
+        method: &Method,
+        url: &Url,
+        path: &str,
+        body: &[u8],
+    ) -> Result<Vec<(&'static str, String)>, String> {
+        let host = match (url.host_str(), url.port()) {
+            (Some(host), Some(port)) => format!("{}:{}", host, port),
+            (Some(host), None) => host.to_string(),
+            (None, _) => return Err("S3 endpoint has no host".to_string()),
+        };
+        let now = OffsetDateTime::now_utc();
+        let date = format!("{:04}{:02}{:02}", now.year(), now.month() as u8, now.day());
+        let timestamp = format!(
+            "{}T{:02}{:02}{:02}Z",
+            date,
+            now.hour(),
+            now.minute(),
+            now.second()
+        );
+        let payload_hash = hex::encode(Sha256::digest(body));
+
+        let signed_headers = "host;x-amz-content-sha256;x-amz-date";
+        let canonical_request = format!(
+            "{}\n{}\n\nhost:{}\nx-amz-content-sha256:{}\nx-amz-date:{}\n\n{}\n{}",
+            method.as_str(),
+            path,
+            host,
+            payload_hash,
+            timestamp,
+            signed_headers,
+            payload_hash
+        );
+        let scope = format!("{}/{}/s3/aws4_request", date, self.region);
+        let string_to_sign = format!(
+            "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+            timestamp,
+            scope,
+            hex::encode(Sha256::digest(canonical_request.as_bytes()))
+        );
+
+        let key = format!("AWS4{}", self.secret_access_key);
+        let key = hmac(key.as_bytes(), date.as_bytes())?;
+        let key = hmac(&key, self.region.as_bytes())?;
+        let key = hmac(&key, b"s3")?;
+        let key = hmac(&key, b"aws4_request")?;
+        let signature = hex::encode(hmac(&key, string_to_sign.as_bytes())?);
+
+        Ok(vec![
+            ("x-amz-content-sha256", payload_hash),
+            ("x-amz-date", timestamp),
+            (
+                "authorization",
+                format!(
+                    "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders={}, Signature={}",
+                    self.access_key_id, scope, signed_headers, signature
+                ),
+            ),
+        ])
+    }
+}
+
+fn hmac(key: &[u8], data: &[u8]) -> Result<Vec<u8>, String> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key)
+        .map_err(|e| format!("Failed to sign S3 request: {}", e))?;
+    mac.update(data);
+    Ok(mac.finalize().into_bytes().to_vec())
+}
+
+// S3's flavour of percent-encoding, slashes are kept in object keys
+fn uri_encode(value: &str, keep_slash: bool) -> String {
+    let mut encoded = String::new();
+    for byte in value.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                encoded.push(byte as char)
+            }
+            b'/' if keep_slash => encoded.push('/'),
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
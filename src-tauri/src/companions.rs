@@ -0,0 +1,367 @@
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::process::{Child, Command};
+use std::time::{Duration, Instant};
+
+use tracing::{info, warn};
+
+use crate::argument_builder::TemplateVars;
+
+const ACT_NAME: &str = "ACT";
+const ACT_EXE: &str = "Advanced Combat Tracker.exe";
+// The splash screen is up long before plugins are loaded, the main window carries the full name
+const ACT_WINDOW_TITLE: &str = "Advanced Combat Tracker";
+const READY_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+// Whether a companion app is started before the game process or right after it
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CompanionTiming {
+    #[default]
+    Before,
+    After,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ActConfig {
+    pub enabled: bool,
+    pub path: String,
+    pub timing: CompanionTiming,
+    pub close_on_exit: bool,
+    // ACT with a lot of plugins takes a while to load, the launch goes on without it after this
+    pub ready_timeout_secs: u64,
+}
+
+impl Default for ActConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            path: String::new(),
+            timing: CompanionTiming::Before,
+            close_on_exit: false,
+            ready_timeout_secs: 60,
+        }
+    }
+}
+
+// Any other program a profile wants running around the game
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ExternalTool {
+    pub enabled: bool,
+    pub name: String,
+    pub path: String,
+    pub args: Vec<String>,
+    pub timing: CompanionTiming,
+    pub kill_on_exit: bool,
+}
+
+impl Default for ExternalTool {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            name: String::new(),
+            path: String::new(),
+            args: Vec::new(),
+            timing: CompanionTiming::Before,
+            kill_on_exit: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ExitAction {
+    Keep,
+    // Asks the app to close through its windows
+    Close,
+    Kill,
+}
+
+// A companion app this launch started, an instance that was already running isn't tracked
+#[derive(Debug, Clone)]
+pub struct Companion {
+    pub name: String,
+    pub pid: u32,
+    pub on_exit: ExitAction,
+}
+
+// Starts ACT if the profile wants it at this point of the launch and waits for its main window
+pub async fn start_act(
+    config: &ActConfig,
+    timing: CompanionTiming,
+) -> Result<Option<Companion>, String> {
+    if !config.enabled || config.timing != timing {
+        return Ok(None);
+    }
+    if !cfg!(windows) {
+        return Err("ACT can only be started on Windows".to_string());
+    }
+
+    if let Some(pid) = running_pids(ACT_EXE).first() {
+        info!("ACT is already running as {}, not starting it again", pid);
+        return Ok(None);
+    }
+
+    let path = Path::new(&config.path);
+    if config.path.is_empty() || !path.is_file() {
+        return Err(format!("ACT not found at {}", config.path));
+    }
+
+    let mut command = Command::new(path);
+    if let Some(dir) = path.parent() {
+        command.current_dir(dir);
+    }
+    let child = command
+        .spawn()
+        .map_err(|e| format!("Failed to start ACT: {}", e))?;
+    let pid = reap(child);
+    info!("Started ACT as {}", pid);
+
+    let timeout = Duration::from_secs(config.ready_timeout_secs);
+    let ready = tauri::async_runtime::spawn_blocking(move || wait_until_ready(pid, timeout))
+        .await
+        .map_err(|e| format!("Failed to wait for ACT: {}", e))?;
+    if ready {
+        info!("ACT is ready");
+    } else {
+        warn!(
+            "ACT was not ready after {}s, continuing without waiting",
+            config.ready_timeout_secs
+        );
+    }
+
+    Ok(Some(Companion {
+        name: ACT_NAME.to_string(),
+        pid,
+        // Closing the window instead of killing the process lets ACT save its settings and logs
+        on_exit: if config.close_on_exit {
+            ExitAction::Close
+        } else {
+            ExitAction::Keep
+        },
+    }))
+}
+
+// Starts the profile's tools meant for this point of the launch. A tool that fails to start
+// doesn't stop the others, its error is returned next to what did start
+pub fn start_tools(
+    tools: &[ExternalTool],
+    timing: CompanionTiming,
+    vars: &TemplateVars,
+) -> (Vec<Companion>, Vec<String>) {
+    let mut started = Vec::new();
+    let mut errors = Vec::new();
+
+    for tool in tools.iter().filter(|t| t.enabled && t.timing == timing) {
+        let name = if tool.name.is_empty() {
+            Path::new(&tool.path)
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().to_string())
+                .unwrap_or_else(|| tool.path.clone())
+        } else {
+            tool.name.clone()
+        };
+
+        match start_tool(tool, vars) {
+            Ok(pid) => {
+                info!("Started {} as {}", name, pid);
+                started.push(Companion {
+                    name,
+                    pid,
+                    on_exit: if tool.kill_on_exit {
+                        ExitAction::Kill
+                    } else {
+                        ExitAction::Keep
+                    },
+                });
+            }
+            Err(e) => {
+                warn!("Failed to start {}: {}", name, e);
+                errors.push(format!("{} was not started: {}", name, e));
+            }
+        }
+    }
+
+    (started, errors)
+}
+
+fn start_tool(tool: &ExternalTool, vars: &TemplateVars) -> Result<u32, String> {
+    let path = Path::new(&tool.path);
+    if tool.path.is_empty() || !path.is_file() {
+        return Err(format!("not found at {}", tool.path));
+    }
+    let args = tool
+        .args
+        .iter()
+        .map(|arg| vars.expand(arg))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let mut command = Command::new(path);
+    command.args(&args);
+    if let Some(dir) = path.parent() {
+        command.current_dir(dir);
+    }
+    let child = command.spawn().map_err(|e| e.to_string())?;
+    Ok(reap(child))
+}
+
+// Called once the game exits
+pub fn close(companion: &Companion) {
+    if companion.on_exit == ExitAction::Keep {
+        return;
+    }
+    if !is_running(companion.pid) {
+        info!("{} already exited", companion.name);
+        return;
+    }
+
+    info!(
+        "Game exited, closing {} ({})",
+        companion.name, companion.pid
+    );
+    match companion.on_exit {
+        ExitAction::Close => {
+            let windows = process_windows(companion.pid);
+            if windows.is_empty() {
+                warn!("{} has no window to close", companion.name);
+            }
+            for (window, _) in windows {
+                close_window(window);
+            }
+        }
+        ExitAction::Kill => {
+            if let Err(e) = kill(companion.pid) {
+                warn!("Failed to close {}: {}", companion.name, e);
+            }
+        }
+        ExitAction::Keep => {}
+    }
+}
+
+// Waits on the child in the background so it doesn't linger as a zombie once it exits
+fn reap(mut child: Child) -> u32 {
+    let pid = child.id();
+    std::thread::spawn(move || {
+        let _ = child.wait();
+    });
+    pid
+}
+
+fn wait_until_ready(pid: u32, timeout: Duration) -> bool {
+    let deadline = Instant::now() + timeout;
+    while Instant::now() < deadline {
+        if process_windows(pid)
+            .iter()
+            .any(|(_, title)| title.starts_with(ACT_WINDOW_TITLE))
+        {
+            return true;
+        }
+        if !is_running(pid) {
+            warn!("ACT exited while starting");
+            return false;
+        }
+        std::thread::sleep(READY_POLL_INTERVAL);
+    }
+    false
+}
+
+#[cfg(windows)]
+fn running_pids(exe_name: &str) -> Vec<u32> {
+    use windows::Win32::Foundation::CloseHandle;
+    use windows::Win32::System::Diagnostics::ToolHelp::{
+        CreateToolhelp32Snapshot, Process32FirstW, Process32NextW, PROCESSENTRY32W,
+        TH32CS_SNAPPROCESS,
+    };
+
+    let mut pids = Vec::new();
+    unsafe {
+        let Ok(snapshot) = CreateToolhelp32Snapshot(TH32CS_SNAPPROCESS, 0) else {
+            return pids;
+        };
+
+        let mut entry = PROCESSENTRY32W {
+            dwSize: std::mem::size_of::<PROCESSENTRY32W>() as u32,
+            ..Default::default()
+        };
+        let mut found = Process32FirstW(snapshot, &mut entry).is_ok();
+        while found {
+            let name_len = entry
+                .szExeFile
+                .iter()
+                .position(|c| *c == 0)
+                .unwrap_or(entry.szExeFile.len());
+            let name = String::from_utf16_lossy(&entry.szExeFile[..name_len]);
+            if name.eq_ignore_ascii_case(exe_name) {
+                pids.push(entry.th32ProcessID);
+            }
+            found = Process32NextW(snapshot, &mut entry).is_ok();
+        }
+        let _ = CloseHandle(snapshot);
+    }
+    pids
+}
+
+#[cfg(not(windows))]
+fn running_pids(_exe_name: &str) -> Vec<u32> {
+    Vec::new()
+}
+
+#[cfg(windows)]
+pub(crate) fn is_running(pid: u32) -> bool {
+    crate::process::windows::ProcessHandle::open(pid).is_ok_and(|process| process.is_running())
+}
+
+#[cfg(not(windows))]
+pub(crate) fn is_running(pid: u32) -> bool {
+    Path::new(&format!("/proc/{}", pid)).exists()
+}
+
+#[cfg(windows)]
+fn kill(pid: u32) -> Result<(), String> {
+    use windows::Win32::Foundation::CloseHandle;
+    use windows::Win32::System::Threading::{OpenProcess, TerminateProcess, PROCESS_TERMINATE};
+
+    unsafe {
+        let handle = OpenProcess(PROCESS_TERMINATE, false, pid).map_err(|e| e.to_string())?;
+        let result = TerminateProcess(handle, 1);
+        let _ = CloseHandle(handle);
+        result.map_err(|e| e.to_string())
+    }
+}
+
+#[cfg(not(windows))]
+fn kill(pid: u32) -> Result<(), String> {
+    let status = Command::new("kill")
+        .arg(pid.to_string())
+        .status()
+        .map_err(|e| format!("Failed to run kill: {}", e))?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(format!("kill exited with {}", status))
+    }
+}
+
+// Visible top level windows of a process with their titles
+#[cfg(windows)]
+fn process_windows(pid: u32) -> Vec<(usize, String)> {
+    crate::process::windows::process_windows(pid)
+        .into_iter()
+        .filter(|window| window.visible)
+        .map(|window| (window.handle, window.title))
+        .collect()
+}
+
+#[cfg(not(windows))]
+fn process_windows(_pid: u32) -> Vec<(usize, String)> {
+    Vec::new()
+}
+
+#[cfg(windows)]
+fn close_window(window: usize) {
+    crate::process::windows::close_window(window);
+}
+
+#[cfg(not(windows))]
+fn close_window(_window: usize) {}
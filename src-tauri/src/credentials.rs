@@ -0,0 +1,90 @@
+use keyring::Entry;
+use serde::{Deserialize, Serialize};
+
+use tracing::{info, warn};
+
+// Windows Credential Manager target names are "<user>.<service>"
+const PASSWORD_SERVICE: &str = "rust-xivloader";
+const OTP_SECRET_SERVICE: &str = "rust-xivloader-otp";
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct StoredCredentials {
+    pub username: String,
+    pub password: String,
+    pub otp_secret: Option<String>,
+}
+
+fn entry(service: &str, username: &str) -> Result<Entry, String> {
+    if username.is_empty() {
+        return Err("Username must not be empty".to_string());
+    }
+    Entry::new(service, username).map_err(|e| format!("Failed to open credential store: {}", e))
+}
+
+fn read_secret(service: &str, username: &str) -> Result<Option<String>, String> {
+    match entry(service, username)?.get_password() {
+        Ok(secret) => Ok(Some(secret)),
+        Err(keyring::Error::NoEntry) => Ok(None),
+        Err(e) => Err(format!("Failed to read credentials: {}", e)),
+    }
+}
+
+fn delete_secret(service: &str, username: &str) -> Result<(), String> {
+    match entry(service, username)?.delete_password() {
+        Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+        Err(e) => Err(format!("Failed to delete credentials: {}", e)),
+    }
+}
+
+pub fn load_password(username: &str) -> Result<String, String> {
+    read_secret(PASSWORD_SERVICE, username)?
+        .ok_or_else(|| format!("No stored password for {}", username))
+}
+
+#[tauri::command]
+pub fn save_credentials(
+    username: String,
+    password: String,
+    otp_secret: Option<String>,
+) -> Result<(), String> {
+    info!("Saving credentials for {}", username);
+
+    entry(PASSWORD_SERVICE, &username)?
+        .set_password(&password)
+        .map_err(|e| format!("Failed to save password: {}", e))?;
+
+    match otp_secret.filter(|s| !s.is_empty()) {
+        Some(secret) => entry(OTP_SECRET_SERVICE, &username)?
+            .set_password(&secret)
+            .map_err(|e| format!("Failed to save OTP secret: {}", e))?,
+        None => delete_secret(OTP_SECRET_SERVICE, &username)?,
+    }
+
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_credentials(username: String) -> Result<Option<StoredCredentials>, String> {
+    let Some(password) = read_secret(PASSWORD_SERVICE, &username)? else {
+        info!("No stored credentials for {}", username);
+        return Ok(None);
+    };
+
+    let otp_secret = read_secret(OTP_SECRET_SERVICE, &username).unwrap_or_else(|e| {
+        warn!("Failed to read OTP secret for {}: {}", username, e);
+        None
+    });
+
+    Ok(Some(StoredCredentials {
+        username,
+        password,
+        otp_secret,
+    }))
+}
+
+#[tauri::command]
+pub fn delete_credentials(username: String) -> Result<(), String> {
+    info!("Deleting stored credentials for {}", username);
+    delete_secret(PASSWORD_SERVICE, &username)?;
+    delete_secret(OTP_SECRET_SERVICE, &username)
+}
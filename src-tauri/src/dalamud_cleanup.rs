@@ -0,0 +1,166 @@
+use serde::Serialize;
+use std::fs;
+use std::path::Path;
+use walkdir::WalkDir;
+
+use tracing::{info, warn};
+
+use crate::game_monitor;
+use crate::plugins::compare_versions;
+
+// Everything setup_dalamud downloads, relative to the Dalamud folder
+const DALAMUD_FOLDERS: [&str; 3] = ["addon/Hooks", "runtime", "dalamudAssets"];
+const TEMP_FILES: [&str; 2] = ["dalamud_temp.zip", "asset_package_temp.zip"];
+// Plugins and their settings are the user's own data, only removed when asked for
+const PLUGIN_FOLDERS: [&str; 2] = ["installedPlugins", "pluginConfigs"];
+// Every update leaves the previous Hooks/<version> behind, a few stay as the offline fallback
+pub const KEEP_DALAMUD_VERSIONS: usize = 3;
+
+#[derive(Debug, Serialize)]
+pub struct RemovedPath {
+    pub path: String,
+    pub bytes: u64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DalamudRemoval {
+    pub removed: Vec<RemovedPath>,
+    pub total_bytes: u64,
+    // Nothing was deleted, the list is what would be
+    pub dry_run: bool,
+}
+
+#[tauri::command]
+pub fn remove_dalamud(
+    dalamud_path: String,
+    include_plugins: bool,
+    dry_run: bool,
+) -> Result<DalamudRemoval, String> {
+    let root = dalamud_root(&dalamud_path);
+    if !root.is_dir() {
+        return Err(format!("Dalamud folder not found at {}", root.display()));
+    }
+    if !dry_run && game_monitor::get_game_status().running {
+        return Err("Close the game before removing Dalamud".to_string());
+    }
+
+    let mut targets: Vec<&str> = DALAMUD_FOLDERS.iter().chain(&TEMP_FILES).copied().collect();
+    if include_plugins {
+        targets.extend(PLUGIN_FOLDERS);
+    }
+
+    let mut removed = Vec::new();
+    for target in targets {
+        let path = root.join(target);
+        if !path.exists() {
+            continue;
+        }
+
+        let bytes = size_on_disk(&path);
+        if !dry_run {
+            let result = if path.is_dir() {
+                fs::remove_dir_all(&path)
+            } else {
+                fs::remove_file(&path)
+            };
+            result.map_err(|e| format!("Failed to remove {}: {}", path.display(), e))?;
+            info!("Removed {}", path.display());
+        }
+        removed.push(RemovedPath {
+            path: path.to_string_lossy().to_string(),
+            bytes,
+        });
+    }
+
+    // Only drop the addon folder if Hooks was all that was in it
+    let addon = root.join("addon");
+    let addon_empty = fs::read_dir(&addon).is_ok_and(|mut entries| entries.next().is_none());
+    if !dry_run && addon_empty {
+        if let Err(e) = fs::remove_dir(&addon) {
+            warn!("Failed to remove {}: {}", addon.display(), e);
+        }
+    }
+
+    Ok(DalamudRemoval {
+        total_bytes: removed.iter().map(|r| r.bytes).sum(),
+        removed,
+        dry_run,
+    })
+}
+
+// Reports the space reclaimed, keep defaults to KEEP_DALAMUD_VERSIONS
+#[tauri::command]
+pub fn prune_dalamud_versions(
+    dalamud_path: String,
+    keep: Option<usize>,
+    dry_run: bool,
+) -> Result<DalamudRemoval, String> {
+    if !dry_run && game_monitor::get_game_status().running {
+        return Err("Close the game before removing old Dalamud versions".to_string());
+    }
+    prune_versions(
+        &dalamud_path,
+        keep.unwrap_or(KEEP_DALAMUD_VERSIONS),
+        dry_run,
+    )
+}
+
+// Removes all but the newest keep versions in Hooks. Folders that aren't named like a
+// version, such as a dev build, are left alone
+pub(crate) fn prune_versions(
+    dalamud_path: &str,
+    keep: usize,
+    dry_run: bool,
+) -> Result<DalamudRemoval, String> {
+    let hooks = dalamud_root(dalamud_path).join("addon/Hooks");
+    let mut versions: Vec<String> = match fs::read_dir(&hooks) {
+        Ok(entries) => entries
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.path().is_dir())
+            .map(|entry| entry.file_name().to_string_lossy().to_string())
+            .filter(|name| name.split('.').all(|part| part.parse::<u64>().is_ok()))
+            .collect(),
+        Err(_) => Vec::new(),
+    };
+    versions.sort_by(|a, b| compare_versions(b, a));
+
+    let mut removed = Vec::new();
+    for version in versions.iter().skip(keep.max(1)) {
+        let path = hooks.join(version);
+        let bytes = size_on_disk(&path);
+        if !dry_run {
+            fs::remove_dir_all(&path)
+                .map_err(|e| format!("Failed to remove {}: {}", path.display(), e))?;
+            info!("Removed Dalamud {}", version);
+        }
+        removed.push(RemovedPath {
+            path: path.to_string_lossy().to_string(),
+            bytes,
+        });
+    }
+
+    Ok(DalamudRemoval {
+        total_bytes: removed.iter().map(|r| r.bytes).sum(),
+        removed,
+        dry_run,
+    })
+}
+
+// Same normalization as the launch, profiles may point at the addon folder itself
+fn dalamud_root(dalamud_path: &str) -> &Path {
+    let root = dalamud_path
+        .strip_suffix("/addon")
+        .or_else(|| dalamud_path.strip_suffix("\\addon"))
+        .unwrap_or(dalamud_path);
+    Path::new(root)
+}
+
+fn size_on_disk(path: &Path) -> u64 {
+    WalkDir::new(path)
+        .into_iter()
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| entry.metadata().ok())
+        .filter(|metadata| metadata.is_file())
+        .map(|metadata| metadata.len())
+        .sum()
+}
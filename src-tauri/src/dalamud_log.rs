@@ -0,0 +1,109 @@
+use serde::Serialize;
+use std::io::SeekFrom;
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+use tokio::fs::File;
+use tokio::io::{AsyncReadExt, AsyncSeekExt};
+use tokio_util::sync::CancellationToken;
+
+use tracing::{info, warn};
+
+pub const DALAMUD_LOG_EVENT: &str = "dalamud://log";
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+// Only one log is followed at a time, starting a new tail stops the previous one
+static CURRENT_TAIL: Mutex<Option<CancellationToken>> = Mutex::new(None);
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DalamudLogEvent {
+    pub lines: Vec<String>,
+}
+
+#[tauri::command]
+pub fn tail_dalamud_log(app: AppHandle, dalamud_path: String) -> Result<(), String> {
+    let log_path = format!("{}/logs/Dalamud.log", dalamud_path);
+    let cancel = CancellationToken::new();
+
+    if let Some(previous) = CURRENT_TAIL.lock().unwrap().replace(cancel.clone()) {
+        previous.cancel();
+    }
+
+    info!("Following Dalamud log at {}", log_path);
+    tauri::async_runtime::spawn(async move {
+        tokio::select! {
+            _ = cancel.cancelled() => info!("Stopped following Dalamud log"),
+            _ = follow_log(&app, &log_path) => {}
+        }
+    });
+
+    Ok(())
+}
+
+#[tauri::command]
+pub fn stop_dalamud_log() {
+    if let Some(cancel) = CURRENT_TAIL.lock().unwrap().take() {
+        cancel.cancel();
+    }
+}
+
+async fn follow_log(app: &AppHandle, log_path: &str) {
+    // Skip what's already there, only lines written from now on are interesting
+    let mut offset = tokio::fs::metadata(log_path)
+        .await
+        .map(|m| m.len())
+        .unwrap_or(0);
+    let mut pending = Vec::new();
+
+    loop {
+        tokio::time::sleep(POLL_INTERVAL).await;
+
+        let Ok(len) = tokio::fs::metadata(log_path).await.map(|m| m.len()) else {
+            // Dalamud hasn't created the log yet
+            continue;
+        };
+
+        // Dalamud starts a fresh log on every game start
+        if len < offset {
+            offset = 0;
+            pending.clear();
+        }
+        if len == offset {
+            continue;
+        }
+
+        let chunk = match read_from(log_path, offset).await {
+            Ok(chunk) => chunk,
+            Err(e) => {
+                warn!("Failed to read Dalamud log: {}", e);
+                continue;
+            }
+        };
+        offset += chunk.len() as u64;
+        pending.extend_from_slice(&chunk);
+
+        // Hold back a trailing partial line until the rest of it is written
+        let Some(last_newline) = pending.iter().rposition(|b| *b == b'\n') else {
+            continue;
+        };
+        let rest = pending.split_off(last_newline + 1);
+        let lines = String::from_utf8_lossy(&pending)
+            .lines()
+            .map(str::to_string)
+            .collect();
+        pending = rest;
+
+        if let Err(e) = app.emit(DALAMUD_LOG_EVENT, DalamudLogEvent { lines }) {
+            warn!("Failed to emit Dalamud log lines: {}", e);
+        }
+    }
+}
+
+async fn read_from(log_path: &str, offset: u64) -> std::io::Result<Vec<u8>> {
+    let mut file = File::open(log_path).await?;
+    file.seek(SeekFrom::Start(offset)).await?;
+
+    let mut buffer = Vec::new();
+    file.read_to_end(&mut buffer).await?;
+    Ok(buffer)
+}
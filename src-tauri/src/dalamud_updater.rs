@@ -0,0 +1,67 @@
+use std::mem;
+use std::sync::Mutex;
+use std::time::Duration;
+use tauri::{AppHandle, Manager};
+use tokio_util::sync::CancellationToken;
+
+use tracing::{info, warn};
+
+use crate::ffxiv::{setup_dalamud, LaunchConfig};
+use crate::http::HttpClient;
+use crate::installs;
+
+// Dalamud and asset releases come out a few times a week at most
+const CHECK_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+static SCHEDULE: Mutex<Option<CancellationToken>> = Mutex::new(None);
+
+// Keeps Dalamud and its assets updated in the background while the launcher is open, so
+// pressing launch doesn't wait on a download. Checks right away and then every hour,
+// calling it again with new settings replaces the running schedule
+#[tauri::command]
+pub fn schedule_dalamud_update(app: AppHandle, mut config: LaunchConfig) -> Result<(), String> {
+    installs::apply(&app, &mut config)?;
+    let enabled = cfg!(windows) && config.enable_dalamud && !config.dalamud_path.trim().is_empty();
+    // Fast launch would skip the very check this is for
+    config.fast_launch = false;
+
+    let cancel = CancellationToken::new();
+    let previous = mem::replace(
+        &mut *SCHEDULE.lock().unwrap(),
+        enabled.then(|| cancel.clone()),
+    );
+    if let Some(previous) = previous {
+        previous.cancel();
+    }
+    if !enabled {
+        info!("Background Dalamud updates are off");
+        return Ok(());
+    }
+
+    info!(
+        "Checking for Dalamud updates in the background every {:?}",
+        CHECK_INTERVAL
+    );
+    tauri::async_runtime::spawn(async move {
+        loop {
+            let client = app.state::<HttpClient>().client();
+            match setup_dalamud(&client, &config, None, &cancel).await {
+                Ok(setup) if setup.offline => {
+                    warn!("Dalamud server unreachable, background update skipped")
+                }
+                Ok(setup) => info!(
+                    "Dalamud {} is ready for the next launch",
+                    setup.version_info.assembly_version
+                ),
+                Err(_) if cancel.is_cancelled() => return,
+                Err(e) => warn!("Background Dalamud update failed: {}", e),
+            }
+
+            tokio::select! {
+                _ = cancel.cancelled() => return,
+                _ = tokio::time::sleep(CHECK_INTERVAL) => {}
+            }
+        }
+    });
+    Ok(())
+}
@@ -0,0 +1,396 @@
+use aes_gcm::aead::rand_core::RngCore;
+use aes_gcm::aead::{Aead, KeyInit, OsRng};
+use aes_gcm::{Aes256Gcm, Nonce};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::io::{Cursor, Read, Write};
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::AppHandle;
+use tauri_plugin_store::StoreExt;
+use walkdir::WalkDir;
+use zip::write::FileOptions;
+use zip::{ZipArchive, ZipWriter};
+
+use tracing::{info, warn};
+
+use crate::archive::safe_relative_path;
+use crate::game_monitor;
+use crate::plugins::{installed_version, DALAMUD_CONFIG_FILE, PLUGIN_FOLDER};
+
+// The launcher's own stores. Credentials stay in the OS keyring and are not exported
+const STORES: [&str; 3] = ["settings.json", "playtime.json", "launch_history.json"];
+// Relative to the Dalamud folder. Plugins themselves are only listed, they are downloaded
+// again on the new machine
+const PLUGIN_CONFIG_FOLDER: &str = "pluginConfigs";
+const MANIFEST_FILE: &str = "manifest.json";
+
+const MAGIC: &[u8; 8] = b"XIVLDATA";
+const FORMAT_VERSION: u8 = 1;
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+// OWASP's current recommendation for PBKDF2-HMAC-SHA256
+const KDF_ROUNDS: u32 = 600_000;
+
+#[derive(Debug, Serialize, Deserialize)]
+struct BundleManifest {
+    launcher_version: String,
+    // Unix timestamp in seconds
+    created_at: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InstalledPlugin {
+    pub internal_name: String,
+    pub version: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DataExport {
+    pub path: String,
+    pub bytes: u64,
+    // Hash of the exported data without the export time, equal for two exports of unchanged data
+    pub fingerprint: String,
+    pub stores: Vec<String>,
+    pub dalamud_files: usize,
+    pub plugins: Vec<InstalledPlugin>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DataImport {
+    pub launcher_version: String,
+    pub created_at: u64,
+    pub stores: Vec<String>,
+    pub dalamud_files: usize,
+    // Not installed by the import, the frontend offers to install them from the repositories
+    pub plugins: Vec<InstalledPlugin>,
+}
+
+// Settings, install list, plugin repositories, playtime, launch history, Dalamud's and the
+// plugins' configuration and the list of installed plugins, encrypted with the password.
+// The game itself and the plugin binaries are left out
+#[tauri::command]
+pub fn export_launcher_data(
+    app: AppHandle,
+    dalamud_path: String,
+    output_path: String,
+    password: String,
+) -> Result<DataExport, String> {
+    let (bundle, mut export) = build_bundle(&app, &dalamud_path)?;
+    let encrypted = encrypt(&bundle, &password)?;
+    fs::write(&output_path, &encrypted)
+        .map_err(|e| format!("Failed to write {}: {}", output_path, e))?;
+
+    info!(
+        "Exported launcher data to {} ({} bytes)",
+        output_path,
+        encrypted.len()
+    );
+    export.path = output_path;
+    export.bytes = encrypted.len() as u64;
+    Ok(export)
+}
+
+// Replaces the stores and Dalamud configuration with the ones from the export
+#[tauri::command]
+pub fn import_launcher_data(
+    app: AppHandle,
+    dalamud_path: String,
+    input_path: String,
+    password: String,
+) -> Result<DataImport, String> {
+    let encrypted =
+        fs::read(&input_path).map_err(|e| format!("Failed to read {}: {}", input_path, e))?;
+    let bundle = decrypt(&encrypted, &password)?;
+    let import = apply_bundle(&app, &dalamud_path, &bundle)?;
+    info!(
+        "Imported launcher data from {}, exported by {}",
+        input_path, import.launcher_version
+    );
+    Ok(import)
+}
+
+pub(crate) fn build_bundle(
+    app: &AppHandle,
+    dalamud_path: &str,
+) -> Result<(Vec<u8>, DataExport), String> {
+    let mut bundle = BundleWriter::new();
+    let manifest = BundleManifest {
+        launcher_version: env!("CARGO_PKG_VERSION").to_string(),
+        created_at: unix_now(),
+    };
+    bundle.add_json(MANIFEST_FILE, &manifest)?;
+
+    let mut stores = Vec::new();
+    for name in STORES {
+        let store = app
+            .store(name)
+            .map_err(|e| format!("Failed to open {}: {}", name, e))?;
+        // The store keeps its keys unordered, sorted so the fingerprint is stable
+        let mut entries = store.entries();
+        if entries.is_empty() {
+            continue;
+        }
+        entries.sort_by(|a, b| a.0.cmp(&b.0));
+        let entries: Map<String, Value> = entries.into_iter().collect();
+        bundle.add_json(&format!("stores/{}", name), &entries)?;
+        stores.push(name.to_string());
+    }
+
+    let dalamud_root = Path::new(dalamud_path);
+    let mut dalamud_files = 0;
+    let config = dalamud_root.join(DALAMUD_CONFIG_FILE);
+    if config.is_file() {
+        bundle.add_file(&format!("dalamud/{}", DALAMUD_CONFIG_FILE), &config)?;
+        dalamud_files += 1;
+    }
+    let plugin_configs = dalamud_root.join(PLUGIN_CONFIG_FOLDER);
+    for entry in WalkDir::new(&plugin_configs)
+        .sort_by_file_name()
+        .into_iter()
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type().is_file())
+    {
+        let Ok(relative) = entry.path().strip_prefix(dalamud_root) else {
+            continue;
+        };
+        let name = format!("dalamud/{}", relative.to_string_lossy().replace('\\', "/"));
+        bundle.add_file(&name, entry.path())?;
+        dalamud_files += 1;
+    }
+
+    let plugins = installed_plugins(&dalamud_root.join(PLUGIN_FOLDER));
+    bundle.add_json("plugins.json", &plugins)?;
+
+    let (bundle, fingerprint) = bundle.finish()?;
+    let export = DataExport {
+        path: String::new(),
+        bytes: bundle.len() as u64,
+        fingerprint,
+        stores,
+        dalamud_files,
+        plugins,
+    };
+    Ok((bundle, export))
+}
+
+pub(crate) fn apply_bundle(
+    app: &AppHandle,
+    dalamud_path: &str,
+    bundle: &[u8],
+) -> Result<DataImport, String> {
+    // Dalamud reads its and the plugins' configuration while the game runs
+    if game_monitor::get_game_status().running {
+        return Err("Close the game before importing launcher data".to_string());
+    }
+    let mut zip = ZipArchive::new(Cursor::new(bundle))
+        .map_err(|e| format!("Failed to read launcher data: {}", e))?;
+    let manifest: BundleManifest = read_json(&mut zip, MANIFEST_FILE)?;
+
+    let mut stores = Vec::new();
+    for name in STORES {
+        let path = format!("stores/{}", name);
+        if zip.by_name(&path).is_err() {
+            continue;
+        }
+        let entries: Map<String, Value> = read_json(&mut zip, &path)?;
+        let store = app
+            .store(name)
+            .map_err(|e| format!("Failed to open {}: {}", name, e))?;
+        store.clear();
+        for (key, value) in entries {
+            store.set(key, value);
+        }
+        store
+            .save()
+            .map_err(|e| format!("Failed to save {}: {}", name, e))?;
+        stores.push(name.to_string());
+    }
+
+    let dalamud_root = Path::new(dalamud_path);
+    let mut dalamud_files = 0;
+    for i in 0..zip.len() {
+        let mut entry = zip
+            .by_index(i)
+            .map_err(|e| format!("Failed to read launcher data: {}", e))?;
+        let Some(relative) = entry.name().strip_prefix("dalamud/") else {
+            continue;
+        };
+        let relative = safe_relative_path(relative)?;
+        if relative != Path::new(DALAMUD_CONFIG_FILE) && !relative.starts_with(PLUGIN_CONFIG_FOLDER)
+        {
+            warn!(
+                "Skipping unexpected file in launcher data: {}",
+                entry.name()
+            );
+            continue;
+        }
+
+        let target = dalamud_root.join(&relative);
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+        }
+        let mut contents = Vec::new();
+        entry.read_to_end(&mut contents).map_err(|e| {
+            format!(
+                "Failed to read {} from launcher data: {}",
+                relative.display(),
+                e
+            )
+        })?;
+        fs::write(&target, contents)
+            .map_err(|e| format!("Failed to write {}: {}", target.display(), e))?;
+        dalamud_files += 1;
+    }
+
+    let plugins = read_json(&mut zip, "plugins.json").unwrap_or_default();
+    Ok(DataImport {
+        launcher_version: manifest.launcher_version,
+        created_at: manifest.created_at,
+        stores,
+        dalamud_files,
+        plugins,
+    })
+}
+
+fn installed_plugins(plugin_directory: &Path) -> Vec<InstalledPlugin> {
+    let Ok(entries) = fs::read_dir(plugin_directory) else {
+        return Vec::new();
+    };
+    let directory = plugin_directory.to_string_lossy();
+
+    let mut plugins: Vec<InstalledPlugin> = entries
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.path().is_dir())
+        .filter_map(|entry| {
+            let internal_name = entry.file_name().to_string_lossy().to_string();
+            let version = installed_version(&directory, &internal_name)?;
+            Some(InstalledPlugin {
+                internal_name,
+                version,
+            })
+        })
+        .collect();
+    plugins.sort_by(|a, b| a.internal_name.cmp(&b.internal_name));
+    plugins
+}
+
+// MAGIC, format version, salt, nonce, then the AES-256-GCM ciphertext of the zip
+pub(crate) fn encrypt(data: &[u8], password: &str) -> Result<Vec<u8>, String> {
+    if password.is_empty() {
+        return Err("A password is needed to encrypt the launcher data".to_string());
+    }
+    let mut salt = [0u8; SALT_LEN];
+    let mut nonce = [0u8; NONCE_LEN];
+    OsRng.fill_bytes(&mut salt);
+    OsRng.fill_bytes(&mut nonce);
+
+    let ciphertext = cipher(password, &salt)?
+        .encrypt(Nonce::from_slice(&nonce), data)
+        .map_err(|e| format!("Failed to encrypt launcher data: {}", e))?;
+
+    let mut output = Vec::with_capacity(MAGIC.len() + 1 + SALT_LEN + NONCE_LEN + ciphertext.len());
+    output.extend_from_slice(MAGIC);
+    output.push(FORMAT_VERSION);
+    output.extend_from_slice(&salt);
+    output.extend_from_slice(&nonce);
+    output.extend_from_slice(&ciphertext);
+    Ok(output)
+}
+
+pub(crate) fn decrypt(data: &[u8], password: &str) -> Result<Vec<u8>, String> {
+    let header = MAGIC.len() + 1 + SALT_LEN + NONCE_LEN;
+    if data.len() < header || !data.starts_with(MAGIC) {
+        return Err("Not a launcher data export".to_string());
+    }
+    let version = data[MAGIC.len()];
+    if version != FORMAT_VERSION {
+        return Err(format!(
+            "Launcher data format {} is not supported, update the launcher",
+            version
+        ));
+    }
+    let salt = &data[MAGIC.len() + 1..MAGIC.len() + 1 + SALT_LEN];
+    let nonce = &data[MAGIC.len() + 1 + SALT_LEN..header];
+
+    // GCM can't tell a wrong password from a damaged file
+    cipher(password, salt)?
+        .decrypt(Nonce::from_slice(nonce), &data[header..])
+        .map_err(|_| "Wrong password, or the launcher data is damaged".to_string())
+}
+
+fn cipher(password: &str, salt: &[u8]) -> Result<Aes256Gcm, String> {
+    let mut key = [0u8; 32];
+    pbkdf2::pbkdf2_hmac::<Sha256>(password.as_bytes(), salt, KDF_ROUNDS, &mut key);
+    Aes256Gcm::new_from_slice(&key).map_err(|e| format!("Failed to set up encryption: {}", e))
+}
+
+// The zip in memory, plus a hash of every file in it except the manifest
+struct BundleWriter {
+    zip: ZipWriter<Cursor<Vec<u8>>>,
+    fingerprint: Sha256,
+}
+
+impl BundleWriter {
+    fn new() -> Self {
+        Self {
+            zip: ZipWriter::new(Cursor::new(Vec::new())),
+            fingerprint: Sha256::new(),
+        }
+    }
+
+    fn add(&mut self, name: &str, contents: &[u8]) -> Result<(), String> {
+        if name != MANIFEST_FILE {
+            self.fingerprint.update(name.as_bytes());
+            self.fingerprint
+                .update((contents.len() as u64).to_le_bytes());
+            self.fingerprint.update(contents);
+        }
+        self.zip
+            .start_file(name, FileOptions::default())
+            .and_then(|_| self.zip.write_all(contents).map_err(Into::into))
+            .map_err(|e| format!("Failed to add {} to launcher data: {}", name, e))
+    }
+
+    fn add_json<T: Serialize>(&mut self, name: &str, value: &T) -> Result<(), String> {
+        let contents = serde_json::to_vec_pretty(value)
+            .map_err(|e| format!("Failed to serialize {}: {}", name, e))?;
+        self.add(name, &contents)
+    }
+
+    fn add_file(&mut self, name: &str, path: &Path) -> Result<(), String> {
+        let contents =
+            fs::read(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        self.add(name, &contents)
+    }
+
+    fn finish(mut self) -> Result<(Vec<u8>, String), String> {
+        let bundle = self
+            .zip
+            .finish()
+            .map_err(|e| format!("Failed to write launcher data: {}", e))?
+            .into_inner();
+        Ok((bundle, hex::encode(self.fingerprint.finalize())))
+    }
+}
+
+fn read_json<T: for<'de> Deserialize<'de>>(
+    zip: &mut ZipArchive<Cursor<&[u8]>>,
+    name: &str,
+) -> Result<T, String> {
+    let entry = zip
+        .by_name(name)
+        .map_err(|e| format!("Launcher data has no {}: {}", name, e))?;
+    serde_json::from_reader(entry).map_err(|e| format!("Failed to parse {}: {}", name, e))
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+}
@@ -0,0 +1,95 @@
+use futures::future::join_all;
+use serde::Serialize;
+use std::net::SocketAddr;
+use std::time::{Duration, Instant};
+use tokio::net::{lookup_host, TcpStream};
+
+use tracing::{debug, info};
+
+const LOBBY_PORT: u16 = 54994;
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(3);
+// Lowest of a few samples, a single connect can catch a hiccup
+const SAMPLES: usize = 3;
+
+// (datacenter, region, lobby host)
+const DATACENTERS: [(&str, &str, &str); 11] = [
+    ("Elemental", "JP", "neolobby01.ffxiv.com"),
+    ("Gaia", "JP", "neolobby02.ffxiv.com"),
+    ("Mana", "JP", "neolobby03.ffxiv.com"),
+    ("Aether", "NA", "neolobby04.ffxiv.com"),
+    ("Primal", "NA", "neolobby05.ffxiv.com"),
+    ("Chaos", "EU", "neolobby06.ffxiv.com"),
+    ("Light", "EU", "neolobby07.ffxiv.com"),
+    ("Crystal", "NA", "neolobby08.ffxiv.com"),
+    ("Materia", "OCE", "neolobby09.ffxiv.com"),
+    ("Meteor", "JP", "neolobby10.ffxiv.com"),
+    ("Dynamis", "NA", "neolobby11.ffxiv.com"),
+];
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DatacenterLatency {
+    pub name: String,
+    pub region: String,
+    pub host: String,
+    pub latency_ms: Option<u64>,
+    pub error: Option<String>,
+}
+
+// TCP connect time to every lobby server, fastest first and unreachable ones last
+#[tauri::command]
+pub async fn ping_datacenters() -> Vec<DatacenterLatency> {
+    let mut results = join_all(DATACENTERS.iter().map(|(name, region, host)| async move {
+        let (latency_ms, error) = match measure(host).await {
+            Ok(latency) => (Some(latency.as_millis() as u64), None),
+            Err(e) => (None, Some(e)),
+        };
+        DatacenterLatency {
+            name: name.to_string(),
+            region: region.to_string(),
+            host: host.to_string(),
+            latency_ms,
+            error,
+        }
+    }))
+    .await;
+
+    results.sort_by_key(|r| r.latency_ms.unwrap_or(u64::MAX));
+    info!(
+        "Datacenter latencies: {}",
+        results
+            .iter()
+            .map(|r| match r.latency_ms {
+                Some(ms) => format!("{} {}ms", r.name, ms),
+                None => format!("{} unreachable", r.name),
+            })
+            .collect::<Vec<_>>()
+            .join(", ")
+    );
+    results
+}
+
+async fn measure(host: &str) -> Result<Duration, String> {
+    // Resolve once up front so DNS time doesn't count towards the round trip
+    let address: SocketAddr = lookup_host((host, LOBBY_PORT))
+        .await
+        .map_err(|e| format!("Failed to resolve {}: {}", host, e))?
+        .next()
+        .ok_or_else(|| format!("No address found for {}", host))?;
+
+    let mut best: Option<Duration> = None;
+    let mut last_error = None;
+    for _ in 0..SAMPLES {
+        let start = Instant::now();
+        match tokio::time::timeout(CONNECT_TIMEOUT, TcpStream::connect(address)).await {
+            Ok(Ok(_)) => {
+                let elapsed = start.elapsed();
+                best = Some(best.map_or(elapsed, |b| b.min(elapsed)));
+            }
+            Ok(Err(e)) => last_error = Some(format!("Failed to connect: {}", e)),
+            Err(_) => last_error = Some("Timed out".to_string()),
+        }
+    }
+    debug!("{} ({}) best of {}: {:?}", host, address, SAMPLES, best);
+
+    best.ok_or_else(|| last_error.unwrap_or_default())
+}
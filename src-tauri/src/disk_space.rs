@@ -0,0 +1,56 @@
+use std::path::Path;
+
+use tracing::{debug, warn};
+
+const MIB: u64 = 1024 * 1024;
+const GIB: u64 = 1024 * MIB;
+
+// Fails early when the volume holding path can't fit what is about to be written, instead of
+// dying halfway through with an IO error. A volume that can't be queried doesn't block anything
+pub fn ensure_free_space(path: &Path, needed: u64, what: &str) -> Result<(), String> {
+    if needed == 0 {
+        return Ok(());
+    }
+    // The target folder may not exist yet, the closest one that does is on the same volume
+    let Some(existing) = path.ancestors().find(|p| p.exists()) else {
+        return Ok(());
+    };
+
+    let available = match fs2::available_space(existing) {
+        Ok(available) => available,
+        Err(e) => {
+            warn!(
+                "Failed to check free space on {}: {}",
+                existing.display(),
+                e
+            );
+            return Ok(());
+        }
+    };
+    debug!(
+        "{} needs {} on {}, {} available",
+        what,
+        format_size(needed),
+        existing.display(),
+        format_size(available)
+    );
+
+    if available < needed {
+        return Err(format!(
+            "Not enough disk space for {}: needs {} free on {}, have {}",
+            what,
+            format_size(needed),
+            existing.display(),
+            format_size(available)
+        ));
+    }
+    Ok(())
+}
+
+pub fn format_size(bytes: u64) -> String {
+    if bytes >= GIB {
+        format!("{:.1} GB", bytes as f64 / GIB as f64)
+    } else {
+        format!("{:.0} MB", (bytes as f64 / MIB as f64).ceil())
+    }
+}
@@ -0,0 +1,330 @@
+use flate2::read::GzDecoder;
+use reqwest::header::USER_AGENT;
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tauri::State;
+use tokio_util::sync::CancellationToken;
+
+use tracing::info;
+
+use crate::ffxiv::download_file;
+use crate::http::{HttpClient, REQUEST_TIMEOUT};
+use crate::platform::{WineConfig, WineRunnerKind};
+use crate::retry::RetryPolicy;
+
+const RELEASES_URL: &str = "https://api.github.com/repos/doitsujin/dxvk/releases";
+// Everything we manage lives in the prefix, so each profile's prefix tracks its own version
+const MANAGER_FOLDER: &str = "xivloader-dxvk";
+const STATE_FILE: &str = "state.json";
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DxvkRelease {
+    pub version: String,
+    pub published_at: String,
+    pub download_url: String,
+    pub size: u64,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DxvkStatus {
+    pub installed: Option<String>,
+    // What a rollback returns to, None means wine's own d3d
+    pub previous: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct GithubRelease {
+    tag_name: String,
+    published_at: String,
+    assets: Vec<GithubAsset>,
+}
+
+#[derive(Deserialize)]
+struct GithubAsset {
+    name: String,
+    browser_download_url: String,
+    size: u64,
+}
+
+#[tauri::command]
+pub async fn list_dxvk_releases(http: State<'_, HttpClient>) -> Result<Vec<DxvkRelease>, String> {
+    fetch_releases(&http.client()).await
+}
+
+#[tauri::command]
+pub fn get_dxvk_status(prefix: String) -> Result<DxvkStatus, String> {
+    Ok(load_state(Path::new(&prefix)))
+}
+
+#[tauri::command]
+pub async fn install_dxvk(
+    http: State<'_, HttpClient>,
+    prefix: String,
+    version: String,
+) -> Result<DxvkStatus, String> {
+    install(&http.client(), Path::new(&prefix), &version).await
+}
+
+#[tauri::command]
+pub async fn rollback_dxvk(
+    http: State<'_, HttpClient>,
+    prefix: String,
+) -> Result<DxvkStatus, String> {
+    let prefix = Path::new(&prefix);
+    let state = load_state(prefix);
+    if state.installed.is_none() {
+        return Err("DXVK is not installed in this prefix".to_string());
+    }
+
+    match state.previous {
+        Some(previous) => {
+            info!("Rolling DXVK back to {}", previous);
+            install(&http.client(), prefix, &previous).await
+        }
+        None => {
+            info!(
+                "Removing DXVK from {}, restoring wine's own libraries",
+                prefix.display()
+            );
+            restore_backup(prefix)?;
+            let state = DxvkStatus::default();
+            save_state(prefix, &state)?;
+            Ok(state)
+        }
+    }
+}
+
+// Called before a wine launch so the prefix matches the version chosen in the profile
+pub async fn ensure_profile_version(client: &Client, wine: &WineConfig) -> Result<(), String> {
+    // Proton brings its own DXVK
+    if wine.runner != WineRunnerKind::Wine || !wine.dxvk || wine.prefix.is_empty() {
+        return Ok(());
+    }
+    let Some(version) = wine.dxvk_version.as_deref().filter(|v| !v.is_empty()) else {
+        return Ok(());
+    };
+
+    let prefix = Path::new(&wine.prefix);
+    if load_state(prefix).installed.as_deref() == Some(version) {
+        return Ok(());
+    }
+    install(client, prefix, version).await.map(|_| ())
+}
+
+async fn fetch_releases(client: &Client) -> Result<Vec<DxvkRelease>, String> {
+    let releases: Vec<GithubRelease> = client
+        .get(RELEASES_URL)
+        // GitHub rejects API requests without a user agent
+        .header(USER_AGENT, "rust-xivloader")
+        .timeout(REQUEST_TIMEOUT)
+        .send()
+        .await
+        .map_err(|e| format!("Failed to list DXVK releases: {}", e))?
+        .json()
+        .await
+        .map_err(|e| format!("Failed to parse DXVK releases: {}", e))?;
+
+    Ok(releases
+        .into_iter()
+        .filter_map(|release| {
+            let asset = release.assets.into_iter().find(|asset| {
+                asset.name.starts_with("dxvk-")
+                    && !asset.name.contains("native")
+                    && asset.name.ends_with(".tar.gz")
+            })?;
+            Some(DxvkRelease {
+                version: release.tag_name.trim_start_matches('v').to_string(),
+                published_at: release.published_at,
+                download_url: asset.browser_download_url,
+                size: asset.size,
+            })
+        })
+        .collect())
+}
+
+async fn install(client: &Client, prefix: &Path, version: &str) -> Result<DxvkStatus, String> {
+    let system32 = prefix.join("drive_c/windows/system32");
+    if !system32.is_dir() {
+        return Err(format!(
+            "{} is not an initialized wine prefix",
+            prefix.display()
+        ));
+    }
+
+    let version_dir = manager_dir(prefix).join("versions").join(version);
+    if !version_dir.join("x64").is_dir() {
+        download_release(client, &version_dir, version).await?;
+    }
+
+    backup_originals(prefix, &version_dir)?;
+    copy_dlls(&version_dir.join("x64"), &system32)?;
+    let syswow64 = prefix.join("drive_c/windows/syswow64");
+    if syswow64.is_dir() {
+        copy_dlls(&version_dir.join("x32"), &syswow64)?;
+    }
+
+    let old = load_state(prefix);
+    let state = DxvkStatus {
+        installed: Some(version.to_string()),
+        previous: old.installed.filter(|v| v != version).or(old.previous),
+    };
+    save_state(prefix, &state)?;
+    info!("Installed DXVK {} into {}", version, prefix.display());
+    Ok(state)
+}
+
+async fn download_release(
+    client: &Client,
+    version_dir: &Path,
+    version: &str,
+) -> Result<(), String> {
+    let release = fetch_releases(client)
+        .await?
+        .into_iter()
+        .find(|release| release.version == version)
+        .ok_or_else(|| format!("DXVK {} was not found", version))?;
+
+    fs::create_dir_all(version_dir)
+        .map_err(|e| format!("Failed to create DXVK directory: {}", e))?;
+    let archive_path = version_dir.join("dxvk.tar.gz");
+    let archive = archive_path.to_string_lossy().to_string();
+
+    info!("Downloading DXVK {}", version);
+    download_file(
+        client,
+        &release.download_url,
+        &archive,
+        None,
+        &RetryPolicy::default(),
+        &CancellationToken::new(),
+    )
+    .await?;
+
+    let result = extract_dlls(&archive_path, version_dir);
+    let _ = fs::remove_file(&archive_path);
+    if result.is_err() {
+        let _ = fs::remove_dir_all(version_dir);
+    }
+    result
+}
+
+// Pulls only <top>/x64/*.dll and <top>/x32/*.dll out of the release archive
+fn extract_dlls(archive_path: &Path, version_dir: &Path) -> Result<(), String> {
+    let file =
+        fs::File::open(archive_path).map_err(|e| format!("Failed to open DXVK archive: {}", e))?;
+    let mut archive = tar::Archive::new(GzDecoder::new(file));
+
+    let entries = archive
+        .entries()
+        .map_err(|e| format!("Failed to read DXVK archive: {}", e))?;
+    for entry in entries {
+        let mut entry = entry.map_err(|e| format!("Failed to read DXVK archive: {}", e))?;
+        let path = entry
+            .path()
+            .map_err(|e| format!("Invalid path in DXVK archive: {}", e))?
+            .into_owned();
+
+        let parts: Vec<_> = path.iter().collect();
+        let [_, arch, name] = parts.as_slice() else {
+            continue;
+        };
+        let is_dll = Path::new(name).extension().is_some_and(|ext| ext == "dll");
+        if !is_dll || (*arch != "x64" && *arch != "x32") {
+            continue;
+        }
+
+        let target_dir = version_dir.join(arch);
+        fs::create_dir_all(&target_dir)
+            .map_err(|e| format!("Failed to create DXVK directory: {}", e))?;
+        entry
+            .unpack(target_dir.join(name))
+            .map_err(|e| format!("Failed to extract {}: {}", path.display(), e))?;
+    }
+
+    if !version_dir.join("x64").is_dir() {
+        return Err("DXVK archive has no x64 libraries".to_string());
+    }
+    Ok(())
+}
+
+fn copy_dlls(from: &Path, to: &Path) -> Result<(), String> {
+    for dll in dll_names(from)? {
+        fs::copy(from.join(&dll), to.join(&dll))
+            .map_err(|e| format!("Failed to install {}: {}", dll, e))?;
+    }
+    Ok(())
+}
+
+fn dll_names(dir: &Path) -> Result<Vec<String>, String> {
+    let entries =
+        fs::read_dir(dir).map_err(|e| format!("Failed to read {}: {}", dir.display(), e))?;
+    Ok(entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.file_name().to_string_lossy().to_string())
+        .filter(|name| name.ends_with(".dll"))
+        .collect())
+}
+
+// Keeps wine's own d3d DLLs the first time DXVK goes in, so removing it is possible later
+fn backup_originals(prefix: &Path, version_dir: &Path) -> Result<(), String> {
+    let backup = manager_dir(prefix).join("backup");
+    if backup.exists() {
+        return Ok(());
+    }
+
+    for (arch, folder) in [("x64", "system32"), ("x32", "syswow64")] {
+        let source = prefix.join("drive_c/windows").join(folder);
+        let reference = version_dir.join(arch);
+        if !source.is_dir() || !reference.is_dir() {
+            continue;
+        }
+
+        let target = backup.join(folder);
+        fs::create_dir_all(&target).map_err(|e| format!("Failed to create DXVK backup: {}", e))?;
+        for dll in dll_names(&reference)? {
+            if source.join(&dll).is_file() {
+                fs::copy(source.join(&dll), target.join(&dll))
+                    .map_err(|e| format!("Failed to back up {}: {}", dll, e))?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn restore_backup(prefix: &Path) -> Result<(), String> {
+    let backup = manager_dir(prefix).join("backup");
+    if !backup.is_dir() {
+        return Err("No backup of the original wine libraries was found".to_string());
+    }
+
+    for folder in ["system32", "syswow64"] {
+        let source = backup.join(folder);
+        if !source.is_dir() {
+            continue;
+        }
+        copy_dlls(&source, &prefix.join("drive_c/windows").join(folder))?;
+    }
+    Ok(())
+}
+
+fn manager_dir(prefix: &Path) -> PathBuf {
+    prefix.join(MANAGER_FOLDER)
+}
+
+fn load_state(prefix: &Path) -> DxvkStatus {
+    fs::read_to_string(manager_dir(prefix).join(STATE_FILE))
+        .ok()
+        .and_then(|contents| serde_json::from_str(&contents).ok())
+        .unwrap_or_default()
+}
+
+fn save_state(prefix: &Path, state: &DxvkStatus) -> Result<(), String> {
+    let dir = manager_dir(prefix);
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    let contents = serde_json::to_string_pretty(state)
+        .map_err(|e| format!("Failed to serialize DXVK state: {}", e))?;
+    fs::write(dir.join(STATE_FILE), contents)
+        .map_err(|e| format!("Failed to save DXVK state: {}", e))
+}
@@ -0,0 +1,25 @@
+use blowfish::cipher::{generic_array::GenericArray, BlockEncrypt, KeyInit};
+use blowfish::BlowfishLE;
+
+// SE's blowfish reads blocks as little endian dwords and zero pads the tail
+pub fn blowfish_encrypt(key: &[u8], data: &[u8]) -> Vec<u8> {
+    // Callers only pass fixed-size ASCII keys, which are always valid blowfish key lengths
+    let cipher = BlowfishLE::new_from_slice(key).expect("invalid blowfish key length");
+
+    let mut buffer = data.to_vec();
+    let padded_len = buffer.len().div_ceil(8) * 8;
+    buffer.resize(padded_len, 0);
+
+    for block in buffer.chunks_exact_mut(8) {
+        cipher.encrypt_block(GenericArray::from_mut_slice(block));
+    }
+
+    buffer
+}
+
+pub fn to_mangled_se_base64(data: &[u8]) -> String {
+    base64::encode(data)
+        .replace('+', "-")
+        .replace('/', "_")
+        .replace('=', "*")
+}
@@ -0,0 +1,59 @@
+use serde::{Deserialize, Serialize};
+
+const RETAIL_OAUTH: &str = "https://ffxiv-login.square-enix.com/oauth/ffxivarr/login";
+const RETAIL_FRONTIER: &str = "https://frontier.ffxiv.com";
+// The FINAL FANTASY XIV user agreement, for when the login page doesn't link its own
+pub const TERMS_OF_SERVICE_URL: &str =
+    "https://support.na.square-enix.com/rule.php?id=5382&tag=users_en";
+
+// Servers the launcher talks to, retail unless a profile points somewhere else
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Endpoints {
+    // Base of the Square Enix account login, top and login.send live under it
+    pub oauth: String,
+    // Sent with the login post, empty uses the top page like the official launcher
+    pub referer: String,
+    // News, banners and the gate status
+    pub frontier: String,
+}
+
+impl Default for Endpoints {
+    fn default() -> Self {
+        Self {
+            oauth: RETAIL_OAUTH.to_string(),
+            referer: String::new(),
+            frontier: RETAIL_FRONTIER.to_string(),
+        }
+    }
+}
+
+impl Endpoints {
+    pub fn oauth_top(&self) -> String {
+        format!("{}/top", base(&self.oauth, RETAIL_OAUTH))
+    }
+
+    pub fn oauth_login(&self) -> String {
+        format!("{}/login.send", base(&self.oauth, RETAIL_OAUTH))
+    }
+
+    pub fn login_referer(&self, top_url: &str) -> String {
+        match self.referer.trim() {
+            "" => top_url.to_string(),
+            referer => referer.to_string(),
+        }
+    }
+
+    // path starts with a slash, e.g. "/worldStatus/gate_status.json"
+    pub fn frontier(&self, path: &str) -> String {
+        format!("{}{}", base(&self.frontier, RETAIL_FRONTIER), path)
+    }
+}
+
+// Profiles written by hand tend to carry a trailing slash or leave the field blank
+fn base<'a>(value: &'a str, default: &'a str) -> &'a str {
+    match value.trim().trim_end_matches('/') {
+        "" => default,
+        value => value,
+    }
+}
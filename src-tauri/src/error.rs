@@ -0,0 +1,87 @@
+use serde::ser::SerializeStruct;
+use serde::{Serialize, Serializer};
+use thiserror::Error;
+
+// Why a launch or update check failed. The frontend gets { code, message } so it can
+// react to the kind of failure without matching on the text
+#[derive(Debug, Error)]
+pub enum LauncherError {
+    // Wrong credentials or OTP, missing stored password, Steam account problems
+    #[error("{0}")]
+    Auth(String),
+    // A server could not be reached or answered with something unexpected
+    #[error("{0}")]
+    Network(String),
+    // Square Enix updated its terms of service and the account hasn't accepted them yet
+    #[error("{message}")]
+    TermsOfService { message: String, url: String },
+    // The gate is closed for maintenance
+    #[error("{0}")]
+    Maintenance(String),
+    // The install has to be patched, or its version files can't be read
+    #[error("{0}")]
+    Patch(String),
+    #[error("{0}")]
+    Dalamud(String),
+    // Starting the game or preparing what it runs in failed
+    #[error("{0}")]
+    Process(String),
+    // The profile has settings that can't work
+    #[error("{0}")]
+    Config(String),
+    #[error("Launch was cancelled")]
+    Cancelled,
+}
+
+impl LauncherError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            LauncherError::Auth(_) => "auth",
+            LauncherError::Network(_) => "network",
+            LauncherError::TermsOfService { .. } => "terms",
+            LauncherError::Maintenance(_) => "maintenance",
+            LauncherError::Patch(_) => "patch",
+            LauncherError::Dalamud(_) => "dalamud",
+            LauncherError::Process(_) => "process",
+            LauncherError::Config(_) => "config",
+            LauncherError::Cancelled => "cancelled",
+        }
+    }
+
+    // Puts context in front of the message without changing the kind
+    pub fn context(self, context: &str) -> Self {
+        let wrap = |message: String| format!("{}: {}", context, message);
+        match self {
+            LauncherError::Auth(m) => LauncherError::Auth(wrap(m)),
+            LauncherError::Network(m) => LauncherError::Network(wrap(m)),
+            LauncherError::TermsOfService { message, url } => LauncherError::TermsOfService {
+                message: wrap(message),
+                url,
+            },
+            LauncherError::Maintenance(m) => LauncherError::Maintenance(wrap(m)),
+            LauncherError::Patch(m) => LauncherError::Patch(wrap(m)),
+            LauncherError::Dalamud(m) => LauncherError::Dalamud(wrap(m)),
+            LauncherError::Process(m) => LauncherError::Process(wrap(m)),
+            LauncherError::Config(m) => LauncherError::Config(wrap(m)),
+            LauncherError::Cancelled => LauncherError::Cancelled,
+        }
+    }
+}
+
+impl Serialize for LauncherError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        // Terms of service errors also carry the page to accept them on
+        let url = match self {
+            LauncherError::TermsOfService { url, .. } => Some(url),
+            _ => None,
+        };
+        let mut state =
+            serializer.serialize_struct("LauncherError", if url.is_some() { 3 } else { 2 })?;
+        state.serialize_field("code", self.code())?;
+        state.serialize_field("message", &self.to_string())?;
+        if let Some(url) = url {
+            state.serialize_field("url", url)?;
+        }
+        state.end()
+    }
+}
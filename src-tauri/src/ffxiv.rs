@@ -1,73 +1,154 @@
-use reqwest::header::{HeaderMap, HeaderValue, CONTENT_TYPE, REFERER, USER_AGENT};
-use reqwest::Client;
+use reqwest::header::{CONTENT_TYPE, ETAG, IF_NONE_MATCH, REFERER, USER_AGENT};
+use reqwest::{Client, StatusCode};
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 use sha1::{Digest, Sha1}; // square enix login system requires sha1
 // after all square enix is small indie company
+use sha2::Sha256;
 use std::collections::HashMap;
 use std::env;
-use std::ffi::OsString;
 use std::fs;
-use std::io::{Error as IoError, Read};
-use std::iter::once;
-use std::os::windows::ffi::OsStrExt;
+use std::io::{Error as IoError, Read, Write};
 use std::path::Path;
-use std::process::{Child, Command, Stdio};
-use std::ptr::{self, null_mut};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
 use std::time::Duration;
 use std::time::Instant;
 use std::time::{SystemTime, UNIX_EPOCH};
 
 use tracing::{debug, error, info, warn};
 
+use crate::archive::extract_zip;
+use crate::argument_builder::{expand_additional_arguments, ArgumentBuilder, TemplateVars};
+use crate::benchmark::{BenchmarkConfig, ProfileType};
+use crate::client_check;
+use crate::companions::{self, ActConfig, Companion, CompanionTiming, ExternalTool};
+use crate::credentials;
+use crate::dalamud_cleanup;
+use crate::disk_space;
+use crate::endpoints::{Endpoints, TERMS_OF_SERVICE_URL};
+use crate::error::LauncherError;
+use crate::game_detection;
+use crate::game_monitor::{self, LauncherBehavior};
+use crate::http::{HttpClient, NetworkSettings, LOGIN_TIMEOUT, REQUEST_TIMEOUT};
+use crate::installs;
+use crate::launch_pipeline::LaunchPipeline;
+use crate::launch_progress::{DownloadTracker, LaunchProgress, LaunchStage, OtpRequiredEvent};
+use crate::mods;
+use crate::news_cache::{self, CachedResponse};
+use crate::otp_listener;
+use crate::patching::downloader::{self, DownloadRequest, PatchDownloader};
+use crate::patching::install;
+use crate::patching::version_check::{
+    self, ExpansionVersion, GameVersionCheck, PatchEntry, UpdateCheckResult,
+};
+use crate::platform::{GamescopeConfig, WineConfig};
+use crate::plugins::compare_versions;
+use crate::redact::Secret;
+use crate::reshade::ReShadeConfig;
+use crate::retry::{self, RetryPolicy};
+use crate::steam::{self, SteamTicket};
+use crate::telemetry::TelemetryConfig;
+use crate::throttle;
+use crate::tspack;
+use tauri::{AppHandle, Emitter, Manager, State};
+use tauri_plugin_opener::OpenerExt;
+use tokio_util::sync::CancellationToken;
+use walkdir::WalkDir;
+
 #[cfg(windows)]
-use std::os::windows::io::{FromRawHandle, RawHandle};
-#[cfg(windows)]
-use std::os::windows::prelude::*;
-#[cfg(windows)]
-use std::os::windows::process::CommandExt;
-#[cfg(windows)]
-use winapi::ctypes::c_void;
-#[cfg(windows)]
-use winapi::shared::minwindef::{BOOL, DWORD, FALSE, TRUE};
-#[cfg(windows)]
-use winapi::um::handleapi::CloseHandle;
+use crate::native_injection::{self, BootStartInfo};
 #[cfg(windows)]
-use winapi::um::minwinbase::SECURITY_ATTRIBUTES;
+use crate::process::windows::SuspendedProcess;
 #[cfg(windows)]
-use winapi::um::processthreadsapi::{
-    CreateProcessW, GetProcessId, ResumeThread, PROCESS_INFORMATION, STARTUPINFOW,
-};
+use crate::tspack::TroubleshootingInfo;
 #[cfg(windows)]
-use winapi::um::securitybaseapi::{InitializeSecurityDescriptor, SetSecurityDescriptorDacl};
+use std::ffi::OsString;
 #[cfg(windows)]
-use winapi::um::winbase::CREATE_SUSPENDED;
+use std::iter::once;
 #[cfg(windows)]
-use winapi::um::winnt::{
-    HANDLE, HANDLE as WINAPI_HANDLE, PROCESS_ALL_ACCESS, SECURITY_DESCRIPTOR,
-    SECURITY_DESCRIPTOR_REVISION,
-};
+use std::process::{Command, Stdio};
 
-#[derive(Debug)]
+// Zero for whatever didn't run, e.g. the login when a cached SID was used
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
 pub struct GameLaunchMetrics {
-    login_time_ms: u64,
-    sid_fetch_time_ms: u64,
-    game_start_time_ms: u64,
+    // Duration of the oauth login that produced the SID
+    pub login_time_ms: u64,
+    // Time from the start of the launch until the SID was available
+    pub sid_fetch_time_ms: u64,
+    // Update check, download and integrity check of Dalamud
+    pub dalamud_setup_time_ms: u64,
+    // Process creation, or the injector run when Dalamud is enabled
+    pub game_start_time_ms: u64,
+    // Rewriting the entrypoint, or the injector run
+    pub injection_time_ms: u64,
+    // From the start of the launch until the game was handed to the monitor
+    pub total_time_ms: u64,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize)]
+pub struct LaunchResult {
+    pub pid: Option<u32>,
+    pub used_dalamud: bool,
+    pub dalamud_version: Option<String>,
+    pub metrics: GameLaunchMetrics,
+    pub warnings: Vec<String>,
+}
+
+#[derive(Debug)]
+pub(crate) struct DalamudLaunch {
+    pub(crate) version: String,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DalamudGameVersionPolicy {
+    // Launch without Dalamud, like XIVLauncher does after a patch
+    #[default]
+    Vanilla,
+    // Inject anyway, plugins may crash the game
+    Warn,
+    // Fail the launch
+    Block,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum InjectionFailurePolicy {
+    // Fail the launch, it can be resumed once the problem is fixed
+    #[default]
+    Abort,
+    // Start the game without Dalamud on the session id already obtained
+    Vanilla,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LaunchConfig {
     pub game_path: String,
+    // Name of a saved game install, its path and settings replace the ones below
+    #[serde(default)]
+    pub install: Option<String>,
+    // Benchmark profiles go through launch_benchmark and only use the benchmark settings,
+    // language and the Wine settings
+    #[serde(default)]
+    pub profile_type: ProfileType,
+    #[serde(default)]
+    pub benchmark: BenchmarkConfig,
     pub username: String,
-    pub password: String,
-    pub otp: Option<String>,
+    // Left empty when the password lives in the OS credential store
+    #[serde(default)]
+    pub password: Secret,
+    pub otp: Option<Secret>,
     #[serde(default = "default_dx11")]
     pub dx11: bool,
     #[serde(default = "default_language")]
     pub language: u32,
-    #[serde(default = "default_region")]
-    pub region: u32,
-    #[serde(default = "default_expansion_level")]
-    pub expansion_level: u32,
+    // Unset uses the defaults below, see region() and expansion_level()
+    #[serde(default)]
+    pub region: Option<u32>,
+    #[serde(default)]
+    pub expansion_level: Option<u32>,
     #[serde(default)]
     pub is_steam: bool,
     #[serde(default = "default_dpi_awareness")]
@@ -78,8 +159,88 @@ pub struct LaunchConfig {
     pub enable_dalamud: bool,
     #[serde(default)]
     pub dalamud_path: String,
+    // Only waited out when the game window can't be detected, e.g. under Wine
     #[serde(default = "default_injection_delay")]
     pub injection_delay: u64,
+    // Rewrite the entrypoint through Dalamud.Boot from the launcher instead of running
+    // Dalamud.Injector.exe
+    #[serde(default)]
+    pub native_injection: bool,
+    #[serde(default = "default_encrypt_arguments")]
+    pub encrypt_arguments: bool,
+    // Wait for the OTP on the XL-compatible listener when none was entered up front
+    #[serde(default)]
+    pub otp_listener: bool,
+    // Reuse the last session id for this account on relaunches instead of logging in again
+    #[serde(default)]
+    pub uid_cache: bool,
+    // Overrides for mirrors or self-hosted Dalamud distribution servers
+    #[serde(default)]
+    pub dalamud_version_info_url: Option<String>,
+    #[serde(default)]
+    pub dalamud_asset_meta_url: Option<String>,
+    #[serde(default)]
+    pub dalamud_download_base: Option<String>,
+    // Skip the Dalamud version and asset requests while the last check is this recent
+    #[serde(default)]
+    pub fast_launch: bool,
+    #[serde(default = "default_fast_launch_ttl")]
+    pub fast_launch_ttl_minutes: u64,
+    // What to do while Dalamud hasn't been updated for the installed game version yet
+    #[serde(default)]
+    pub dalamud_game_version_policy: DalamudGameVersionPolicy,
+    // Loads Dalamud without any plugins, to get out of a plugin crashing the game
+    #[serde(default)]
+    pub dalamud_safe_mode: bool,
+    #[serde(default)]
+    pub injection_failure_policy: InjectionFailurePolicy,
+    #[serde(default)]
+    pub launcher_behavior: LauncherBehavior,
+    // Relaunch once with a fresh SID if the game crashes soon after starting
+    #[serde(default)]
+    pub relaunch_on_crash: bool,
+    #[serde(default = "default_crash_relaunch_window")]
+    pub crash_relaunch_window_minutes: u64,
+    // Zip the FFXIV_CHR folders before every launch
+    #[serde(default)]
+    pub backup_character_data: bool,
+    // The game's settings folder, empty uses the one under Documents
+    #[serde(default)]
+    pub game_user_dir: String,
+    // Set on the relaunch after an expired session id, so that is only retried once
+    #[serde(skip)]
+    pub sid_retried: bool,
+    // Runner used to start the game on Linux
+    #[serde(default)]
+    pub wine: WineConfig,
+    #[serde(default)]
+    pub gamescope: GamescopeConfig,
+    // Run through gamemoderun on Linux, None turns it on for the Steam Deck
+    #[serde(default)]
+    pub gamemode: Option<bool>,
+    #[serde(default)]
+    pub act: ActConfig,
+    // Started and stopped around the game like ACT, in the order they are listed
+    #[serde(default)]
+    pub external_tools: Vec<ExternalTool>,
+    #[serde(default)]
+    pub reshade: ReShadeConfig,
+    // Handed to Penumbra on its first start, when it has no mod directory yet
+    #[serde(default)]
+    pub penumbra_mod_directory: String,
+    // Backoff for login, Dalamud and asset requests that hit a server error or a dropped connection
+    #[serde(default)]
+    pub retry: RetryPolicy,
+    #[serde(default)]
+    pub network: NetworkSettings,
+    // Anonymous launch reports, off by default
+    #[serde(default)]
+    pub telemetry: TelemetryConfig,
+    // Serves the patch server's paths, e.g. a LAN cache. Empty downloads from Square Enix
+    #[serde(default)]
+    pub patch_mirror: String,
+    #[serde(default)]
+    pub endpoints: Endpoints,
 }
 
 fn default_dx11() -> bool {
@@ -88,259 +249,539 @@ fn default_dx11() -> bool {
 fn default_language() -> u32 {
     1
 }
-fn default_region() -> u32 {
-    3
-}
-fn default_expansion_level() -> u32 {
-    4
-}
 fn default_dpi_awareness() -> String {
     "Aware".to_string()
 }
 fn default_injection_delay() -> u64 {
     5000
 }
+fn default_encrypt_arguments() -> bool {
+    true
+}
+fn default_crash_relaunch_window() -> u64 {
+    5
+}
+fn default_fast_launch_ttl() -> u64 {
+    60
+}
 
-#[derive(Debug)]
-struct ProcessHandles {
-    pid: u32,
-    process_handle: WINAPI_HANDLE,
-    thread_handle: WINAPI_HANDLE,
+impl LaunchConfig {
+    // 3 unless the profile overrides it, the value launches always sent
+    pub fn region(&self) -> u32 {
+        self.region.unwrap_or(3)
+    }
+
+    pub fn expansion_level(&self) -> u32 {
+        self.expansion_level
+            .unwrap_or_else(|| game_detection::detect_expansion_level(&self.game_path))
+    }
 }
 
-impl Drop for ProcessHandles {
-    fn drop(&mut self) {
-        unsafe {
-            CloseHandle(self.thread_handle);
-            CloseHandle(self.process_handle);
-        }
+// __COMPAT_LAYER shims the game inherits, picked from the dpi_awareness setting
+pub(crate) fn compat_layer(dpi_awareness: &str) -> Result<&'static str, String> {
+    match dpi_awareness.to_ascii_lowercase().as_str() {
+        "aware" => Ok("RunAsInvoker HighDPIAware"),
+        "unaware" => Ok("RunAsInvoker DPIUnaware"),
+        // No shim forces per-monitor awareness from outside, the client's own manifest handles it
+        "permonitorv2" => Ok("RunAsInvoker"),
+        other => Err(format!("Unknown DPI awareness mode: {}", other)),
     }
 }
 
+// Our own environment with __COMPAT_LAYER and the extra variables replaced
 #[cfg(windows)]
-fn create_suspended_game_process(game_path: &str, args: &str) -> Result<u32, String> {
-    unsafe {
-        let game_path_wide: Vec<u16> = OsString::from(game_path)
-            .encode_wide()
-            .chain(once(0))
-            .collect();
-        let args_wide: Vec<u16> = OsString::from(args).encode_wide().chain(once(0)).collect();
-
-        let mut startup_info: STARTUPINFOW = std::mem::zeroed();
-        startup_info.cb = std::mem::size_of::<STARTUPINFOW>() as u32;
-
-        let mut process_info: PROCESS_INFORMATION = std::mem::zeroed();
-        let mut security_attributes: SECURITY_ATTRIBUTES = std::mem::zeroed();
-        security_attributes.nLength = std::mem::size_of::<SECURITY_ATTRIBUTES>() as u32;
-        security_attributes.bInheritHandle = TRUE;
-
-        let mut security_descriptor: SECURITY_DESCRIPTOR = std::mem::zeroed();
-        let security_descriptor_ptr = &mut security_descriptor as *mut _ as *mut c_void;
-
-        if InitializeSecurityDescriptor(security_descriptor_ptr, SECURITY_DESCRIPTOR_REVISION) == 0
-        {
-            return Err(format!(
-                "Failed to initialize security descriptor: {}",
-                IoError::last_os_error()
-            ));
-        }
-
-        // Set a NULL DACL to allow access to everyone
-        if SetSecurityDescriptorDacl(security_descriptor_ptr, TRUE, null_mut(), FALSE) == 0 {
-            return Err(format!(
-                "Failed to set security descriptor DACL: {}",
-                IoError::last_os_error()
-            ));
-        }
-
-        security_attributes.lpSecurityDescriptor = security_descriptor_ptr;
-
-        let result = CreateProcessW(
-            game_path_wide.as_ptr(),
-            args_wide.as_ptr() as *mut _,
-            &mut security_attributes,
-            &mut security_attributes,
-            TRUE,
-            CREATE_SUSPENDED,
-            null_mut(),
-            null_mut(),
-            &mut startup_info,
-            &mut process_info,
-        );
+fn game_environment(compat_layer: &str, extra: &[(&str, &str)]) -> Vec<(OsString, OsString)> {
+    let overrides: Vec<(&str, &str)> = once(("__COMPAT_LAYER", compat_layer))
+        .chain(extra.iter().copied())
+        .collect();
+    env::vars_os()
+        .filter(|(key, _)| {
+            !overrides
+                .iter()
+                .any(|(name, _)| key.eq_ignore_ascii_case(name))
+        })
+        .chain(
+            overrides
+                .iter()
+                .map(|(key, value)| (OsString::from(key), OsString::from(value))),
+        )
+        .collect()
+}
 
-        if result == 0 {
-            return Err(format!(
-                "Failed to create process: {}",
-                IoError::last_os_error()
-            ));
-        }
+// The caller resumes the process once whatever has to happen before the game runs is done
+#[cfg(windows)]
+pub(crate) fn create_suspended_game_process(
+    game_path: &str,
+    args: &str,
+    compat_layer: &str,
+    environment: &[(&str, &str)],
+) -> Result<SuspendedProcess, String> {
+    let environment = game_environment(compat_layer, environment);
+    SuspendedProcess::spawn(game_path, args, &environment).map_err(|e| e.to_string())
+}
+
+// Id and token of the launch currently in flight, cancelled by cancel_launch
+static CURRENT_LAUNCH: Mutex<Option<(u64, CancellationToken)>> = Mutex::new(None);
+// The last launch that failed part way, kept for resume_launch
+static FAILED_LAUNCH: Mutex<Option<LaunchPipeline>> = Mutex::new(None);
+static NEXT_LAUNCH_ID: AtomicU64 = AtomicU64::new(0);
+
+// Takes the launch slot, a launch still in flight is cancelled
+pub(crate) fn begin_launch() -> (u64, CancellationToken) {
+    let launch_id = NEXT_LAUNCH_ID.fetch_add(1, Ordering::Relaxed);
+    let cancel = CancellationToken::new();
+    let previous = CURRENT_LAUNCH
+        .lock()
+        .unwrap()
+        .replace((launch_id, cancel.clone()));
+    if let Some((_, previous)) = previous {
+        warn!("A launch was already in progress, cancelling it");
+        previous.cancel();
+    }
+    (launch_id, cancel)
+}
 
-        // Get the PID before we clean up handles
-        let pid = GetProcessId(process_info.hProcess);
+// Only clears the slot if a newer launch hasn't replaced it
+pub(crate) fn end_launch(launch_id: u64) {
+    let mut current = CURRENT_LAUNCH.lock().unwrap();
+    if matches!(current.as_ref(), Some((id, _)) if *id == launch_id) {
+        current.take();
+    }
+}
 
-        // Resume the thread
-        if ResumeThread(process_info.hThread) == u32::MAX {
-            let err = format!("Failed to resume process: {}", IoError::last_os_error());
-            CloseHandle(process_info.hThread);
-            CloseHandle(process_info.hProcess);
-            return Err(err);
+#[tauri::command]
+pub fn cancel_launch() -> Result<(), String> {
+    match CURRENT_LAUNCH.lock().unwrap().take() {
+        Some((_, token)) => {
+            info!("Cancelling launch");
+            token.cancel();
+            Ok(())
         }
+        None => Err("No launch is in progress".to_string()),
+    }
+}
 
-        // Clean up handles
-        CloseHandle(process_info.hThread);
-        CloseHandle(process_info.hProcess);
+pub(crate) async fn with_cancel<T>(
+    cancel: &CancellationToken,
+    future: impl std::future::Future<Output = T>,
+) -> Result<T, LauncherError> {
+    tokio::select! {
+        _ = cancel.cancelled() => Err(LauncherError::Cancelled),
+        result = future => Ok(result),
+    }
+}
 
-        Ok(pid)
+fn ensure_game_profile(config: &LaunchConfig) -> Result<(), LauncherError> {
+    if config.profile_type == ProfileType::Benchmark {
+        return Err(LauncherError::Config(
+            "This is a benchmark profile, start it as a benchmark instead".to_string(),
+        ));
     }
+    Ok(())
 }
 
 #[tauri::command]
-pub async fn launch_game(config: LaunchConfig) -> Result<String, String> {
-    let total_start_time = Instant::now();
-    let mut metrics = Vec::new();
-    info!("Starting game launch process with config: {:?}", config);
+pub async fn launch_game(
+    app: AppHandle,
+    mut config: LaunchConfig,
+) -> Result<LaunchResult, LauncherError> {
+    ensure_game_profile(&config)?;
+    installs::apply(&app, &mut config).map_err(LauncherError::Config)?;
+
+    // Proxy settings live in the profile, the shared client follows whichever one launches
+    let client = {
+        let http = app.state::<HttpClient>();
+        http.configure(&config.network)
+            .map_err(LauncherError::Config)?;
+        http.client()
+    };
 
-    // Set up Dalamud first if enabled
-    if config.enable_dalamud {
-        info!("Dalamud is enabled, starting Dalamud setup");
-        let dalamud_start = Instant::now();
-        match setup_dalamud(&config).await {
-            Ok(_) => {
-                let dalamud_duration = dalamud_start.elapsed();
-                metrics.push(format!("Dalamud setup: {:.2?}", dalamud_duration));
-                info!(
-                    "Dalamud setup completed successfully in {:.2?}",
-                    dalamud_duration
-                );
-            }
-            Err(e) => {
-                error!("Dalamud setup failed: {}", e);
-                return Err(format!("Dalamud setup failed: {}", e));
-            }
-        }
+    // A new launch starts over, whatever the last failed one got done is dropped
+    FAILED_LAUNCH.lock().unwrap().take();
+    run_pipeline(LaunchPipeline::new(Some(app), client, config)).await
+}
+
+// Picks up the last failed launch at the step it stopped at, without downloading Dalamud
+// or logging in again if those already went through
+#[tauri::command]
+pub async fn resume_launch(otp: Option<String>) -> Result<LaunchResult, LauncherError> {
+    let mut pipeline =
+        FAILED_LAUNCH.lock().unwrap().take().ok_or_else(|| {
+            LauncherError::Config("There is no failed launch to resume".to_string())
+        })?;
+    if let Some(otp) = otp.filter(|otp| !otp.is_empty()) {
+        pipeline.set_otp(otp);
+    }
+    run_pipeline(pipeline).await
+}
+
+async fn run_pipeline(mut pipeline: LaunchPipeline) -> Result<LaunchResult, LauncherError> {
+    let (launch_id, cancel) = begin_launch();
+    let result = pipeline.run(&cancel).await;
+    tspack::record_launch(&result);
+    pipeline.record_history(&result);
+    pipeline.report_telemetry(&result);
+    end_launch(launch_id);
+
+    // A cancelled launch was stopped on purpose, there is nothing to resume
+    if matches!(&result, Err(e) if !matches!(e, LauncherError::Cancelled)) {
+        info!(
+            "Keeping the failed launch to resume at {:?}",
+            pipeline.step()
+        );
+        *FAILED_LAUNCH.lock().unwrap() = Some(pipeline);
     }
 
-    // Prepare game path
-    let path_start = Instant::now();
-    let game_path = if config.dx11 {
-        format!("{}/game/ffxiv_dx11.exe", config.game_path)
+    result
+}
+
+// Launch from the command line, without events, monitoring or launcher window handling
+pub async fn launch_headless(
+    config: LaunchConfig,
+    cancel: &CancellationToken,
+) -> Result<LaunchResult, LauncherError> {
+    ensure_game_profile(&config)?;
+    // Installs live in the launcher's settings store, profiles carry their own paths
+    if config.install.is_some() {
+        return Err(LauncherError::Config(
+            "Game installs can only be picked in the launcher, set game_path in the profile instead"
+                .to_string(),
+        ));
+    }
+    let http = HttpClient::with_settings(&config.network).map_err(LauncherError::Config)?;
+    LaunchPipeline::new(None, http.client(), config)
+        .run(cancel)
+        .await
+}
+
+// Helpers deep in the launch report a cancel as their own failure, the token knows better
+pub(crate) fn cancelled_or(error: LauncherError, cancel: &CancellationToken) -> LauncherError {
+    if cancel.is_cancelled() {
+        LauncherError::Cancelled
     } else {
-        format!("{}/game/ffxiv.exe", config.game_path)
-    };
-    info!("Using game executable: {}", game_path);
-
-    // Verify executable exists
-    if !Path::new(&game_path).exists() {
-        error!("Game executable not found at {}", game_path);
-        return Err(format!("Game executable not found at {}", game_path));
-    }
-    metrics.push(format!("Path preparation: {:.2?}", path_start.elapsed()));
-    info!("Game executable found");
-
-    // Get a fresh session ID right before launching
-    info!("Getting fresh session ID");
-    let sid_start = Instant::now();
-    let sid = match get_session_id(&config).await {
-        Ok(s) => {
-            let sid_duration = sid_start.elapsed();
-            metrics.push(format!("Session ID retrieval: {:.2?}", sid_duration));
-            info!(
-                "Successfully obtained fresh session ID in {:.2?}",
-                sid_duration
-            );
-            s
+        error
+    }
+}
+
+// Everything a launch checks up front, without logging in or starting the game
+pub async fn dry_run(config: &LaunchConfig) -> Result<Vec<String>, String> {
+    let mut report = Vec::new();
+    if config.install.is_some() {
+        return Err(
+            "Game installs can only be picked in the launcher, set game_path in the profile instead"
+                .to_string(),
+        );
+    }
+
+    let client = client_check::check_client(&config.game_path, config.dx11);
+    if !client.errors.is_empty() {
+        return Err(client.errors.join("\n"));
+    }
+    report.push(format!(
+        "Game executable: {} ({}, version {})",
+        client.executable,
+        client.architecture.unwrap_or_default(),
+        client.file_version.as_deref().unwrap_or("unknown")
+    ));
+    report.extend(client.warnings);
+    report.push(format!(
+        "Game version: {}",
+        read_game_version(&config.game_path)?.trim()
+    ));
+    report.push(format!(
+        "Expansion level: {} ({})",
+        config.expansion_level(),
+        if config.expansion_level.is_some() {
+            "from profile"
+        } else {
+            "detected"
         }
+    ));
+
+    let extra_args =
+        expand_additional_arguments(&config.additional_launch_args, &TemplateVars::new(config))?;
+    report.push(format!("Additional arguments: {}", extra_args.len()));
+    report.push(format!(
+        "Compatibility layer: {}",
+        compat_layer(&config.dpi_awareness)?
+    ));
+
+    if config.password.is_empty() {
+        credentials::load_password(&config.username)?;
+        report.push(format!(
+            "Password: stored credentials for {}",
+            config.username
+        ));
+    } else {
+        report.push("Password: provided in profile".to_string());
+    }
+
+    let http = HttpClient::with_settings(&config.network)?;
+    match fetch_gate_status(&http.client(), &config.endpoints).await {
+        Ok(gate) if gate.open => report.push("Game gate: open".to_string()),
+        Ok(_) => report.push("Game gate: closed for maintenance".to_string()),
+        Err(e) => report.push(format!("Game gate: unknown ({})", e)),
+    }
+
+    if config.enable_dalamud {
+        let sources = DalamudSources::from_config(config);
+        let version_info = check_dalamud_version(&http.client(), &sources, false)
+            .await
+            .map_err(|e| e.to_string())?;
+        report.push(format!(
+            "Dalamud: {} from {}",
+            version_info.assembly_version, sources.version_info_url
+        ));
+    } else {
+        report.push("Dalamud: disabled".to_string());
+    }
+
+    Ok(report)
+}
+
+// Companion apps never fail a launch, problems with them end up as warnings
+pub(crate) async fn start_companions(
+    config: &LaunchConfig,
+    timing: CompanionTiming,
+    vars: &TemplateVars,
+    warnings: &mut Vec<String>,
+) -> Vec<Companion> {
+    let mut started = Vec::new();
+    match companions::start_act(&config.act, timing).await {
+        Ok(companion) => started.extend(companion),
         Err(e) => {
-            error!("Failed to get session ID: {}", e);
-            return Err(format!("Failed to get session ID: {}", e));
+            warn!("Failed to start ACT: {}", e);
+            warnings.push(format!("ACT was not started: {}", e));
         }
-    };
+    }
+
+    let (tools, errors) = companions::start_tools(&config.external_tools, timing, vars);
+    started.extend(tools);
+    warnings.extend(errors);
+    started
+}
 
-    // Prepare launch arguments with fresh session ID
-    let args_start = Instant::now();
-    let args = format!(
-        "DEV.DataPathType=1 DEV.MaxEntitledExpansionID={} DEV.TestSID={} DEV.UseSqPack=1 SYS.Region={} language={}",
-        config.expansion_level,
-        sid,
-        config.region,
-        config.language
+#[tauri::command]
+pub async fn check_for_updates(
+    app: AppHandle,
+    http: State<'_, HttpClient>,
+    mut config: LaunchConfig,
+) -> Result<UpdateCheckResult, LauncherError> {
+    installs::apply(&app, &mut config).map_err(LauncherError::Config)?;
+    info!("Checking for game updates at: {}", config.game_path);
+    let versions =
+        version_check::read_installed_versions(&config.game_path, config.expansion_level());
+    info!(
+        "Installed versions: boot {}, game {}",
+        versions.boot, versions.game
     );
-    metrics.push(format!(
-        "Arguments preparation: {:.2?}",
-        args_start.elapsed()
-    ));
-    info!("Launch arguments prepared: {}", args);
 
-    // Launch the game with or without Dalamud
-    let launch_start = Instant::now();
-    if config.enable_dalamud {
-        info!("Starting game with Dalamud entrypoint injection");
-        match inject_dalamud(&config, &sid).await {
-            Ok(_) => {
-                let launch_duration = launch_start.elapsed();
-                metrics.push(format!(
-                    "Dalamud injection and launch: {:.2?}",
-                    launch_duration
-                ));
-                info!(
-                    "Game launched with Dalamud successfully in {:.2?}",
-                    launch_duration
-                );
-            }
-            Err(e) => {
-                error!("Failed to launch game with Dalamud: {}", e);
-                return Err(format!("Failed to launch game with Dalamud: {}", e));
+    let client = &http.client();
+
+    // Boot has to be current before the game version report is accepted
+    let boot_patches = version_check::check_boot_version(client, &versions)
+        .await
+        .map_err(LauncherError::Network)?;
+    if !boot_patches.is_empty() {
+        info!("{} boot patches pending", boot_patches.len());
+        return Ok(UpdateCheckResult {
+            versions,
+            boot_patches,
+            game_patches: Vec::new(),
+            warnings: Vec::new(),
+        });
+    }
+
+    let sid = get_session_id(client, &config, None, &CancellationToken::new()).await?;
+    let game_patches =
+        match version_check::check_game_version(client, &config.game_path, &versions, &sid)
+            .await
+            .map_err(LauncherError::Network)?
+        {
+            GameVersionCheck::UpToDate { .. } => Vec::new(),
+            GameVersionCheck::NeedsPatch { patches, .. } => patches,
+            GameVersionCheck::NeedsBootPatch => {
+                return Err(LauncherError::Patch(
+                    "Patch server rejected the boot files, please repair the boot installation"
+                        .to_string(),
+                ))
             }
-        }
+        };
+    info!("{} game patches pending", game_patches.len());
+
+    let warnings = if game_patches.is_empty() {
+        Vec::new()
     } else {
-        info!("Attempting to create game process without Dalamud");
-        match create_suspended_game_process(&game_path, &args) {
-            Ok(p) => {
-                let launch_duration = launch_start.elapsed();
-                metrics.push(format!("Game process creation: {:.2?}", launch_duration));
-                info!(
-                    "Game process created successfully with PID: {} in {:.2?}",
-                    p, launch_duration
-                );
-            }
-            Err(e) => {
-                error!("Failed to create game process: {}", e);
-                return Err(format!("Failed to launch game: {}", e));
+        mods::patch_warnings(&config.game_path)
+    };
+    Ok(UpdateCheckResult {
+        versions,
+        boot_patches,
+        game_patches,
+        warnings,
+    })
+}
+
+#[tauri::command]
+pub async fn download_patches(
+    app: AppHandle,
+    download_dir: String,
+    patches: Vec<PatchEntry>,
+    mirror: Option<String>,
+) -> Result<Vec<String>, String> {
+    info!("Downloading {} patches to {}", patches.len(), download_dir);
+    let mirror = mirror.unwrap_or_default();
+    downloader::validate_mirror(&mirror)?;
+
+    let requests: Vec<DownloadRequest> = patches.iter().map(install::download_request).collect();
+
+    let downloader = PatchDownloader::new(app.state::<HttpClient>().client(), &download_dir)
+        .mirror(&mirror)
+        .on_progress(move |progress| {
+            if let Err(e) = app.emit("patch-download-progress", &progress) {
+                warn!("Failed to emit download progress: {}", e);
             }
+        });
+
+    let paths = downloader.download_all(&requests).await?;
+    Ok(paths
+        .into_iter()
+        .map(|p| p.to_string_lossy().to_string())
+        .collect())
+}
+
+// Sends the boot hashes and installed versions the way the official launcher does after logging in.
+// The server answers with the unique id to start the game with, or with what has to be patched first
+pub(crate) async fn register_session(
+    client: &Client,
+    config: &LaunchConfig,
+    sid: &str,
+    cancel: &CancellationToken,
+) -> Result<String, LauncherError> {
+    let versions =
+        version_check::read_installed_versions(&config.game_path, config.expansion_level());
+    let check = with_cancel(
+        cancel,
+        version_check::check_game_version(client, &config.game_path, &versions, sid),
+    )
+    .await?
+    .map_err(LauncherError::Network)?;
+
+    match check {
+        GameVersionCheck::UpToDate { unique_id } => {
+            info!("Version report accepted for game {}", versions.game);
+            Ok(unique_id)
         }
+        GameVersionCheck::NeedsPatch { patches, .. } => Err(LauncherError::Patch(format!(
+            "The game needs {} patches before it can start, check for updates first",
+            patches.len()
+        ))),
+        GameVersionCheck::NeedsBootPatch => Err(LauncherError::Patch(
+            "The boot files are out of date, check for updates to patch them first".to_string(),
+        )),
     }
+}
 
-    let total_elapsed = total_start_time.elapsed();
-    metrics.push(format!("Total launch time: {:.2?}", total_elapsed));
+// Mirror the server layout (e.g. game/ex1/6b936f08/D2023.04.28.0000.0001.patch) under the download directory
+pub(crate) fn patch_file_name(url: &str) -> String {
+    url.split("://")
+        .nth(1)
+        .and_then(|rest| rest.split_once('/'))
+        .map(|(_, path)| path.split('?').next().unwrap_or(path).to_string())
+        .unwrap_or_else(|| url.rsplit('/').next().unwrap_or(url).to_string())
+}
 
-    // Join all metrics into a single string
-    let metrics_str = metrics.join("\n");
-    info!("Launch performance metrics:\n{}", metrics_str);
+pub(crate) fn build_game_arguments(config: &LaunchConfig, sid: &str) -> Result<String, String> {
+    let mut builder = ArgumentBuilder::new()
+        .append("DEV.DataPathType", 1)
+        .append("DEV.MaxEntitledExpansionID", config.expansion_level())
+        .append("DEV.TestSID", sid)
+        .append("DEV.UseSqPack", 1)
+        .append("SYS.Region", config.region())
+        .append("language", config.language);
+    // Together with the environment from steam_environment(), tells the client Steam
+    // started it so it reports playtime and shows the overlay
+    if config.is_steam {
+        builder = builder.append("IsSteam", 1);
+    }
 
-    Ok(format!(
-        "Game launched successfully. Performance metrics:\n{}",
-        metrics_str
-    ))
+    let vars = TemplateVars {
+        sid_present: !sid.is_empty(),
+        ..TemplateVars::new(config)
+    };
+    for (key, value) in expand_additional_arguments(&config.additional_launch_args, &vars)? {
+        builder = builder.append(&key, value);
+    }
+
+    // Plaintext arguments expose the SID to anything that can read the command line
+    if config.encrypt_arguments {
+        Ok(builder.build_encrypted())
+    } else {
+        warn!("Argument encryption is disabled, launching with plaintext arguments");
+        Ok(builder.build())
+    }
+}
+
+// What the Steam client sets for the games it starts itself
+pub(crate) fn steam_environment(config: &LaunchConfig) -> &'static [(&'static str, &'static str)] {
+    if config.is_steam {
+        &[("IS_FFXIV_LAUNCH_FROM_STEAM", "1")]
+    } else {
+        &[]
+    }
 }
 
-async fn get_session_id(config: &LaunchConfig) -> Result<String, String> {
+// New codes asked for after Square Enix refuses one, before the login gives up
+const MAX_OTP_PROMPTS: u32 = 3;
+
+pub(crate) async fn get_session_id(
+    client: &Client,
+    config: &LaunchConfig,
+    progress: Option<&LaunchProgress>,
+    cancel: &CancellationToken,
+) -> Result<String, LauncherError> {
     let start_time = Instant::now();
     info!("Starting session ID retrieval");
 
-    let client = Client::builder()
-        .timeout(Duration::from_secs(200)) // Add a 200 second timeout - 30 seconds would fail before square gives session id as their server for login are famously slow
-        .build()
-        .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
-    info!("HTTP client created in {:?}", start_time.elapsed());
+    if let Some(progress) = progress {
+        progress.report(LaunchStage::Oauth, "Contacting Square Enix login servers");
+    }
+
+    // Steam accounts have to present an encrypted auth session ticket to oauth
+    let steam_ticket = if config.is_steam {
+        info!("Getting Steam auth session ticket");
+        Some(steam::get_encrypted_ticket().map_err(LauncherError::Auth)?)
+    } else {
+        None
+    };
+    let top_url = oauth_top_url(
+        &config.endpoints,
+        config.region(),
+        config.is_steam,
+        steam_ticket.as_ref(),
+    );
 
     let stored_start = Instant::now();
     info!("Getting stored value");
-    let stored = match get_stored(config.is_steam).await {
-        Ok(s) => {
+    let stored = match with_cancel(
+        cancel,
+        get_stored(client, &top_url, config.is_steam, &config.retry),
+    )
+    .await?
+    {
+        Ok((s, steam_username)) => {
             info!(
                 "Successfully retrieved stored value in {:?}",
                 stored_start.elapsed()
             );
+            if let Some(steam_username) = steam_username {
+                if !steam_username.eq_ignore_ascii_case(&config.username) {
+                    return Err(LauncherError::Auth(format!(
+                        "This Steam account is linked to the Square Enix ID {}, not {}",
+                        steam_username, config.username
+                    )));
+                }
+            }
             s
         }
         Err(e) => {
@@ -353,37 +794,200 @@ async fn get_session_id(config: &LaunchConfig) -> Result<String, String> {
         }
     };
 
+    let password = if config.password.is_empty() {
+        info!("No password supplied, using stored credentials");
+        credentials::load_password(&config.username).map_err(LauncherError::Auth)?
+    } else {
+        config.password.expose().to_string()
+    };
+
+    let otp = match config.otp.as_ref().filter(|otp| !otp.is_empty()) {
+        Some(otp) => otp.expose().to_string(),
+        None if config.otp_listener => {
+            info!("Waiting for OTP from the listener or manual entry");
+            with_cancel(cancel, otp_listener::wait_for_otp())
+                .await?
+                .map_err(LauncherError::Auth)?
+        }
+        None => String::new(),
+    };
+
     let form_start = Instant::now();
     let mut form = HashMap::new();
     form.insert("_STORED_", stored);
     form.insert("sqexid", config.username.clone());
-    form.insert("password", config.password.clone());
-    form.insert("otppw", config.otp.clone().unwrap_or_default());
+    form.insert("password", password);
+    form.insert("otppw", otp);
     info!("Form prepared in {:?}", form_start.elapsed());
 
-    let login_start = Instant::now();
-    info!("Sending login request to Square Enix");
-    let response = match client.post("https://ffxiv-login.square-enix.com/oauth/ffxivarr/login/login.send")
-        .header(USER_AGENT, get_user_agent())
-        .header(REFERER, format!("https://ffxiv-login.square-enix.com/oauth/ffxivarr/login/top?lng=en&rgn=3&isft=0&issteam={}", 
-            if config.is_steam { "1" } else { "0" }))
-        .header(CONTENT_TYPE, "application/x-www-form-urlencoded")
-        .form(&form)
-        .send()
-        .await {
-            Ok(r) => {
-                info!("Login request sent successfully in {:?}", login_start.elapsed());
-                r
+    // A refused code doesn't spend the _STORED_ value, so only a new code is asked for
+    let mut otp_prompts = 0;
+    let mut busy_attempts = 1;
+    let result = loop {
+        let body = send_login(client, config, &top_url, &form, progress, cancel).await?;
+        let parse_start = Instant::now();
+        info!("Parsing response for session ID");
+        let result = parse_session_id(&body);
+        match &result {
+            Ok(_) => info!(
+                "Successfully extracted session ID in {:?}",
+                parse_start.elapsed()
+            ),
+            // The body itself stays out of the log, it can hold the SID or the _STORED_ value
+            Err(e) => error!(
+                "Failed to extract session ID after {:?} ({}), response was {} bytes",
+                parse_start.elapsed(),
+                e,
+                body.len()
+            ),
+        }
+
+        // Peak hours turn logins away with a busy message, those are tried again under the
+        // profile's retry policy like a server error
+        if let Some(message) = busy_refusal(&body) {
+            let busy = || {
+                LauncherError::Network(format!(
+                    "The login servers are busy, try again in a few minutes: {}",
+                    message
+                ))
+            };
+            if busy_attempts >= config.retry.max_attempts {
+                break Err(busy());
             }
-            Err(e) => {
-                error!("Failed to send login request after {:?}: {}", login_start.elapsed(), e);
-                return Err(format!("Failed to send login request: {}", e));
+            let delay = config.retry.delay(busy_attempts - 1);
+            warn!(
+                "Login servers are busy (attempt {} of {}), retrying in {:?}: {}",
+                busy_attempts, config.retry.max_attempts, delay, message
+            );
+            if let Some(progress) = progress {
+                progress.report(
+                    LaunchStage::SessionId,
+                    format!(
+                        "Login servers are busy, retrying in {}s",
+                        delay.as_secs().max(1)
+                    ),
+                );
             }
+            with_cancel(cancel, tokio::time::sleep(delay)).await?;
+            busy_attempts += 1;
+
+            // The code sent with the refused login is spent, the retry needs a fresh
+            // _STORED_ value and a new code, or the busy error goes to the user
+            if !form["otppw"].is_empty() {
+                let event = OtpRequiredEvent {
+                    message: message.clone(),
+                    attempt: busy_attempts - 1,
+                };
+                let Some(otp) = prompt_for_otp(config, progress, &event, cancel).await? else {
+                    break Err(busy());
+                };
+                let (stored, _) = with_cancel(
+                    cancel,
+                    get_stored(client, &top_url, config.is_steam, &config.retry),
+                )
+                .await??;
+                form.insert("_STORED_", stored);
+                form.insert("otppw", otp);
+            }
+            continue;
+        }
+
+        let Some(message) = otp_refusal(&body) else {
+            break result;
+        };
+        if form["otppw"].is_empty() || otp_prompts >= MAX_OTP_PROMPTS {
+            break result;
+        }
+        otp_prompts += 1;
+        let event = OtpRequiredEvent {
+            message,
+            attempt: otp_prompts,
+        };
+        warn!(
+            "The OTP was refused, asking for a new one ({}/{})",
+            otp_prompts, MAX_OTP_PROMPTS
+        );
+        let Some(otp) = prompt_for_otp(config, progress, &event, cancel).await? else {
+            break result;
         };
+        form.insert("otppw", otp);
+    };
+
+    info!("Total session ID retrieval took {:?}", start_time.elapsed());
+    result
+}
+
+// Asks the launcher window or the OTP listener for a new code. None when neither can
+// answer, e.g. a headless launch without the listener
+async fn prompt_for_otp(
+    config: &LaunchConfig,
+    progress: Option<&LaunchProgress>,
+    event: &OtpRequiredEvent,
+    cancel: &CancellationToken,
+) -> Result<Option<String>, LauncherError> {
+    let requested = progress.is_some_and(|progress| progress.request_otp(event));
+    if !requested && !config.otp_listener {
+        return Ok(None);
+    }
+    info!("Waiting for a new OTP");
+    let otp = with_cancel(cancel, otp_listener::wait_for_otp())
+        .await?
+        .map_err(LauncherError::Auth)?;
+    Ok(Some(otp))
+}
+
+async fn send_login(
+    client: &Client,
+    config: &LaunchConfig,
+    top_url: &str,
+    form: &HashMap<&str, String>,
+    progress: Option<&LaunchProgress>,
+    cancel: &CancellationToken,
+) -> Result<String, LauncherError> {
+    let login_start = Instant::now();
+    info!("Sending login request to Square Enix");
+    if let Some(progress) = progress {
+        progress.report(LaunchStage::SessionId, "Logging in");
+    }
+    // A one-time password can only be spent once, so a login carrying one isn't repeated
+    let login_retry = if form["otppw"].is_empty() {
+        config.retry.clone()
+    } else {
+        RetryPolicy::none()
+    };
+    let login_request = retry::send(&login_retry, "Login request", || {
+        client
+            .post(config.endpoints.oauth_login())
+            .header(USER_AGENT, get_user_agent())
+            .header(REFERER, config.endpoints.login_referer(top_url))
+            .header(CONTENT_TYPE, "application/x-www-form-urlencoded")
+            .form(form)
+            .timeout(LOGIN_TIMEOUT)
+    });
+    let response = match with_cancel(cancel, login_request).await? {
+        Ok(r) => {
+            info!(
+                "Login request sent successfully in {:?}",
+                login_start.elapsed()
+            );
+            r
+        }
+        Err(e) => {
+            error!(
+                "Failed to send login request after {:?}: {}",
+                login_start.elapsed(),
+                e
+            );
+            return Err(LauncherError::Network(format!(
+                "Failed to send login request: {}",
+                e
+            )));
+        }
+    };
 
     let body_start = Instant::now();
     info!("Reading response body");
-    let body = match response.text().await {
+    let body = match with_cancel(cancel, response.text()).await? {
         Ok(b) => {
             info!(
                 "Successfully received response body in {:?}",
@@ -397,56 +1001,158 @@ async fn get_session_id(config: &LaunchConfig) -> Result<String, String> {
                 body_start.elapsed(),
                 e
             );
-            return Err(format!("Failed to read response: {}", e));
+            return Err(LauncherError::Network(format!(
+                "Failed to read response: {}",
+                e
+            )));
         }
     };
+    Ok(body)
+}
 
-    let parse_start = Instant::now();
-    info!("Parsing response for session ID");
-    let re = regex::Regex::new(r"sid,(?P<sid>.*),terms").unwrap();
-    let result = match re.captures(&body) {
-        Some(caps) => {
-            let sid = caps["sid"].to_string();
-            info!(
-                "Successfully extracted session ID in {:?}",
-                parse_start.elapsed()
-            );
-            Ok(sid)
-        }
-        None => {
-            error!(
-                "Failed to extract session ID after {:?}. Response body: {}",
-                parse_start.elapsed(),
-                body
-            );
-            Err("Failed to extract session ID".to_string())
-        }
-    };
+// Links to the terms from the page when there is one, the user agreement otherwise
+fn terms_of_service_error(body: &str) -> LauncherError {
+    let link =
+        regex::Regex::new(r#"(?i)href="(?P<url>https://[^"]*(?:terms|rule|agreement)[^"]*)""#)
+            .unwrap();
+    let url = link
+        .captures(body)
+        .map(|caps| caps["url"].replace("&amp;", "&"))
+        .unwrap_or_else(|| TERMS_OF_SERVICE_URL.to_string());
+    let message = "Square Enix updated the terms of service. Log in once with the official \
+                   launcher to accept them, then launch again";
+    LauncherError::TermsOfService {
+        message: message.to_string(),
+        url,
+    }
+}
 
-    info!("Total session ID retrieval took {:?}", start_time.elapsed());
-    result
+// Opens the terms a TermsOfService error pointed at in the browser
+#[tauri::command]
+pub fn open_tos(app: AppHandle, url: Option<String>) -> Result<(), String> {
+    let url = url
+        .filter(|url| !url.is_empty())
+        .unwrap_or_else(|| TERMS_OF_SERVICE_URL.to_string());
+    if !url.starts_with("https://") {
+        return Err(format!("Refusing to open {}", url));
+    }
+    app.opener()
+        .open_url(&url, None::<&str>)
+        .map_err(|e| format!("Failed to open the terms of service: {}", e))
 }
 
-async fn get_stored(is_steam: bool) -> Result<String, String> {
-    let start_time = Instant::now();
-    info!("Starting stored value retrieval");
+fn login_refusal(body: &str) -> Option<String> {
+    let re = regex::Regex::new(r#"login=auth,ng,err,(?P<message>[^"]*)"#).unwrap();
+    re.captures(body)
+        .map(|caps| caps["message"].trim().to_string())
+}
 
-    let client = Client::builder()
-        .timeout(Duration::from_secs(30)) // Add a 30 second timeout
-        .build()
-        .map_err(|e| format!("Failed to create HTTP client: {}", e))?;
+// The refusal message when the login was turned away because the servers are congested,
+// as opposed to a problem with the account
+fn busy_refusal(body: &str) -> Option<String> {
+    let message = login_refusal(body)?;
+    let lowercase = message.to_lowercase();
+    ["busy", "congested", "try again later", "混雑"]
+        .iter()
+        .any(|needle| lowercase.contains(needle))
+        .then_some(message)
+}
 
-    let url = format!(
-        "https://ffxiv-login.square-enix.com/oauth/ffxivarr/login/top?lng=en&rgn=3&isft=0&issteam={}", 
+// The refusal message when the login failed on the one-time password rather than the
+// username or password
+fn otp_refusal(body: &str) -> Option<String> {
+    let message = login_refusal(body)?;
+    let lowercase = message.to_lowercase();
+    [
+        "one-time password",
+        "one time password",
+        "ワンタイムパスワード",
+    ]
+    .iter()
+    .any(|needle| lowercase.contains(needle))
+    .then_some(message)
+}
+
+// The login answers with a script calling window.external.user, either with the sid
+// or with the reason the login was refused
+pub(crate) fn parse_session_id(body: &str) -> Result<String, LauncherError> {
+    // A sid comes with "terms,0" while the account still has to accept updated terms, the
+    // game server turns it away
+    let terms = regex::Regex::new(r"terms,(?P<accepted>\d)").unwrap();
+    if terms
+        .captures(body)
+        .is_some_and(|caps| &caps["accepted"] == "0")
+    {
+        return Err(terms_of_service_error(body));
+    }
+    let re = regex::Regex::new(r"sid,(?P<sid>.*),terms").unwrap();
+    if let Some(caps) = re.captures(body) {
+        return Ok(caps["sid"].to_string());
+    }
+
+    // Error and maintenance pages link the terms in their footer, so a mention alone is
+    // only worth a note next to the generic error
+    let lowercase = body.to_lowercase();
+    if login_refusal(body).is_none()
+        && ["terms of service", "user agreement", "利用規約"]
+            .iter()
+            .any(|needle| lowercase.contains(needle))
+    {
+        warn!("The login response mentions the terms of service but carries no terms flag");
+    }
+
+    match login_refusal(body) {
+        Some(message) => Err(LauncherError::Auth(format!(
+            "Square Enix refused the login: {}",
+            message
+        ))),
+        None => Err(LauncherError::Auth(
+            "Failed to extract session ID, check the username, password and OTP".to_string(),
+        )),
+    }
+}
+
+// The login page takes the same region number the game gets as SYS.Region
+fn oauth_top_url(
+    endpoints: &Endpoints,
+    region: u32,
+    is_steam: bool,
+    steam_ticket: Option<&SteamTicket>,
+) -> String {
+    let mut url = format!(
+        "{}?lng=en&rgn={}&isft=0&issteam={}",
+        endpoints.oauth_top(),
+        region,
         if is_steam { "1" } else { "0" }
     );
-    info!("Requesting stored value from: {}", url);
+    if let Some(ticket) = steam_ticket {
+        url.push_str(&format!(
+            "&session_ticket={}&ticket_size={}",
+            ticket.text, ticket.length
+        ));
+    }
+    url
+}
 
-    let response = match client
-        .get(&url)
-        .header(USER_AGENT, get_user_agent())
-        .send()
-        .await
+// Returns the _STORED_ value, plus the Square Enix ID a Steam account is bound to
+async fn get_stored(
+    client: &Client,
+    url: &str,
+    is_steam: bool,
+    retry_policy: &RetryPolicy,
+) -> Result<(String, Option<String>), LauncherError> {
+    let start_time = Instant::now();
+    info!("Starting stored value retrieval");
+
+    info!("Requesting stored value from oauth top page");
+
+    let response = match retry::send(retry_policy, "Stored value request", || {
+        client
+            .get(url)
+            .header(USER_AGENT, get_user_agent())
+            .timeout(REQUEST_TIMEOUT)
+    })
+    .await
     {
         Ok(r) => {
             info!(
@@ -461,7 +1167,10 @@ async fn get_stored(is_steam: bool) -> Result<String, String> {
                 start_time.elapsed(),
                 e
             );
-            return Err(format!("Failed to get stored value: {}", e));
+            return Err(LauncherError::Network(format!(
+                "Failed to get stored value: {}",
+                e
+            )));
         }
     };
 
@@ -476,14 +1185,15 @@ async fn get_stored(is_steam: bool) -> Result<String, String> {
                 start_time.elapsed(),
                 e
             );
-            return Err(format!("Failed to read response: {}", e));
+            return Err(LauncherError::Network(format!(
+                "Failed to read response: {}",
+                e
+            )));
         }
     };
 
-    let re = regex::Regex::new(r#"<input.*?name="_STORED_".*?value="([^"]*)"#).unwrap();
-    match re.captures(&body) {
-        Some(caps) => {
-            let stored = caps.get(1).unwrap().as_str().to_string();
+    match parse_stored(&body, is_steam) {
+        Some(stored) => {
             info!(
                 "Successfully extracted stored value in {:?}",
                 start_time.elapsed()
@@ -492,16 +1202,34 @@ async fn get_stored(is_steam: bool) -> Result<String, String> {
         }
         None => {
             error!(
-                "Could not find _STORED_ value in response after {:?}. Response body: {}",
+                "Could not find _STORED_ value in response after {:?}, response was {} bytes",
                 start_time.elapsed(),
-                body
+                body.len()
             );
-            Err("Could not find _STORED_ value".to_string())
+            Err(LauncherError::Network(
+                "Could not find _STORED_ value".to_string(),
+            ))
         }
     }
 }
 
-fn get_user_agent() -> String {
+// The _STORED_ value from the oauth top page, plus the Square Enix ID that Steam service
+// accounts get pinned in a hidden field
+pub(crate) fn parse_stored(body: &str, is_steam: bool) -> Option<(String, Option<String>)> {
+    let steam_username = if is_steam {
+        let re =
+            regex::Regex::new(r#"<input name="sqexid" type="hidden" value="([^"]*)""#).unwrap();
+        re.captures(body).map(|caps| caps[1].to_string())
+    } else {
+        None
+    };
+
+    let re = regex::Regex::new(r#"<input.*?name="_STORED_".*?value="([^"]*)"#).unwrap();
+    re.captures(body)
+        .map(|caps| (caps[1].to_string(), steam_username))
+}
+
+pub(crate) fn get_user_agent() -> String {
     format!(
         "SQEXAuthor/2.0.0(Windows 6.2; ja-jp; {})",
         make_computer_id()
@@ -535,11 +1263,11 @@ fn make_computer_id() -> String {
 }
 
 #[derive(Debug, Serialize, Deserialize)]
-struct DalamudVersionInfo {
+pub(crate) struct DalamudVersionInfo {
     key: String,
     track: String,
     #[serde(rename = "assemblyVersion")]
-    assembly_version: String,
+    pub(crate) assembly_version: String,
     #[serde(rename = "runtimeVersion")]
     runtime_version: String,
     #[serde(rename = "runtimeRequired")]
@@ -553,6 +1281,26 @@ struct DalamudVersionInfo {
     download_url: String,
 }
 
+impl DalamudVersionInfo {
+    // Why this build shouldn't be loaded into the installed game, if it shouldn't
+    pub(crate) fn game_mismatch(&self, game_version: &str) -> Option<String> {
+        let supported = self.supported_game_ver.trim();
+        if !supported.is_empty() && supported != game_version {
+            Some(format!(
+                "Dalamud {} supports game version {}, but {} is installed",
+                self.assembly_version, supported, game_version
+            ))
+        } else if !self.is_applicable_for_current_game_ver {
+            Some(format!(
+                "Dalamud {} has not been updated for the current game version yet",
+                self.assembly_version
+            ))
+        } else {
+            None
+        }
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 struct DalamudChangelog {
     date: String,
@@ -561,11 +1309,11 @@ struct DalamudChangelog {
 }
 
 #[derive(Debug, Serialize, Deserialize)]
-struct DalamudChange {
-    message: String,
-    author: String,
-    sha: String,
-    date: String,
+pub struct DalamudChange {
+    pub message: String,
+    pub author: String,
+    pub sha: String,
+    pub date: String,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -576,85 +1324,255 @@ struct AssetInfo {
     assets: Vec<AssetFile>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 struct AssetFile {
     url: String,
     #[serde(rename = "fileName")]
     file_name: String,
     hash: Option<String>,
+    // Older asset meta has no algorithm and only SHA-1 hashes
+    #[serde(rename = "hashAlgorithm", default)]
+    hash_algorithm: Option<HashAlgorithm>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) enum HashAlgorithm {
+    #[serde(rename = "SHA1", alias = "Sha1", alias = "sha1")]
+    Sha1,
+    #[serde(rename = "SHA256", alias = "Sha256", alias = "sha256")]
+    Sha256,
+}
+
+impl AssetFile {
+    // Falls back to the hash length when the meta doesn't name the algorithm
+    fn hash_algorithm(&self, hash: &str) -> HashAlgorithm {
+        match self.hash_algorithm {
+            Some(algorithm) => algorithm,
+            None if hash.len() == 64 => HashAlgorithm::Sha256,
+            None => HashAlgorithm::Sha1,
+        }
+    }
+}
+
+const DEFAULT_DALAMUD_BASE: &str = "https://kamori.goats.dev";
+const DEFAULT_VERSION_INFO_URL: &str = "https://kamori.goats.dev/Dalamud/Release/VersionInfo";
+const DEFAULT_ASSET_META_URL: &str = "https://kamori.goats.dev/Dalamud/Asset/Meta";
+// Held for the whole setup, the background updater and a launch would otherwise download
+// into the same folders
+static DALAMUD_SETUP: tokio::sync::Mutex<()> = tokio::sync::Mutex::const_new(());
+// Next to asset.ver in the Dalamud directory
+const DALAMUD_CHECK_FILE: &str = "dalamudCheck.json";
+
+// Written after a launch checked Dalamud and its assets against the server
+#[derive(Debug, Serialize, Deserialize)]
+struct DalamudCheck {
+    assembly_version: String,
+    asset_version: i32,
+    // Unix timestamp in seconds
+    checked_at: u64,
+    // Switching to another server checks again
+    version_info_url: String,
+}
+
+// Where Dalamud metadata and downloads come from, kamori unless overridden in the config
+struct DalamudSources {
+    version_info_url: String,
+    asset_meta_url: String,
+    download_base: String,
+    retry: RetryPolicy,
+}
+
+impl DalamudSources {
+    fn from_config(config: &LaunchConfig) -> Self {
+        let non_empty = |value: &Option<String>| {
+            value
+                .as_deref()
+                .map(|v| v.trim().trim_end_matches('/'))
+                .filter(|v| !v.is_empty())
+                .map(str::to_string)
+        };
+
+        Self {
+            version_info_url: non_empty(&config.dalamud_version_info_url)
+                .unwrap_or_else(|| DEFAULT_VERSION_INFO_URL.to_string()),
+            asset_meta_url: non_empty(&config.dalamud_asset_meta_url)
+                .unwrap_or_else(|| DEFAULT_ASSET_META_URL.to_string()),
+            download_base: non_empty(&config.dalamud_download_base)
+                .unwrap_or_else(|| DEFAULT_DALAMUD_BASE.to_string()),
+            retry: config.retry.clone(),
+        }
+    }
+
+    fn runtime_url(&self, pack: &str, version: &str) -> String {
+        format!(
+            "{}/Dalamud/Release/Runtime/{}/{}",
+            self.download_base, pack, version
+        )
+    }
+
+    // Metadata from kamori links back to kamori, point those links at the mirror instead
+    fn download_url(&self, url: &str) -> String {
+        match url.strip_prefix(DEFAULT_DALAMUD_BASE) {
+            Some(path) => format!("{}{}", self.download_base, path),
+            None => url.to_string(),
+        }
+    }
 }
 
 async fn check_dalamud_version(
     client: &Client,
+    sources: &DalamudSources,
     is_staging: bool,
-) -> Result<DalamudVersionInfo, String> {
+) -> Result<DalamudVersionInfo, LauncherError> {
+    let separator = if sources.version_info_url.contains('?') {
+        '&'
+    } else {
+        '?'
+    };
     let url = format!(
-        "https://kamori.goats.dev/Dalamud/Release/VersionInfo?track={}",
+        "{}{}track={}",
+        sources.version_info_url,
+        separator,
         if is_staging { "staging" } else { "release" }
     );
 
-    let response = client
-        .get(&url)
-        .timeout(Duration::from_secs(30))
-        .send()
-        .await
-        .map_err(|e| format!("Failed to get version info: {}", e))?;
+    let response = retry::send(&sources.retry, "Dalamud version request", || {
+        client.get(&url).timeout(REQUEST_TIMEOUT)
+    })
+    .await
+    .map_err(|e| LauncherError::Network(format!("Failed to get version info: {}", e)))?;
 
     response
         .json::<DalamudVersionInfo>()
         .await
-        .map_err(|e| format!("Failed to parse version info: {}", e))
+        .map_err(|e| LauncherError::Network(format!("Failed to parse version info: {}", e)))
 }
 
-async fn check_asset_version(client: &Client) -> Result<AssetInfo, String> {
-    let response = client
-        .get("https://kamori.goats.dev/Dalamud/Asset/Meta")
-        .timeout(Duration::from_secs(30))
-        .send()
-        .await
-        .map_err(|e| format!("Failed to get asset info: {}", e))?;
+async fn check_asset_version(
+    client: &Client,
+    sources: &DalamudSources,
+) -> Result<AssetInfo, LauncherError> {
+    let response = retry::send(&sources.retry, "Dalamud asset request", || {
+        client.get(&sources.asset_meta_url).timeout(REQUEST_TIMEOUT)
+    })
+    .await
+    .map_err(|e| LauncherError::Network(format!("Failed to get asset info: {}", e)))?;
 
     response
         .json::<AssetInfo>()
         .await
-        .map_err(|e| format!("Failed to parse asset info: {}", e))
+        .map_err(|e| LauncherError::Network(format!("Failed to parse asset info: {}", e)))
+}
+
+#[derive(Debug)]
+pub(crate) struct DalamudSetup {
+    pub(crate) version_info: DalamudVersionInfo,
+    // Launching from a cached install because the distribution server was unreachable
+    pub(crate) offline: bool,
+}
+
+// Falls back to what's already installed so an outage on the server doesn't block launching
+async fn setup_offline_dalamud(
+    config: &LaunchConfig,
+    base_path: &str,
+    error: LauncherError,
+) -> Result<DalamudSetup, LauncherError> {
+    warn!(
+        "Failed to check Dalamud version, trying cached install: {}",
+        error
+    );
+
+    let hooks_path = base_path.to_string();
+    let cached = run_blocking(move || Ok(find_cached_dalamud(&hooks_path)))
+        .await
+        .map_err(LauncherError::Dalamud)?;
+    let Some(version_info) = cached else {
+        return Err(error.context("No usable cached Dalamud install to fall back to"));
+    };
+    info!(
+        "Using cached Dalamud version: {}",
+        version_info.assembly_version
+    );
+
+    let runtime_path = format!("{}/runtime", config.dalamud_path);
+    if version_info.runtime_required
+        && !is_runtime_installed(&runtime_path, &version_info.runtime_version)
+    {
+        return Err(error.context(&format!(
+            "Cached Dalamud needs .NET runtime {}, which is not installed",
+            version_info.runtime_version
+        )));
+    }
+
+    let version_path = format!("{}/Hooks/{}", base_path, version_info.assembly_version);
+    verify_dalamud_install(config, &version_path)?;
+
+    Ok(DalamudSetup {
+        version_info,
+        offline: true,
+    })
 }
 
-async fn setup_dalamud(config: &LaunchConfig) -> Result<String, String> {
+pub(crate) async fn setup_dalamud(
+    client: &Client,
+    config: &LaunchConfig,
+    progress: Option<&LaunchProgress>,
+    cancel: &CancellationToken,
+) -> Result<DalamudSetup, LauncherError> {
     info!("Setting up Dalamud with base path: {}", config.dalamud_path);
     let start_time = Instant::now();
+    if let Some(progress) = progress {
+        progress.report(LaunchStage::DalamudCheck, "Checking Dalamud version");
+    }
+    let _setup = match DALAMUD_SETUP.try_lock() {
+        Ok(guard) => guard,
+        Err(_) => {
+            info!("Waiting for the background Dalamud update to finish");
+            with_cancel(cancel, DALAMUD_SETUP.lock()).await?
+        }
+    };
 
-    // Normalize base path - ensure we don't have duplicate /addon
-    let base_path =
-        if config.dalamud_path.ends_with("/addon") || config.dalamud_path.ends_with("\\addon") {
-            info!(
-                "Base path already ends with addon, using as is: {}",
-                config.dalamud_path
-            );
-            config.dalamud_path.clone()
-        } else {
-            let path = format!("{}/addon", config.dalamud_path);
-            info!("Adding /addon to base path: {}", path);
-            path
-        };
+    let base_path = dalamud_addon_path(&config.dalamud_path);
+    info!("Using Dalamud addon path: {}", base_path);
+
+    let sources = DalamudSources::from_config(config);
+    if let Some(version_info) = recent_dalamud_check(config, &base_path, &sources) {
+        let version_path = format!("{}/Hooks/{}", base_path, version_info.assembly_version);
+        verify_dalamud_install(config, &version_path)?;
+        info!(
+            "Fast launch with Dalamud {} in {:.2?}",
+            version_info.assembly_version,
+            start_time.elapsed()
+        );
+        return Ok(DalamudSetup {
+            version_info,
+            offline: false,
+        });
+    }
 
     // Fast version check first
-    let client = Client::new();
-    let version_info = check_dalamud_version(&client, false).await?;
+    let version_info =
+        match with_cancel(cancel, check_dalamud_version(client, &sources, false)).await? {
+            Ok(version_info) => version_info,
+            Err(e) => return setup_offline_dalamud(config, &base_path, e).await,
+        };
     info!("Remote Dalamud version: {}", version_info.assembly_version);
 
     // Check local version and integrity before any downloads
     let current_version_path = format!("{}/Hooks/{}", base_path, version_info.assembly_version);
     let needs_dalamud_update = if Path::new(&current_version_path).exists() {
         info!("Found existing Dalamud installation, checking integrity");
-        !check_dalamud_integrity(&current_version_path)?
+        let path = current_version_path.clone();
+        !run_blocking(move || check_dalamud_integrity(&path))
+            .await
+            .map_err(LauncherError::Dalamud)?
     } else {
         info!("No existing Dalamud installation found");
         true
     };
 
     // Fast asset version check
-    let asset_info = check_asset_version(&client).await?;
+    let asset_info = with_cancel(cancel, check_asset_version(client, &sources)).await??;
     let asset_ver_path = format!("{}/dalamudAssets/asset.ver", config.dalamud_path);
     let current_asset_ver = fs::read_to_string(&asset_ver_path)
         .unwrap_or_else(|_| "0".to_string())
@@ -665,8 +1583,9 @@ async fn setup_dalamud(config: &LaunchConfig) -> Result<String, String> {
     // Create required directories only if we need to update something
     if needs_dalamud_update || needs_asset_update {
         // Create base directories
-        fs::create_dir_all(&base_path)
-            .map_err(|e| format!("Failed to create Dalamud base directory: {}", e))?;
+        fs::create_dir_all(&base_path).map_err(|e| {
+            LauncherError::Dalamud(format!("Failed to create Dalamud base directory: {}", e))
+        })?;
 
         // Required directories relative to XIVLOADER root (not addon)
         let root_directories = [
@@ -681,18 +1600,27 @@ async fn setup_dalamud(config: &LaunchConfig) -> Result<String, String> {
         for dir in root_directories {
             let path = format!("{}/{}", config.dalamud_path, dir);
             if !Path::new(&path).exists() {
-                fs::create_dir_all(&path)
-                    .map_err(|e| format!("Failed to create directory {}: {}", dir, e))?;
+                fs::create_dir_all(&path).map_err(|e| {
+                    LauncherError::Dalamud(format!("Failed to create directory {}: {}", dir, e))
+                })?;
                 info!("Created directory: {}", path);
 
                 #[cfg(windows)]
                 {
-                    let metadata = fs::metadata(&path)
-                        .map_err(|e| format!("Failed to get metadata for {}: {}", path, e))?;
+                    let metadata = fs::metadata(&path).map_err(|e| {
+                        LauncherError::Dalamud(format!(
+                            "Failed to get metadata for {}: {}",
+                            path, e
+                        ))
+                    })?;
                     let mut perms = metadata.permissions();
                     perms.set_readonly(false);
-                    fs::set_permissions(&path, perms)
-                        .map_err(|e| format!("Failed to set permissions for {}: {}", path, e))?;
+                    fs::set_permissions(&path, perms).map_err(|e| {
+                        LauncherError::Dalamud(format!(
+                            "Failed to set permissions for {}: {}",
+                            path, e
+                        ))
+                    })?;
                 }
             }
         }
@@ -704,31 +1632,88 @@ async fn setup_dalamud(config: &LaunchConfig) -> Result<String, String> {
             "Updating Dalamud to version {}",
             version_info.assembly_version
         );
+        if let Some(progress) = progress {
+            progress.report(
+                LaunchStage::DalamudDownload,
+                format!("Downloading Dalamud {}", version_info.assembly_version),
+            );
+        }
 
         // Create Hooks directory
         let hooks_dir = format!("{}/Hooks", base_path);
-        fs::create_dir_all(&hooks_dir)
-            .map_err(|e| format!("Failed to create Hooks directory: {}", e))?;
+        fs::create_dir_all(&hooks_dir).map_err(|e| {
+            LauncherError::Dalamud(format!("Failed to create Hooks directory: {}", e))
+        })?;
 
         // Download and extract Dalamud
         let temp_path = format!("{}/dalamud_temp.zip", config.dalamud_path);
-        download_file(&client, &version_info.download_url, &temp_path).await?;
+        let download_url = sources.download_url(&version_info.download_url);
+        let mut tracker =
+            progress.map(|p| p.download(format!("Dalamud {}", version_info.assembly_version)));
+        download_file(
+            client,
+            &download_url,
+            &temp_path,
+            tracker.as_mut(),
+            &sources.retry,
+            cancel,
+        )
+        .await
+        .map_err(|e| cancelled_or(LauncherError::Dalamud(e), cancel))?;
 
         // Create version directory
-        fs::create_dir_all(&current_version_path)
-            .map_err(|e| format!("Failed to create version directory: {}", e))?;
+        fs::create_dir_all(&current_version_path).map_err(|e| {
+            LauncherError::Dalamud(format!("Failed to create version directory: {}", e))
+        })?;
 
         // Extract to version directory
-        extract_zip(&temp_path, &current_version_path)?;
-        fs::remove_file(&temp_path).map_err(|e| format!("Failed to remove temp file: {}", e))?;
+        extract_in_background(
+            &temp_path,
+            &current_version_path,
+            progress,
+            format!("Extracting Dalamud {}", version_info.assembly_version),
+            cancel,
+        )
+        .await?;
+        fs::remove_file(&temp_path)
+            .map_err(|e| LauncherError::Dalamud(format!("Failed to remove temp file: {}", e)))?;
 
         // Write version info
         fs::write(
             format!("{}/version.json", current_version_path),
-            serde_json::to_string(&version_info)
-                .map_err(|e| format!("Failed to serialize version info: {}", e))?,
+            serde_json::to_string(&version_info).map_err(|e| {
+                LauncherError::Dalamud(format!("Failed to serialize version info: {}", e))
+            })?,
         )
-        .map_err(|e| format!("Failed to write version info: {}", e))?;
+        .map_err(|e| LauncherError::Dalamud(format!("Failed to write version info: {}", e)))?;
+
+        // Lets later launches spot files removed or changed since, e.g. by antivirus
+        let path = current_version_path.clone();
+        run_blocking(move || write_dalamud_hashes(&path))
+            .await
+            .map_err(LauncherError::Dalamud)?;
+
+        // A running game may still have an old version loaded
+        if !game_monitor::get_game_status().running {
+            let dalamud_path = config.dalamud_path.clone();
+            let pruned = run_blocking(move || {
+                dalamud_cleanup::prune_versions(
+                    &dalamud_path,
+                    dalamud_cleanup::KEEP_DALAMUD_VERSIONS,
+                    false,
+                )
+            })
+            .await;
+            match pruned {
+                Ok(pruned) if !pruned.removed.is_empty() => info!(
+                    "Removed {} old Dalamud versions, {} bytes reclaimed",
+                    pruned.removed.len(),
+                    pruned.total_bytes
+                ),
+                Ok(_) => {}
+                Err(e) => warn!("Failed to remove old Dalamud versions: {}", e),
+            }
+        }
 
         info!("Dalamud update completed");
     } else {
@@ -744,59 +1729,164 @@ async fn setup_dalamud(config: &LaunchConfig) -> Result<String, String> {
 
         // Download and extract the package
         let temp_path = format!("{}/asset_package_temp.zip", config.dalamud_path);
-        download_file(&client, &asset_info.package_url, &temp_path).await?;
+        let package_url = sources.download_url(&asset_info.package_url);
+        if let Some(progress) = progress {
+            progress.report(
+                LaunchStage::DalamudDownload,
+                format!("Downloading Dalamud assets {}", asset_info.version),
+            );
+        }
+        let mut tracker =
+            progress.map(|p| p.download(format!("Dalamud assets {}", asset_info.version)));
+        download_file(
+            client,
+            &package_url,
+            &temp_path,
+            tracker.as_mut(),
+            &sources.retry,
+            cancel,
+        )
+        .await
+        .map_err(|e| cancelled_or(LauncherError::Dalamud(e), cancel))?;
 
         // Extract package to assets directory
         let assets_dir = format!("{}/dalamudAssets", config.dalamud_path);
-        extract_zip(&temp_path, &assets_dir)?;
-        fs::remove_file(&temp_path).map_err(|e| format!("Failed to remove temp file: {}", e))?;
+        extract_in_background(
+            &temp_path,
+            &assets_dir,
+            progress,
+            format!("Extracting Dalamud assets {}", asset_info.version),
+            cancel,
+        )
+        .await?;
+        fs::remove_file(&temp_path)
+            .map_err(|e| LauncherError::Dalamud(format!("Failed to remove temp file: {}", e)))?;
 
         // Verify all required files exist and check hashes
-        for asset in &asset_info.assets {
-            let target_path = format!("{}/dalamudAssets/{}", config.dalamud_path, asset.file_name);
-            if !Path::new(&target_path).exists() {
-                error!(
-                    "Required asset file not found after extraction: {}",
-                    asset.file_name
-                );
-                return Err(format!("Missing required asset file: {}", asset.file_name));
-            }
-
-            if let Some(expected_hash) = &asset.hash {
-                let contents = fs::read(&target_path)
-                    .map_err(|e| format!("Failed to read file {}: {}", asset.file_name, e))?;
-
-                let mut hasher = Sha1::new();
-                hasher.update(&contents);
-                let file_hash = hex::encode(hasher.finalize()).to_uppercase();
-
-                if file_hash != *expected_hash {
-                    error!(
-                        "Hash mismatch for {}: expected {}, got {}",
-                        asset.file_name, expected_hash, file_hash
-                    );
-                    return Err(format!("Hash verification failed for {}", asset.file_name));
-                }
-            }
-        }
+        let assets = asset_info.assets.clone();
+        run_blocking(move || verify_assets(&assets_dir, &assets))
+            .await
+            .map_err(LauncherError::Dalamud)?;
 
         // Update version file
         fs::write(&asset_ver_path, asset_info.version.to_string())
-            .map_err(|e| format!("Failed to write asset version: {}", e))?;
+            .map_err(|e| LauncherError::Dalamud(format!("Failed to write asset version: {}", e)))?;
 
         info!("Asset update completed");
     } else {
         info!("Assets are up to date");
     }
 
+    // Dalamud runs on its own .NET install rather than whatever the system has
+    if version_info.runtime_required {
+        let runtime_path = format!("{}/runtime", config.dalamud_path);
+        ensure_dalamud_runtime(
+            client,
+            &sources,
+            &runtime_path,
+            &version_info.runtime_version,
+            progress,
+            cancel,
+        )
+        .await?;
+    }
+
+    verify_dalamud_install(config, &current_version_path)?;
+
+    let check = DalamudCheck {
+        assembly_version: version_info.assembly_version.clone(),
+        asset_version: asset_info.version,
+        checked_at: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs(),
+        version_info_url: sources.version_info_url.clone(),
+    };
+    // Only fast launches read it, a launch that can't write it just checks again next time
+    if let Err(e) = save_dalamud_check(config, &check) {
+        warn!("{}", e);
+    }
+
+    info!("Dalamud setup completed in {:.2?}", start_time.elapsed());
+    Ok(DalamudSetup {
+        version_info,
+        offline: false,
+    })
+}
+
+fn save_dalamud_check(config: &LaunchConfig, check: &DalamudCheck) -> Result<(), LauncherError> {
+    let json = serde_json::to_string(check)
+        .map_err(|e| LauncherError::Dalamud(format!("Failed to serialize Dalamud check: {}", e)))?;
+    fs::write(
+        format!("{}/{}", config.dalamud_path, DALAMUD_CHECK_FILE),
+        json,
+    )
+    .map_err(|e| LauncherError::Dalamud(format!("Failed to save Dalamud check: {}", e)))
+}
+
+// With fast launch on, the installed Dalamud as of the last check if that is recent enough
+// and nothing changed on disk since
+fn recent_dalamud_check(
+    config: &LaunchConfig,
+    base_path: &str,
+    sources: &DalamudSources,
+) -> Option<DalamudVersionInfo> {
+    if !config.fast_launch {
+        return None;
+    }
+    let contents =
+        fs::read_to_string(format!("{}/{}", config.dalamud_path, DALAMUD_CHECK_FILE)).ok()?;
+    let check = serde_json::from_str::<DalamudCheck>(&contents).ok()?;
+
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    let age = now.saturating_sub(check.checked_at);
+    if age > config.fast_launch_ttl_minutes * 60
+        || check.version_info_url != sources.version_info_url
+    {
+        info!("Last Dalamud check is {}s old, checking again", age);
+        return None;
+    }
+
+    let asset_version =
+        fs::read_to_string(format!("{}/dalamudAssets/asset.ver", config.dalamud_path))
+            .ok()?
+            .trim()
+            .parse::<i32>()
+            .ok()?;
+    let version_path = format!("{}/Hooks/{}", base_path, check.assembly_version);
+    let version_info = fs::read_to_string(format!("{}/version.json", version_path))
+        .ok()
+        .and_then(|contents| serde_json::from_str::<DalamudVersionInfo>(&contents).ok())?;
+    if asset_version != check.asset_version
+        || version_info.assembly_version != check.assembly_version
+    {
+        return None;
+    }
+
+    let runtime_path = format!("{}/runtime", config.dalamud_path);
+    if version_info.runtime_required
+        && !is_runtime_installed(&runtime_path, &version_info.runtime_version)
+    {
+        return None;
+    }
+    Some(version_info)
+}
+
+fn verify_dalamud_install(
+    config: &LaunchConfig,
+    current_version_path: &str,
+) -> Result<(), LauncherError> {
     // Verify critical files exist
     let injector_path = format!("{}/Dalamud.Injector.exe", current_version_path);
     if !Path::new(&injector_path).exists() {
         error!("Dalamud injector not found at: {}", injector_path);
-        return Err(format!(
+        return Err(LauncherError::Dalamud(format!(
             "Dalamud injector not found at {}. Please ensure Dalamud is properly installed.",
             injector_path
-        ));
+        )));
     }
 
     let fasm_dll = format!(
@@ -810,10 +1900,10 @@ async fn setup_dalamud(config: &LaunchConfig) -> Result<String, String> {
     );
     if !Path::new(&fasm_dll).exists() {
         error!("FASM DLL not found at: {}", fasm_dll);
-        return Err(format!(
+        return Err(LauncherError::Dalamud(format!(
             "FASM DLL not found at {}. Please ensure Dalamud is properly installed.",
             fasm_dll
-        ));
+        )));
     }
 
     // Handle font files
@@ -839,12 +1929,150 @@ async fn setup_dalamud(config: &LaunchConfig) -> Result<String, String> {
         }
     }
 
-    let elapsed = start_time.elapsed();
-    info!("Dalamud setup completed in {:.2?}", elapsed);
-    Ok(format!("Dalamud setup completed in {:.2?}", elapsed))
+    Ok(())
+}
+
+// Newest installed Dalamud that still passes its integrity check
+fn find_cached_dalamud(base_path: &str) -> Option<DalamudVersionInfo> {
+    let entries = fs::read_dir(format!("{}/Hooks", base_path)).ok()?;
+
+    entries
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let version_path = entry.path();
+            let contents = fs::read_to_string(version_path.join("version.json")).ok()?;
+            let version_info = serde_json::from_str::<DalamudVersionInfo>(&contents).ok()?;
+            let intact = check_dalamud_integrity(&version_path.to_string_lossy()).ok()?;
+            intact.then_some(version_info)
+        })
+        .max_by(|a, b| compare_versions(&a.assembly_version, &b.assembly_version))
+}
+
+// Last check before Dalamud is started. A runtime that doesn't match the Dalamud build only
+// shows up as a hostfxr error from inside the game
+pub(crate) fn check_dalamud_runtime(
+    config: &LaunchConfig,
+    version_info: &DalamudVersionInfo,
+) -> Result<(), LauncherError> {
+    if !version_info.runtime_required {
+        return Ok(());
+    }
+    let runtime_path = format!("{}/runtime", config.dalamud_path);
+    if is_runtime_installed(&runtime_path, &version_info.runtime_version) {
+        return Ok(());
+    }
+
+    let installed = fs::read_to_string(format!("{}/version", runtime_path))
+        .map(|v| v.trim().to_string())
+        .ok()
+        .filter(|v| !v.is_empty());
+    Err(LauncherError::Dalamud(match installed {
+        Some(installed) => format!(
+            "Dalamud {} needs .NET runtime {}, but {} is installed",
+            version_info.assembly_version, version_info.runtime_version, installed
+        ),
+        None => format!(
+            "Dalamud {} needs .NET runtime {}, which is missing or incomplete",
+            version_info.assembly_version, version_info.runtime_version
+        ),
+    }))
 }
 
-async fn download_file(client: &Client, url: &str, path: &str) -> Result<(), String> {
+fn is_runtime_installed(runtime_path: &str, version: &str) -> bool {
+    let installed_version = fs::read_to_string(format!("{}/version", runtime_path))
+        .map(|v| v.trim().to_string())
+        .unwrap_or_default();
+    if installed_version != version {
+        return false;
+    }
+
+    ["Microsoft.NETCore.App", "Microsoft.WindowsDesktop.App"]
+        .iter()
+        .all(|pack| Path::new(&format!("{}/shared/{}/{}", runtime_path, pack, version)).exists())
+        && Path::new(&format!("{}/host/fxr", runtime_path)).exists()
+}
+
+async fn ensure_dalamud_runtime(
+    client: &Client,
+    sources: &DalamudSources,
+    runtime_path: &str,
+    version: &str,
+    progress: Option<&LaunchProgress>,
+    cancel: &CancellationToken,
+) -> Result<(), LauncherError> {
+    if is_runtime_installed(runtime_path, version) {
+        info!(".NET runtime {} is up to date", version);
+        return Ok(());
+    }
+
+    info!("Installing .NET runtime {} to {}", version, runtime_path);
+    if let Some(progress) = progress {
+        progress.report(
+            LaunchStage::DalamudDownload,
+            format!("Downloading .NET runtime {}", version),
+        );
+    }
+
+    // Start from a clean folder so packs from an older runtime don't linger
+    if Path::new(runtime_path).exists() {
+        fs::remove_dir_all(runtime_path)
+            .map_err(|e| LauncherError::Dalamud(format!("Failed to remove old runtime: {}", e)))?;
+    }
+    fs::create_dir_all(runtime_path).map_err(|e| {
+        LauncherError::Dalamud(format!("Failed to create runtime directory: {}", e))
+    })?;
+
+    for pack in ["DotNet", "WindowsDesktop"] {
+        let url = sources.runtime_url(pack, version);
+        let temp_path = format!("{}/{}_temp.zip", runtime_path, pack.to_lowercase());
+        let mut tracker =
+            progress.map(|p| p.download(format!(".NET runtime {} ({})", version, pack)));
+        download_file(
+            client,
+            &url,
+            &temp_path,
+            tracker.as_mut(),
+            &sources.retry,
+            cancel,
+        )
+        .await
+        .map_err(|e| cancelled_or(LauncherError::Dalamud(e), cancel))?;
+
+        extract_in_background(
+            &temp_path,
+            runtime_path,
+            progress,
+            format!("Extracting .NET runtime {} ({})", version, pack),
+            cancel,
+        )
+        .await?;
+        fs::remove_file(&temp_path)
+            .map_err(|e| LauncherError::Dalamud(format!("Failed to remove temp file: {}", e)))?;
+    }
+
+    // Only written once both packs are in place, so an interrupted install is retried
+    fs::write(format!("{}/version", runtime_path), version)
+        .map_err(|e| LauncherError::Dalamud(format!("Failed to write runtime version: {}", e)))?;
+
+    if !is_runtime_installed(runtime_path, version) {
+        return Err(LauncherError::Dalamud(format!(
+            ".NET runtime {} is incomplete after extraction",
+            version
+        )));
+    }
+
+    info!(".NET runtime {} installed", version);
+    Ok(())
+}
+
+pub(crate) async fn download_file(
+    client: &Client,
+    url: &str,
+    path: &str,
+    mut tracker: Option<&mut DownloadTracker<'_>>,
+    retry_policy: &RetryPolicy,
+    cancel: &CancellationToken,
+) -> Result<(), String> {
     info!("Starting download from: {}", url);
 
     let mut current_url = url.to_string();
@@ -854,11 +2082,12 @@ async fn download_file(client: &Client, url: &str, path: &str) -> Result<(), Str
     while retries < MAX_RETRIES {
         info!("Attempting download from: {}", current_url);
 
-        let response = client
-            .get(&current_url)
-            .timeout(Duration::from_secs(300))
-            .send()
+        let request = retry::send(retry_policy, "Download", || {
+            client.get(&current_url).timeout(Duration::from_secs(300))
+        });
+        let mut response = with_cancel(cancel, request)
             .await
+            .map_err(|e| e.to_string())?
             .map_err(|e| format!("Failed to download file: {}", e))?;
 
         // Check if we got redirected
@@ -876,14 +2105,54 @@ async fn download_file(client: &Client, url: &str, path: &str) -> Result<(), Str
 
         // If we got a successful response, download the file
         if response.status().is_success() {
+            if let Some(length) = response.content_length() {
+                let name = Path::new(path)
+                    .file_name()
+                    .map(|n| n.to_string_lossy().to_string())
+                    .unwrap_or_else(|| path.to_string());
+                disk_space::ensure_free_space(
+                    Path::new(path),
+                    length,
+                    &format!("downloading {}", name),
+                )?;
+            }
             info!("Download started, writing to: {}", path);
-            let bytes = response
-                .bytes()
-                .await
-                .map_err(|e| format!("Failed to get response bytes: {}", e))?;
+            let mut file =
+                fs::File::create(path).map_err(|e| format!("Failed to create file: {}", e))?;
+            if let Some(tracker) = tracker.as_deref_mut() {
+                tracker.start(response.content_length());
+            }
 
-            fs::write(path, bytes).map_err(|e| format!("Failed to write file: {}", e))?;
+            // Read chunk by chunk so a cancelled launch stops the download promptly
+            loop {
+                let chunk = match with_cancel(cancel, response.chunk()).await {
+                    Ok(chunk) => {
+                        chunk.map_err(|e| format!("Failed to get response bytes: {}", e))?
+                    }
+                    Err(e) => {
+                        drop(file);
+                        let _ = fs::remove_file(path);
+                        return Err(e.to_string());
+                    }
+                };
+                let Some(chunk) = chunk else {
+                    break;
+                };
+                file.write_all(&chunk)
+                    .map_err(|e| format!("Failed to write file: {}", e))?;
+                if let Some(tracker) = tracker.as_deref_mut() {
+                    tracker.advance(chunk.len() as u64);
+                }
+                if let Err(e) = with_cancel(cancel, throttle::consume(chunk.len() as u64)).await {
+                    drop(file);
+                    let _ = fs::remove_file(path);
+                    return Err(e.to_string());
+                }
+            }
 
+            if let Some(tracker) = tracker.as_deref_mut() {
+                tracker.finish();
+            }
             info!("Download completed successfully");
             return Ok(());
         }
@@ -898,19 +2167,127 @@ async fn download_file(client: &Client, url: &str, path: &str) -> Result<(), Str
     Err(format!("Too many redirects while downloading from {}", url))
 }
 
-fn extract_zip(zip_path: &str, extract_path: &str) -> Result<(), String> {
-    use std::fs::File;
-    use zip::ZipArchive;
+// Unpacking a download is blocking IO too, the tracker is made on the worker thread
+async fn extract_in_background(
+    zip_path: &str,
+    extract_path: &str,
+    progress: Option<&LaunchProgress>,
+    label: String,
+    cancel: &CancellationToken,
+) -> Result<(), LauncherError> {
+    let zip_path = zip_path.to_string();
+    let extract_path = extract_path.to_string();
+    let progress = progress.cloned();
+    let task_cancel = cancel.clone();
+    run_blocking(move || {
+        let mut tracker = progress.as_ref().map(|p| p.download(label));
+        extract_zip(&zip_path, &extract_path, tracker.as_mut(), &task_cancel)
+    })
+    .await
+    .map_err(|e| cancelled_or(LauncherError::Dalamud(e), cancel))
+}
+
+// Hashing reads whole files, keep it off the async runtime so the UI stays responsive
+pub(crate) async fn run_blocking<T, F>(f: F) -> Result<T, String>
+where
+    T: Send + 'static,
+    F: FnOnce() -> Result<T, String> + Send + 'static,
+{
+    tauri::async_runtime::spawn_blocking(f)
+        .await
+        .map_err(|e| format!("Background task failed: {}", e))?
+}
+
+pub(crate) fn sha1_file(path: &str) -> Result<String, IoError> {
+    hash_file(path, HashAlgorithm::Sha1)
+}
+
+// Lowercase hex
+pub(crate) fn hash_file(path: &str, algorithm: HashAlgorithm) -> Result<String, IoError> {
+    match algorithm {
+        HashAlgorithm::Sha1 => digest_file::<Sha1>(path),
+        HashAlgorithm::Sha256 => digest_file::<Sha256>(path),
+    }
+}
+
+fn digest_file<D: Digest>(path: &str) -> Result<String, IoError> {
+    let mut file = fs::File::open(path)?;
+    let mut hasher = D::new();
+    let mut buffer = vec![0u8; 64 * 1024];
+    loop {
+        let read = file.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+    }
+    Ok(hex::encode(hasher.finalize()))
+}
+
+fn verify_assets(assets_dir: &str, assets: &[AssetFile]) -> Result<(), String> {
+    for asset in assets {
+        let target_path = format!("{}/{}", assets_dir, asset.file_name);
+        if !Path::new(&target_path).exists() {
+            error!(
+                "Required asset file not found after extraction: {}",
+                asset.file_name
+            );
+            return Err(format!("Missing required asset file: {}", asset.file_name));
+        }
+
+        if let Some(expected_hash) = &asset.hash {
+            let algorithm = asset.hash_algorithm(expected_hash);
+            let file_hash = hash_file(&target_path, algorithm)
+                .map_err(|e| format!("Failed to read file {}: {}", asset.file_name, e))?;
 
-    let file = File::open(zip_path).map_err(|e| format!("Failed to open zip file: {}", e))?;
+            // The meta has used both upper and lowercase hex
+            if !file_hash.eq_ignore_ascii_case(expected_hash) {
+                error!(
+                    "{:?} mismatch for {}: expected {}, got {}",
+                    algorithm, asset.file_name, expected_hash, file_hash
+                );
+                return Err(format!("Hash verification failed for {}", asset.file_name));
+            }
+        }
+    }
 
-    let mut archive =
-        ZipArchive::new(file).map_err(|e| format!("Failed to read zip archive: {}", e))?;
+    Ok(())
+}
 
-    archive
-        .extract(extract_path)
-        .map_err(|e| format!("Failed to extract zip: {}", e))?;
+// Every file in the version folder by its path relative to it, in the format
+// check_dalamud_integrity reads
+fn write_dalamud_hashes(path: &str) -> Result<(), String> {
+    let root = Path::new(path);
+    let mut hashes = HashMap::new();
+    for entry in WalkDir::new(root)
+        .into_iter()
+        .filter_map(|entry| entry.ok())
+    {
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        let relative = entry
+            .path()
+            .strip_prefix(root)
+            .map_err(|e| format!("Failed to hash {}: {}", entry.path().display(), e))?
+            .to_string_lossy()
+            .replace('\\', "/");
+        if relative == "hashes.json" {
+            continue;
+        }
+        let hash = sha1_file(&entry.path().to_string_lossy())
+            .map_err(|e| format!("Failed to read file {}: {}", relative, e))?;
+        hashes.insert(relative, hash);
+    }
+    if hashes.is_empty() {
+        return Err(format!("No Dalamud files found in {}", path));
+    }
 
+    let contents = serde_json::to_string_pretty(&hashes)
+        .map_err(|e| format!("Failed to serialize hashes.json: {}", e))?;
+    fs::write(format!("{}/hashes.json", path), contents)
+        .map_err(|e| format!("Failed to write hashes.json: {}", e))?;
+    info!("Wrote hashes for {} Dalamud files", hashes.len());
     Ok(())
 }
 
@@ -929,17 +2306,15 @@ fn check_dalamud_integrity(path: &str) -> Result<bool, String> {
     for (file, hash) in hashes {
         let file_path = format!("{}/{}", path, file);
         if !Path::new(&file_path).exists() {
+            warn!("Dalamud file {} is missing", file);
             return Ok(false);
         }
 
-        let contents =
-            fs::read(&file_path).map_err(|e| format!("Failed to read file {}: {}", file, e))?;
+        let file_hash =
+            sha1_file(&file_path).map_err(|e| format!("Failed to read file {}: {}", file, e))?;
 
-        let mut hasher = Sha1::new();
-        hasher.update(&contents);
-        let file_hash = hex::encode(hasher.finalize());
-
-        if file_hash != hash {
+        if !file_hash.eq_ignore_ascii_case(&hash) {
+            warn!("Dalamud file {} does not match its hash", file);
             return Ok(false);
         }
     }
@@ -947,72 +2322,53 @@ fn check_dalamud_integrity(path: &str) -> Result<bool, String> {
     Ok(true)
 }
 
-#[cfg(windows)]
-async fn inject_dalamud(config: &LaunchConfig, sid: &str) -> Result<String, String> {
-    // Get Dalamud version info first to construct correct paths
-    let client = Client::new();
-    let version_info = check_dalamud_version(&client, false).await?;
-    info!("Using Dalamud version: {}", version_info.assembly_version);
-
-    // Normalize base path for injection
-    let base_path =
-        if config.dalamud_path.ends_with("/addon") || config.dalamud_path.ends_with("\\addon") {
-            config.dalamud_path.clone()
-        } else {
-            format!("{}/addon", config.dalamud_path)
-        };
-    info!("Using Dalamud base path for injection: {}", base_path);
+#[cfg(not(windows))]
+pub(crate) async fn inject_dalamud(
+    _config: &LaunchConfig,
+    _version_info: &DalamudVersionInfo,
+    _pid: u32,
+    _cancel: &CancellationToken,
+) -> Result<DalamudLaunch, LauncherError> {
+    Err(LauncherError::Dalamud(
+        "Dalamud injection is only supported on Windows".to_string(),
+    ))
+}
 
-    // Construct version-specific paths
-    let version_path = format!("{}/Hooks/{}", base_path, version_info.assembly_version);
+#[cfg(windows)]
+pub(crate) async fn inject_dalamud(
+    config: &LaunchConfig,
+    version_info: &DalamudVersionInfo,
+    pid: u32,
+    cancel: &CancellationToken,
+) -> Result<DalamudLaunch, LauncherError> {
+    info!("Using Dalamud version: {}", version_info.assembly_version);
+    let version_path = dalamud_version_path(config, version_info);
     let injector_path = format!("{}/Dalamud.Injector.exe", version_path);
     info!("Using version-specific injector at: {}", injector_path);
 
-    // Wait for the configured injection delay
-    if config.injection_delay > 0 {
-        info!(
-            "Waiting {}ms before injecting Dalamud",
-            config.injection_delay
-        );
-        tokio::time::sleep(tokio::time::Duration::from_millis(config.injection_delay)).await;
-    }
-
-    let start_info = DalamudStartInfo {
-        working_directory: version_path.clone(), // Use version-specific path
-        configuration_path: format!("{}/config", config.dalamud_path),
-        plugin_directory: format!("{}/installedPlugins", config.dalamud_path),
-        asset_directory: format!("{}/dalamudAssets", config.dalamud_path),
-        client_language: config.language,
-        delay_initialize: false,
-        game_version: get_game_version(&config.game_path)?,
-        logging_path: format!("{}/logs", config.dalamud_path),
-        troubleshooting_pack: Some("{}".to_string()),
-        delay_initialize_ms: config.injection_delay as i32,
-    };
-
-    let start_info_json = serde_json::to_string(&start_info)
-        .map_err(|e| format!("Failed to serialize start info: {}", e))?;
-
-    let start_info_b64 = base64::encode(start_info_json.as_bytes());
-    info!("Dalamud start info (base64): {}", start_info_b64);
+    let game_version = read_game_version(&config.game_path).map_err(LauncherError::Patch)?;
+    // The injector takes its paths as arguments, the pack only carries the troubleshooting data
+    let tspack_json =
+        serde_json::to_string(&TroubleshootingInfo::collect(config)).map_err(|e| {
+            LauncherError::Dalamud(format!("Failed to serialize troubleshooting info: {}", e))
+        })?;
+    let tspack_b64 = base64::encode(tspack_json.as_bytes());
+    info!(
+        "Dalamud injection prepared for game version {}",
+        game_version
+    );
 
     if !Path::new(&injector_path).exists() {
         error!("Dalamud injector not found at: {}", injector_path);
-        return Err(format!(
+        return Err(LauncherError::Dalamud(format!(
             "Dalamud injector not found at {}. Please ensure Dalamud is properly installed.",
             injector_path
-        ));
+        )));
     }
     info!("Verified injector exists at: {}", injector_path);
 
-    let game_path = if config.dx11 {
-        format!("{}/game/ffxiv_dx11.exe", config.game_path)
-    } else {
-        format!("{}/game/ffxiv.exe", config.game_path)
-    };
-
     // Prepare all argument strings
-    let game_arg = format!("--game={}", game_path);
+    let pid_arg = pid.to_string();
     let working_dir_arg = format!("--dalamud-working-directory={}", version_path); // Use version-specific path
     let config_path_arg = format!(
         "--dalamud-configuration-path={}/config",
@@ -1028,23 +2384,13 @@ async fn inject_dalamud(config: &LaunchConfig, sid: &str) -> Result<String, Stri
     );
     let log_path_arg = format!("--logpath={}/logs", config.dalamud_path);
     let lang_arg = format!("--dalamud-client-language={}", config.language);
-    let delay_arg = format!("--dalamud-delay-initialize={}", config.injection_delay);
-    let tspack_arg = format!("--dalamud-tspack-b64={}", start_info_b64);
-
-    // Prepare game arguments
-    let game_args = format!(
-        "DEV.DataPathType=1 DEV.MaxEntitledExpansionID={} DEV.TestSID={} DEV.UseSqPack=1 SYS.Region={} language={}",
-        config.expansion_level,
-        sid,
-        config.region,
-        config.language
-    );
+    let delay_arg = "--dalamud-delay-initialize=0".to_string();
+    let tspack_arg = format!("--dalamud-tspack-b64={}", tspack_b64);
 
-    // Build arguments for entrypoint injection
-    let args = vec![
-        "launch",
-        "--mode=entrypoint",
-        &game_arg,
+    // Load Dalamud into the game the launch started
+    let mut args = vec![
+        "inject",
+        &pid_arg,
         &working_dir_arg,
         &config_path_arg,
         &plugin_dir_arg,
@@ -1053,9 +2399,17 @@ async fn inject_dalamud(config: &LaunchConfig, sid: &str) -> Result<String, Stri
         &lang_arg,
         &delay_arg,
         &tspack_arg,
-        "--", // Separator for game arguments
-        &game_args,
     ];
+    if config.dalamud_safe_mode {
+        info!("Safe mode, Dalamud will not load plugins");
+        args.push("--no-plugin");
+    }
+    // Logged without the troubleshooting pack, which carries account details
+    let logged_args: Vec<&str> = args
+        .iter()
+        .copied()
+        .filter(|arg| *arg != tspack_arg)
+        .collect();
 
     // Set up the command with proper working directory and environment
     let mut command = Command::new(&injector_path);
@@ -1066,69 +2420,212 @@ async fn inject_dalamud(config: &LaunchConfig, sid: &str) -> Result<String, Stri
         .stderr(Stdio::piped());
 
     // Add DALAMUD_RUNTIME environment variable if needed
-    let runtime_path = format!("{}/runtime", config.dalamud_path);
-    if Path::new(&runtime_path).exists() {
+    if let Some(runtime_path) = dalamud_runtime_path(config) {
         info!("Setting DALAMUD_RUNTIME to: {}", runtime_path);
         command.env("DALAMUD_RUNTIME", &runtime_path);
-        command.env("__COMPAT_LAYER", "RunAsInvoker HighDPIAware");
     }
 
-    info!("Running Dalamud injector with command: {:?}", command);
+    // Last point the launch can be abandoned before Dalamud is in the game
+    if cancel.is_cancelled() {
+        return Err(LauncherError::Cancelled);
+    }
+
+    info!(
+        "Running Dalamud injector {} {}",
+        injector_path,
+        logged_args.join(" ")
+    );
 
     let output = command
         .output()
-        .map_err(|e| format!("Failed to run injector: {}", e))?;
+        .map_err(|e| LauncherError::Dalamud(format!("Failed to run injector: {}", e)))?;
 
     if !output.status.success() {
         let error = String::from_utf8_lossy(&output.stderr);
         let stdout = String::from_utf8_lossy(&output.stdout);
         error!("Injector failed with error: {}", error);
         error!("Injector stdout: {}", stdout);
-        return Err(format!("Injector failed: {}", error));
+        return Err(LauncherError::Dalamud(format!(
+            "Injector failed: {}",
+            error
+        )));
     }
 
     let stdout = String::from_utf8_lossy(&output.stdout);
     info!("Dalamud injector stdout: {}", stdout);
 
     info!("Dalamud injection completed successfully");
-    Ok("Dalamud injection completed successfully".to_string())
+    Ok(DalamudLaunch {
+        version: version_info.assembly_version.clone(),
+    })
 }
 
-fn get_game_version(game_path: &str) -> Result<String, String> {
+// Has Dalamud.Boot rewrite the entrypoint of a game created suspended, so Dalamud loads
+// as soon as the caller resumes it
+#[cfg(windows)]
+pub(crate) fn inject_dalamud_native(
+    config: &LaunchConfig,
+    version_info: &DalamudVersionInfo,
+    game_path: &str,
+    process: &SuspendedProcess,
+) -> Result<DalamudLaunch, LauncherError> {
+    info!(
+        "Injecting Dalamud {} into game process {}",
+        version_info.assembly_version,
+        process.pid()
+    );
+    let version_path = dalamud_version_path(config, version_info);
+    let logging_path = format!("{}/logs", config.dalamud_path);
+    let start_info = BootStartInfo {
+        working_directory: version_path.clone(),
+        configuration_path: format!("{}/config", config.dalamud_path),
+        log_path: logging_path.clone(),
+        plugin_directory: format!("{}/installedPlugins", config.dalamud_path),
+        asset_directory: format!("{}/dalamudAssets", config.dalamud_path),
+        language: config.language,
+        game_version: read_game_version(&config.game_path)
+            .map_err(LauncherError::Patch)?
+            .trim()
+            .to_string(),
+        troubleshooting_pack_data: serde_json::to_string(&TroubleshootingInfo::collect(config))
+            .map_err(|e| {
+                LauncherError::Dalamud(format!("Failed to serialize troubleshooting info: {}", e))
+            })?,
+        delay_initialize_ms: 0,
+        boot_log_path: format!("{}/dalamud.boot.log", logging_path),
+        no_load_plugins: config.dalamud_safe_mode,
+    };
+    if config.dalamud_safe_mode {
+        info!("Safe mode, Dalamud will not load plugins");
+    }
+
+    native_injection::rewrite_entry_point(process, &version_path, game_path, &start_info)
+        .map_err(LauncherError::Dalamud)?;
+    Ok(DalamudLaunch {
+        version: version_info.assembly_version.clone(),
+    })
+}
+
+// The addon folder inside the Dalamud folder, unless the setting already points at it
+fn dalamud_addon_path(dalamud_path: &str) -> String {
+    if dalamud_path.ends_with("/addon") || dalamud_path.ends_with("\\addon") {
+        dalamud_path.to_string()
+    } else {
+        format!("{}/addon", dalamud_path)
+    }
+}
+
+// Hooks/<version> under the addon folder, where the injector and Dalamud.Boot live
+#[cfg(windows)]
+fn dalamud_version_path(config: &LaunchConfig, version_info: &DalamudVersionInfo) -> String {
+    format!(
+        "{}/Hooks/{}",
+        dalamud_addon_path(&config.dalamud_path),
+        version_info.assembly_version
+    )
+}
+
+// The .NET runtime Dalamud downloads, handed to the game in DALAMUD_RUNTIME
+#[cfg(windows)]
+pub(crate) fn dalamud_runtime_path(config: &LaunchConfig) -> Option<String> {
+    let runtime_path = format!("{}/runtime", config.dalamud_path);
+    Path::new(&runtime_path).exists().then_some(runtime_path)
+}
+
+pub(crate) fn read_game_version(game_path: &str) -> Result<String, String> {
     let ver_path = format!("{}/game/ffxivgame.ver", game_path);
     fs::read_to_string(&ver_path).map_err(|e| format!("Failed to read game version: {}", e))
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-struct DalamudStartInfo {
-    working_directory: String,
-    configuration_path: String,
-    plugin_directory: String,
-    asset_directory: String,
-    client_language: u32,
-    delay_initialize: bool,
-    game_version: String,
-    logging_path: String,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    troubleshooting_pack: Option<String>,
-    delay_initialize_ms: i32,
-}
-
-impl Default for DalamudStartInfo {
-    fn default() -> Self {
-        Self {
-            working_directory: String::new(),
-            configuration_path: String::new(),
-            plugin_directory: String::new(),
-            asset_directory: String::new(),
-            client_language: 1,
-            delay_initialize: false,
-            game_version: String::new(),
-            logging_path: String::new(),
-            troubleshooting_pack: None,
-            delay_initialize_ms: 0,
-        }
+#[derive(Debug, Clone, Serialize)]
+pub struct GameVersionReport {
+    pub boot: String,
+    pub game: String,
+    pub expansions: Vec<ExpansionVersion>,
+    // Newest intact Dalamud install, when a Dalamud folder was given
+    pub dalamud: Option<DalamudCompatibility>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DalamudCompatibility {
+    pub version: String,
+    pub supported_game_ver: String,
+    // Dalamud refuses to load into a game build it wasn't made for
+    pub matches_game: bool,
+}
+
+#[tauri::command]
+pub async fn get_game_version(
+    game_path: String,
+    expansion_level: Option<u32>,
+    dalamud_path: Option<String>,
+) -> Result<GameVersionReport, String> {
+    if !Path::new(&game_path).join("game").is_dir() {
+        return Err(format!("No game install found at {}", game_path));
     }
+
+    run_blocking(move || {
+        let expansion_level =
+            expansion_level.unwrap_or_else(|| game_detection::detect_expansion_level(&game_path));
+        let versions = version_check::read_installed_versions(&game_path, expansion_level);
+
+        let dalamud = dalamud_path
+            .filter(|path| !path.is_empty())
+            .and_then(|path| find_cached_dalamud(&dalamud_addon_path(&path)))
+            .map(|info| DalamudCompatibility {
+                matches_game: info.game_mismatch(&versions.game).is_none(),
+                version: info.assembly_version,
+                supported_game_ver: info.supported_game_ver,
+            });
+
+        Ok(GameVersionReport {
+            boot: versions.boot,
+            game: versions.game,
+            expansions: versions.expansions,
+            dalamud,
+        })
+    })
+    .await
+}
+
+#[derive(Debug, Serialize)]
+pub struct DalamudChangelogReport {
+    pub track: String,
+    pub version: String,
+    pub date: String,
+    pub changes: Vec<DalamudChange>,
+    // None when no usable Dalamud is installed yet
+    pub installed_version: Option<String>,
+    pub update_available: bool,
+}
+
+// What the next Dalamud update brings, from the same server a launch would update from
+#[tauri::command]
+pub async fn get_dalamud_changelog(
+    app: AppHandle,
+    mut config: LaunchConfig,
+) -> Result<DalamudChangelogReport, String> {
+    installs::apply(&app, &mut config)?;
+    let client = app.state::<HttpClient>().client();
+    let sources = DalamudSources::from_config(&config);
+    let version_info = check_dalamud_version(&client, &sources, false)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let base_path = dalamud_addon_path(&config.dalamud_path);
+    let installed_version = run_blocking(move || Ok(find_cached_dalamud(&base_path)))
+        .await?
+        .map(|info| info.assembly_version);
+
+    Ok(DalamudChangelogReport {
+        update_available: installed_version.as_deref()
+            != Some(version_info.assembly_version.as_str()),
+        installed_version,
+        track: version_info.track,
+        version: version_info.changelog.version,
+        date: version_info.changelog.date,
+        changes: version_info.changelog.changes,
+    })
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -1136,6 +2633,15 @@ pub struct Headlines {
     pub news: Vec<News>,
     pub topics: Vec<News>,
     pub pinned: Vec<News>,
+    // Set when frontier was unreachable and this is the last saved copy
+    #[serde(default)]
+    pub cached: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Banners {
+    pub banners: Vec<Banner>,
+    pub cached: bool,
 }
 
 fn deserialize_string_or_number<'de, D>(deserializer: D) -> Result<Option<i32>, D::Error>
@@ -1190,84 +2696,1090 @@ pub struct News {
     pub tag: Option<String>,
 }
 
-#[tauri::command]
-pub async fn get_news(language: u32, force_na: bool) -> Result<Headlines, String> {
-    let unix_timestamp = SystemTime::now()
-        .duration_since(UNIX_EPOCH)
-        .unwrap()
-        .as_millis();
+// Frontier serves every region from one host, the region only decides which feed is home
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum NewsRegion {
+    Japan,
+    NorthAmerica,
+    Europe,
+}
+
+impl NewsRegion {
+    // Numbered like SYS.Region. force_na wins so EU accounts can read the NA feed
+    fn resolve(region: Option<u32>, language: u32, force_na: bool) -> Self {
+        if force_na {
+            return NewsRegion::NorthAmerica;
+        }
+        match region {
+            Some(1) => NewsRegion::Japan,
+            Some(2) => NewsRegion::NorthAmerica,
+            Some(3) => NewsRegion::Europe,
+            _ if language == 0 => NewsRegion::Japan,
+            _ => NewsRegion::Europe,
+        }
+    }
+
+    fn english_locale(self) -> &'static str {
+        match self {
+            NewsRegion::NorthAmerica => "en-us",
+            // The official launcher shows JP accounts the EU English feed as well
+            NewsRegion::Japan | NewsRegion::Europe => "en-gb",
+        }
+    }
+
+    fn home_locale(self) -> &'static str {
+        match self {
+            NewsRegion::Japan => "ja-jp",
+            NewsRegion::NorthAmerica | NewsRegion::Europe => self.english_locale(),
+        }
+    }
+}
 
-    let lang_code = match language {
-        1 => "en-us",
+// Locales frontier is asked for, in order: override, game language, region home, NA English
+fn news_locales(language: u32, region: NewsRegion, locale_override: Option<&str>) -> Vec<String> {
+    let english = region.english_locale();
+    let language_locale = match language {
+        0 => "ja-jp",
+        1 => english,
         2 => "de-de",
         3 => "fr-fr",
-        _ => "en-us",
+        4 => "ko-kr",
+        5 => "zh-cn",
+        _ => english,
     };
 
-    let url = format!(
-        "https://frontier.ffxiv.com/news/headline.json?lang={}&media=pcapp&_={}",
-        lang_code, unix_timestamp
-    );
+    let mut locales: Vec<String> = Vec::new();
+    let candidates = locale_override
+        .filter(|l| !l.is_empty())
+        .into_iter()
+        .chain([language_locale, region.home_locale(), "en-us"]);
+    for locale in candidates {
+        let locale = locale.to_lowercase();
+        if !locales.contains(&locale) {
+            locales.push(locale);
+        }
+    }
+    locales
+}
 
-    let client = Client::new();
-    let resp = client
-        .get(&url)
-        .header("User-Agent", get_user_agent())
-        .send()
-        .await
-        .map_err(|e| format!("Failed to get news: {}", e))?;
+// Tries each locale until one answers with something to show. The flag is set when the
+// answer came from the disk cache because frontier couldn't be reached
+async fn fetch_localized<T: DeserializeOwned>(
+    cache_dir: Option<&Path>,
+    client: &Client,
+    feed: &str,
+    locales: &[String],
+    url_for: impl Fn(&str) -> String,
+    has_content: impl Fn(&T) -> bool,
+) -> Result<(T, bool), String> {
+    let mut last_error = "No locales to fetch".to_string();
+
+    for (i, locale) in locales.iter().enumerate() {
+        let cached = news_cache::load(cache_dir, feed, locale);
+
+        // Don't keep the launcher waiting on a slow frontier when there's something to show
+        let timeout = if cached.is_some() {
+            CACHED_NEWS_TIMEOUT
+        } else {
+            REQUEST_TIMEOUT
+        };
+        let mut request = client
+            .get(url_for(locale))
+            .header(USER_AGENT, get_user_agent())
+            .timeout(timeout);
+        if let Some(etag) = cached.as_ref().and_then(|c| c.etag.as_deref()) {
+            request = request.header(IF_NONE_MATCH, etag);
+        }
 
-    let text = resp
-        .text()
-        .await
-        .map_err(|e| format!("Failed to get response text: {}", e))?;
+        let response = match request.send().await {
+            Ok(response) => response,
+            Err(e) => {
+                warn!("Failed to fetch {} {}: {}", locale, feed, e);
+                if let Some(value) = cached.and_then(|c| serde_json::from_str(&c.body).ok()) {
+                    info!("Serving cached {} {}", locale, feed);
+                    return Ok((value, true));
+                }
+                last_error = format!("Failed to fetch {} {}: {}", locale, feed, e);
+                continue;
+            }
+        };
 
-    println!("{:?}", text); // Log the response text in plain text
+        let status = response.status();
+        let (text, update) = match cached {
+            Some(cached) if status == StatusCode::NOT_MODIFIED => {
+                debug!("{} {} not modified", locale, feed);
+                (cached.body, None)
+            }
+            _ if !status.is_success() => {
+                warn!("Failed to fetch {} {}: status {}", locale, feed, status);
+                last_error = format!("Failed to fetch {} {}: status {}", locale, feed, status);
+                continue;
+            }
+            _ => {
+                let etag = response
+                    .headers()
+                    .get(ETAG)
+                    .and_then(|v| v.to_str().ok())
+                    .map(str::to_string);
+                let text = response
+                    .text()
+                    .await
+                    .map_err(|e| format!("Failed to get response text: {}", e))?;
+                let update = CachedResponse {
+                    etag,
+                    body: text.clone(),
+                };
+                (text, Some(update))
+            }
+        };
+        debug!("Frontier {} response for {}: {}", feed, locale, text);
 
-    serde_json::from_str(&text).map_err(|e| format!("Failed to parse news JSON: {}", e))
+        match serde_json::from_str::<T>(&text) {
+            Ok(value) if has_content(&value) || i + 1 == locales.len() => {
+                if let Some(update) = update {
+                    news_cache::store(cache_dir, feed, locale, &update);
+                }
+                return Ok((value, false));
+            }
+            Ok(_) => info!("No {} {} available, falling back", locale, feed),
+            Err(e) => {
+                warn!("Failed to parse {} {}: {}", locale, feed, e);
+                last_error = format!("Failed to parse {} {}: {}", locale, feed, e);
+            }
+        }
+    }
+
+    Err(last_error)
 }
 
 #[tauri::command]
-pub async fn get_banners(language: u32, force_na: bool) -> Result<Vec<Banner>, String> {
+pub async fn get_news(
+    app: AppHandle,
+    http: State<'_, HttpClient>,
+    language: u32,
+    force_na: bool,
+    region: Option<u32>,
+    locale_override: Option<String>,
+    endpoints: Option<Endpoints>,
+) -> Result<Headlines, String> {
+    let endpoints = endpoints.unwrap_or_default();
     let unix_timestamp = SystemTime::now()
         .duration_since(UNIX_EPOCH)
         .unwrap()
         .as_millis();
 
-    let lang_code = match language {
-        1 => "en-us",
-        2 => "de-de",
-        3 => "fr-fr",
-        _ => "en-us",
-    };
+    let region = NewsRegion::resolve(region, language, force_na);
+    let locales = news_locales(language, region, locale_override.as_deref());
+    let (mut headlines, cached) = fetch_localized(
+        news_cache::cache_dir(&app).as_deref(),
+        &http.client(),
+        "headlines",
+        &locales,
+        |lang_code| {
+            endpoints.frontier(&format!(
+                "/news/headline.json?lang={}&media=pcapp&_={}",
+                lang_code, unix_timestamp
+            ))
+        },
+        |headlines: &Headlines| {
+            !headlines.news.is_empty()
+                || !headlines.topics.is_empty()
+                || !headlines.pinned.is_empty()
+        },
+    )
+    .await?;
 
-    let url = format!(
-        "https://frontier.ffxiv.com/v2/topics/{}/banner.json?lang={}&media=pcapp&_={}",
-        lang_code, lang_code, unix_timestamp
-    );
+    headlines.cached = cached;
+    Ok(headlines)
+}
+
+#[tauri::command]
+pub async fn get_banners(
+    app: AppHandle,
+    http: State<'_, HttpClient>,
+    language: u32,
+    force_na: bool,
+    region: Option<u32>,
+    locale_override: Option<String>,
+    endpoints: Option<Endpoints>,
+) -> Result<Banners, String> {
+    let endpoints = endpoints.unwrap_or_default();
+    #[derive(Deserialize)]
+    struct BannerRoot {
+        banner: Vec<Banner>,
+    }
+
+    let unix_timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_millis();
 
-    let client = Client::new();
-    let resp = client
-        .get(&url)
-        .header("User-Agent", get_user_agent())
+    let region = NewsRegion::resolve(region, language, force_na);
+    let locales = news_locales(language, region, locale_override.as_deref());
+    let (root, cached): (BannerRoot, bool) = fetch_localized(
+        news_cache::cache_dir(&app).as_deref(),
+        &http.client(),
+        "banners",
+        &locales,
+        |lang_code| {
+            endpoints.frontier(&format!(
+                "/v2/topics/{}/banner.json?lang={}&media=pcapp&_={}",
+                lang_code, lang_code, unix_timestamp
+            ))
+        },
+        |root: &BannerRoot| !root.banner.is_empty(),
+    )
+    .await?;
+
+    Ok(Banners {
+        banners: root.banner,
+        cached,
+    })
+}
+
+// Frontier gets this long to answer when a cached copy can be shown instead
+const CACHED_NEWS_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone, Serialize)]
+pub struct GateStatus {
+    pub open: bool,
+    pub message: Vec<String>,
+    pub news: Vec<String>,
+}
+
+#[tauri::command]
+pub async fn get_gate_status(
+    http: State<'_, HttpClient>,
+    endpoints: Option<Endpoints>,
+) -> Result<GateStatus, String> {
+    fetch_gate_status(&http.client(), &endpoints.unwrap_or_default()).await
+}
+
+pub(crate) async fn fetch_gate_status(
+    client: &Client,
+    endpoints: &Endpoints,
+) -> Result<GateStatus, String> {
+    // The server reports 1 while the gate is open and 0 during maintenance
+    #[derive(Deserialize)]
+    struct GateStatusRoot {
+        status: u8,
+        #[serde(default)]
+        message: Vec<String>,
+        #[serde(default)]
+        news: Vec<String>,
+    }
+
+    let unix_timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_millis();
+
+    let root: GateStatusRoot = client
+        .get(endpoints.frontier(&format!(
+            "/worldStatus/gate_status.json?_={}",
+            unix_timestamp
+        )))
+        .header(USER_AGENT, get_user_agent())
+        .timeout(REQUEST_TIMEOUT)
         .send()
         .await
-        .map_err(|e| format!("Failed to get banners: {}", e))?;
+        .map_err(|e| format!("Failed to get gate status: {}", e))?
+        .json()
+        .await
+        .map_err(|e| format!("Failed to parse gate status: {}", e))?;
+
+    Ok(GateStatus {
+        open: root.status == 1,
+        message: root.message,
+        news: root.news,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::matchers::{body_string_contains, header, method, path, query_param};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    const SID: &str = "0123456789abcdef0123456789abcdef01234567";
+    const TOP_PAGE: &str =
+        r#"<form><input type="hidden" name="_STORED_" value="stored-value"></form>"#;
+
+    fn login_config(server: &MockServer) -> LaunchConfig {
+        let mut config: LaunchConfig = serde_json::from_value(serde_json::json!({
+            "game_path": "",
+            "username": "someone",
+            "password": "hunter2",
+            "otp": null,
+        }))
+        .unwrap();
+        config.endpoints.oauth = server.uri();
+        config.retry = RetryPolicy {
+            max_attempts: 3,
+            base_delay_ms: 1,
+            max_delay_ms: 1,
+        };
+        config
+    }
+
+    fn login_reply(payload: &str) -> ResponseTemplate {
+        ResponseTemplate::new(200).set_body_string(format!(
+            r#"<script>window.external.user("{}");</script>"#,
+            payload
+        ))
+    }
+
+    fn accepted() -> ResponseTemplate {
+        login_reply(&format!(
+            "login=auth,ok,sid,{},terms,1,region,3,etmadd,0,playable,1,ps3pkg,0,maxex,5,product,1",
+            SID
+        ))
+    }
+
+    async fn mount_top(server: &MockServer, response: ResponseTemplate) {
+        Mock::given(method("GET"))
+            .and(path("/top"))
+            .and(query_param("rgn", "3"))
+            .and(query_param("issteam", "0"))
+            .respond_with(response)
+            .mount(server)
+            .await;
+    }
+
+    async fn login(config: &LaunchConfig) -> Result<String, LauncherError> {
+        let client = Client::builder().no_proxy().build().unwrap();
+        get_session_id(&client, config, None, &CancellationToken::new()).await
+    }
+
+    #[tokio::test]
+    async fn logs_in_with_the_stored_value() {
+        let server = MockServer::start().await;
+        mount_top(
+            &server,
+            ResponseTemplate::new(200).set_body_string(TOP_PAGE),
+        )
+        .await;
+        Mock::given(method("POST"))
+            .and(path("/login.send"))
+            .and(body_string_contains("_STORED_=stored-value"))
+            .and(body_string_contains("sqexid=someone"))
+            .and(body_string_contains("password=hunter2"))
+            .respond_with(accepted())
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        assert_eq!(login(&login_config(&server)).await.unwrap(), SID);
+    }
+
+    #[tokio::test]
+    async fn login_page_is_asked_for_the_profile_region() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/top"))
+            .and(query_param("rgn", "1"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(TOP_PAGE))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/login.send"))
+            .respond_with(accepted())
+            .mount(&server)
+            .await;
+
+        let mut config = login_config(&server);
+        config.region = Some(1);
+        assert_eq!(login(&config).await.unwrap(), SID);
+    }
+
+    #[tokio::test]
+    async fn missing_stored_value_stops_before_the_login() {
+        let server = MockServer::start().await;
+        mount_top(
+            &server,
+            ResponseTemplate::new(200).set_body_string("<html>No form here</html>"),
+        )
+        .await;
+        Mock::given(method("POST"))
+            .and(path("/login.send"))
+            .respond_with(accepted())
+            .expect(0)
+            .mount(&server)
+            .await;
+
+        match login(&login_config(&server)).await {
+            Err(LauncherError::Network(message)) => assert!(message.contains("_STORED_")),
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn refused_login_reports_the_message() {
+        let server = MockServer::start().await;
+        mount_top(
+            &server,
+            ResponseTemplate::new(200).set_body_string(TOP_PAGE),
+        )
+        .await;
+        Mock::given(method("POST"))
+            .and(path("/login.send"))
+            .respond_with(login_reply(
+                "login=auth,ng,err,The ID or password entered is incorrect.",
+            ))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        match login(&login_config(&server)).await {
+            Err(LauncherError::Auth(message)) => {
+                assert!(message.contains("The ID or password entered is incorrect."))
+            }
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn busy_login_is_retried() {
+        let server = MockServer::start().await;
+        mount_top(
+            &server,
+            ResponseTemplate::new(200).set_body_string(TOP_PAGE),
+        )
+        .await;
+        Mock::given(method("POST"))
+            .and(path("/login.send"))
+            .respond_with(login_reply(
+                "login=auth,ng,err,The servers are currently busy. Please try again later.",
+            ))
+            .up_to_n_times(1)
+            .with_priority(1)
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/login.send"))
+            .respond_with(accepted())
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        assert_eq!(login(&login_config(&server)).await.unwrap(), SID);
+    }
+
+    #[tokio::test]
+    async fn busy_login_gives_up_after_the_retry_policy() {
+        let server = MockServer::start().await;
+        mount_top(
+            &server,
+            ResponseTemplate::new(200).set_body_string(TOP_PAGE),
+        )
+        .await;
+        Mock::given(method("POST"))
+            .and(path("/login.send"))
+            .respond_with(login_reply("login=auth,ng,err,The servers are busy."))
+            .expect(3)
+            .mount(&server)
+            .await;
+
+        match login(&login_config(&server)).await {
+            Err(LauncherError::Network(message)) => assert!(message.contains("busy")),
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    // The code went out with the first login, nothing can ask for a new one here
+    #[tokio::test]
+    async fn busy_login_with_an_otp_is_not_resent() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/top"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(TOP_PAGE))
+            .expect(1)
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(path("/login.send"))
+            .and(body_string_contains("otppw=123456"))
+            .respond_with(login_reply("login=auth,ng,err,The servers are busy."))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let mut config = login_config(&server);
+        config.otp = Some(Secret::from("123456".to_string()));
+        match login(&config).await {
+            Err(LauncherError::Network(message)) => assert!(message.contains("busy")),
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn unaccepted_terms_are_reported() {
+        let server = MockServer::start().await;
+        mount_top(
+            &server,
+            ResponseTemplate::new(200).set_body_string(TOP_PAGE),
+        )
+        .await;
+        Mock::given(method("POST"))
+            .and(path("/login.send"))
+            .respond_with(login_reply(&format!(
+                "login=auth,ok,sid,{},terms,0,region,3,etmadd,0,playable,1,ps3pkg,0,maxex,5,product,1",
+                SID
+            )))
+            .mount(&server)
+            .await;
+
+        match login(&login_config(&server)).await {
+            Err(LauncherError::TermsOfService { url, .. }) => {
+                assert_eq!(url, TERMS_OF_SERVICE_URL)
+            }
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn terms_link_in_an_error_page_is_not_a_terms_block() {
+        let server = MockServer::start().await;
+        mount_top(
+            &server,
+            ResponseTemplate::new(200).set_body_string(TOP_PAGE),
+        )
+        .await;
+        Mock::given(method("POST"))
+            .and(path("/login.send"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(
+                r#"<p>An error has occurred.</p><footer><a href="https://support.na.square-enix.com/rule.php?id=5382">Terms of Service</a></footer>"#,
+            ))
+            .mount(&server)
+            .await;
+
+        match login(&login_config(&server)).await {
+            Err(LauncherError::Auth(message)) => {
+                assert!(message.contains("Failed to extract session ID"))
+            }
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn server_errors_on_the_top_page_are_retried_then_reported() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/top"))
+            .respond_with(ResponseTemplate::new(503))
+            .expect(3)
+            .mount(&server)
+            .await;
+
+        match login(&login_config(&server)).await {
+            Err(LauncherError::Network(message)) => {
+                assert!(message.contains("Failed to get stored value"))
+            }
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn server_errors_on_the_login_are_reported() {
+        let server = MockServer::start().await;
+        mount_top(
+            &server,
+            ResponseTemplate::new(200).set_body_string(TOP_PAGE),
+        )
+        .await;
+        Mock::given(method("POST"))
+            .and(path("/login.send"))
+            .respond_with(ResponseTemplate::new(500))
+            .expect(3)
+            .mount(&server)
+            .await;
+
+        match login(&login_config(&server)).await {
+            Err(LauncherError::Network(message)) => {
+                assert!(message.contains("Failed to send login request"))
+            }
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn client_errors_on_the_login_are_not_retried() {
+        let server = MockServer::start().await;
+        mount_top(
+            &server,
+            ResponseTemplate::new(200).set_body_string(TOP_PAGE),
+        )
+        .await;
+        Mock::given(method("POST"))
+            .and(path("/login.send"))
+            .respond_with(ResponseTemplate::new(403).set_body_string("Forbidden"))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        assert!(matches!(
+            login(&login_config(&server)).await,
+            Err(LauncherError::Auth(_))
+        ));
+    }
+
+    // "hello" under each algorithm
+    const HELLO_SHA1: &str = "aaf4c61ddcc5e8a2dabede0f3b482cd9aea9434d";
+    const HELLO_SHA256: &str = "2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824";
+
+    fn asset(file_name: &str, hash: &str, algorithm: Option<HashAlgorithm>) -> AssetFile {
+        AssetFile {
+            url: String::new(),
+            file_name: file_name.to_string(),
+            hash: Some(hash.to_string()),
+            hash_algorithm: algorithm,
+        }
+    }
+
+    #[test]
+    fn asset_hash_algorithm_comes_from_the_meta_or_the_hash_length() {
+        let named = asset("a", HELLO_SHA1, Some(HashAlgorithm::Sha256));
+        assert_eq!(named.hash_algorithm(HELLO_SHA1), HashAlgorithm::Sha256);
+        let unnamed = asset("a", HELLO_SHA1, None);
+        assert_eq!(unnamed.hash_algorithm(HELLO_SHA1), HashAlgorithm::Sha1);
+        assert_eq!(unnamed.hash_algorithm(HELLO_SHA256), HashAlgorithm::Sha256);
+    }
+
+    #[test]
+    fn asset_meta_names_the_algorithm_in_any_case() {
+        for (name, algorithm) in [
+            ("SHA1", HashAlgorithm::Sha1),
+            ("sha1", HashAlgorithm::Sha1),
+            ("SHA256", HashAlgorithm::Sha256),
+            ("Sha256", HashAlgorithm::Sha256),
+        ] {
+            let file: AssetFile = serde_json::from_value(serde_json::json!({
+                "url": "",
+                "fileName": "a",
+                "hash": null,
+                "hashAlgorithm": name,
+            }))
+            .unwrap();
+            assert_eq!(file.hash_algorithm, Some(algorithm));
+        }
+    }
+
+    #[test]
+    fn hash_file_supports_both_algorithms() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("hello.txt");
+        fs::write(&path, "hello").unwrap();
+        let path = path.to_string_lossy();
+        assert_eq!(hash_file(&path, HashAlgorithm::Sha1).unwrap(), HELLO_SHA1);
+        assert_eq!(
+            hash_file(&path, HashAlgorithm::Sha256).unwrap(),
+            HELLO_SHA256
+        );
+    }
+
+    #[test]
+    fn verify_assets_catches_corrupted_files_for_both_algorithms() {
+        let dir = tempfile::tempdir().unwrap();
+        for name in ["good1.txt", "good256.txt"] {
+            fs::write(dir.path().join(name), "hello").unwrap();
+        }
+        for name in ["bad1.txt", "bad256.txt"] {
+            fs::write(dir.path().join(name), "hellp").unwrap();
+        }
+        let assets_dir = dir.path().to_string_lossy();
+
+        let good = [
+            asset("good1.txt", HELLO_SHA1, Some(HashAlgorithm::Sha1)),
+            asset("good256.txt", &HELLO_SHA256.to_uppercase(), None),
+        ];
+        assert!(verify_assets(&assets_dir, &good).is_ok());
+
+        for bad in [
+            asset("bad1.txt", HELLO_SHA1, None),
+            asset("bad256.txt", HELLO_SHA256, Some(HashAlgorithm::Sha256)),
+        ] {
+            let name = bad.file_name.clone();
+            let error = verify_assets(&assets_dir, &[good[0].clone(), bad]).unwrap_err();
+            assert_eq!(error, format!("Hash verification failed for {}", name));
+        }
 
-    let text = resp
-        .text()
+        let missing = asset("missing.txt", HELLO_SHA1, None);
+        assert!(verify_assets(&assets_dir, &[missing]).is_err());
+    }
+
+    #[test]
+    fn addon_path_is_only_appended_once() {
+        assert_eq!(dalamud_addon_path("C:/XIVLauncher"), "C:/XIVLauncher/addon");
+        assert_eq!(
+            dalamud_addon_path("C:/XIVLauncher/addon"),
+            "C:/XIVLauncher/addon"
+        );
+        assert_eq!(
+            dalamud_addon_path("C:\\XIVLauncher\\addon"),
+            "C:\\XIVLauncher\\addon"
+        );
+    }
+
+    fn test_client() -> Client {
+        Client::builder().no_proxy().build().unwrap()
+    }
+
+    fn frontier(server: &MockServer) -> Endpoints {
+        Endpoints {
+            frontier: server.uri(),
+            ..Endpoints::default()
+        }
+    }
+
+    // Nothing listens there once the listener is gone. Mock servers are pooled, dropping one
+    // doesn't close it
+    fn unreachable_url() -> String {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        format!("http://{}", listener.local_addr().unwrap())
+    }
+
+    #[tokio::test]
+    async fn gate_status_reports_maintenance() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/worldStatus/gate_status.json"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(
+                r#"{"status":0,"message":["Maintenance"],"news":["https://example.com/news"]}"#,
+            ))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let gate = fetch_gate_status(&test_client(), &frontier(&server))
+            .await
+            .unwrap();
+        assert!(!gate.open);
+        assert_eq!(gate.message, ["Maintenance"]);
+        assert_eq!(gate.news, ["https://example.com/news"]);
+    }
+
+    #[tokio::test]
+    async fn open_gate_needs_no_message() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/worldStatus/gate_status.json"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(r#"{"status":1}"#))
+            .mount(&server)
+            .await;
+
+        let gate = fetch_gate_status(&test_client(), &frontier(&server))
+            .await
+            .unwrap();
+        assert!(gate.open);
+        assert!(gate.message.is_empty() && gate.news.is_empty());
+    }
+
+    #[tokio::test]
+    async fn gate_status_fails_on_server_errors_and_bad_json() {
+        for response in [
+            ResponseTemplate::new(503),
+            ResponseTemplate::new(200).set_body_string("<html>"),
+        ] {
+            let server = MockServer::start().await;
+            Mock::given(method("GET"))
+                .and(path("/worldStatus/gate_status.json"))
+                .respond_with(response)
+                .mount(&server)
+                .await;
+
+            let error = fetch_gate_status(&test_client(), &frontier(&server))
+                .await
+                .unwrap_err();
+            assert!(
+                error.starts_with("Failed to parse gate status"),
+                "{}",
+                error
+            );
+        }
+
+        let endpoints = Endpoints {
+            frontier: unreachable_url(),
+            ..Endpoints::default()
+        };
+        let error = fetch_gate_status(&test_client(), &endpoints)
+            .await
+            .unwrap_err();
+        assert!(error.starts_with("Failed to get gate status"), "{}", error);
+    }
+
+    const HEADLINES: &str = r#"{"news":[{"date":"2024-01-01T00:00:00Z","title":"Patch notes","url":"https://example.com/1","id":"1","tag":null}],"topics":[],"pinned":[]}"#;
+    const NO_HEADLINES: &str = r#"{"news":[],"topics":[],"pinned":[]}"#;
+
+    async fn mount_headlines(server: &MockServer, locale: &str, response: ResponseTemplate) {
+        Mock::given(method("GET"))
+            .and(path("/news/headline.json"))
+            .and(query_param("lang", locale))
+            .respond_with(response)
+            .expect(1)
+            .mount(server)
+            .await;
+    }
+
+    async fn fetch_headlines(
+        cache_dir: Option<&Path>,
+        base: &str,
+        locales: &[&str],
+    ) -> Result<(Headlines, bool), String> {
+        let locales: Vec<String> = locales.iter().map(|l| l.to_string()).collect();
+        fetch_localized(
+            cache_dir,
+            &test_client(),
+            "headlines",
+            &locales,
+            |locale| format!("{}/news/headline.json?lang={}", base, locale),
+            |headlines: &Headlines| !headlines.news.is_empty(),
+        )
         .await
-        .map_err(|e| format!("Failed to get response text: {}", e))?;
+    }
 
-    println!("{:?}", text); // Log the response text in plain text
+    #[tokio::test]
+    async fn news_falls_back_to_the_next_locale() {
+        let server = MockServer::start().await;
+        mount_headlines(&server, "de-de", ResponseTemplate::new(404)).await;
+        mount_headlines(
+            &server,
+            "fr-fr",
+            ResponseTemplate::new(200).set_body_string(NO_HEADLINES),
+        )
+        .await;
+        mount_headlines(
+            &server,
+            "en-us",
+            ResponseTemplate::new(200).set_body_string(HEADLINES),
+        )
+        .await;
 
-    #[derive(Deserialize)]
-    struct BannerRoot {
-        banner: Vec<Banner>,
+        let (headlines, cached) =
+            fetch_headlines(None, &server.uri(), &["de-de", "fr-fr", "en-us"])
+                .await
+                .unwrap();
+        assert!(!cached);
+        assert_eq!(headlines.news[0].title, "Patch notes");
     }
 
-    let root: BannerRoot =
-        serde_json::from_str(&text).map_err(|e| format!("Failed to parse banner JSON: {}", e))?;
+    #[tokio::test]
+    async fn news_fails_when_no_locale_parses() {
+        let server = MockServer::start().await;
+        mount_headlines(
+            &server,
+            "de-de",
+            ResponseTemplate::new(200).set_body_string("not json"),
+        )
+        .await;
+        mount_headlines(&server, "en-us", ResponseTemplate::new(500)).await;
 
-    Ok(root.banner)
+        let error = fetch_headlines(None, &server.uri(), &["de-de", "en-us"])
+            .await
+            .unwrap_err();
+        assert_eq!(
+            error,
+            "Failed to fetch en-us headlines: status 500 Internal Server Error"
+        );
+
+        let server = MockServer::start().await;
+        mount_headlines(
+            &server,
+            "en-us",
+            ResponseTemplate::new(200).set_body_string("not json"),
+        )
+        .await;
+        let error = fetch_headlines(None, &server.uri(), &["en-us"])
+            .await
+            .unwrap_err();
+        assert!(
+            error.starts_with("Failed to parse en-us headlines"),
+            "{}",
+            error
+        );
+    }
+
+    #[tokio::test]
+    async fn news_reuses_the_cache_when_not_modified() {
+        let cache = tempfile::tempdir().unwrap();
+        let response = CachedResponse {
+            etag: Some("\"v1\"".to_string()),
+            body: HEADLINES.to_string(),
+        };
+        news_cache::store(Some(cache.path()), "headlines", "en-us", &response);
+
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/news/headline.json"))
+            .and(header("if-none-match", "\"v1\""))
+            .respond_with(ResponseTemplate::new(304))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let (headlines, cached) = fetch_headlines(Some(cache.path()), &server.uri(), &["en-us"])
+            .await
+            .unwrap();
+        assert!(!cached);
+        assert_eq!(headlines.news.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn news_serves_the_cache_when_frontier_is_unreachable() {
+        let cache = tempfile::tempdir().unwrap();
+        let response = CachedResponse {
+            etag: None,
+            body: HEADLINES.to_string(),
+        };
+        news_cache::store(Some(cache.path()), "headlines", "en-us", &response);
+
+        let base = unreachable_url();
+
+        let (headlines, cached) = fetch_headlines(Some(cache.path()), &base, &["en-us"])
+            .await
+            .unwrap();
+        assert!(cached);
+        assert_eq!(headlines.news.len(), 1);
+
+        let error = fetch_headlines(None, &base, &["en-us"]).await.unwrap_err();
+        assert!(
+            error.starts_with("Failed to fetch en-us headlines"),
+            "{}",
+            error
+        );
+    }
+
+    const VERSION_INFO: &str = r#"{"key":"","track":"release","assemblyVersion":"9.0.0.1","runtimeVersion":"8.0.5","runtimeRequired":true,"supportedGameVer":"2024.01.01.0000.0000","isApplicableForCurrentGameVer":true,"changelog":{"date":"","version":"9.0.0.1","changes":[]},"downloadUrl":"https://kamori.goats.dev/Dalamud/Release/Download/9.0.0.1"}"#;
+    const ASSET_META: &str = r#"{"version":42,"packageUrl":"https://kamori.goats.dev/Dalamud/Asset/Package","assets":[{"url":"https://example.com/a","fileName":"UIRes/a.png","hash":null}]}"#;
+
+    fn dalamud_sources(server: &MockServer, version_info_url: &str) -> DalamudSources {
+        DalamudSources {
+            version_info_url: format!("{}{}", server.uri(), version_info_url),
+            asset_meta_url: format!("{}/Dalamud/Asset/Meta", server.uri()),
+            download_base: server.uri(),
+            retry: RetryPolicy {
+                max_attempts: 3,
+                base_delay_ms: 1,
+                max_delay_ms: 1,
+            },
+        }
+    }
+
+    #[tokio::test]
+    async fn dalamud_version_request_names_the_track() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/Dalamud/Release/VersionInfo"))
+            .and(query_param("key", "abc"))
+            .and(query_param("track", "staging"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(VERSION_INFO))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let sources = dalamud_sources(&server, "/Dalamud/Release/VersionInfo?key=abc");
+        let version_info = check_dalamud_version(&test_client(), &sources, true)
+            .await
+            .unwrap();
+        assert_eq!(version_info.assembly_version, "9.0.0.1");
+        assert_eq!(
+            sources.download_url(&version_info.download_url),
+            format!("{}/Dalamud/Release/Download/9.0.0.1", server.uri())
+        );
+    }
+
+    #[tokio::test]
+    async fn dalamud_version_request_retries_server_errors() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/Dalamud/Release/VersionInfo"))
+            .respond_with(ResponseTemplate::new(503))
+            .up_to_n_times(2)
+            .with_priority(1)
+            .expect(2)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/Dalamud/Release/VersionInfo"))
+            .and(query_param("track", "release"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(VERSION_INFO))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let sources = dalamud_sources(&server, "/Dalamud/Release/VersionInfo");
+        let version_info = check_dalamud_version(&test_client(), &sources, false)
+            .await
+            .unwrap();
+        assert!(version_info.runtime_required);
+    }
+
+    #[tokio::test]
+    async fn dalamud_version_failures_are_network_errors() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/Dalamud/Release/VersionInfo"))
+            .respond_with(ResponseTemplate::new(503))
+            .expect(3)
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/broken/VersionInfo"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("{}"))
+            .mount(&server)
+            .await;
+
+        for (url, expected) in [
+            ("/Dalamud/Release/VersionInfo", "Failed to get version info"),
+            ("/broken/VersionInfo", "Failed to parse version info"),
+        ] {
+            let sources = dalamud_sources(&server, url);
+            match check_dalamud_version(&test_client(), &sources, false).await {
+                Err(LauncherError::Network(message)) => {
+                    assert!(message.starts_with(expected), "{}", message)
+                }
+                other => panic!("unexpected result: {:?}", other),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn asset_meta_is_read() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/Dalamud/Asset/Meta"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(ASSET_META))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let sources = dalamud_sources(&server, "/Dalamud/Release/VersionInfo");
+        let asset_info = check_asset_version(&test_client(), &sources).await.unwrap();
+        assert_eq!(asset_info.version, 42);
+        assert_eq!(asset_info.assets[0].file_name, "UIRes/a.png");
+    }
+
+    #[tokio::test]
+    async fn asset_meta_failures_are_network_errors() {
+        for (response, expected) in [
+            (ResponseTemplate::new(500), "Failed to get asset info"),
+            (ResponseTemplate::new(404), "Failed to parse asset info"),
+        ] {
+            let server = MockServer::start().await;
+            Mock::given(method("GET"))
+                .and(path("/Dalamud/Asset/Meta"))
+                .respond_with(response)
+                .mount(&server)
+                .await;
+
+            let sources = dalamud_sources(&server, "/Dalamud/Release/VersionInfo");
+            match check_asset_version(&test_client(), &sources).await {
+                Err(LauncherError::Network(message)) => {
+                    assert!(message.starts_with(expected), "{}", message)
+                }
+                other => panic!("unexpected result: {:?}", other),
+            }
+        }
+    }
 }
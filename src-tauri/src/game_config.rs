@@ -0,0 +1,278 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Manager};
+
+use tracing::info;
+
+use crate::game_monitor;
+
+// Under Documents, where the game keeps its settings and character data
+const USER_FOLDER: &str = "My Games/FINAL FANTASY XIV - A Realm Reborn";
+const DISPLAY_SECTION: &str = "Display Settings";
+const CUTSCENE_SECTION: &str = "Cutscene Settings";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum GameConfigFile {
+    #[serde(rename = "FFXIV.cfg")]
+    Game,
+    #[serde(rename = "FFXIV_BOOT.cfg")]
+    Boot,
+}
+
+impl GameConfigFile {
+    fn file_name(self) -> &'static str {
+        match self {
+            GameConfigFile::Game => "FFXIV.cfg",
+            GameConfigFile::Boot => "FFXIV_BOOT.cfg",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ScreenMode {
+    Windowed,
+    Fullscreen,
+    Borderless,
+}
+
+impl ScreenMode {
+    fn from_value(value: &str) -> Option<Self> {
+        match value {
+            "0" => Some(ScreenMode::Windowed),
+            "1" => Some(ScreenMode::Fullscreen),
+            "2" => Some(ScreenMode::Borderless),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn value(self) -> &'static str {
+        match self {
+            ScreenMode::Windowed => "0",
+            ScreenMode::Fullscreen => "1",
+            ScreenMode::Borderless => "2",
+        }
+    }
+}
+
+// The options the settings page offers. None leaves the value in the file alone
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GameDisplaySettings {
+    pub screen_mode: Option<ScreenMode>,
+    // Window size, the fullscreen resolution is kept separately by the game
+    pub screen_width: Option<u32>,
+    pub screen_height: Option<u32>,
+    pub fullscreen_width: Option<u32>,
+    pub fullscreen_height: Option<u32>,
+    // 0 to 100, 50 is the game's default
+    pub gamma: Option<u32>,
+    pub skip_opening_cutscene: Option<bool>,
+}
+
+// The game's config format: <Section> headers followed by tab separated key and value lines.
+// Lines are kept as they are so a write only touches the values it changes
+struct ConfigFile {
+    lines: Vec<String>,
+}
+
+impl ConfigFile {
+    fn load(path: &Path) -> Result<Self, String> {
+        let contents = fs::read_to_string(path).map_err(|e| {
+            format!(
+                "Failed to read {}, start the game once to create it: {}",
+                path.display(),
+                e
+            )
+        })?;
+        Ok(Self {
+            lines: contents.lines().map(|line| line.to_string()).collect(),
+        })
+    }
+
+    fn save(&self, path: &Path) -> Result<(), String> {
+        // The game writes CRLF, and keeps a trailing newline
+        let mut contents = self.lines.join("\r\n");
+        contents.push_str("\r\n");
+        fs::write(path, contents).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+    }
+
+    fn section(line: &str) -> Option<&str> {
+        line.trim()
+            .strip_prefix('<')
+            .and_then(|line| line.strip_suffix('>'))
+    }
+
+    fn entry(line: &str) -> Option<(&str, &str)> {
+        let (key, value) = line.split_once('\t')?;
+        Some((key.trim(), value.trim()))
+    }
+
+    fn values(&self) -> BTreeMap<String, String> {
+        self.lines
+            .iter()
+            .filter_map(|line| Self::entry(line))
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect()
+    }
+
+    fn get(&self, key: &str) -> Option<&str> {
+        self.lines
+            .iter()
+            .filter_map(|line| Self::entry(line))
+            .find(|(name, _)| *name == key)
+            .map(|(_, value)| value)
+    }
+
+    // Keys are unique across sections, a missing key is added at the end of its section
+    fn set(&mut self, section: Option<&str>, key: &str, value: &str) -> Result<(), String> {
+        if value.contains(['\t', '\r', '\n']) {
+            return Err(format!("Invalid value for {}", key));
+        }
+        let line = format!("{}\t{}", key, value);
+        if let Some(index) = self
+            .lines
+            .iter()
+            .position(|line| Self::entry(line).is_some_and(|(name, _)| name == key))
+        {
+            self.lines[index] = line;
+            return Ok(());
+        }
+
+        let section =
+            section.ok_or_else(|| format!("{} is not in the file and has no section", key))?;
+        let start = self
+            .lines
+            .iter()
+            .position(|line| Self::section(line) == Some(section))
+            .ok_or_else(|| format!("Section {} not found", section))?;
+        let end = self.lines[start + 1..]
+            .iter()
+            .position(|line| Self::section(line).is_some())
+            .map_or(self.lines.len(), |offset| start + 1 + offset);
+        // Before the blank lines that separate sections
+        let mut insert_at = end;
+        while insert_at > start + 1 && self.lines[insert_at - 1].trim().is_empty() {
+            insert_at -= 1;
+        }
+        self.lines.insert(insert_at, line);
+        Ok(())
+    }
+}
+
+// Documents/My Games/FINAL FANTASY XIV - A Realm Reborn, or the folder the profile points at,
+// e.g. the one inside a Wine prefix
+pub(crate) fn game_user_dir(app: &AppHandle, user_dir: Option<String>) -> Result<PathBuf, String> {
+    if let Some(dir) = user_dir.filter(|dir| !dir.trim().is_empty()) {
+        return Ok(PathBuf::from(dir));
+    }
+    let documents = app
+        .path()
+        .document_dir()
+        .map_err(|e| format!("Failed to find the Documents folder: {}", e))?;
+    Ok(documents.join(USER_FOLDER))
+}
+
+// The game rewrites its config when it exits, changes made while it runs are lost
+fn ensure_game_closed() -> Result<(), String> {
+    if game_monitor::get_game_status().running {
+        return Err("Close the game before changing its settings".to_string());
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_game_display_settings(
+    app: AppHandle,
+    user_dir: Option<String>,
+) -> Result<GameDisplaySettings, String> {
+    let path = game_user_dir(&app, user_dir)?.join(GameConfigFile::Game.file_name());
+    let config = ConfigFile::load(&path)?;
+    let number = |key: &str| config.get(key).and_then(|value| value.parse().ok());
+
+    Ok(GameDisplaySettings {
+        screen_mode: config.get("ScreenMode").and_then(ScreenMode::from_value),
+        screen_width: number("ScreenWidth"),
+        screen_height: number("ScreenHeight"),
+        fullscreen_width: number("FullScreenWidth"),
+        fullscreen_height: number("FullScreenHeight"),
+        gamma: number("Gamma"),
+        skip_opening_cutscene: config.get("CutsceneMovieOpening").map(|value| value == "1"),
+    })
+}
+
+#[tauri::command]
+pub fn set_game_display_settings(
+    app: AppHandle,
+    user_dir: Option<String>,
+    settings: GameDisplaySettings,
+) -> Result<GameDisplaySettings, String> {
+    ensure_game_closed()?;
+    if settings.gamma.is_some_and(|gamma| gamma > 100) {
+        return Err("Gamma has to be between 0 and 100".to_string());
+    }
+    let dir = game_user_dir(&app, user_dir.clone())?;
+    let path = dir.join(GameConfigFile::Game.file_name());
+    let mut config = ConfigFile::load(&path)?;
+
+    let display = Some(DISPLAY_SECTION);
+    if let Some(mode) = settings.screen_mode {
+        config.set(display, "ScreenMode", mode.value())?;
+    }
+    let numbers = [
+        ("ScreenWidth", settings.screen_width),
+        ("ScreenHeight", settings.screen_height),
+        ("FullScreenWidth", settings.fullscreen_width),
+        ("FullScreenHeight", settings.fullscreen_height),
+        ("Gamma", settings.gamma),
+    ];
+    for (key, value) in numbers
+        .into_iter()
+        .filter_map(|(key, value)| value.map(|value| (key, value)))
+    {
+        config.set(display, key, &value.to_string())?;
+    }
+    if let Some(skip) = settings.skip_opening_cutscene {
+        config.set(
+            Some(CUTSCENE_SECTION),
+            "CutsceneMovieOpening",
+            if skip { "1" } else { "0" },
+        )?;
+    }
+
+    config.save(&path)?;
+    info!("Updated display settings in {}", path.display());
+    get_game_display_settings(app, user_dir)
+}
+
+// Every key and value in either file, for options without a typed setting
+#[tauri::command]
+pub fn get_game_config_values(
+    app: AppHandle,
+    user_dir: Option<String>,
+    file: GameConfigFile,
+) -> Result<BTreeMap<String, String>, String> {
+    let path = game_user_dir(&app, user_dir)?.join(file.file_name());
+    Ok(ConfigFile::load(&path)?.values())
+}
+
+// Only changes keys that are already in the file, the game ignores ones it doesn't know
+#[tauri::command]
+pub fn set_game_config_values(
+    app: AppHandle,
+    user_dir: Option<String>,
+    file: GameConfigFile,
+    values: BTreeMap<String, String>,
+) -> Result<BTreeMap<String, String>, String> {
+    ensure_game_closed()?;
+    let path = game_user_dir(&app, user_dir)?.join(file.file_name());
+    let mut config = ConfigFile::load(&path)?;
+    for (key, value) in &values {
+        config.set(None, key, value)?;
+    }
+    config.save(&path)?;
+    info!("Updated {} values in {}", values.len(), path.display());
+    Ok(config.values())
+}
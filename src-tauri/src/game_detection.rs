@@ -0,0 +1,212 @@
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use tracing::{debug, info};
+
+const STEAM_GAME_FOLDER: &str = "steamapps/common/FINAL FANTASY XIV Online";
+
+const COMMON_INSTALL_PATHS: &[&str] = &[
+    "C:/Program Files (x86)/SquareEnix/FINAL FANTASY XIV - A Realm Reborn",
+    "C:/Program Files/SquareEnix/FINAL FANTASY XIV - A Realm Reborn",
+    "C:/SquareEnix/FINAL FANTASY XIV - A Realm Reborn",
+    "C:/Program Files (x86)/Steam/steamapps/common/FINAL FANTASY XIV Online",
+    "C:/Program Files/Steam/steamapps/common/FINAL FANTASY XIV Online",
+];
+
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GamePathSource {
+    Registry,
+    Steam,
+    CommonLocation,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct GamePathCandidate {
+    pub path: String,
+    pub source: GamePathSource,
+    pub game_version: Option<String>,
+    pub is_steam: bool,
+    pub expansion_level: u32,
+}
+
+#[tauri::command]
+pub fn detect_game_path() -> Vec<GamePathCandidate> {
+    let mut found: Vec<(PathBuf, GamePathSource)> = Vec::new();
+
+    found.extend(
+        registry_install_paths()
+            .into_iter()
+            .map(|path| (path, GamePathSource::Registry)),
+    );
+    found.extend(
+        steam_library_paths()
+            .into_iter()
+            .map(|library| (library.join(STEAM_GAME_FOLDER), GamePathSource::Steam)),
+    );
+    found.extend(
+        COMMON_INSTALL_PATHS
+            .iter()
+            .map(|path| (PathBuf::from(path), GamePathSource::CommonLocation)),
+    );
+
+    let mut candidates: Vec<GamePathCandidate> = Vec::new();
+    for (path, source) in found {
+        if !is_valid_game_path(&path) {
+            debug!("No game install at {:?}", path);
+            continue;
+        }
+
+        let path = path.to_string_lossy().replace('\\', "/");
+        let path = path.trim_end_matches('/').to_string();
+        // The same install is often found by several sources
+        if candidates
+            .iter()
+            .any(|candidate| candidate.path.eq_ignore_ascii_case(&path))
+        {
+            continue;
+        }
+
+        let game_version = fs::read_to_string(format!("{}/game/ffxivgame.ver", path))
+            .ok()
+            .map(|v| v.trim().to_string());
+        let is_steam = matches!(source, GamePathSource::Steam) || path.contains("steamapps/common");
+
+        let expansion_level = detect_expansion_level(&path);
+        candidates.push(GamePathCandidate {
+            path,
+            source,
+            game_version,
+            is_steam,
+            expansion_level,
+        });
+    }
+
+    info!("Detected {} game install(s)", candidates.len());
+    candidates
+}
+
+// Highest expansion with both its sqpack folder and version file, 0 for a base game install.
+// Claiming more than the install has fails the login with an entitlement error
+pub fn detect_expansion_level(game_path: &str) -> u32 {
+    let sqpack = Path::new(game_path).join("game/sqpack");
+    (1..)
+        .take_while(|ex| {
+            let dir = sqpack.join(format!("ex{}", ex));
+            dir.is_dir() && dir.join(format!("ex{}.ver", ex)).is_file()
+        })
+        .last()
+        .unwrap_or(0)
+}
+
+fn is_valid_game_path(path: &Path) -> bool {
+    path.join("game/ffxiv_dx11.exe").is_file() && path.join("boot").is_dir()
+}
+
+fn steam_library_paths() -> Vec<PathBuf> {
+    let Some(steam_path) = steam_install_path() else {
+        return Vec::new();
+    };
+
+    let mut libraries = vec![steam_path.clone()];
+    let vdf_path = steam_path.join("steamapps/libraryfolders.vdf");
+    if let Ok(contents) = fs::read_to_string(&vdf_path) {
+        libraries.extend(parse_library_folders(&contents));
+    }
+    libraries
+}
+
+// libraryfolders.vdf lists every library as a line like: "path"  "D:\\SteamLibrary"
+fn parse_library_folders(contents: &str) -> Vec<PathBuf> {
+    contents
+        .lines()
+        .filter_map(|line| {
+            let line = line.trim();
+            let value = line.strip_prefix("\"path\"")?.trim().trim_matches('"');
+            Some(PathBuf::from(value.replace("\\\\", "\\")))
+        })
+        .collect()
+}
+
+#[cfg(windows)]
+fn registry_install_paths() -> Vec<PathBuf> {
+    use windows::Win32::System::Registry::HKEY_LOCAL_MACHINE;
+
+    const UNINSTALL_KEYS: &[&str] = &[
+        // Installed through the SE installer
+        "SOFTWARE\\WOW6432Node\\Microsoft\\Windows\\CurrentVersion\\Uninstall\\{2B41E132-07DF-4925-A3D3-F2D1765CCDFE}",
+        // Installed through Steam
+        "SOFTWARE\\WOW6432Node\\Microsoft\\Windows\\CurrentVersion\\Uninstall\\Steam App 39210",
+    ];
+
+    UNINSTALL_KEYS
+        .iter()
+        .filter_map(|key| read_registry_string(HKEY_LOCAL_MACHINE, key, "InstallLocation"))
+        .map(PathBuf::from)
+        .collect()
+}
+
+#[cfg(not(windows))]
+fn registry_install_paths() -> Vec<PathBuf> {
+    Vec::new()
+}
+
+#[cfg(windows)]
+fn steam_install_path() -> Option<PathBuf> {
+    use windows::Win32::System::Registry::HKEY_CURRENT_USER;
+
+    read_registry_string(HKEY_CURRENT_USER, "Software\\Valve\\Steam", "SteamPath")
+        .map(PathBuf::from)
+}
+
+#[cfg(not(windows))]
+fn steam_install_path() -> Option<PathBuf> {
+    let home = std::env::var("HOME").ok()?;
+    let path = PathBuf::from(home).join(".local/share/Steam");
+    path.is_dir().then_some(path)
+}
+
+#[cfg(windows)]
+fn read_registry_string(
+    root: windows::Win32::System::Registry::HKEY,
+    key: &str,
+    value: &str,
+) -> Option<String> {
+    use windows::core::HSTRING;
+    use windows::Win32::System::Registry::{RegGetValueW, RRF_RT_REG_SZ};
+
+    let key = HSTRING::from(key);
+    let value = HSTRING::from(value);
+
+    unsafe {
+        let mut size = 0u32;
+        RegGetValueW(
+            root,
+            &key,
+            &value,
+            RRF_RT_REG_SZ,
+            None,
+            None,
+            Some(&mut size),
+        )
+        .ok()
+        .ok()?;
+
+        let mut buffer = vec![0u16; (size as usize).div_ceil(2)];
+        RegGetValueW(
+            root,
+            &key,
+            &value,
+            RRF_RT_REG_SZ,
+            None,
+            Some(buffer.as_mut_ptr() as *mut _),
+            Some(&mut size),
+        )
+        .ok()
+        .ok()?;
+
+        let len = buffer.iter().position(|c| *c == 0).unwrap_or(buffer.len());
+        Some(String::from_utf16_lossy(&buffer[..len]))
+    }
+}
@@ -0,0 +1,357 @@
+use serde::{Deserialize, Serialize};
+#[cfg(windows)]
+use std::sync::Arc;
+use std::sync::Mutex;
+#[cfg(target_os = "linux")]
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, Manager};
+
+use tracing::{error, info, warn};
+
+use crate::companions::{self, Companion};
+use crate::ffxiv::{self, LaunchConfig};
+use crate::playtime;
+#[cfg(windows)]
+use crate::process::windows::{descendants, ProcessHandle};
+use crate::uid_cache;
+
+pub const GAME_STARTED_EVENT: &str = "game://started";
+pub const GAME_EXITED_EVENT: &str = "game://exited";
+pub const GAME_RELAUNCHING_EVENT: &str = "game://relaunching";
+const MAIN_WINDOW: &str = "main";
+
+// What happens to the launcher window once the game is running
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LauncherBehavior {
+    #[default]
+    Keep,
+    // Hidden while the game runs and shown again when it exits
+    Hide,
+    // Exits the launcher, which also stops monitoring the game and recording its playtime
+    Close,
+}
+
+#[derive(Debug, Default)]
+pub struct MonitorOptions {
+    pub restore_launcher: bool,
+    pub relaunch: Option<CrashRelaunch>,
+    // A game started on an expired session id quits at the title screen, relaunched with a
+    // fresh login when it exits within the window
+    pub expired_sid: Option<CrashRelaunch>,
+    // Apps started alongside the game, closed with it if their settings ask for that
+    pub companions: Vec<Companion>,
+    // Account the session's playtime is recorded for
+    pub account: String,
+    // The handle the game was created with, None when it has to be opened by pid
+    #[cfg(windows)]
+    pub process: Option<ProcessHandle>,
+    // Joins with the Wine runner's exit code, None when only the pid is known
+    #[cfg(target_os = "linux")]
+    pub exit: Option<JoinHandle<Option<u32>>>,
+}
+
+#[derive(Debug)]
+pub struct CrashRelaunch {
+    pub config: LaunchConfig,
+    // Only crashes this soon after starting are treated as a failed launch
+    pub window: Duration,
+}
+
+struct TrackedGame {
+    pid: u32,
+    started: Instant,
+    // Held open so kill_game can't hit another process that reused the pid
+    #[cfg(windows)]
+    process: Option<Arc<ProcessHandle>>,
+}
+
+static CURRENT_GAME: Mutex<Option<TrackedGame>> = Mutex::new(None);
+
+#[derive(Debug, Clone, Serialize)]
+pub struct GameStartedEvent {
+    pub pid: u32,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct GameExitedEvent {
+    pub pid: u32,
+    // None when the exit code couldn't be read
+    pub code: Option<u32>,
+    pub uptime_ms: u64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct GameStatus {
+    pub running: bool,
+    pub pid: Option<u32>,
+    pub uptime_ms: Option<u64>,
+}
+
+// Starts watching a launched game, replacing whatever was tracked before
+pub fn track_game(app: &AppHandle, pid: u32, options: MonitorOptions) {
+    let started = Instant::now();
+    let started_at = playtime::unix_now();
+
+    #[cfg(windows)]
+    let mut options = options;
+    #[cfg(windows)]
+    let process = options
+        .process
+        .take()
+        .or_else(|| {
+            ProcessHandle::open_terminable(pid)
+                .map_err(|e| warn!("Failed to open game process {}: {}", pid, e))
+                .ok()
+        })
+        .map(Arc::new);
+
+    *CURRENT_GAME.lock().unwrap() = Some(TrackedGame {
+        pid,
+        started,
+        #[cfg(windows)]
+        process: process.clone(),
+    });
+
+    info!("Monitoring game process {}", pid);
+    if let Err(e) = app.emit(GAME_STARTED_EVENT, GameStartedEvent { pid }) {
+        warn!("Failed to emit game started event: {}", e);
+    }
+
+    #[cfg(windows)]
+    let wait = move || wait_for_exit(pid, process);
+    #[cfg(target_os = "linux")]
+    let mut options = options;
+    #[cfg(target_os = "linux")]
+    let exit = options.exit.take();
+    #[cfg(target_os = "linux")]
+    let wait = move || wait_for_exit(pid, exit);
+    #[cfg(not(any(windows, target_os = "linux")))]
+    let wait = move || wait_for_exit(pid);
+
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        let code = match tauri::async_runtime::spawn_blocking(wait).await {
+            Ok(code) => code,
+            Err(e) => {
+                warn!("Game monitor for {} failed: {}", pid, e);
+                None
+            }
+        };
+        let uptime_ms = started.elapsed().as_millis() as u64;
+        info!(
+            "Game process {} exited with code {:?} after {}ms",
+            pid, code, uptime_ms
+        );
+
+        // Not tracked anymore if kill_game stopped it or a newer launch replaced it
+        let still_tracked = {
+            let mut current = CURRENT_GAME.lock().unwrap();
+            let tracked = matches!(current.as_ref(), Some(game) if game.pid == pid);
+            if tracked {
+                current.take();
+            }
+            tracked
+        };
+
+        let event = GameExitedEvent {
+            pid,
+            code,
+            uptime_ms,
+        };
+        if let Err(e) = app.emit(GAME_EXITED_EVENT, event) {
+            warn!("Failed to emit game exited event: {}", e);
+        }
+        playtime::record_session(&app, &options.account, started_at, playtime::unix_now());
+
+        if options.restore_launcher {
+            show_launcher(&app);
+        }
+
+        // An exit code that couldn't be read isn't taken for a crash
+        let crashed = matches!(code, Some(code) if code != 0);
+        let quit_early = |relaunch: &CrashRelaunch| {
+            still_tracked && crashed && started.elapsed() < relaunch.window
+        };
+        let relaunch = match options.expired_sid.filter(quit_early) {
+            Some(retry) => Some((retry, true)),
+            None => options
+                .relaunch
+                .filter(quit_early)
+                .map(|relaunch| (relaunch, false)),
+        };
+        match relaunch {
+            // The relaunched game keeps using the companions that are already running
+            Some((relaunch, expired_sid)) => {
+                relaunch_game(app, pid, relaunch.config, expired_sid).await
+            }
+            None => options.companions.iter().for_each(companions::close),
+        }
+    });
+}
+
+pub fn apply_launcher_behavior(app: &AppHandle, behavior: LauncherBehavior) {
+    match behavior {
+        LauncherBehavior::Keep => {}
+        LauncherBehavior::Hide => {
+            if let Some(window) = app.get_webview_window(MAIN_WINDOW) {
+                if let Err(e) = window.hide() {
+                    warn!("Failed to hide launcher window: {}", e);
+                }
+            }
+        }
+        LauncherBehavior::Close => {
+            info!("Game is running, closing the launcher");
+            app.exit(0);
+        }
+    }
+}
+
+fn show_launcher(app: &AppHandle) {
+    if let Some(window) = app.get_webview_window(MAIN_WINDOW) {
+        if let Err(e) = window.show().and_then(|_| window.set_focus()) {
+            warn!("Failed to show launcher window: {}", e);
+        }
+    }
+}
+
+async fn relaunch_game(app: AppHandle, pid: u32, mut config: LaunchConfig, expired_sid: bool) {
+    if expired_sid {
+        info!(
+            "Game process {} quit right after start, likely on an expired session id, relaunching",
+            pid
+        );
+        // The cached id may be the one the game rejected
+        uid_cache::forget(&config.username, config.is_steam);
+        config.sid_retried = true;
+    } else {
+        info!(
+            "Game process {} crashed shortly after start, relaunching",
+            pid
+        );
+        // Retry once, a game that keeps crashing needs the user to look at it
+        config.relaunch_on_crash = false;
+    }
+    if let Err(e) = app.emit(GAME_RELAUNCHING_EVENT, GameStartedEvent { pid }) {
+        warn!("Failed to emit game relaunching event: {}", e);
+    }
+
+    // A fresh login is needed for a new SID, the old one-time password is already spent
+    config.otp = None;
+
+    if let Err(e) = ffxiv::launch_game(app, config).await {
+        error!("Automatic relaunch failed: {}", e);
+    }
+}
+
+#[tauri::command]
+pub fn get_game_status() -> GameStatus {
+    match CURRENT_GAME.lock().unwrap().as_ref() {
+        Some(game) => GameStatus {
+            running: true,
+            pid: Some(game.pid),
+            uptime_ms: Some(game.started.elapsed().as_millis() as u64),
+        },
+        None => GameStatus {
+            running: false,
+            pid: None,
+            uptime_ms: None,
+        },
+    }
+}
+
+#[tauri::command]
+pub fn kill_game() -> Result<(), String> {
+    let game = CURRENT_GAME
+        .lock()
+        .unwrap()
+        .take()
+        .ok_or_else(|| "No game is running".to_string())?;
+
+    info!("Terminating game process {}", game.pid);
+    terminate_process_tree(&game)
+}
+
+#[cfg(windows)]
+fn terminate_process_tree(game: &TrackedGame) -> Result<(), String> {
+    let process = game.process.as_ref().ok_or_else(|| {
+        format!(
+            "Failed to terminate game process {}: it couldn't be opened when it started",
+            game.pid
+        )
+    })?;
+
+    // Children of the game first, so none of them outlives it
+    match descendants(process) {
+        Ok(children) => {
+            for child in children {
+                match ProcessHandle::open_terminable(child).and_then(|child| child.terminate()) {
+                    Ok(()) => info!("Terminated process {}", child),
+                    Err(e) => warn!("Failed to terminate process {}: {}", child, e),
+                }
+            }
+        }
+        Err(e) => warn!("Failed to list the processes of game {}: {}", game.pid, e),
+    }
+
+    process
+        .terminate()
+        .map_err(|e| format!("Failed to terminate game process {}: {}", game.pid, e))
+}
+
+#[cfg(not(windows))]
+fn terminate_process_tree(game: &TrackedGame) -> Result<(), String> {
+    let pid = game.pid;
+    let status = std::process::Command::new("kill")
+        .args(["-9", &pid.to_string()])
+        .status()
+        .map_err(|e| format!("Failed to run kill: {}", e))?;
+
+    if status.success() {
+        Ok(())
+    } else {
+        Err(format!("Failed to terminate game process {}", pid))
+    }
+}
+
+#[cfg(windows)]
+fn wait_for_exit(pid: u32, process: Option<Arc<ProcessHandle>>) -> Option<u32> {
+    let process = match process.map_or_else(|| ProcessHandle::open(pid).map(Arc::new), Ok) {
+        Ok(process) => process,
+        Err(e) => {
+            warn!("Failed to open game process {}: {}", pid, e);
+            return None;
+        }
+    };
+    process
+        .wait()
+        .map_err(|e| warn!("Failed to wait for game process {}: {}", pid, e))
+        .ok()
+}
+
+#[cfg(target_os = "linux")]
+fn wait_for_exit(pid: u32, exit: Option<JoinHandle<Option<u32>>>) -> Option<u32> {
+    match exit {
+        Some(exit) => exit.join().unwrap_or_else(|_| {
+            warn!("Reaper for game process {} panicked", pid);
+            None
+        }),
+        None => poll_for_exit(pid),
+    }
+}
+
+#[cfg(not(any(windows, target_os = "linux")))]
+fn wait_for_exit(pid: u32) -> Option<u32> {
+    poll_for_exit(pid)
+}
+
+#[cfg(not(windows))]
+fn poll_for_exit(pid: u32) -> Option<u32> {
+    // Without the child only the pid is known, so poll until it disappears
+    let proc_path = format!("/proc/{}", pid);
+    while std::path::Path::new(&proc_path).exists() {
+        std::thread::sleep(std::time::Duration::from_secs(1));
+    }
+    None
+}
@@ -0,0 +1,65 @@
+use std::time::{Duration, Instant};
+use tokio_util::sync::CancellationToken;
+
+use tracing::{info, warn};
+
+use crate::companions;
+
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+// A cold start from a slow hard drive takes well under this to show the window
+pub const READY_TIMEOUT: Duration = Duration::from_secs(120);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Readiness {
+    Ready,
+    // Gone before its window showed up, the game monitor reports how it ended
+    Exited,
+    // Timed out, or there's no way to look at the game's windows on this platform
+    Unknown,
+    // The launch was cancelled while waiting
+    Cancelled,
+}
+
+// Waits until the game has created its render window, which it does once the client has
+// loaded far enough for anything hooking or overlaying it
+pub async fn wait_for_game(pid: u32, timeout: Duration, cancel: &CancellationToken) -> Readiness {
+    if !cfg!(windows) {
+        return Readiness::Unknown;
+    }
+
+    let start = Instant::now();
+    while start.elapsed() < timeout {
+        if has_game_window(pid) {
+            info!("Game window appeared after {:.2?}", start.elapsed());
+            return Readiness::Ready;
+        }
+        if !companions::is_running(pid) {
+            warn!("Game process {} exited before its window appeared", pid);
+            return Readiness::Exited;
+        }
+        tokio::select! {
+            _ = cancel.cancelled() => {
+                info!("Stopped waiting for the game window, the launch was cancelled");
+                return Readiness::Cancelled;
+            }
+            _ = tokio::time::sleep(POLL_INTERVAL) => {}
+        }
+    }
+    warn!("No game window after {:.2?}", timeout);
+    Readiness::Unknown
+}
+
+#[cfg(windows)]
+fn has_game_window(pid: u32) -> bool {
+    // Class of the window both the DX9 and DX11 clients render into
+    const GAME_WINDOW_CLASS: &str = "FFXIVGAME";
+
+    crate::process::windows::process_windows(pid)
+        .iter()
+        .any(|window| window.class == GAME_WINDOW_CLASS)
+}
+
+#[cfg(not(windows))]
+fn has_game_window(_pid: u32) -> bool {
+    false
+}
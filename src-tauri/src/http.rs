@@ -0,0 +1,155 @@
+use reqwest::{Certificate, Client, NoProxy, Proxy};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use tauri::State;
+
+use tracing::info;
+
+use crate::redact::Secret;
+use crate::throttle;
+
+// Per-request limit for small API calls, downloads set their own
+pub const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+// Square Enix login servers are famously slow to hand out a session id
+pub const LOGIN_TIMEOUT: Duration = Duration::from_secs(200);
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(15);
+const POOL_IDLE_TIMEOUT: Duration = Duration::from_secs(90);
+const PROXY_ENV: &str = "XIVLOADER_PROXY";
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ProxyConfig {
+    // http://, https://, socks5:// or socks5h://, empty falls back to XIVLOADER_PROXY
+    pub url: String,
+    pub username: String,
+    pub password: Secret,
+    // Hosts, domains (.example.com) or IP ranges that skip the proxy
+    pub bypass: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NetworkSettings {
+    pub proxy: ProxyConfig,
+    // Extra root certificates (PEM bundles or DER files) trusted on top of the system ones,
+    // for TLS-intercepting proxies and local mirrors with their own CA
+    pub ca_certificates: Vec<String>,
+    // KiB per second shared by patch, Dalamud and asset downloads, 0 for no limit
+    pub download_limit_kib: u64,
+}
+
+// One client for the whole app so connections to the login, patch and Dalamud
+// servers get reused between requests. Managed as Tauri state, rebuilt when the
+// network settings change
+#[derive(Clone)]
+pub struct HttpClient(Arc<RwLock<(NetworkSettings, Client)>>);
+
+impl HttpClient {
+    pub fn new() -> Result<Self, String> {
+        Self::with_settings(&NetworkSettings::default())
+    }
+
+    pub fn with_settings(settings: &NetworkSettings) -> Result<Self, String> {
+        let client = build_client(settings)?;
+        throttle::set_limit(settings.download_limit_kib);
+        Ok(HttpClient(Arc::new(RwLock::new((
+            settings.clone(),
+            client,
+        )))))
+    }
+
+    // Clients share their connection pool, so handing out clones is cheap
+    pub fn client(&self) -> Client {
+        self.0.read().unwrap().1.clone()
+    }
+
+    // Requests already in flight finish on the old client
+    pub fn configure(&self, settings: &NetworkSettings) -> Result<(), String> {
+        if self.0.read().unwrap().0 == *settings {
+            return Ok(());
+        }
+        let client = build_client(settings)?;
+        throttle::set_limit(settings.download_limit_kib);
+        *self.0.write().unwrap() = (settings.clone(), client);
+        info!("HTTP client rebuilt with new network settings");
+        Ok(())
+    }
+}
+
+#[tauri::command]
+pub fn set_network_settings(
+    http: State<'_, HttpClient>,
+    settings: NetworkSettings,
+) -> Result<(), String> {
+    http.configure(&settings)
+}
+
+fn build_client(settings: &NetworkSettings) -> Result<Client, String> {
+    // No overall timeout here, it would cut off large downloads
+    let mut builder = Client::builder()
+        .connect_timeout(CONNECT_TIMEOUT)
+        .pool_idle_timeout(POOL_IDLE_TIMEOUT)
+        .tcp_keepalive(POOL_IDLE_TIMEOUT);
+
+    if let Some(proxy) = proxy(&settings.proxy)? {
+        builder = builder.proxy(proxy);
+    }
+    for path in settings
+        .ca_certificates
+        .iter()
+        .filter(|p| !p.trim().is_empty())
+    {
+        let certificates = load_certificates(Path::new(path.trim()))?;
+        info!("Trusting {} certificates from {}", certificates.len(), path);
+        for certificate in certificates {
+            builder = builder.add_root_certificate(certificate);
+        }
+    }
+
+    builder
+        .build()
+        .map_err(|e| format!("Failed to create HTTP client: {}", e))
+}
+
+fn proxy(config: &ProxyConfig) -> Result<Option<Proxy>, String> {
+    let url = config.url.trim();
+    let mut proxy = if !url.is_empty() {
+        info!("Using proxy {}", url);
+        Proxy::all(url).map_err(|e| format!("Invalid proxy {}: {}", url, e))?
+    } else if let Some(url) = std::env::var(PROXY_ENV).ok().filter(|p| !p.is_empty()) {
+        info!("Using HTTP proxy from {}", PROXY_ENV);
+        Proxy::all(&url).map_err(|e| format!("Invalid proxy in {}: {}", PROXY_ENV, e))?
+    } else {
+        return Ok(None);
+    };
+
+    if !config.username.is_empty() {
+        proxy = proxy.basic_auth(&config.username, config.password.expose());
+    }
+    if !config.bypass.is_empty() {
+        proxy = proxy.no_proxy(NoProxy::from_string(&config.bypass.join(",")));
+    }
+    Ok(Some(proxy))
+}
+
+fn load_certificates(path: &Path) -> Result<Vec<Certificate>, String> {
+    let data = fs::read(path)
+        .map_err(|e| format!("Failed to read certificate {}: {}", path.display(), e))?;
+
+    // PEM files may start with a comment, so look for the marker anywhere
+    let certificates = if data.windows(10).any(|w| w == b"-----BEGIN") {
+        Certificate::from_pem_bundle(&data)
+    } else {
+        Certificate::from_der(&data).map(|certificate| vec![certificate])
+    }
+    .map_err(|e| format!("Invalid certificate {}: {}", path.display(), e))?;
+
+    if certificates.is_empty() {
+        return Err(format!("No certificates found in {}", path.display()));
+    }
+    Ok(certificates)
+}
@@ -0,0 +1,140 @@
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+use tauri_plugin_store::StoreExt;
+
+use tracing::info;
+
+use crate::ffxiv::LaunchConfig;
+
+// Shared with the frontend settings store
+const SETTINGS_STORE: &str = "settings.json";
+const INSTALLS_KEY: &str = "gameInstalls";
+
+// A copy of the game with its own folder, Dalamud directory and settings, e.g. a second
+// copy on another drive. Picked by name when launching
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GameInstall {
+    pub name: String,
+    pub game_path: String,
+    // Empty shares the Dalamud directory of the launch settings
+    #[serde(default)]
+    pub dalamud_path: String,
+    #[serde(default)]
+    pub enable_dalamud: bool,
+    #[serde(default = "default_dx11")]
+    pub dx11: bool,
+    #[serde(default)]
+    pub is_steam: bool,
+    // None detects it from the install like an unset profile does
+    #[serde(default)]
+    pub expansion_level: Option<u32>,
+    #[serde(default)]
+    pub additional_launch_args: String,
+}
+
+fn default_dx11() -> bool {
+    true
+}
+
+impl GameInstall {
+    fn apply_to(&self, config: &mut LaunchConfig) {
+        config.game_path = self.game_path.clone();
+        if !self.dalamud_path.trim().is_empty() {
+            config.dalamud_path = self.dalamud_path.clone();
+        }
+        config.enable_dalamud = self.enable_dalamud;
+        config.dx11 = self.dx11;
+        config.is_steam = self.is_steam;
+        config.expansion_level = self.expansion_level;
+        config.additional_launch_args = self.additional_launch_args.clone();
+    }
+}
+
+fn load_installs(app: &AppHandle) -> Result<Vec<GameInstall>, String> {
+    let store = app
+        .store(SETTINGS_STORE)
+        .map_err(|e| format!("Failed to open settings store: {}", e))?;
+
+    match store.get(INSTALLS_KEY) {
+        Some(value) => serde_json::from_value(value)
+            .map_err(|e| format!("Failed to parse game installs: {}", e)),
+        None => Ok(Vec::new()),
+    }
+}
+
+fn save_installs(app: &AppHandle, installs: &[GameInstall]) -> Result<(), String> {
+    let store = app
+        .store(SETTINGS_STORE)
+        .map_err(|e| format!("Failed to open settings store: {}", e))?;
+
+    let value = serde_json::to_value(installs)
+        .map_err(|e| format!("Failed to serialize game installs: {}", e))?;
+    store.set(INSTALLS_KEY, value);
+    store
+        .save()
+        .map_err(|e| format!("Failed to save game installs: {}", e))
+}
+
+// Overrides the install specific settings when the launch names an install
+pub fn apply(app: &AppHandle, config: &mut LaunchConfig) -> Result<(), String> {
+    let Some(name) = config.install.clone().filter(|name| !name.is_empty()) else {
+        return Ok(());
+    };
+    let installs = load_installs(app)?;
+    let install = installs
+        .iter()
+        .find(|install| install.name.eq_ignore_ascii_case(&name))
+        .ok_or_else(|| format!("Unknown game install {}", name))?;
+
+    info!(
+        "Launching install {} at {}",
+        install.name, install.game_path
+    );
+    install.apply_to(config);
+    Ok(())
+}
+
+#[tauri::command]
+pub fn get_game_installs(app: AppHandle) -> Result<Vec<GameInstall>, String> {
+    load_installs(&app)
+}
+
+// Adds the install, or replaces the one with the same name
+#[tauri::command]
+pub fn save_game_install(app: AppHandle, install: GameInstall) -> Result<Vec<GameInstall>, String> {
+    let install = GameInstall {
+        name: install.name.trim().to_string(),
+        ..install
+    };
+    if install.name.is_empty() {
+        return Err("The install needs a name".to_string());
+    }
+    if install.game_path.trim().is_empty() {
+        return Err(format!("No game path set for {}", install.name));
+    }
+
+    let mut installs = load_installs(&app)?;
+    match installs
+        .iter_mut()
+        .find(|existing| existing.name.eq_ignore_ascii_case(&install.name))
+    {
+        Some(existing) => *existing = install,
+        None => {
+            info!("Adding game install {}", install.name);
+            installs.push(install);
+        }
+    }
+    save_installs(&app, &installs)?;
+    Ok(installs)
+}
+
+#[tauri::command]
+pub fn remove_game_install(app: AppHandle, name: String) -> Result<Vec<GameInstall>, String> {
+    let mut installs = load_installs(&app)?;
+    installs.retain(|install| !install.name.eq_ignore_ascii_case(&name));
+
+    info!("Removed game install {}", name);
+    save_installs(&app, &installs)?;
+    Ok(installs)
+}
@@ -0,0 +1,123 @@
+use serde::{Deserialize, Serialize};
+use tauri::AppHandle;
+use tauri_plugin_store::StoreExt;
+
+use tracing::warn;
+
+use crate::error::LauncherError;
+use crate::ffxiv::{GameLaunchMetrics, LaunchResult};
+use crate::launch_pipeline::LaunchStep;
+use crate::redact;
+
+const HISTORY_STORE: &str = "launch_history.json";
+const HISTORY_KEY: &str = "launches";
+// Enough to compare launches across a few game and Dalamud updates
+const MAX_HISTORY: usize = 200;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LaunchOutcome {
+    Success,
+    Failed,
+    Cancelled,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StepDuration {
+    pub step: LaunchStep,
+    pub duration_ms: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LaunchHistoryEntry {
+    // Unix timestamp in seconds
+    pub at: u64,
+    pub launcher_version: String,
+    pub game_version: Option<String>,
+    pub outcome: LaunchOutcome,
+    // Error code and redacted message of a failed launch, and the step it failed at
+    pub error: Option<String>,
+    pub failed_step: Option<LaunchStep>,
+    pub used_dalamud: bool,
+    pub dalamud_version: Option<String>,
+    // Every step that ran in this attempt, a resumed launch only has the ones it redid
+    pub steps: Vec<StepDuration>,
+    pub total_ms: u64,
+    pub metrics: GameLaunchMetrics,
+}
+
+impl LaunchHistoryEntry {
+    pub fn new(
+        result: &Result<LaunchResult, LauncherError>,
+        step: LaunchStep,
+        steps: Vec<StepDuration>,
+        game_version: Option<String>,
+        metrics: GameLaunchMetrics,
+    ) -> Self {
+        let total_ms = steps.iter().map(|step| step.duration_ms).sum();
+        let (outcome, error, failed_step) = match result {
+            Ok(_) => (LaunchOutcome::Success, None, None),
+            Err(LauncherError::Cancelled) => (LaunchOutcome::Cancelled, None, Some(step)),
+            Err(e) => (
+                LaunchOutcome::Failed,
+                Some(format!("{}: {}", e.code(), redact::scrub(&e.to_string()))),
+                Some(step),
+            ),
+        };
+        let launch = result.as_ref().ok();
+
+        Self {
+            at: crate::playtime::unix_now(),
+            launcher_version: env!("CARGO_PKG_VERSION").to_string(),
+            game_version,
+            outcome,
+            error,
+            failed_step,
+            used_dalamud: launch.is_some_and(|launch| launch.used_dalamud),
+            dalamud_version: launch.and_then(|launch| launch.dalamud_version.clone()),
+            steps,
+            total_ms,
+            metrics,
+        }
+    }
+}
+
+fn load_history(app: &AppHandle) -> Result<Vec<LaunchHistoryEntry>, String> {
+    let store = app
+        .store(HISTORY_STORE)
+        .map_err(|e| format!("Failed to open launch history: {}", e))?;
+
+    match store.get(HISTORY_KEY) {
+        Some(value) => serde_json::from_value(value)
+            .map_err(|e| format!("Failed to parse launch history: {}", e)),
+        None => Ok(Vec::new()),
+    }
+}
+
+pub fn record(app: &AppHandle, entry: LaunchHistoryEntry) {
+    let result = load_history(app).and_then(|mut history| {
+        history.push(entry);
+        let excess = history.len().saturating_sub(MAX_HISTORY);
+        history.drain(..excess);
+
+        let store = app
+            .store(HISTORY_STORE)
+            .map_err(|e| format!("Failed to open launch history: {}", e))?;
+        let value = serde_json::to_value(&history)
+            .map_err(|e| format!("Failed to serialize launch history: {}", e))?;
+        store.set(HISTORY_KEY, value);
+        store
+            .save()
+            .map_err(|e| format!("Failed to save launch history: {}", e))
+    });
+
+    if let Err(e) = result {
+        warn!("Failed to record launch: {}", e);
+    }
+}
+
+// Oldest first
+#[tauri::command]
+pub fn get_launch_history(app: AppHandle) -> Result<Vec<LaunchHistoryEntry>, String> {
+    load_history(&app)
+}
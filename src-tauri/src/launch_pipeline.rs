@@ -0,0 +1,914 @@
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use std::mem;
+use std::path::Path;
+#[cfg(target_os = "linux")]
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter, Manager};
+use tokio_util::sync::CancellationToken;
+
+use tracing::{error, info, warn};
+
+use crate::argument_builder::{expand_additional_arguments, TemplateVars};
+use crate::chr_backup;
+use crate::client_check;
+use crate::companions::{self, Companion, CompanionTiming};
+use crate::error::LauncherError;
+use crate::ffxiv::{
+    build_game_arguments, cancelled_or, check_dalamud_runtime, compat_layer, fetch_gate_status,
+    get_session_id, inject_dalamud, read_game_version, register_session, run_blocking,
+    setup_dalamud, start_companions, steam_environment, with_cancel, DalamudGameVersionPolicy,
+    DalamudSetup, GameLaunchMetrics, InjectionFailurePolicy, LaunchConfig, LaunchResult,
+};
+#[cfg(windows)]
+use crate::ffxiv::{create_suspended_game_process, dalamud_runtime_path, inject_dalamud_native};
+use crate::game_monitor::{self, CrashRelaunch, LauncherBehavior, MonitorOptions};
+use crate::game_ready::{wait_for_game, Readiness, READY_TIMEOUT};
+use crate::launch_history::{self, LaunchHistoryEntry, StepDuration};
+use crate::launch_progress::{LaunchProgress, LaunchStage};
+use crate::mods;
+use crate::patching::downloader::{self, PatchDownloader};
+use crate::patching::{install, version_check};
+#[cfg(windows)]
+use crate::process::windows::{ProcessHandle, SuspendedProcess};
+use crate::reshade;
+use crate::telemetry::{self, LaunchReport};
+use crate::uid_cache;
+
+// Boot patches are small, they are downloaded to the cache and removed once applied
+const BOOT_PATCH_FOLDER: &str = "boot_patches";
+// Square Enix doesn't say how soon a session id has to be used, a game started this long
+// after the login may find it expired
+const STALE_SID_AGE: Duration = Duration::from_secs(2 * 60);
+// The game quits at the title screen within seconds when the lobby rejects the id
+const EXPIRED_SID_EXIT_WINDOW: Duration = Duration::from_secs(60);
+
+// Steps of a launch in the order they run. A failed launch keeps what the steps before
+// it produced, running the pipeline again picks up at the step that failed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LaunchStep {
+    // Settings, game files, the gate, the Wine prefix and companions that start first
+    Prepare,
+    EnsureDalamud,
+    // Square Enix rejects the version report of an outdated boot
+    BootPatch,
+    Oauth,
+    BuildArgs,
+    Spawn,
+    Inject,
+    Monitor,
+    Done,
+}
+
+impl LaunchStep {
+    fn next(self) -> Self {
+        match self {
+            LaunchStep::Prepare => LaunchStep::EnsureDalamud,
+            LaunchStep::EnsureDalamud => LaunchStep::BootPatch,
+            LaunchStep::BootPatch => LaunchStep::Oauth,
+            LaunchStep::Oauth => LaunchStep::BuildArgs,
+            LaunchStep::BuildArgs => LaunchStep::Spawn,
+            LaunchStep::Spawn => LaunchStep::Inject,
+            LaunchStep::Inject => LaunchStep::Monitor,
+            LaunchStep::Monitor | LaunchStep::Done => LaunchStep::Done,
+        }
+    }
+}
+
+pub struct LaunchPipeline {
+    // None for headless launches, which are neither monitored nor reported to the frontend
+    app: Option<AppHandle>,
+    client: Client,
+    config: LaunchConfig,
+    step: LaunchStep,
+    progress: LaunchProgress,
+    started: Instant,
+    metrics: Vec<String>,
+    // Steps run by the current attempt, for the launch history
+    step_durations: Vec<StepDuration>,
+    launch_metrics: GameLaunchMetrics,
+    warnings: Vec<String>,
+    game_path: String,
+    companions: Vec<Companion>,
+    dalamud_setup: Option<DalamudSetup>,
+    sid: Option<String>,
+    // When the current sid was obtained, None for one reused from the cache
+    sid_obtained: Option<Instant>,
+    args: Option<String>,
+    pid: Option<u32>,
+    // The game between spawn() and inject(), created but not yet running
+    #[cfg(windows)]
+    suspended: Option<SuspendedProcess>,
+    // The resumed game, handed to the monitor
+    #[cfg(windows)]
+    process: Option<ProcessHandle>,
+    // The Wine runner's reaper, handed to the monitor for the exit code
+    #[cfg(target_os = "linux")]
+    exit: Option<JoinHandle<Option<u32>>>,
+    // How waiting for the game window went, so it's only waited for once
+    readiness: Option<Readiness>,
+    dalamud_version: Option<String>,
+}
+
+impl LaunchPipeline {
+    pub fn new(app: Option<AppHandle>, client: Client, config: LaunchConfig) -> Self {
+        let game_path = if config.dx11 {
+            format!("{}/game/ffxiv_dx11.exe", config.game_path)
+        } else {
+            format!("{}/game/ffxiv.exe", config.game_path)
+        };
+        Self {
+            progress: LaunchProgress::new(app.clone()),
+            app,
+            client,
+            config,
+            step: LaunchStep::Prepare,
+            started: Instant::now(),
+            metrics: Vec::new(),
+            step_durations: Vec::new(),
+            launch_metrics: GameLaunchMetrics::default(),
+            warnings: Vec::new(),
+            game_path,
+            companions: Vec::new(),
+            dalamud_setup: None,
+            sid: None,
+            sid_obtained: None,
+            args: None,
+            pid: None,
+            #[cfg(windows)]
+            suspended: None,
+            #[cfg(windows)]
+            process: None,
+            #[cfg(target_os = "linux")]
+            exit: None,
+            readiness: None,
+            dalamud_version: None,
+        }
+    }
+
+    pub fn step(&self) -> LaunchStep {
+        self.step
+    }
+
+    fn game_version(&self) -> Option<String> {
+        read_game_version(&self.config.game_path)
+            .ok()
+            .map(|version| version.trim().to_string())
+    }
+
+    // Headless launches have no store to keep a history in
+    pub fn record_history(&self, result: &Result<LaunchResult, LauncherError>) {
+        let Some(app) = &self.app else {
+            return;
+        };
+        let game_version = self.game_version();
+        // A failed launch still has the timings of the steps it got through
+        let entry = LaunchHistoryEntry::new(
+            result,
+            self.step,
+            self.step_durations.clone(),
+            game_version,
+            self.launch_metrics.clone(),
+        );
+        launch_history::record(app, entry);
+    }
+
+    // Only sent when the profile opted in
+    pub fn report_telemetry(&self, result: &Result<LaunchResult, LauncherError>) {
+        if !self.config.telemetry.enabled {
+            return;
+        }
+        let report = LaunchReport::new(
+            result,
+            self.step,
+            self.step_durations.clone(),
+            self.launch_metrics.clone(),
+            self.game_version(),
+            self.config.native_injection,
+        );
+        telemetry::send(&self.client, &self.config.telemetry, report);
+    }
+
+    // A rejected one-time password is the usual reason to resume at the login
+    pub fn set_otp(&mut self, otp: String) {
+        self.config.otp = Some(otp.into());
+    }
+
+    pub async fn run(&mut self, cancel: &CancellationToken) -> Result<LaunchResult, LauncherError> {
+        if self.step == LaunchStep::Prepare {
+            info!(
+                "Starting game launch process with config: {:?}",
+                self.config
+            );
+        } else {
+            info!("Resuming launch at {:?}", self.step);
+        }
+        self.step_durations.clear();
+
+        while self.step != LaunchStep::Done {
+            // Once the game is running the launch goes on, so the game still gets monitored
+            if cancel.is_cancelled() && self.pid.is_none() {
+                return Err(LauncherError::Cancelled);
+            }
+            let step_start = Instant::now();
+            let result = match self.step {
+                LaunchStep::Prepare => self.prepare(cancel).await,
+                LaunchStep::EnsureDalamud => self.ensure_dalamud(cancel).await,
+                LaunchStep::BootPatch => self.boot_patch(cancel).await,
+                LaunchStep::Oauth => self.oauth(cancel).await,
+                LaunchStep::BuildArgs => self.build_args(),
+                LaunchStep::Spawn => self.spawn(),
+                LaunchStep::Inject => self.inject(cancel).await,
+                LaunchStep::Monitor => self.monitor(cancel).await,
+                LaunchStep::Done => Ok(()),
+            };
+            self.step_durations.push(StepDuration {
+                step: self.step,
+                duration_ms: step_start.elapsed().as_millis() as u64,
+            });
+            if let Err(e) = result {
+                warn!("Launch stopped at {:?}: {}", self.step, e);
+                return Err(cancelled_or(e, cancel));
+            }
+            info!(
+                "Launch step {:?} took {:.2?}",
+                self.step,
+                step_start.elapsed()
+            );
+            self.step = self.step.next();
+        }
+
+        let total_elapsed = self.started.elapsed();
+        self.launch_metrics.total_time_ms = total_elapsed.as_millis() as u64;
+        self.metrics
+            .push(format!("Total launch time: {:.2?}", total_elapsed));
+        info!("Launch performance metrics:\n{}", self.metrics.join("\n"));
+        self.progress
+            .report(LaunchStage::Completed, "Game launched");
+
+        Ok(LaunchResult {
+            pid: self.pid,
+            used_dalamud: self.dalamud_setup.is_some(),
+            dalamud_version: self.dalamud_version.clone(),
+            metrics: self.launch_metrics.clone(),
+            warnings: mem::take(&mut self.warnings),
+        })
+    }
+
+    // For the placeholders in external tool arguments
+    fn template_vars(&self) -> TemplateVars {
+        TemplateVars {
+            sid_present: self.sid.is_some(),
+            pid: self.pid,
+            ..TemplateVars::new(&self.config)
+        }
+    }
+
+    async fn prepare(&mut self, cancel: &CancellationToken) -> Result<(), LauncherError> {
+        let config = &self.config;
+
+        // Reject bad settings before spending a login on them
+        expand_additional_arguments(&config.additional_launch_args, &TemplateVars::new(config))
+            .map_err(LauncherError::Config)?;
+        compat_layer(&config.dpi_awareness).map_err(LauncherError::Config)?;
+        downloader::validate_mirror(&config.patch_mirror).map_err(LauncherError::Config)?;
+
+        let path_start = Instant::now();
+        info!("Using game executable: {}", self.game_path);
+        let (game_path, dx11) = (config.game_path.clone(), config.dx11);
+        let client = run_blocking(move || Ok(client_check::check_client(&game_path, dx11)))
+            .await
+            .map_err(LauncherError::Process)?;
+        if !client.errors.is_empty() {
+            error!("Game client check failed: {}", client.errors.join("; "));
+            return Err(LauncherError::Config(client.errors.join("\n")));
+        }
+        info!(
+            "Game client: {} {}",
+            client.architecture.unwrap_or_default(),
+            client
+                .file_version
+                .as_deref()
+                .unwrap_or("without version information")
+        );
+        self.warnings.extend(client.warnings);
+        self.metrics
+            .push(format!("Path preparation: {:.2?}", path_start.elapsed()));
+
+        // Logging in during maintenance fails with a confusing session id error, check the gate first
+        self.progress
+            .report(LaunchStage::GateCheck, "Checking game server status");
+        match with_cancel(cancel, fetch_gate_status(&self.client, &config.endpoints)).await? {
+            Ok(gate) if !gate.open => {
+                let mut message = "The game is closed for maintenance".to_string();
+                if !gate.message.is_empty() {
+                    message.push_str(&format!(": {}", gate.message.join(" ")));
+                }
+                return Err(LauncherError::Maintenance(message));
+            }
+            Ok(_) => info!("Game gate is open"),
+            Err(e) => warn!(
+                "Failed to check gate status, trying to log in anyway: {}",
+                e
+            ),
+        }
+
+        // The prefix has to be ready before a session id is spent on it
+        #[cfg(target_os = "linux")]
+        crate::dxvk::ensure_profile_version(&self.client, &config.wine)
+            .await
+            .map_err(|e| LauncherError::Process(format!("Failed to install DXVK: {}", e)))?;
+
+        // ReShade only hooks the DX11 client
+        if config.dx11 {
+            if let Err(e) = reshade::prepare(&config.game_path, &config.reshade) {
+                warn!("Failed to prepare ReShade: {}", e);
+                self.warnings.push(format!("ReShade was not set up: {}", e));
+            }
+        }
+
+        // Before the game can rewrite them, a failed backup doesn't stop the launch
+        if config.backup_character_data {
+            if let Some(app) = self.app.clone() {
+                let user_dir = Some(config.game_user_dir.clone());
+                let result =
+                    run_blocking(move || chr_backup::create_backup(&app, user_dir, None)).await;
+                if let Err(e) = result {
+                    warn!("Failed to back up character data: {}", e);
+                    self.warnings
+                        .push(format!("Character data was not backed up: {}", e));
+                }
+            }
+        }
+
+        // Last, so a resumed launch doesn't start them twice
+        let vars = self.template_vars();
+        let companions = start_companions(
+            &self.config,
+            CompanionTiming::Before,
+            &vars,
+            &mut self.warnings,
+        )
+        .await;
+        self.companions.extend(companions);
+        Ok(())
+    }
+
+    async fn ensure_dalamud(&mut self, cancel: &CancellationToken) -> Result<(), LauncherError> {
+        let config = &self.config;
+        if !config.enable_dalamud {
+            return Ok(());
+        }
+        if !cfg!(windows) {
+            warn!("Dalamud injection is only supported on Windows, launching without it");
+            self.warnings.push(
+                "Dalamud is not supported on this platform yet, starting without it".to_string(),
+            );
+            return Ok(());
+        }
+
+        info!("Dalamud is enabled, starting Dalamud setup");
+        if let Err(e) = mods::prepare_penumbra(&config.dalamud_path, &config.penumbra_mod_directory)
+        {
+            warn!("Failed to set up Penumbra: {}", e);
+            self.warnings
+                .push(format!("Penumbra's mod directory was not set: {}", e));
+        }
+        self.warnings
+            .extend(mods::detect(&config.game_path, &config.dalamud_path).warnings);
+
+        let dalamud_start = Instant::now();
+        let setup = setup_dalamud(&self.client, config, Some(&self.progress), cancel)
+            .await
+            .map_err(|e| {
+                error!("Dalamud setup failed: {}", e);
+                e.context("Dalamud setup failed")
+            })?;
+        if setup.offline {
+            self.warnings.push(format!(
+                "Dalamud server unreachable, launching cached Dalamud {}",
+                setup.version_info.assembly_version
+            ));
+        }
+        let dalamud_duration = dalamud_start.elapsed();
+        self.launch_metrics.dalamud_setup_time_ms = dalamud_duration.as_millis() as u64;
+        self.metrics
+            .push(format!("Dalamud setup: {:.2?}", dalamud_duration));
+        info!(
+            "Dalamud setup completed successfully in {:.2?}",
+            dalamud_duration
+        );
+        if config.dalamud_safe_mode {
+            self.warnings
+                .push("Dalamud safe mode is on, no plugins will be loaded".to_string());
+        }
+
+        // Right after a patch Dalamud lags behind the game until it's updated for it
+        let game_version = read_game_version(&self.config.game_path)
+            .map_err(LauncherError::Patch)?
+            .trim()
+            .to_string();
+        if let Some(mismatch) = setup.version_info.game_mismatch(&game_version) {
+            warn!("{}", mismatch);
+            match self.config.dalamud_game_version_policy {
+                DalamudGameVersionPolicy::Block => {
+                    return Err(LauncherError::Dalamud(format!(
+                        "{}. Wait for a Dalamud update or launch without it",
+                        mismatch
+                    )));
+                }
+                DalamudGameVersionPolicy::Warn => self
+                    .warnings
+                    .push(format!("{}, loading it anyway", mismatch)),
+                DalamudGameVersionPolicy::Vanilla => {
+                    self.warnings
+                        .push(format!("{}, launching without Dalamud", mismatch));
+                    return Ok(());
+                }
+            }
+        }
+        self.dalamud_setup = Some(setup);
+        Ok(())
+    }
+
+    // Installs pending boot patches the way the official launcher does on start, so a boot
+    // update doesn't need a trip through it before the login works again
+    async fn boot_patch(&mut self, cancel: &CancellationToken) -> Result<(), LauncherError> {
+        let versions = version_check::read_installed_versions(
+            &self.config.game_path,
+            self.config.expansion_level(),
+        );
+        let patches = match with_cancel(
+            cancel,
+            version_check::check_boot_version(&self.client, &versions),
+        )
+        .await?
+        {
+            Ok(patches) => patches,
+            Err(e) => {
+                warn!("Failed to check for boot patches, logging in anyway: {}", e);
+                return Ok(());
+            }
+        };
+        if patches.is_empty() {
+            info!("Boot {} is up to date", versions.boot);
+            return Ok(());
+        }
+
+        self.progress.report(
+            LaunchStage::BootPatch,
+            format!("Installing {} boot patches", patches.len()),
+        );
+        let patch_start = Instant::now();
+        let download_dir = match &self.app {
+            Some(app) => app
+                .path()
+                .app_cache_dir()
+                .map_err(|e| LauncherError::Patch(format!("Failed to find cache folder: {}", e)))?
+                .join(BOOT_PATCH_FOLDER),
+            None => std::env::temp_dir().join(BOOT_PATCH_FOLDER),
+        };
+        let mut downloader = PatchDownloader::new(self.client.clone(), download_dir)
+            .mirror(&self.config.patch_mirror);
+        if let Some(app) = self.app.clone() {
+            downloader = downloader.on_progress(move |progress| {
+                if let Err(e) = app.emit("patch-download-progress", &progress) {
+                    warn!("Failed to emit download progress: {}", e);
+                }
+            });
+        }
+
+        // Not cancellable, stopping halfway would leave boot files from two versions
+        install::install_patches(&downloader, Path::new(&self.config.game_path), &patches)
+            .await
+            .map_err(|e| {
+                error!("Failed to install boot patches: {}", e);
+                LauncherError::Patch(format!("Failed to install boot patches: {}", e))
+            })?;
+
+        self.metrics
+            .push(format!("Boot patching: {:.2?}", patch_start.elapsed()));
+        info!(
+            "Installed {} boot patches in {:.2?}",
+            patches.len(),
+            patch_start.elapsed()
+        );
+        Ok(())
+    }
+
+    async fn oauth(&mut self, cancel: &CancellationToken) -> Result<(), LauncherError> {
+        let config = &self.config;
+        let game_version = read_game_version(&config.game_path)
+            .map_err(LauncherError::Patch)?
+            .trim()
+            .to_string();
+        let expansion_level = config.expansion_level();
+        let cached_sid = if config.uid_cache {
+            uid_cache::get(
+                &config.username,
+                config.is_steam,
+                &game_version,
+                expansion_level,
+            )
+        } else {
+            None
+        };
+
+        if let Some(sid) = cached_sid {
+            info!("Reusing cached session ID");
+            self.progress
+                .report(LaunchStage::SessionId, "Using cached login");
+            self.metrics
+                .push("Session ID retrieval: cached".to_string());
+            self.sid = Some(sid);
+            self.sid_obtained = None;
+            return Ok(());
+        }
+
+        let sid_start = Instant::now();
+        let oauth_sid = get_session_id(&self.client, config, Some(&self.progress), cancel)
+            .await
+            .map_err(|e| {
+                error!("Failed to get session ID: {}", e);
+                e.context("Failed to get session ID")
+            })?;
+        let sid_duration = sid_start.elapsed();
+        self.launch_metrics.login_time_ms = sid_duration.as_millis() as u64;
+        self.metrics
+            .push(format!("Session ID retrieval: {:.2?}", sid_duration));
+        info!(
+            "Successfully obtained fresh session ID in {:.2?}",
+            sid_duration
+        );
+
+        // The oauth id only becomes a launchable unique id once the version report is accepted
+        self.progress
+            .report(LaunchStage::VersionCheck, "Checking game version");
+        let report_start = Instant::now();
+        let unique_id = register_session(&self.client, config, &oauth_sid, cancel).await?;
+        self.launch_metrics.sid_fetch_time_ms = self.started.elapsed().as_millis() as u64;
+        self.metrics
+            .push(format!("Version report: {:.2?}", report_start.elapsed()));
+
+        if config.uid_cache {
+            uid_cache::store(
+                &config.username,
+                config.is_steam,
+                &unique_id,
+                &game_version,
+                expansion_level,
+            );
+        }
+        self.sid = Some(unique_id);
+        self.sid_obtained = Some(Instant::now());
+        Ok(())
+    }
+
+    fn build_args(&mut self) -> Result<(), LauncherError> {
+        let sid = self.sid.as_deref().unwrap_or_default();
+        let args_start = Instant::now();
+        let args = build_game_arguments(&self.config, sid).map_err(LauncherError::Config)?;
+        if !self.config.encrypt_arguments {
+            self.warnings.push(
+                "Launch arguments are not encrypted, the session ID is visible to other processes"
+                    .to_string(),
+            );
+        }
+        self.metrics.push(format!(
+            "Arguments preparation: {:.2?}",
+            args_start.elapsed()
+        ));
+        // Not the arguments themselves, they hold the SID
+        info!("Launch arguments prepared ({} characters)", args.len());
+        self.args = Some(args);
+        Ok(())
+    }
+
+    // On Windows the game is created suspended and only runs once inject() resumes it
+    fn spawn(&mut self) -> Result<(), LauncherError> {
+        if let Some(setup) = &self.dalamud_setup {
+            if let Err(e) = check_dalamud_runtime(&self.config, &setup.version_info) {
+                error!("{}", e);
+                // Dalamud setup installs the runtime it needs, so resuming redoes it
+                self.dalamud_setup = None;
+                self.step = LaunchStep::EnsureDalamud;
+                return Err(LauncherError::Dalamud(format!(
+                    "{}. Try launching again to download it",
+                    e
+                )));
+            }
+        }
+        info!("Attempting to create game process");
+        self.progress
+            .report(LaunchStage::ProcessStart, "Starting game process");
+        let launch_start = Instant::now();
+        let game_path = &self.game_path;
+        let args = self.args.as_deref().unwrap_or_default();
+        #[cfg(windows)]
+        let spawned = {
+            // Dalamud.Boot looks for the runtime in the game's environment
+            let runtime_path =
+                dalamud_runtime_path(&self.config).filter(|_| self.dalamud_setup.is_some());
+            let mut environment = steam_environment(&self.config).to_vec();
+            environment.extend(
+                runtime_path
+                    .iter()
+                    .map(|path| ("DALAMUD_RUNTIME", path.as_str())),
+            );
+            compat_layer(&self.config.dpi_awareness).and_then(|layer| {
+                create_suspended_game_process(game_path, args, layer, &environment)
+            })
+        };
+        #[cfg(target_os = "linux")]
+        let spawned = crate::platform::linux::spawn_game(
+            &self.config.wine,
+            &self.config.gamescope,
+            self.config.gamemode,
+            game_path,
+            args,
+            steam_environment(&self.config),
+        )
+        .map(|game| {
+            self.exit = Some(game.exit);
+            game.pid
+        });
+        #[cfg(not(any(windows, target_os = "linux")))]
+        let spawned: Result<u32, String> = {
+            let _ = (game_path, args);
+            Err("Launching the game is not supported on this platform".to_string())
+        };
+
+        let spawned = spawned.map_err(|e| {
+            error!("Failed to create game process: {}", e);
+            LauncherError::Process(format!("Failed to launch game: {}", e))
+        })?;
+        let launch_duration = launch_start.elapsed();
+        self.launch_metrics.game_start_time_ms = launch_duration.as_millis() as u64;
+        self.metrics
+            .push(format!("Game process creation: {:.2?}", launch_duration));
+        #[cfg(windows)]
+        {
+            info!(
+                "Game process created suspended with PID: {} in {:.2?}",
+                spawned.pid(),
+                launch_duration
+            );
+            self.suspended = Some(spawned);
+        }
+        #[cfg(not(windows))]
+        {
+            info!(
+                "Game process created successfully with PID: {} in {:.2?}",
+                spawned, launch_duration
+            );
+            self.pid = Some(spawned);
+        }
+        Ok(())
+    }
+
+    async fn inject(&mut self, cancel: &CancellationToken) -> Result<(), LauncherError> {
+        match self.start_game(cancel).await {
+            Err(LauncherError::Dalamud(e))
+                if self.config.injection_failure_policy == InjectionFailurePolicy::Vanilla
+                    && !cancel.is_cancelled() =>
+            {
+                self.launch_vanilla(e)
+            }
+            // Cancelled before Dalamud got into the game. The game is already running and
+            // goes on without it, like a cancel after injection
+            Err(e) if cancel.is_cancelled() && self.pid.is_some() => {
+                info!("Launch cancelled before injecting Dalamud: {}", e);
+                self.warnings.push(
+                    "The launch was cancelled, the game was started without Dalamud".to_string(),
+                );
+                self.dalamud_setup = None;
+                self.dalamud_version = None;
+                Ok(())
+            }
+            Err(e) => {
+                self.abandon_game();
+                Err(e)
+            }
+            result => result,
+        }
+    }
+
+    // A game started for an injection that didn't happen. Resuming starts a new one
+    fn abandon_game(&mut self) {
+        let Some(pid) = self.pid.take() else {
+            return;
+        };
+        warn!("Closing game process {} started without Dalamud", pid);
+        #[cfg(windows)]
+        if let Some(process) = self.process.take() {
+            if let Err(e) = process.terminate() {
+                warn!("Failed to close game process {}: {}", pid, e);
+            }
+        }
+        self.readiness = None;
+        self.step = LaunchStep::Spawn;
+    }
+
+    // After a failed injection. The native path already terminated its suspended game, so
+    // this starts over at process creation with the session id and arguments it has
+    fn launch_vanilla(&mut self, error: String) -> Result<(), LauncherError> {
+        warn!("Launching without Dalamud after: {}", error);
+        self.warnings
+            .push(format!("{}. The game was started without Dalamud", error));
+        self.dalamud_setup = None;
+        self.dalamud_version = None;
+        // The injector failed on a game that's already running, which goes on without it
+        if self.pid.is_some() {
+            return Ok(());
+        }
+
+        // Left at Spawn if this fails too, so a resume creates the game again
+        self.step = LaunchStep::Spawn;
+        self.spawn()?;
+        #[cfg(windows)]
+        if let Some(process) = self.suspended.take() {
+            self.start_suspended(process)?;
+        }
+        self.step = LaunchStep::Inject;
+        Ok(())
+    }
+
+    async fn start_game(&mut self, cancel: &CancellationToken) -> Result<(), LauncherError> {
+        #[cfg(windows)]
+        if let Some(process) = self.suspended.take() {
+            self.start_suspended(process).map_err(|e| {
+                // The suspended game is terminated with the error, resuming spawns a new one
+                self.step = LaunchStep::Spawn;
+                e
+            })?;
+        }
+
+        // Native injection already happened before the game was resumed
+        if self.config.native_injection || self.dalamud_setup.is_none() {
+            return Ok(());
+        }
+        let Some(pid) = self.pid else {
+            return Ok(());
+        };
+
+        // The injector loads Dalamud into the running game, which has to have come far
+        // enough for that first
+        self.progress.report(
+            LaunchStage::Injection,
+            "Waiting for the game window before injecting Dalamud",
+        );
+        let wait_start = Instant::now();
+        match self.wait_for_window(pid, cancel).await {
+            Readiness::Ready | Readiness::Unknown => {}
+            Readiness::Exited => {
+                return Err(LauncherError::Process(
+                    "The game exited before Dalamud could be injected".to_string(),
+                ))
+            }
+            Readiness::Cancelled => return Err(LauncherError::Cancelled),
+        }
+        self.metrics.push(format!(
+            "Waiting for the game: {:.2?}",
+            wait_start.elapsed()
+        ));
+
+        let Some(setup) = &self.dalamud_setup else {
+            return Ok(());
+        };
+        info!("Injecting Dalamud into game process {}", pid);
+        self.progress
+            .report(LaunchStage::Injection, "Injecting Dalamud");
+        let inject_start = Instant::now();
+        let launch = inject_dalamud(&self.config, &setup.version_info, pid, cancel)
+            .await
+            .map_err(|e| {
+                error!("Failed to inject Dalamud: {}", e);
+                e.context("Failed to inject Dalamud")
+            })?;
+
+        let inject_duration = inject_start.elapsed();
+        self.launch_metrics.injection_time_ms = inject_duration.as_millis() as u64;
+        self.metrics
+            .push(format!("Dalamud injection: {:.2?}", inject_duration));
+        info!("Dalamud injected in {:.2?}", inject_duration);
+        self.dalamud_version = Some(launch.version);
+        Ok(())
+    }
+
+    // Waits for the game window, or for the configured delay where it can't be detected
+    async fn wait_for_window(&mut self, pid: u32, cancel: &CancellationToken) -> Readiness {
+        if let Some(readiness) = self.readiness {
+            return readiness;
+        }
+        let readiness = match wait_for_game(pid, READY_TIMEOUT, cancel).await {
+            Readiness::Unknown => {
+                info!(
+                    "Could not detect the game window, waiting {}ms instead",
+                    self.config.injection_delay
+                );
+                let delay = Duration::from_millis(self.config.injection_delay);
+                match with_cancel(cancel, tokio::time::sleep(delay)).await {
+                    Ok(()) => Readiness::Unknown,
+                    Err(_) => Readiness::Cancelled,
+                }
+            }
+            readiness => readiness,
+        };
+        self.readiness = Some(readiness);
+        readiness
+    }
+
+    // Anything that has to happen before the game's own code runs goes here, then the game
+    // is let go
+    #[cfg(windows)]
+    fn start_suspended(&mut self, process: SuspendedProcess) -> Result<(), LauncherError> {
+        let native_setup = self
+            .dalamud_setup
+            .as_ref()
+            .filter(|_| self.config.native_injection);
+        if let Some(setup) = native_setup {
+            self.progress
+                .report(LaunchStage::Injection, "Injecting Dalamud");
+            let inject_start = Instant::now();
+            let launch =
+                inject_dalamud_native(&self.config, &setup.version_info, &self.game_path, &process)
+                    .map_err(|e| {
+                        error!("Failed to inject Dalamud: {}", e);
+                        e.context("Failed to inject Dalamud")
+                    })?;
+
+            let inject_duration = inject_start.elapsed();
+            self.launch_metrics.game_start_time_ms += inject_duration.as_millis() as u64;
+            self.launch_metrics.injection_time_ms = inject_duration.as_millis() as u64;
+            self.metrics
+                .push(format!("Dalamud injection: {:.2?}", inject_duration));
+            self.dalamud_version = Some(launch.version);
+        }
+
+        let process = process.resume().map_err(|e| {
+            error!("Failed to resume game process: {}", e);
+            LauncherError::Process(format!("Failed to launch game: {}", e))
+        })?;
+        info!("Game process {} resumed", process.pid());
+        self.pid = Some(process.pid());
+        self.process = Some(process);
+        Ok(())
+    }
+
+    async fn monitor(&mut self, cancel: &CancellationToken) -> Result<(), LauncherError> {
+        let Some(pid) = self.pid else {
+            return Ok(());
+        };
+        // A cached id may be hours old already
+        let sid_at_risk = self
+            .sid_obtained
+            .is_none_or(|obtained| obtained.elapsed() > STALE_SID_AGE);
+
+        // Overlays and tools started after the game need its window to attach to
+        self.progress
+            .report(LaunchStage::WaitingForGame, "Waiting for the game window");
+        // A cancelled wait still hands the running game to the monitor
+        if self.wait_for_window(pid, cancel).await == Readiness::Exited {
+            self.warnings
+                .push("The game exited before its window appeared".to_string());
+        }
+
+        if companions::is_running(pid) {
+            let vars = self.template_vars();
+            let companions = start_companions(
+                &self.config,
+                CompanionTiming::After,
+                &vars,
+                &mut self.warnings,
+            )
+            .await;
+            self.companions.extend(companions);
+        }
+
+        if let Some(app) = &self.app {
+            let config = &self.config;
+            let options = MonitorOptions {
+                restore_launcher: config.launcher_behavior == LauncherBehavior::Hide,
+                relaunch: config.relaunch_on_crash.then(|| CrashRelaunch {
+                    config: config.clone(),
+                    window: Duration::from_secs(config.crash_relaunch_window_minutes * 60),
+                }),
+                expired_sid: (sid_at_risk && !config.sid_retried).then(|| CrashRelaunch {
+                    config: config.clone(),
+                    window: EXPIRED_SID_EXIT_WINDOW,
+                }),
+                companions: mem::take(&mut self.companions),
+                account: config.username.clone(),
+                #[cfg(windows)]
+                process: self.process.take(),
+                #[cfg(target_os = "linux")]
+                exit: self.exit.take(),
+            };
+            game_monitor::track_game(app, pid, options);
+            game_monitor::apply_launcher_behavior(app, config.launcher_behavior);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,200 @@
+use serde::Serialize;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter};
+
+use tracing::{debug, info, warn};
+
+pub const LAUNCH_PROGRESS_EVENT: &str = "launch://progress";
+pub const DOWNLOAD_PROGRESS_EVENT: &str = "launch://download";
+// Asks the frontend for a new code through submit_otp after the last one was refused
+pub const OTP_REQUIRED_EVENT: &str = "launch://otp-required";
+// Emitting on every chunk would flood the frontend
+const DOWNLOAD_REPORT_INTERVAL: Duration = Duration::from_millis(250);
+
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LaunchStage {
+    GateCheck,
+    DalamudCheck,
+    DalamudDownload,
+    BootPatch,
+    Oauth,
+    SessionId,
+    VersionCheck,
+    ProcessStart,
+    Injection,
+    WaitingForGame,
+    Completed,
+}
+
+impl LaunchStage {
+    // Rough share of a typical launch spent before each stage starts
+    fn percent(&self) -> u8 {
+        match self {
+            LaunchStage::GateCheck => 2,
+            LaunchStage::DalamudCheck => 5,
+            LaunchStage::DalamudDownload => 15,
+            LaunchStage::BootPatch => 30,
+            LaunchStage::Oauth => 40,
+            LaunchStage::SessionId => 60,
+            LaunchStage::VersionCheck => 70,
+            LaunchStage::ProcessStart => 80,
+            LaunchStage::Injection => 85,
+            LaunchStage::WaitingForGame => 90,
+            LaunchStage::Completed => 100,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct LaunchProgressEvent {
+    pub stage: LaunchStage,
+    pub percent: u8,
+    pub elapsed_ms: u64,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DownloadProgressEvent {
+    pub label: String,
+    pub downloaded: u64,
+    // None when the server didn't send a content length
+    pub total: Option<u64>,
+    pub bytes_per_second: u64,
+    pub eta_ms: Option<u64>,
+    pub completed: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct OtpRequiredEvent {
+    // Why the last code was refused, as Square Enix put it
+    pub message: String,
+    pub attempt: u32,
+}
+
+#[derive(Clone)]
+pub struct LaunchProgress {
+    // None for headless launches, which only log the stages
+    app: Option<AppHandle>,
+    started: Instant,
+}
+
+impl LaunchProgress {
+    pub fn new(app: Option<AppHandle>) -> Self {
+        Self {
+            app,
+            started: Instant::now(),
+        }
+    }
+
+    pub fn report(&self, stage: LaunchStage, message: impl Into<String>) {
+        let event = LaunchProgressEvent {
+            stage,
+            percent: stage.percent(),
+            elapsed_ms: self.started.elapsed().as_millis() as u64,
+            message: message.into(),
+        };
+        debug!("Launch progress: {:?}", event);
+
+        let Some(app) = &self.app else {
+            info!("{}", event.message);
+            return;
+        };
+        if let Err(e) = app.emit(LAUNCH_PROGRESS_EVENT, &event) {
+            warn!("Failed to emit launch progress: {}", e);
+        }
+    }
+
+    // False for headless launches, nothing is there to answer
+    pub fn request_otp(&self, event: &OtpRequiredEvent) -> bool {
+        let Some(app) = &self.app else {
+            return false;
+        };
+        match app.emit(OTP_REQUIRED_EVENT, event) {
+            Ok(()) => true,
+            Err(e) => {
+                warn!("Failed to request a new OTP: {}", e);
+                false
+            }
+        }
+    }
+
+    pub fn download(&self, label: impl Into<String>) -> DownloadTracker<'_> {
+        DownloadTracker {
+            progress: self,
+            label: label.into(),
+            total: None,
+            downloaded: 0,
+            last_report: Instant::now(),
+            last_downloaded: 0,
+        }
+    }
+
+    fn emit_download(&self, event: &DownloadProgressEvent) {
+        // Headless launches log the stage message, per-chunk progress would just be noise
+        let Some(app) = &self.app else {
+            return;
+        };
+        if let Err(e) = app.emit(DOWNLOAD_PROGRESS_EVENT, event) {
+            warn!("Failed to emit download progress: {}", e);
+        }
+    }
+}
+
+pub struct DownloadTracker<'a> {
+    progress: &'a LaunchProgress,
+    label: String,
+    total: Option<u64>,
+    downloaded: u64,
+    last_report: Instant,
+    last_downloaded: u64,
+}
+
+impl DownloadTracker<'_> {
+    // Called once the response arrives and the size is known
+    pub fn start(&mut self, total: Option<u64>) {
+        self.total = total;
+        self.downloaded = 0;
+        self.last_downloaded = 0;
+        self.last_report = Instant::now();
+        self.emit(0, false);
+    }
+
+    pub fn advance(&mut self, bytes: u64) {
+        self.downloaded += bytes;
+
+        let elapsed = self.last_report.elapsed();
+        if elapsed < DOWNLOAD_REPORT_INTERVAL {
+            return;
+        }
+        // Speed over the last interval, so stalls show up right away
+        let bytes_per_second =
+            ((self.downloaded - self.last_downloaded) as f64 / elapsed.as_secs_f64()) as u64;
+        self.last_report = Instant::now();
+        self.last_downloaded = self.downloaded;
+        self.emit(bytes_per_second, false);
+    }
+
+    pub fn finish(&mut self) {
+        self.total = Some(self.downloaded);
+        self.emit(0, true);
+    }
+
+    fn emit(&self, bytes_per_second: u64, completed: bool) {
+        let eta_ms = match self.total {
+            Some(total) if bytes_per_second > 0 => {
+                Some(total.saturating_sub(self.downloaded) * 1000 / bytes_per_second)
+            }
+            _ => None,
+        };
+
+        self.progress.emit_download(&DownloadProgressEvent {
+            label: self.label.clone(),
+            downloaded: self.downloaded,
+            total: self.total,
+            bytes_per_second,
+            eta_ms,
+            completed,
+        });
+    }
+}
@@ -1,4 +1,54 @@
+mod archive;
+mod argument_builder;
+mod benchmark;
+mod chr_backup;
+pub mod cli;
+mod client_check;
+mod cloud_sync;
+mod companions;
+mod credentials;
+mod dalamud_cleanup;
+mod dalamud_log;
+mod dalamud_updater;
+mod data_export;
+mod datacenters;
+mod disk_space;
+mod dxvk;
+mod endpoints;
+mod error;
+mod encryption;
 mod ffxiv;
+mod game_config;
+mod game_detection;
+mod game_monitor;
+mod game_ready;
+mod http;
+mod installs;
+mod launch_history;
+mod launch_pipeline;
+mod launch_progress;
+mod maintenance;
+mod migration;
+mod mods;
+#[cfg(windows)]
+mod native_injection;
+mod news_cache;
+mod otp_listener;
+mod patching;
+mod platform;
+mod playtime;
+mod plugins;
+mod process;
+mod redact;
+mod retry;
+mod reshade;
+mod secrets;
+mod self_update;
+mod steam;
+mod telemetry;
+mod throttle;
+mod tspack;
+mod uid_cache;
 
 // Learn more about Tauri commands at https://tauri.app/develop/calling-rust/
 #[tauri::command]
@@ -14,16 +64,87 @@ pub fn run() {
         .plugin(
             tauri_plugin_log::Builder::new()
                 .level(log::LevelFilter::Trace)
+                // trace output gets shared in bug reports, keep secrets out of it
+                .format(redact::format_log)
                 .build(),
         )
         .plugin(tauri_plugin_opener::init())
+        // Endpoints and the signing key are set per check, see self_update
+        .plugin(tauri_plugin_updater::Builder::new().build())
+        .manage(http::HttpClient::new().expect("failed to create HTTP client"))
         .invoke_handler(tauri::generate_handler![ 
             // fully standard tauri handling
             // ui cannot access any commands without putting it here
             greet,
             ffxiv::launch_game,
+            ffxiv::resume_launch,
+            ffxiv::cancel_launch,
+            benchmark::launch_benchmark,
+            http::set_network_settings,
+            ffxiv::check_for_updates,
+            ffxiv::download_patches,
+            ffxiv::get_game_version,
+            client_check::check_game_client,
+            ffxiv::get_dalamud_changelog,
+            patching::install::install_game,
             ffxiv::get_news,
-            ffxiv::get_banners
+            ffxiv::get_banners,
+            ffxiv::get_gate_status,
+            maintenance::get_maintenance_schedule,
+            datacenters::ping_datacenters,
+            credentials::save_credentials,
+            credentials::get_credentials,
+            credentials::delete_credentials,
+            otp_listener::submit_otp,
+            ffxiv::open_tos,
+            plugins::get_plugin_repositories,
+            plugins::add_plugin_repository,
+            plugins::remove_plugin_repository,
+            plugins::set_plugin_repository_enabled,
+            plugins::list_available_plugins,
+            plugins::install_plugin,
+            plugins::update_all_plugins,
+            plugins::list_installed_plugins,
+            plugins::set_plugin_enabled,
+            dalamud_log::tail_dalamud_log,
+            dalamud_log::stop_dalamud_log,
+            dalamud_cleanup::remove_dalamud,
+            dalamud_cleanup::prune_dalamud_versions,
+            data_export::export_launcher_data,
+            data_export::import_launcher_data,
+            cloud_sync::sync_push,
+            cloud_sync::sync_pull,
+            game_config::get_game_display_settings,
+            game_config::set_game_display_settings,
+            game_config::get_game_config_values,
+            game_config::set_game_config_values,
+            chr_backup::backup_character_data,
+            chr_backup::list_character_backups,
+            chr_backup::restore_character_backup,
+            dalamud_updater::schedule_dalamud_update,
+            tspack::export_tspack,
+            uid_cache::clear_uid_cache,
+            game_monitor::get_game_status,
+            game_monitor::kill_game,
+            playtime::get_playtime_stats,
+            launch_history::get_launch_history,
+            game_detection::detect_game_path,
+            installs::get_game_installs,
+            installs::save_game_install,
+            installs::remove_game_install,
+            migration::import_xivlauncher_settings,
+            secrets::protect_secret,
+            secrets::unprotect_secret,
+            self_update::check_launcher_update,
+            self_update::install_launcher_update,
+            dxvk::list_dxvk_releases,
+            dxvk::get_dxvk_status,
+            dxvk::install_dxvk,
+            dxvk::rollback_dxvk,
+            reshade::get_reshade_status,
+            reshade::update_reshade,
+            reshade::create_reshade_preset_folder,
+            mods::get_mod_status
         ])
         .run(tauri::generate_context!())
 // standard tauri error handler
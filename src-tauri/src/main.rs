@@ -2,5 +2,10 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
 fn main() {
+    // Any arguments mean a headless CLI run, e.g. `xivloader launch --profile main`
+    if let Some(code) = rust_xivloader_lib::cli::run_from_args() {
+        std::process::exit(code);
+    }
+
     rust_xivloader_lib::run()
 }
@@ -0,0 +1,200 @@
+use regex::Regex;
+use reqwest::header::USER_AGENT;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::State;
+use time::{Date, Month, PrimitiveDateTime, Time, UtcOffset};
+
+use tracing::{debug, info, warn};
+
+use crate::endpoints::Endpoints;
+use crate::ffxiv::get_user_agent;
+use crate::http::{HttpClient, REQUEST_TIMEOUT};
+
+// The NA Lodestone always writes maintenance times in Pacific time with English month names
+const HEADLINE_PATH: &str = "/news/headline.json?lang=en-us&media=pcapp";
+// Only the newest few announcements can still be relevant
+const MAX_ANNOUNCEMENTS: usize = 5;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MaintenanceWindow {
+    pub title: String,
+    pub url: String,
+    // Unix timestamps in seconds
+    pub start: i64,
+    pub end: i64,
+    pub active: bool,
+}
+
+#[derive(Deserialize)]
+struct HeadlineItem {
+    title: String,
+    url: String,
+    tag: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct HeadlineRoot {
+    #[serde(default)]
+    news: Vec<HeadlineItem>,
+    #[serde(default)]
+    pinned: Vec<HeadlineItem>,
+}
+
+// Upcoming and ongoing maintenance, soonest first
+#[tauri::command]
+pub async fn get_maintenance_schedule(
+    http: State<'_, HttpClient>,
+    endpoints: Option<Endpoints>,
+) -> Result<Vec<MaintenanceWindow>, String> {
+    let client = http.client();
+    let root: HeadlineRoot = client
+        .get(endpoints.unwrap_or_default().frontier(HEADLINE_PATH))
+        .header(USER_AGENT, get_user_agent())
+        .timeout(REQUEST_TIMEOUT)
+        .send()
+        .await
+        .map_err(|e| format!("Failed to get news: {}", e))?
+        .json()
+        .await
+        .map_err(|e| format!("Failed to parse news JSON: {}", e))?;
+
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs() as i64;
+
+    let mut announcements: Vec<HeadlineItem> = root
+        .pinned
+        .into_iter()
+        .chain(root.news)
+        .filter(is_maintenance_announcement)
+        .collect();
+    // Pinned items are often repeated in the news list
+    let mut seen = HashSet::new();
+    announcements.retain(|item| seen.insert(item.url.clone()));
+    announcements.truncate(MAX_ANNOUNCEMENTS);
+
+    let mut windows = Vec::new();
+    for item in announcements {
+        let page = match fetch_page(&client, &item.url).await {
+            Ok(page) => page,
+            Err(e) => {
+                warn!(
+                    "Failed to read maintenance announcement {}: {}",
+                    item.url, e
+                );
+                continue;
+            }
+        };
+        let Some((start, end)) = parse_maintenance_time(&page) else {
+            debug!("No maintenance time found in {}", item.url);
+            continue;
+        };
+        if end <= now || windows.iter().any(|w: &MaintenanceWindow| w.start == start) {
+            continue;
+        }
+
+        windows.push(MaintenanceWindow {
+            title: item.title,
+            url: item.url,
+            start,
+            end,
+            active: start <= now,
+        });
+    }
+
+    windows.sort_by_key(|w| w.start);
+    info!("Found {} upcoming maintenance window(s)", windows.len());
+    Ok(windows)
+}
+
+fn is_maintenance_announcement(item: &HeadlineItem) -> bool {
+    let tagged = item
+        .tag
+        .as_deref()
+        .is_some_and(|tag| tag.eq_ignore_ascii_case("maintenance"));
+    // Follow-ups repeat the original window or announce that it already ended
+    let title = item.title.to_lowercase();
+    (tagged || title.contains("maintenance"))
+        && !title.contains("follow-up")
+        && !title.contains("completed")
+}
+
+async fn fetch_page(client: &reqwest::Client, url: &str) -> Result<String, String> {
+    client
+        .get(url)
+        .header(USER_AGENT, get_user_agent())
+        .timeout(REQUEST_TIMEOUT)
+        .send()
+        .await
+        .map_err(|e| e.to_string())?
+        .text()
+        .await
+        .map_err(|e| e.to_string())
+}
+
+// Finds the "[Date & Time]" section of a Lodestone post, e.g.
+// "Jan. 6, 2025 20:00 to Jan. 7, 2025 0:00 (PST)" or "Jan. 6, 2025 1:00 to 9:00 (PST)"
+fn parse_maintenance_time(html: &str) -> Option<(i64, i64)> {
+    let tags = Regex::new(r"<[^>]*>").unwrap();
+    let text = tags.replace_all(html, " ").replace("&amp;", "&");
+
+    let re = Regex::new(
+        r"\[Date & Time\]\s*(?:From\s+)?([A-Z][a-z]{2,3})\.?\s+(\d{1,2}),\s+(\d{4})\s+(\d{1,2}):(\d{2})\s+to\s+(?:([A-Z][a-z]{2,3})\.?\s+(\d{1,2}),\s+(\d{4})\s+)?(\d{1,2}):(\d{2})\s*\((PST|PDT|GMT|BST|UTC)\)",
+    )
+    .unwrap();
+    let caps = re.captures(&text)?;
+
+    let offset = match &caps[11] {
+        "PST" => UtcOffset::from_hms(-8, 0, 0),
+        "PDT" => UtcOffset::from_hms(-7, 0, 0),
+        "BST" => UtcOffset::from_hms(1, 0, 0),
+        _ => Ok(UtcOffset::UTC),
+    }
+    .ok()?;
+
+    let start_date = parse_date(&caps[1], &caps[2], &caps[3])?;
+    let start = PrimitiveDateTime::new(start_date, parse_time(&caps[4], &caps[5])?);
+
+    let end_date = match (caps.get(6), caps.get(7), caps.get(8)) {
+        (Some(month), Some(day), Some(year)) => {
+            parse_date(month.as_str(), day.as_str(), year.as_str())?
+        }
+        _ => start_date,
+    };
+    let mut end = PrimitiveDateTime::new(end_date, parse_time(&caps[9], &caps[10])?);
+    // "20:00 to 0:00" without an end date runs past midnight
+    if end <= start {
+        end += time::Duration::days(1);
+    }
+
+    Some((
+        start.assume_offset(offset).unix_timestamp(),
+        end.assume_offset(offset).unix_timestamp(),
+    ))
+}
+
+fn parse_date(month: &str, day: &str, year: &str) -> Option<Date> {
+    let month = match month.get(..3)? {
+        "Jan" => Month::January,
+        "Feb" => Month::February,
+        "Mar" => Month::March,
+        "Apr" => Month::April,
+        "May" => Month::May,
+        "Jun" => Month::June,
+        "Jul" => Month::July,
+        "Aug" => Month::August,
+        "Sep" => Month::September,
+        "Oct" => Month::October,
+        "Nov" => Month::November,
+        "Dec" => Month::December,
+        _ => return None,
+    };
+    Date::from_calendar_date(year.parse().ok()?, month, day.parse().ok()?).ok()
+}
+
+fn parse_time(hour: &str, minute: &str) -> Option<Time> {
+    Time::from_hms(hour.parse().ok()?, minute.parse().ok()?, 0).ok()
+}
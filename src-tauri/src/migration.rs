@@ -0,0 +1,156 @@
+use serde::Serialize;
+use serde_json::{Map, Value};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use tracing::{info, warn};
+
+const LAUNCHER_CONFIG_FILE: &str = "launcherConfigV3.json";
+const ACCOUNTS_FILE: &str = "accountsList.json";
+
+// Field names match the frontend GameConfig so the result can be merged into it directly
+#[derive(Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct XivLauncherImport {
+    pub source_path: String,
+    pub game_path: Option<String>,
+    pub dx11: Option<bool>,
+    pub language: Option<u32>,
+    pub is_free_trial: Option<bool>,
+    pub encrypt_arguments: Option<bool>,
+    pub additional_launch_args: Option<String>,
+    pub dpi_awareness: Option<String>,
+    pub otp_listener: Option<bool>,
+    pub dalamud_enabled: Option<bool>,
+    pub dalamud_inject_delay: Option<u64>,
+    // XIVLauncher keeps Dalamud, plugins and configs in the same layout we use
+    pub dalamud_path: String,
+    pub dalamud_plugin_path: String,
+    pub dalamud_config_path: String,
+    pub accounts: Vec<ImportedAccount>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportedAccount {
+    pub username: String,
+    pub use_otp: bool,
+    pub is_steam: bool,
+    // Passwords stay in XIVLauncher's credential entries and have to be entered again
+    pub save_password: bool,
+}
+
+#[tauri::command]
+pub fn import_xivlauncher_settings(
+    xivlauncher_path: Option<String>,
+) -> Result<XivLauncherImport, String> {
+    let root = match xivlauncher_path.filter(|p| !p.is_empty()) {
+        Some(path) => PathBuf::from(path),
+        None => default_xivlauncher_path()?,
+    };
+
+    let config_path = root.join(LAUNCHER_CONFIG_FILE);
+    if !config_path.is_file() {
+        return Err(format!(
+            "No XIVLauncher configuration found at {}",
+            config_path.display()
+        ));
+    }
+    info!("Importing XIVLauncher settings from {}", root.display());
+
+    let config: Map<String, Value> = serde_json::from_str(
+        &fs::read_to_string(&config_path)
+            .map_err(|e| format!("Failed to read {}: {}", LAUNCHER_CONFIG_FILE, e))?,
+    )
+    .map_err(|e| format!("Failed to parse {}: {}", LAUNCHER_CONFIG_FILE, e))?;
+
+    let root_str = root.to_string_lossy().replace('\\', "/");
+    let import = XivLauncherImport {
+        source_path: root_str.clone(),
+        game_path: get_string(&config, "GamePath"),
+        dx11: get_bool(&config, "IsDx11"),
+        language: get_string(&config, "Language").and_then(|l| client_language(&l)),
+        is_free_trial: get_bool(&config, "IsFt"),
+        encrypt_arguments: get_bool(&config, "EncryptArguments"),
+        additional_launch_args: get_string(&config, "AdditionalArgs"),
+        dpi_awareness: get_string(&config, "DpiAwareness"),
+        otp_listener: get_bool(&config, "IsOtpServer"),
+        dalamud_enabled: get_bool(&config, "InGameAddonEnabled"),
+        dalamud_inject_delay: get_string(&config, "DalamudInjectionDelayMs")
+            .and_then(|d| d.parse().ok()),
+        dalamud_path: root_str.clone(),
+        dalamud_plugin_path: format!("{}/installedPlugins", root_str),
+        dalamud_config_path: root_str.clone(),
+        accounts: read_accounts(&root),
+    };
+
+    info!(
+        "Imported XIVLauncher settings with {} account(s)",
+        import.accounts.len()
+    );
+    Ok(import)
+}
+
+fn default_xivlauncher_path() -> Result<PathBuf, String> {
+    let app_data = std::env::var("APPDATA")
+        .map_err(|_| "Could not locate %AppData% to find XIVLauncher".to_string())?;
+    Ok(Path::new(&app_data).join("XIVLauncher"))
+}
+
+fn read_accounts(root: &Path) -> Vec<ImportedAccount> {
+    let contents = match fs::read_to_string(root.join(ACCOUNTS_FILE)) {
+        Ok(contents) => contents,
+        Err(e) => {
+            warn!("No XIVLauncher accounts imported: {}", e);
+            return Vec::new();
+        }
+    };
+
+    let accounts: Vec<Map<String, Value>> = match serde_json::from_str(&contents) {
+        Ok(accounts) => accounts,
+        Err(e) => {
+            warn!("Failed to parse {}: {}", ACCOUNTS_FILE, e);
+            return Vec::new();
+        }
+    };
+
+    accounts
+        .iter()
+        .filter_map(|account| {
+            Some(ImportedAccount {
+                username: get_string(account, "UserName")?,
+                use_otp: get_bool(account, "UseOtp").unwrap_or(false),
+                is_steam: get_bool(account, "UseSteamServiceAccount").unwrap_or(false),
+                save_password: get_bool(account, "SavePassword").unwrap_or(false),
+            })
+        })
+        .collect()
+}
+
+// XIVLauncher's config store writes most values as strings, so accept both forms
+fn get_string(map: &Map<String, Value>, key: &str) -> Option<String> {
+    match map.get(key)? {
+        Value::String(s) if !s.is_empty() => Some(s.clone()),
+        Value::Number(n) => Some(n.to_string()),
+        Value::Bool(b) => Some(b.to_string()),
+        _ => None,
+    }
+}
+
+fn get_bool(map: &Map<String, Value>, key: &str) -> Option<bool> {
+    match map.get(key)? {
+        Value::Bool(b) => Some(*b),
+        Value::String(s) => s.to_ascii_lowercase().parse().ok(),
+        _ => None,
+    }
+}
+
+fn client_language(language: &str) -> Option<u32> {
+    match language {
+        "Japanese" | "0" => Some(0),
+        "English" | "1" => Some(1),
+        "German" | "2" => Some(2),
+        "French" | "3" => Some(3),
+        _ => None,
+    }
+}
@@ -0,0 +1,153 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use tracing::{info, warn};
+
+// Dalamud keeps plugin settings next to the configuration path the start info points at
+const PENUMBRA_CONFIG: &str = "pluginConfigs/Penumbra.json";
+// TexTools writes its mod list into the game folder and edits the sqpack indexes in place
+const TEXTOOLS_MODLIST: &str = "game/XivMods.json";
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ModStatus {
+    pub penumbra_directory: Option<String>,
+    pub penumbra_mod_count: usize,
+    pub textools_modlist: Option<String>,
+    pub textools_mod_count: usize,
+    pub warnings: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct TexToolsModList {
+    #[serde(default, rename = "Mods")]
+    mods: Vec<TexToolsMod>,
+}
+
+#[derive(Deserialize)]
+struct TexToolsMod {
+    #[serde(default = "default_enabled")]
+    enabled: bool,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+#[tauri::command]
+pub fn get_mod_status(game_path: String, dalamud_path: String) -> ModStatus {
+    detect(&game_path, &dalamud_path)
+}
+
+pub fn detect(game_path: &str, dalamud_path: &str) -> ModStatus {
+    let mut status = ModStatus::default();
+
+    if let Some(directory) = penumbra_directory(dalamud_path) {
+        status.penumbra_mod_count = fs::read_dir(&directory)
+            .map(|entries| {
+                entries
+                    .filter_map(|entry| entry.ok())
+                    .filter(|entry| entry.path().is_dir())
+                    .count()
+            })
+            .unwrap_or(0);
+        if !directory.is_dir() {
+            status.warnings.push(format!(
+                "Penumbra's mod directory {} does not exist",
+                directory.display()
+            ));
+        }
+        status.penumbra_directory = Some(directory.to_string_lossy().to_string());
+    }
+
+    let modlist = Path::new(game_path).join(TEXTOOLS_MODLIST);
+    if modlist.is_file() {
+        status.textools_mod_count = fs::read_to_string(&modlist)
+            .ok()
+            .and_then(|contents| serde_json::from_str::<TexToolsModList>(&contents).ok())
+            .map(|list| list.mods.iter().filter(|m| m.enabled).count())
+            .unwrap_or(0);
+        status.textools_modlist = Some(modlist.to_string_lossy().to_string());
+    }
+
+    status.warnings.extend(conflict_warnings(&status));
+    status
+}
+
+// Patching writes the original sqpack files back, so anything TexTools changed gets in the way
+pub fn patch_warnings(game_path: &str) -> Vec<String> {
+    let modlist = Path::new(game_path).join(TEXTOOLS_MODLIST);
+    if !modlist.is_file() {
+        return Vec::new();
+    }
+    vec![
+        "TexTools mods are installed. Disable them in TexTools before patching, patched files \
+         can overwrite them and leave the game unable to start"
+            .to_string(),
+    ]
+}
+
+fn conflict_warnings(status: &ModStatus) -> Vec<String> {
+    let mut warnings = Vec::new();
+    if status.textools_mod_count > 0 && status.penumbra_directory.is_some() {
+        warnings.push(
+            "Both TexTools and Penumbra mods are installed, TexTools changes to the game files \
+             apply under every Penumbra collection"
+                .to_string(),
+        );
+    }
+    warnings
+}
+
+// Sets Penumbra's mod directory for a first start, one Penumbra already knows is left alone
+pub fn prepare_penumbra(dalamud_path: &str, mod_directory: &str) -> Result<(), String> {
+    if mod_directory.is_empty() {
+        return Ok(());
+    }
+    if penumbra_directory(dalamud_path).is_some() {
+        return Ok(());
+    }
+
+    fs::create_dir_all(mod_directory)
+        .map_err(|e| format!("Failed to create Penumbra mod directory: {}", e))?;
+
+    let path = penumbra_config_path(dalamud_path);
+    let mut config = fs::read_to_string(&path)
+        .ok()
+        .and_then(|contents| serde_json::from_str::<Value>(&contents).ok())
+        .filter(Value::is_object)
+        .unwrap_or_else(|| Value::Object(Default::default()));
+    config["ModDirectory"] = Value::String(mod_directory.to_string());
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)
+            .map_err(|e| format!("Failed to create plugin config directory: {}", e))?;
+    }
+    let contents = serde_json::to_string_pretty(&config)
+        .map_err(|e| format!("Failed to serialize Penumbra config: {}", e))?;
+    fs::write(&path, contents).map_err(|e| format!("Failed to write Penumbra config: {}", e))?;
+    info!("Set Penumbra's mod directory to {}", mod_directory);
+    Ok(())
+}
+
+fn penumbra_config_path(dalamud_path: &str) -> PathBuf {
+    Path::new(dalamud_path).join(PENUMBRA_CONFIG)
+}
+
+fn penumbra_directory(dalamud_path: &str) -> Option<PathBuf> {
+    let path = penumbra_config_path(dalamud_path);
+    let contents = fs::read_to_string(&path).ok()?;
+    let config: Value = match serde_json::from_str(&contents) {
+        Ok(config) => config,
+        Err(e) => {
+            warn!("Failed to parse {}: {}", path.display(), e);
+            return None;
+        }
+    };
+    config
+        .get("ModDirectory")
+        .and_then(Value::as_str)
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+}
@@ -0,0 +1,120 @@
+use serde::Serialize;
+use std::io::Error as IoError;
+use std::os::windows::ffi::OsStrExt;
+use std::os::windows::io::{AsHandle, AsRawHandle};
+use std::path::Path;
+use windows::core::{s, PCWSTR};
+use windows::Win32::Foundation::{FreeLibrary, HANDLE, HMODULE};
+use windows::Win32::System::LibraryLoader::{
+    GetProcAddress, LoadLibraryExW, LOAD_WITH_ALTERED_SEARCH_PATH,
+};
+
+use tracing::{error, info};
+
+use crate::process::windows::SuspendedProcess;
+
+// What Dalamud.Boot reads from the load info, fields left out keep Boot's defaults
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct BootStartInfo {
+    pub working_directory: String,
+    pub configuration_path: String,
+    pub log_path: String,
+    pub plugin_directory: String,
+    pub asset_directory: String,
+    pub language: u32,
+    pub game_version: String,
+    pub troubleshooting_pack_data: String,
+    pub delay_initialize_ms: i32,
+    pub boot_log_path: String,
+    pub no_load_plugins: bool,
+}
+
+// Does what `Dalamud.Injector.exe launch --mode=entrypoint` does without the extra process:
+// have Dalamud.Boot rewrite the entrypoint of the suspended game so it loads Dalamud before
+// the game's own code runs. The caller resumes the game afterwards.
+// The remote allocation and the bootstrap written into it are left to Boot. Their layout is
+// private to each Boot build, a copy of it here would break on the next Dalamud update
+pub fn rewrite_entry_point(
+    process: &SuspendedProcess,
+    boot_directory: &str,
+    game_path: &str,
+    start_info: &BootStartInfo,
+) -> Result<(), String> {
+    let load_info = serde_json::to_string(start_info)
+        .map_err(|e| format!("Failed to serialize Dalamud start info: {}", e))?;
+    let boot = BootLibrary::load(&Path::new(boot_directory).join("Dalamud.Boot.dll"))?;
+
+    let result = unsafe {
+        (boot.rewrite_entry_point)(
+            HANDLE(process.as_handle().as_raw_handle()),
+            wide(game_path).as_ptr(),
+            wide(&load_info).as_ptr(),
+        )
+    };
+    if result != 0 {
+        let error = IoError::from_raw_os_error(result as i32);
+        error!("Dalamud.Boot failed to rewrite the entrypoint: {}", error);
+        return Err(format!(
+            "Dalamud.Boot could not rewrite the game's entrypoint: {}",
+            error
+        ));
+    }
+
+    info!("Entrypoint of game process {} rewritten", process.pid());
+    Ok(())
+}
+
+fn wide(value: &str) -> Vec<u16> {
+    std::ffi::OsStr::new(value)
+        .encode_wide()
+        .chain(std::iter::once(0))
+        .collect()
+}
+
+// Exported by Dalamud.Boot for the injector, returns 0 or a Win32 error code
+type RewriteRemoteEntryPointW =
+    unsafe extern "system" fn(process: HANDLE, game_path: *const u16, load_info: *const u16) -> u32;
+
+// Dalamud.Boot loaded into the launcher only for the rewrite, unloaded again on drop so
+// the next launch can use another Dalamud version
+struct BootLibrary {
+    module: HMODULE,
+    rewrite_entry_point: RewriteRemoteEntryPointW,
+}
+
+impl BootLibrary {
+    fn load(path: &Path) -> Result<Self, String> {
+        let path_wide = wide(&path.to_string_lossy());
+        unsafe {
+            // Altered search path so Boot's own dependencies resolve from its folder
+            let module = LoadLibraryExW(
+                PCWSTR(path_wide.as_ptr()),
+                HANDLE::default(),
+                LOAD_WITH_ALTERED_SEARCH_PATH,
+            )
+            .map_err(|e| format!("Failed to load {}: {}", path.display(), e))?;
+
+            let Some(export) = GetProcAddress(module, s!("RewriteRemoteEntryPointW")) else {
+                let _ = FreeLibrary(module);
+                return Err(format!(
+                    "{} does not export RewriteRemoteEntryPointW, the Dalamud version may be too old",
+                    path.display()
+                ));
+            };
+
+            Ok(Self {
+                module,
+                rewrite_entry_point: std::mem::transmute::<_, RewriteRemoteEntryPointW>(export),
+            })
+        }
+    }
+}
+
+impl Drop for BootLibrary {
+    fn drop(&mut self) {
+        unsafe {
+            let _ = FreeLibrary(self.module);
+        }
+    }
+}
@@ -0,0 +1,57 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Manager};
+
+use tracing::{debug, warn};
+
+const CACHE_FOLDER: &str = "news";
+
+// Last good frontier response for one feed and locale
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CachedResponse {
+    pub etag: Option<String>,
+    pub body: String,
+}
+
+// None when the platform has no cache directory, news is then never cached
+pub fn cache_dir(app: &AppHandle) -> Option<PathBuf> {
+    Some(app.path().app_cache_dir().ok()?.join(CACHE_FOLDER))
+}
+
+fn cache_path(dir: &Path, feed: &str, locale: &str) -> PathBuf {
+    dir.join(format!("{}-{}.json", feed, locale))
+}
+
+pub fn load(dir: Option<&Path>, feed: &str, locale: &str) -> Option<CachedResponse> {
+    let path = cache_path(dir?, feed, locale);
+    let contents = fs::read_to_string(&path).ok()?;
+    match serde_json::from_str(&contents) {
+        Ok(cached) => Some(cached),
+        Err(e) => {
+            warn!("Ignoring unreadable news cache {}: {}", path.display(), e);
+            None
+        }
+    }
+}
+
+// A failed write only costs the offline fallback, so it's logged rather than returned
+pub fn store(dir: Option<&Path>, feed: &str, locale: &str, response: &CachedResponse) {
+    let Some(dir) = dir else {
+        warn!("No cache directory available for news");
+        return;
+    };
+    let path = cache_path(dir, feed, locale);
+
+    let result = path
+        .parent()
+        .map_or(Ok(()), fs::create_dir_all)
+        .and_then(|_| {
+            let contents = serde_json::to_string(response).map_err(std::io::Error::other)?;
+            fs::write(&path, contents)
+        });
+    match result {
+        Ok(()) => debug!("Cached {} news for {}", feed, locale),
+        Err(e) => warn!("Failed to cache {} news: {}", feed, e),
+    }
+}
@@ -0,0 +1,119 @@
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::oneshot;
+
+use tracing::{info, warn};
+
+// Same port and path XIVLauncher uses, so existing OTP macros and phone apps keep working
+const OTP_PORT: u16 = 4646;
+const OTP_PATH: &str = "/ffxivlauncher/";
+const OTP_TIMEOUT: Duration = Duration::from_secs(300);
+
+// Set while a login is waiting, so a code typed into the UI can race the listener
+static PENDING_MANUAL_OTP: Mutex<Option<oneshot::Sender<String>>> = Mutex::new(None);
+
+#[tauri::command]
+pub fn submit_otp(otp: String) -> Result<(), String> {
+    let otp = otp.trim().to_string();
+    if !is_valid_otp(&otp) {
+        return Err("OTP must be a 6 digit code".to_string());
+    }
+
+    let sender = PENDING_MANUAL_OTP
+        .lock()
+        .unwrap()
+        .take()
+        .ok_or_else(|| "No login is waiting for an OTP".to_string())?;
+
+    sender
+        .send(otp)
+        .map_err(|_| "Login stopped waiting for an OTP".to_string())
+}
+
+// Resolves with whichever arrives first: a manually submitted code or one sent to the listener
+pub async fn wait_for_otp() -> Result<String, String> {
+    let (sender, receiver) = oneshot::channel();
+    *PENDING_MANUAL_OTP.lock().unwrap() = Some(sender);
+
+    let listener = match TcpListener::bind(("127.0.0.1", OTP_PORT)).await {
+        Ok(listener) => {
+            info!(
+                "Waiting for OTP on http://localhost:{}{}",
+                OTP_PORT, OTP_PATH
+            );
+            Some(listener)
+        }
+        Err(e) => {
+            warn!(
+                "Failed to start OTP listener on port {}, only manual entry will work: {}",
+                OTP_PORT, e
+            );
+            None
+        }
+    };
+
+    let result = tokio::select! {
+        otp = receiver => otp.map_err(|_| "OTP entry was cancelled".to_string()),
+        otp = accept_otp(listener) => Ok(otp),
+        _ = tokio::time::sleep(OTP_TIMEOUT) => Err("Timed out waiting for OTP".to_string()),
+    };
+
+    PENDING_MANUAL_OTP.lock().unwrap().take();
+    result
+}
+
+async fn accept_otp(listener: Option<TcpListener>) -> String {
+    let Some(listener) = listener else {
+        return std::future::pending().await;
+    };
+
+    loop {
+        let (stream, addr) = match listener.accept().await {
+            Ok(connection) => connection,
+            Err(e) => {
+                warn!("Failed to accept OTP connection: {}", e);
+                continue;
+            }
+        };
+
+        match handle_connection(stream).await {
+            Some(otp) => {
+                info!("Received OTP from {}", addr);
+                return otp;
+            }
+            None => warn!("Ignoring invalid OTP request from {}", addr),
+        }
+    }
+}
+
+async fn handle_connection(mut stream: TcpStream) -> Option<String> {
+    let mut buffer = [0u8; 1024];
+    let read = stream.read(&mut buffer).await.ok()?;
+    let request = String::from_utf8_lossy(&buffer[..read]);
+
+    // Only the request line matters, e.g. "GET /ffxivlauncher/123456 HTTP/1.1"
+    let path = request.lines().next()?.split_whitespace().nth(1)?;
+    let otp = path
+        .strip_prefix(OTP_PATH)
+        .map(|otp| otp.trim_end_matches('/'));
+
+    let (status, otp) = match otp {
+        Some(otp) if is_valid_otp(otp) => ("200 OK", Some(otp.to_string())),
+        _ => ("400 Bad Request", None),
+    };
+
+    let response = format!(
+        "HTTP/1.1 {}\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+        status
+    );
+    let _ = stream.write_all(response.as_bytes()).await;
+    let _ = stream.shutdown().await;
+
+    otp
+}
+
+fn is_valid_otp(otp: &str) -> bool {
+    otp.len() == 6 && otp.chars().all(|c| c.is_ascii_digit())
+}
@@ -0,0 +1,584 @@
+use futures::stream::{self, StreamExt, TryStreamExt};
+use reqwest::header::{ACCEPT_RANGES, CONTENT_LENGTH, RANGE};
+use reqwest::{Client, StatusCode, Url};
+use serde::{Deserialize, Serialize};
+use sha1::{Digest, Sha1};
+use std::io::{Read, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::fs::{self, OpenOptions};
+use tokio::io::{AsyncSeekExt, AsyncWriteExt};
+use tokio::sync::Mutex;
+
+use tracing::{info, warn};
+
+use crate::disk_space;
+use crate::throttle;
+
+// How much data a segment writes before its progress is persisted
+const STATE_FLUSH_INTERVAL: u64 = 4 * 1024 * 1024;
+const PROGRESS_REPORT_INTERVAL: Duration = Duration::from_millis(250);
+// Segments smaller than this are not worth an extra connection
+const MIN_SEGMENT_SIZE: u64 = 8 * 1024 * 1024;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DownloadRequest {
+    pub url: String,
+    // Path relative to the download directory
+    pub file_name: String,
+    // Expected size in bytes, 0 if unknown
+    pub length: u64,
+    // SHA-1 of each block of hash_block_size bytes, empty when the patch list has none
+    #[serde(default)]
+    pub hash_block_size: u64,
+    #[serde(default)]
+    pub hashes: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DownloadProgress {
+    pub file_name: String,
+    pub downloaded: u64,
+    pub total: u64,
+    pub completed: bool,
+}
+
+pub type ProgressCallback = Arc<dyn Fn(DownloadProgress) + Send + Sync>;
+
+// Persisted next to the partial file so a download can resume after a restart
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct DownloadState {
+    url: String,
+    total: u64,
+    segments: Vec<Segment>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Segment {
+    start: u64,
+    // Inclusive, matching HTTP range semantics
+    end: u64,
+    downloaded: u64,
+}
+
+impl Segment {
+    fn len(&self) -> u64 {
+        self.end - self.start + 1
+    }
+
+    fn is_complete(&self) -> bool {
+        self.downloaded >= self.len()
+    }
+}
+
+struct FileProgress {
+    file_name: String,
+    total: u64,
+    downloaded: AtomicU64,
+    last_report: std::sync::Mutex<Instant>,
+}
+
+pub struct PatchDownloader {
+    client: Client,
+    download_dir: PathBuf,
+    connections_per_file: usize,
+    max_concurrent_files: usize,
+    on_progress: Option<ProgressCallback>,
+    // Base URL that serves the same paths as the patch server, e.g. a LAN cache
+    mirror: Option<String>,
+}
+
+impl PatchDownloader {
+    pub fn new(client: Client, download_dir: impl Into<PathBuf>) -> Self {
+        Self {
+            client,
+            download_dir: download_dir.into(),
+            connections_per_file: 4,
+            max_concurrent_files: 2,
+            on_progress: None,
+            mirror: None,
+        }
+    }
+
+    // Empty downloads straight from Square Enix, check the URL with validate_mirror first
+    pub fn mirror(mut self, base: &str) -> Self {
+        let base = base.trim().trim_end_matches('/');
+        self.mirror = (!base.is_empty()).then(|| base.to_string());
+        self
+    }
+
+    pub fn on_progress(
+        mut self,
+        callback: impl Fn(DownloadProgress) + Send + Sync + 'static,
+    ) -> Self {
+        self.on_progress = Some(Arc::new(callback));
+        self
+    }
+
+    pub async fn download_all(&self, requests: &[DownloadRequest]) -> Result<Vec<PathBuf>, String> {
+        // Part files are allocated at full size, so only files not started yet still need room
+        let mut needed = 0;
+        for request in requests {
+            let target = self.download_dir.join(&request.file_name);
+            let started = file_len(&target).await.is_some()
+                || file_len(&with_suffix(&target, ".part")).await.is_some();
+            if !started {
+                needed += request.length;
+            }
+        }
+        disk_space::ensure_free_space(&self.download_dir, needed, "the patch download")?;
+
+        info!(
+            "Downloading {} files with up to {} at a time",
+            requests.len(),
+            self.max_concurrent_files
+        );
+
+        // buffered keeps results in request order so patches stay in apply order
+        let downloads: Vec<_> = requests
+            .iter()
+            .map(|request| self.download(request))
+            .collect();
+        stream::iter(downloads)
+            .buffered(self.max_concurrent_files)
+            .try_collect()
+            .await
+    }
+
+    // Tries the mirror first. Whatever it serves has to match the patch list, anything
+    // else is thrown away and downloaded from Square Enix instead
+    pub async fn download(&self, request: &DownloadRequest) -> Result<PathBuf, String> {
+        if let Some(mirrored) = self.mirrored(request) {
+            match self.download_verified(&mirrored).await {
+                Ok(path) => return Ok(path),
+                Err(e) => warn!(
+                    "Mirror download of {} failed, using {} instead: {}",
+                    request.file_name, request.url, e
+                ),
+            }
+        }
+        self.download_verified(request).await
+    }
+
+    fn mirrored(&self, request: &DownloadRequest) -> Option<DownloadRequest> {
+        let base = self.mirror.as_ref()?;
+        let path = Url::parse(&request.url).ok()?.path().to_string();
+        Some(DownloadRequest {
+            url: format!("{}{}", base, path),
+            ..request.clone()
+        })
+    }
+
+    async fn download_verified(&self, request: &DownloadRequest) -> Result<PathBuf, String> {
+        let path = self.fetch(request).await?;
+        let verify_path = path.clone();
+        let expected = request.clone();
+        let result = tokio::task::spawn_blocking(move || verify_file(&verify_path, &expected))
+            .await
+            .map_err(|e| format!("Background task failed: {}", e))?;
+        if let Err(e) = result {
+            let _ = fs::remove_file(&path).await;
+            return Err(e);
+        }
+        Ok(path)
+    }
+
+    async fn fetch(&self, request: &DownloadRequest) -> Result<PathBuf, String> {
+        let target = self.download_dir.join(&request.file_name);
+        let part_path = with_suffix(&target, ".part");
+        let state_path = with_suffix(&target, ".state");
+
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)
+                .await
+                .map_err(|e| format!("Failed to create directory {}: {}", parent.display(), e))?;
+        }
+
+        if request.length > 0 && file_len(&target).await == Some(request.length) {
+            info!("{} is already downloaded", request.file_name);
+            self.report_complete(&request.file_name, request.length);
+            return Ok(target);
+        }
+
+        let (total, supports_ranges) = self.probe(request).await?;
+        let state = match load_state(&state_path, &part_path, request, total).await {
+            Some(state) if supports_ranges => {
+                let done: u64 = state.segments.iter().map(|s| s.downloaded).sum();
+                info!(
+                    "Resuming {} at {} of {} bytes",
+                    request.file_name, done, total
+                );
+                state
+            }
+            _ => {
+                let connections = if supports_ranges && total > 0 {
+                    self.connections_per_file
+                } else {
+                    1
+                };
+                let state = DownloadState {
+                    url: request.url.clone(),
+                    total,
+                    segments: plan_segments(total, connections),
+                };
+
+                let file = fs::File::create(&part_path)
+                    .await
+                    .map_err(|e| format!("Failed to create {}: {}", part_path.display(), e))?;
+                if total > 0 {
+                    file.set_len(total).await.map_err(|e| {
+                        format!("Failed to allocate {}: {}", part_path.display(), e)
+                    })?;
+                }
+                save_state(&state_path, &state).await?;
+                state
+            }
+        };
+
+        let progress = Arc::new(FileProgress {
+            file_name: request.file_name.clone(),
+            total,
+            downloaded: AtomicU64::new(state.segments.iter().map(|s| s.downloaded).sum()),
+            last_report: std::sync::Mutex::new(Instant::now()),
+        });
+
+        let pending: Vec<usize> = state
+            .segments
+            .iter()
+            .enumerate()
+            .filter(|(_, segment)| !segment.is_complete())
+            .map(|(index, _)| index)
+            .collect();
+        let state = Arc::new(Mutex::new(state));
+
+        futures::future::try_join_all(pending.into_iter().map(|index| {
+            self.download_segment(
+                &request.url,
+                &part_path,
+                &state_path,
+                state.clone(),
+                index,
+                progress.clone(),
+            )
+        }))
+        .await?;
+
+        let written = file_len(&part_path).await.unwrap_or(0);
+        if total > 0 && written != total {
+            return Err(format!(
+                "Downloaded size mismatch for {}: expected {}, got {}",
+                request.file_name, total, written
+            ));
+        }
+
+        fs::rename(&part_path, &target)
+            .await
+            .map_err(|e| format!("Failed to move {} into place: {}", target.display(), e))?;
+        let _ = fs::remove_file(&state_path).await;
+
+        info!("Finished downloading {}", request.file_name);
+        self.report_complete(&request.file_name, written);
+        Ok(target)
+    }
+
+    async fn probe(&self, request: &DownloadRequest) -> Result<(u64, bool), String> {
+        let response = self
+            .client
+            .head(&request.url)
+            .send()
+            .await
+            .map_err(|e| format!("Failed to query {}: {}", request.url, e))?;
+
+        if !response.status().is_success() {
+            return Err(format!(
+                "Download of {} failed with status: {}",
+                request.url,
+                response.status()
+            ));
+        }
+
+        let supports_ranges = response
+            .headers()
+            .get(ACCEPT_RANGES)
+            .and_then(|v| v.to_str().ok())
+            .map(|v| v.eq_ignore_ascii_case("bytes"))
+            .unwrap_or(false);
+
+        let length = response
+            .headers()
+            .get(CONTENT_LENGTH)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.parse::<u64>().ok())
+            .unwrap_or(request.length);
+
+        if request.length > 0 && length != request.length {
+            warn!(
+                "Server reports {} bytes for {}, expected {}",
+                length, request.file_name, request.length
+            );
+        }
+
+        Ok((length, supports_ranges))
+    }
+
+    async fn download_segment(
+        &self,
+        url: &str,
+        part_path: &Path,
+        state_path: &Path,
+        state: Arc<Mutex<DownloadState>>,
+        index: usize,
+        progress: Arc<FileProgress>,
+    ) -> Result<(), String> {
+        let (start, end, total, already_downloaded) = {
+            let state = state.lock().await;
+            let segment = &state.segments[index];
+            (segment.start, segment.end, state.total, segment.downloaded)
+        };
+        let mut position = start + already_downloaded;
+
+        let mut request = self.client.get(url);
+        if total > 0 {
+            request = request.header(RANGE, format!("bytes={}-{}", position, end));
+        }
+
+        let mut response = request
+            .send()
+            .await
+            .map_err(|e| format!("Failed to download {}: {}", url, e))?;
+
+        // A plain 200 is only usable when this segment covers the whole file from the start
+        let status = response.status();
+        let full_body_ok = position == 0 && (total == 0 || end + 1 == total);
+        if !status.is_success() || (status != StatusCode::PARTIAL_CONTENT && !full_body_ok) {
+            return Err(format!(
+                "Download of {} failed with status: {}",
+                url, status
+            ));
+        }
+
+        let mut file = OpenOptions::new()
+            .write(true)
+            .open(part_path)
+            .await
+            .map_err(|e| format!("Failed to open {}: {}", part_path.display(), e))?;
+        file.seek(SeekFrom::Start(position))
+            .await
+            .map_err(|e| format!("Failed to seek in {}: {}", part_path.display(), e))?;
+
+        let mut unsaved = 0u64;
+        while let Some(chunk) = response
+            .chunk()
+            .await
+            .map_err(|e| format!("Failed to read download stream: {}", e))?
+        {
+            file.write_all(&chunk)
+                .await
+                .map_err(|e| format!("Failed to write {}: {}", part_path.display(), e))?;
+
+            let len = chunk.len() as u64;
+            position += len;
+            unsaved += len;
+            self.report(&progress, len);
+            throttle::consume(len).await;
+
+            if unsaved >= STATE_FLUSH_INTERVAL {
+                file.flush()
+                    .await
+                    .map_err(|e| format!("Failed to flush {}: {}", part_path.display(), e))?;
+                update_state(state_path, &state, index, position - start).await?;
+                unsaved = 0;
+            }
+        }
+
+        file.flush()
+            .await
+            .map_err(|e| format!("Failed to flush {}: {}", part_path.display(), e))?;
+        update_state(state_path, &state, index, position - start).await?;
+
+        if total > 0 && position != end + 1 {
+            return Err(format!(
+                "Connection closed early while downloading {} ({} of {} bytes)",
+                url,
+                position - start,
+                end - start + 1
+            ));
+        }
+
+        Ok(())
+    }
+
+    fn report(&self, progress: &FileProgress, len: u64) {
+        let downloaded = progress.downloaded.fetch_add(len, Ordering::Relaxed) + len;
+
+        let Some(callback) = &self.on_progress else {
+            return;
+        };
+
+        {
+            let mut last_report = progress.last_report.lock().unwrap();
+            if last_report.elapsed() < PROGRESS_REPORT_INTERVAL {
+                return;
+            }
+            *last_report = Instant::now();
+        }
+
+        callback(DownloadProgress {
+            file_name: progress.file_name.clone(),
+            downloaded,
+            total: progress.total,
+            completed: false,
+        });
+    }
+
+    fn report_complete(&self, file_name: &str, total: u64) {
+        if let Some(callback) = &self.on_progress {
+            callback(DownloadProgress {
+                file_name: file_name.to_string(),
+                downloaded: total,
+                total,
+                completed: true,
+            });
+        }
+    }
+}
+
+// Block hashes when the patch list has them, boot patches only come with a length
+fn verify_file(path: &Path, request: &DownloadRequest) -> Result<(), String> {
+    let len = std::fs::metadata(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?
+        .len();
+    if request.length > 0 && len != request.length {
+        return Err(format!(
+            "{} is {} bytes, the patch list says {}",
+            request.file_name, len, request.length
+        ));
+    }
+    if request.hashes.is_empty() || request.hash_block_size == 0 {
+        return Ok(());
+    }
+
+    let mut file = std::fs::File::open(path)
+        .map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+    let mut buffer = vec![0u8; request.hash_block_size as usize];
+    for (index, expected) in request.hashes.iter().enumerate() {
+        let mut filled = 0;
+        while filled < buffer.len() {
+            let read = file
+                .read(&mut buffer[filled..])
+                .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+            if read == 0 {
+                break;
+            }
+            filled += read;
+        }
+        let actual = hex::encode(Sha1::digest(&buffer[..filled]));
+        if !actual.eq_ignore_ascii_case(expected) {
+            return Err(format!(
+                "Block {} of {} doesn't match the patch list hash",
+                index, request.file_name
+            ));
+        }
+    }
+    info!(
+        "Verified {} blocks of {}",
+        request.hashes.len(),
+        request.file_name
+    );
+    Ok(())
+}
+
+// Only http and https, the downloader can't do anything with other schemes
+pub fn validate_mirror(base: &str) -> Result<(), String> {
+    let base = base.trim();
+    if base.is_empty() {
+        return Ok(());
+    }
+    let url = Url::parse(base).map_err(|e| format!("Invalid patch mirror {}: {}", base, e))?;
+    if !matches!(url.scheme(), "http" | "https") {
+        return Err(format!(
+            "Patch mirror {} has to be an http or https URL",
+            base
+        ));
+    }
+    Ok(())
+}
+
+fn plan_segments(total: u64, connections: usize) -> Vec<Segment> {
+    if total == 0 {
+        // Unknown size, a single open ended segment
+        return vec![Segment {
+            start: 0,
+            end: u64::MAX - 1,
+            downloaded: 0,
+        }];
+    }
+
+    let connections = (connections as u64)
+        .min(total.div_ceil(MIN_SEGMENT_SIZE))
+        .max(1);
+    let segment_size = total.div_ceil(connections);
+
+    (0..connections)
+        .map(|i| {
+            let start = i * segment_size;
+            Segment {
+                start,
+                end: (start + segment_size).min(total) - 1,
+                downloaded: 0,
+            }
+        })
+        .filter(|segment| segment.start < total)
+        .collect()
+}
+
+async fn load_state(
+    state_path: &Path,
+    part_path: &Path,
+    request: &DownloadRequest,
+    total: u64,
+) -> Option<DownloadState> {
+    let contents = fs::read_to_string(state_path).await.ok()?;
+    let state: DownloadState = serde_json::from_str(&contents).ok()?;
+
+    // Only resume if the partial file still belongs to the same download
+    if state.url != request.url || state.total != total || total == 0 {
+        return None;
+    }
+    if file_len(part_path).await != Some(total) {
+        return None;
+    }
+
+    Some(state)
+}
+
+async fn save_state(state_path: &Path, state: &DownloadState) -> Result<(), String> {
+    let contents = serde_json::to_string(state)
+        .map_err(|e| format!("Failed to serialize download state: {}", e))?;
+    fs::write(state_path, contents)
+        .await
+        .map_err(|e| format!("Failed to write {}: {}", state_path.display(), e))
+}
+
+async fn update_state(
+    state_path: &Path,
+    state: &Mutex<DownloadState>,
+    index: usize,
+    downloaded: u64,
+) -> Result<(), String> {
+    let mut state = state.lock().await;
+    state.segments[index].downloaded = downloaded;
+    save_state(state_path, &state).await
+}
+
+async fn file_len(path: &Path) -> Option<u64> {
+    fs::metadata(path).await.ok().map(|m| m.len())
+}
+
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(suffix);
+    PathBuf::from(name)
+}
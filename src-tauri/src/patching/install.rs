@@ -0,0 +1,229 @@
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Emitter, Manager};
+use tokio_util::sync::CancellationToken;
+
+use tracing::{info, warn};
+
+use crate::ffxiv::{get_session_id, patch_file_name, LaunchConfig};
+use crate::http::HttpClient;
+use crate::patching::downloader::{self, DownloadRequest, PatchDownloader};
+use crate::patching::version_check::{
+    self, GameVersionCheck, PatchEntry, PatchRepository, BASE_GAME_VERSION,
+};
+use crate::patching::zipatch::{ZiPatchApplier, ZiPatchConfig};
+
+pub const INSTALL_PROGRESS_EVENT: &str = "install://progress";
+// The server hands out the whole chain from the base version, a second round only
+// happens when a patch landed while installing
+const MAX_PATCH_ROUNDS: usize = 3;
+// Dawntrail
+const LATEST_EXPANSION: u32 = 5;
+
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum InstallStage {
+    Layout,
+    BootPatches,
+    Login,
+    GamePatches,
+    Completed,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct InstallProgressEvent {
+    pub stage: InstallStage,
+    pub message: String,
+}
+
+// Sets up an empty installation at game_path and patches it up to the current version,
+// the same way the official launcher bootstraps itself after its installer ran
+#[tauri::command]
+pub async fn install_game(
+    app: AppHandle,
+    config: LaunchConfig,
+    download_dir: String,
+) -> Result<(), String> {
+    downloader::validate_mirror(&config.patch_mirror)?;
+    let client = app.state::<HttpClient>().client();
+    // Nothing is installed yet to detect from, so lay out every expansion unless told otherwise
+    let expansion_level = config.expansion_level.unwrap_or(LATEST_EXPANSION);
+    let game_path = PathBuf::from(&config.game_path);
+    let report = |stage: InstallStage, message: String| {
+        info!("{}", message);
+        if let Err(e) = app.emit(
+            INSTALL_PROGRESS_EVENT,
+            InstallProgressEvent { stage, message },
+        ) {
+            warn!("Failed to emit install progress: {}", e);
+        }
+    };
+
+    report(
+        InstallStage::Layout,
+        format!("Creating game folders in {}", game_path.display()),
+    );
+    create_layout(&game_path, expansion_level)?;
+
+    let downloader = PatchDownloader::new(client.clone(), &download_dir)
+        .mirror(&config.patch_mirror)
+        .on_progress({
+            let app = app.clone();
+            move |progress| {
+                if let Err(e) = app.emit("patch-download-progress", &progress) {
+                    warn!("Failed to emit download progress: {}", e);
+                }
+            }
+        });
+
+    for _ in 0..MAX_PATCH_ROUNDS {
+        let versions = version_check::read_installed_versions(&config.game_path, expansion_level);
+        let patches = version_check::check_boot_version(&client, &versions).await?;
+        if patches.is_empty() {
+            break;
+        }
+        report(
+            InstallStage::BootPatches,
+            format!("Installing {} boot patches", patches.len()),
+        );
+        install_patches(&downloader, &game_path, &patches).await?;
+    }
+
+    report(
+        InstallStage::Login,
+        "Logging in to get the game patches".to_string(),
+    );
+    for _ in 0..MAX_PATCH_ROUNDS {
+        let versions = version_check::read_installed_versions(&config.game_path, expansion_level);
+        let sid = get_session_id(&client, &config, None, &CancellationToken::new())
+            .await
+            .map_err(|e| e.to_string())?;
+        let patches =
+            match version_check::check_game_version(&client, &config.game_path, &versions, &sid)
+                .await?
+            {
+                GameVersionCheck::UpToDate { .. } => break,
+                GameVersionCheck::NeedsPatch { patches, .. } => patches,
+                GameVersionCheck::NeedsBootPatch => {
+                    return Err("Boot files changed while installing, please retry".to_string())
+                }
+            };
+        report(
+            InstallStage::GamePatches,
+            format!("Installing {} game patches", patches.len()),
+        );
+        install_patches(&downloader, &game_path, &patches).await?;
+    }
+
+    report(InstallStage::Completed, "Game installed".to_string());
+    Ok(())
+}
+
+pub(crate) async fn install_patches(
+    downloader: &PatchDownloader,
+    game_path: &Path,
+    patches: &[PatchEntry],
+) -> Result<(), String> {
+    let requests: Vec<DownloadRequest> = patches.iter().map(download_request).collect();
+    let paths = downloader.download_all(&requests).await?;
+
+    let game_path = game_path.to_path_buf();
+    let patches = patches.to_vec();
+    tauri::async_runtime::spawn_blocking(move || {
+        for (patch, path) in patches.iter().zip(&paths) {
+            let target = repository_dir(&game_path, patch.repository);
+            ZiPatchApplier::new(ZiPatchConfig::new(target)).apply_file(path)?;
+            write_version(&game_path, patch)?;
+            // Patches are large, nothing needs them once they are applied
+            if let Err(e) = fs::remove_file(path) {
+                warn!("Failed to remove patch {}: {}", path.display(), e);
+            }
+        }
+        Ok(())
+    })
+    .await
+    .map_err(|e| format!("Patch installation failed: {}", e))?
+}
+
+pub(crate) fn download_request(patch: &PatchEntry) -> DownloadRequest {
+    // Only SHA-1 block hashes are handed out, anything else is checked by length alone
+    let sha1 = patch
+        .hash_type
+        .as_deref()
+        .is_some_and(|hash_type| hash_type.eq_ignore_ascii_case("sha1"));
+    DownloadRequest {
+        url: patch.url.clone(),
+        file_name: patch_file_name(&patch.url),
+        length: patch.length,
+        hash_block_size: if sha1 {
+            patch.hash_block_size.unwrap_or(0)
+        } else {
+            0
+        },
+        hashes: if sha1 {
+            patch.hashes.clone()
+        } else {
+            Vec::new()
+        },
+    }
+}
+
+fn create_layout(game_path: &Path, expansion_level: u32) -> Result<(), String> {
+    if game_path.join("game/ffxivgame.ver").exists() {
+        return Err(format!(
+            "A game installation already exists at {}",
+            game_path.display()
+        ));
+    }
+
+    let mut dirs = vec![game_path.join("boot"), game_path.join("game/sqpack/ffxiv")];
+    dirs.extend((1..=expansion_level).map(|ex| game_path.join(format!("game/sqpack/ex{}", ex))));
+    for dir in &dirs {
+        fs::create_dir_all(dir)
+            .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    }
+
+    let mut version_files = vec![
+        game_path.join("boot/ffxivboot.ver"),
+        game_path.join("game/ffxivgame.ver"),
+    ];
+    version_files.extend(
+        (1..=expansion_level).map(|ex| game_path.join(format!("game/sqpack/ex{0}/ex{0}.ver", ex))),
+    );
+    for file in &version_files {
+        fs::write(file, BASE_GAME_VERSION)
+            .map_err(|e| format!("Failed to write {}: {}", file.display(), e))?;
+    }
+    Ok(())
+}
+
+// Boot patches apply inside boot/, everything else inside game/
+fn repository_dir(game_path: &Path, repository: PatchRepository) -> PathBuf {
+    match repository {
+        PatchRepository::Boot => game_path.join("boot"),
+        _ => game_path.join("game"),
+    }
+}
+
+// Patches don't carry their version files, the launcher writes them after each one
+fn write_version(game_path: &Path, patch: &PatchEntry) -> Result<(), String> {
+    let ex = |n: u32| game_path.join(format!("game/sqpack/ex{0}/ex{0}", n));
+    let base = match patch.repository {
+        PatchRepository::Boot => game_path.join("boot/ffxivboot"),
+        PatchRepository::Game => game_path.join("game/ffxivgame"),
+        PatchRepository::Ex1 => ex(1),
+        PatchRepository::Ex2 => ex(2),
+        PatchRepository::Ex3 => ex(3),
+        PatchRepository::Ex4 => ex(4),
+        PatchRepository::Ex5 => ex(5),
+    };
+
+    // The official launcher restores from the .bck copy when the .ver file is damaged
+    for extension in ["ver", "bck"] {
+        let path = base.with_extension(extension);
+        fs::write(&path, &patch.version_id)
+            .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+    }
+    Ok(())
+}
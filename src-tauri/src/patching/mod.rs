@@ -0,0 +1,4 @@
+pub mod downloader;
+pub mod install;
+pub mod version_check;
+pub mod zipatch;
@@ -0,0 +1,295 @@
+use reqwest::header::{CONTENT_TYPE, USER_AGENT};
+use reqwest::{Client, StatusCode};
+use serde::{Deserialize, Serialize};
+use sha1::{Digest, Sha1};
+use std::fs;
+use time::OffsetDateTime;
+
+use tracing::{info, warn};
+
+use crate::http::REQUEST_TIMEOUT;
+
+const PATCHER_USER_AGENT: &str = "FFXIV PATCH CLIENT";
+const BOOT_VERSION_URL: &str = "http://patch-bootver.ffxiv.com/http/win32/ffxivneo_release_boot";
+const GAME_VERSION_URL: &str = "https://patch-gamever.ffxiv.com/http/win32/ffxivneo_release_game";
+
+// Boot executables SE expects to see hashed in the version report
+const BOOT_FILES_TO_HASH: [&str; 4] = [
+    "ffxivboot.exe",
+    "ffxivboot64.exe",
+    "ffxivlauncher64.exe",
+    "ffxivupdater64.exe",
+];
+
+// Used when the install is missing a version file entirely
+pub const BASE_GAME_VERSION: &str = "2012.01.01.0000.0000";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExpansionVersion {
+    pub expansion: u32,
+    pub version: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InstalledVersions {
+    pub boot: String,
+    pub game: String,
+    pub expansions: Vec<ExpansionVersion>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PatchRepository {
+    Boot,
+    Game,
+    Ex1,
+    Ex2,
+    Ex3,
+    Ex4,
+    Ex5,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PatchEntry {
+    pub version_id: String,
+    pub repository: PatchRepository,
+    pub url: String,
+    pub length: u64,
+    pub hash_type: Option<String>,
+    pub hash_block_size: Option<u64>,
+    pub hashes: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UpdateCheckResult {
+    pub versions: InstalledVersions,
+    pub boot_patches: Vec<PatchEntry>,
+    pub game_patches: Vec<PatchEntry>,
+    // Things to sort out before the patches are applied, like installed TexTools mods
+    #[serde(default)]
+    pub warnings: Vec<String>,
+}
+
+#[derive(Debug)]
+pub enum GameVersionCheck {
+    // Server accepted the report, carries the unique id to launch the game with
+    UpToDate { unique_id: String },
+    // Patches come first, a new unique id is needed after them anyway
+    NeedsPatch { patches: Vec<PatchEntry> },
+    // Server rejected the boot hashes, boot has to be patched first
+    NeedsBootPatch,
+}
+
+pub fn read_installed_versions(game_path: &str, expansion_level: u32) -> InstalledVersions {
+    let boot = read_version_file(&format!("{}/boot/ffxivboot.ver", game_path));
+    let game = read_version_file(&format!("{}/game/ffxivgame.ver", game_path));
+
+    let expansions = (1..=expansion_level)
+        .map(|ex| ExpansionVersion {
+            expansion: ex,
+            version: read_version_file(&format!("{}/game/sqpack/ex{}/ex{}.ver", game_path, ex, ex)),
+        })
+        .collect();
+
+    InstalledVersions {
+        boot,
+        game,
+        expansions,
+    }
+}
+
+fn read_version_file(path: &str) -> String {
+    match fs::read_to_string(path) {
+        Ok(version) => version.trim().to_string(),
+        Err(e) => {
+            warn!("Failed to read version file {}: {}", path, e);
+            BASE_GAME_VERSION.to_string()
+        }
+    }
+}
+
+pub fn boot_hash_report(game_path: &str, boot_version: &str) -> Result<String, String> {
+    let hashes = BOOT_FILES_TO_HASH
+        .iter()
+        .map(|file| {
+            let path = format!("{}/boot/{}", game_path, file);
+            let contents =
+                fs::read(&path).map_err(|e| format!("Failed to read {}: {}", path, e))?;
+
+            let mut hasher = Sha1::new();
+            hasher.update(&contents);
+            Ok(format!(
+                "{}/{}/{}",
+                file,
+                contents.len(),
+                hex::encode(hasher.finalize())
+            ))
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+
+    Ok(format!("{}={}", boot_version, hashes.join(",")))
+}
+
+pub fn version_report(game_path: &str, versions: &InstalledVersions) -> Result<String, String> {
+    let mut report = boot_hash_report(game_path, &versions.boot)?;
+    for ex in &versions.expansions {
+        report.push_str(&format!("\nex{}\t{}", ex.expansion, ex.version));
+    }
+    Ok(report)
+}
+
+pub async fn check_boot_version(
+    client: &Client,
+    versions: &InstalledVersions,
+) -> Result<Vec<PatchEntry>, String> {
+    let url = format!(
+        "{}/{}/?time={}",
+        BOOT_VERSION_URL,
+        versions.boot,
+        launcher_time_rounded()
+    );
+    info!("Checking boot version at: {}", url);
+
+    let response = client
+        .get(&url)
+        .header(USER_AGENT, PATCHER_USER_AGENT)
+        .timeout(REQUEST_TIMEOUT)
+        .send()
+        .await
+        .map_err(|e| format!("Failed to check boot version: {}", e))?;
+
+    if !response.status().is_success() {
+        return Err(format!(
+            "Boot version check failed with status: {}",
+            response.status()
+        ));
+    }
+
+    let body = response
+        .text()
+        .await
+        .map_err(|e| format!("Failed to read boot version response: {}", e))?;
+
+    Ok(parse_patch_list(&body))
+}
+
+pub async fn check_game_version(
+    client: &Client,
+    game_path: &str,
+    versions: &InstalledVersions,
+    sid: &str,
+) -> Result<GameVersionCheck, String> {
+    let url = format!("{}/{}/{}", GAME_VERSION_URL, versions.game, sid);
+    let report = version_report(game_path, versions)?;
+    info!("Registering session for game version {}", versions.game);
+
+    let response = client
+        .post(&url)
+        .header("X-Hash-Check", "enabled")
+        .header(USER_AGENT, PATCHER_USER_AGENT)
+        .header(CONTENT_TYPE, "application/x-www-form-urlencoded")
+        .body(report)
+        .timeout(REQUEST_TIMEOUT)
+        .send()
+        .await
+        .map_err(|e| format!("Failed to check game version: {}", e))?;
+
+    match response.status() {
+        StatusCode::CONFLICT => return Ok(GameVersionCheck::NeedsBootPatch),
+        StatusCode::GONE => {
+            return Err("The patch server no longer services this game version".to_string())
+        }
+        status if !status.is_success() => {
+            return Err(format!("Game version check failed with status: {}", status))
+        }
+        _ => {}
+    }
+
+    let unique_id = response
+        .headers()
+        .get("X-Patch-Unique-Id")
+        .and_then(|v| v.to_str().ok())
+        .map(|v| v.to_string())
+        .ok_or_else(|| "Patch server response did not contain a unique id".to_string())?;
+
+    let body = response
+        .text()
+        .await
+        .map_err(|e| format!("Failed to read game version response: {}", e))?;
+
+    let patches = parse_patch_list(&body);
+    if patches.is_empty() {
+        Ok(GameVersionCheck::UpToDate { unique_id })
+    } else {
+        Ok(GameVersionCheck::NeedsPatch { patches })
+    }
+}
+
+// The patch list is a multipart body where every patch is one tab separated line
+pub fn parse_patch_list(body: &str) -> Vec<PatchEntry> {
+    body.lines()
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.trim_end().split('\t').collect();
+            if fields.len() < 6 {
+                return None;
+            }
+            let length = fields[0].parse::<u64>().ok()?;
+
+            let entry = if fields.len() >= 9 {
+                PatchEntry {
+                    version_id: fields[4].to_string(),
+                    repository: repository_from_url(fields[8]),
+                    url: fields[8].to_string(),
+                    length,
+                    hash_type: Some(fields[5].to_string()),
+                    hash_block_size: fields[6].parse().ok(),
+                    hashes: fields[7].split(',').map(|h| h.to_string()).collect(),
+                }
+            } else {
+                PatchEntry {
+                    version_id: fields[4].to_string(),
+                    repository: repository_from_url(fields[5]),
+                    url: fields[5].to_string(),
+                    length,
+                    hash_type: None,
+                    hash_block_size: None,
+                    hashes: Vec::new(),
+                }
+            };
+
+            Some(entry)
+        })
+        .collect()
+}
+
+fn repository_from_url(url: &str) -> PatchRepository {
+    if url.contains("/boot/") {
+        return PatchRepository::Boot;
+    }
+
+    match url
+        .split("/game/")
+        .nth(1)
+        .and_then(|rest| rest.split('/').next())
+    {
+        Some("ex1") => PatchRepository::Ex1,
+        Some("ex2") => PatchRepository::Ex2,
+        Some("ex3") => PatchRepository::Ex3,
+        Some("ex4") => PatchRepository::Ex4,
+        Some("ex5") => PatchRepository::Ex5,
+        _ => PatchRepository::Game,
+    }
+}
+
+// SE expects the current UTC time rounded down to ten minutes
+fn launcher_time_rounded() -> String {
+    let now = OffsetDateTime::now_utc();
+    format!(
+        "{:04}-{:02}-{:02}-{:02}-{}0",
+        now.year(),
+        now.month() as u8,
+        now.day(),
+        now.hour(),
+        now.minute() / 10
+    )
+}
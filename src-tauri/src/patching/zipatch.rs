@@ -0,0 +1,952 @@
+use flate2::read::DeflateDecoder;
+use std::collections::HashMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufReader, Cursor, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+use tracing::{debug, info, warn};
+
+use crate::archive::safe_relative_path;
+
+const ZIPATCH_MAGIC: [u8; 12] = [
+    0x91, 0x5A, 0x49, 0x50, 0x41, 0x54, 0x43, 0x48, 0x0D, 0x0A, 0x1A, 0x0A,
+];
+
+// Sqpack data is addressed in 128 byte blocks
+const BLOCK_SHIFT: u32 = 7;
+const SQPK_HEADER_SIZE: usize = 1024;
+const UNCOMPRESSED_BLOCK_MARKER: u32 = 32000;
+// Size, type and checksum around each chunk body
+const CHUNK_OVERHEAD: u64 = 12;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Platform {
+    Win32,
+    Ps3,
+    Ps4,
+}
+
+impl Platform {
+    fn from_id(id: u16) -> Result<Self, String> {
+        match id {
+            0 => Ok(Platform::Win32),
+            1 => Ok(Platform::Ps3),
+            2 => Ok(Platform::Ps4),
+            _ => Err(format!("Unknown patch platform id: {}", id)),
+        }
+    }
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            Platform::Win32 => "win32",
+            Platform::Ps3 => "ps3",
+            Platform::Ps4 => "ps4",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct SqpackFileId {
+    pub main_id: u16,
+    pub sub_id: u16,
+    pub file_id: u32,
+}
+
+impl SqpackFileId {
+    fn read(reader: &mut impl Read) -> io::Result<Self> {
+        Ok(Self {
+            main_id: read_u16_be(reader)?,
+            sub_id: read_u16_be(reader)?,
+            file_id: read_u32_be(reader)?,
+        })
+    }
+
+    fn base_name(&self, platform: Platform) -> String {
+        format!(
+            "sqpack/{}/{:02x}{:04x}.{}",
+            expansion_folder((self.sub_id >> 8) as u8),
+            self.main_id,
+            self.sub_id,
+            platform.as_str()
+        )
+    }
+
+    pub fn dat_path(&self, platform: Platform) -> String {
+        format!("{}.dat{}", self.base_name(platform), self.file_id)
+    }
+
+    pub fn index_path(&self, platform: Platform) -> String {
+        if self.file_id == 0 {
+            format!("{}.index", self.base_name(platform))
+        } else {
+            format!("{}.index{}", self.base_name(platform), self.file_id)
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum Chunk {
+    FileHeader { version: u8, patch_type: String },
+    ApplyOption { option: ApplyOption, value: bool },
+    ApplyFreeSpace,
+    AddDirectory(String),
+    DeleteDirectory(String),
+    Sqpk(SqpkCommand),
+    EndOfFile,
+    Unknown(String),
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum ApplyOption {
+    IgnoreMissing,
+    IgnoreOldMismatch,
+    Unknown(u32),
+}
+
+#[derive(Debug)]
+pub enum SqpkCommand {
+    AddData {
+        target: SqpackFileId,
+        block_offset: u64,
+        data: Vec<u8>,
+        block_delete_size: u64,
+    },
+    DeleteData {
+        target: SqpackFileId,
+        block_offset: u64,
+        block_count: u32,
+    },
+    ExpandData {
+        target: SqpackFileId,
+        block_offset: u64,
+        block_count: u32,
+    },
+    Header {
+        file_kind: SqpkHeaderFileKind,
+        header_kind: SqpkHeaderKind,
+        target: SqpackFileId,
+        data: Vec<u8>,
+    },
+    File(SqpkFile),
+    TargetInfo {
+        platform: Platform,
+        region: i16,
+        is_debug: bool,
+        version: u16,
+    },
+    Index,
+    PatchInfo,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SqpkHeaderFileKind {
+    Dat,
+    Index,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SqpkHeaderKind {
+    Version,
+    Index,
+    Data,
+}
+
+#[derive(Debug)]
+pub struct SqpkFile {
+    pub operation: SqpkFileOperation,
+    pub file_offset: u64,
+    pub expansion_id: u16,
+    pub path: String,
+    pub blocks: Vec<Vec<u8>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SqpkFileOperation {
+    AddFile,
+    RemoveAll,
+    DeleteFile,
+    MakeDirTree,
+}
+
+pub struct ZiPatchFile<R: Read> {
+    reader: R,
+    // Bytes left after the chunks read so far, no chunk can claim more
+    remaining: u64,
+    finished: bool,
+}
+
+impl ZiPatchFile<BufReader<File>> {
+    pub fn open(path: &Path) -> Result<Self, String> {
+        let file = File::open(path)
+            .map_err(|e| format!("Failed to open patch file {}: {}", path.display(), e))?;
+        let len = file
+            .metadata()
+            .map_err(|e| format!("Failed to read patch file {}: {}", path.display(), e))?
+            .len();
+        Self::from_reader(BufReader::new(file), len)
+    }
+}
+
+impl<R: Read> ZiPatchFile<R> {
+    // `len` is the length of the whole patch, header included
+    pub fn from_reader(mut reader: R, len: u64) -> Result<Self, String> {
+        let mut magic = [0u8; 12];
+        reader
+            .read_exact(&mut magic)
+            .map_err(|e| format!("Failed to read patch header: {}", e))?;
+
+        if magic != ZIPATCH_MAGIC {
+            return Err("Not a ZiPatch file".to_string());
+        }
+
+        Ok(Self {
+            reader,
+            remaining: len.saturating_sub(ZIPATCH_MAGIC.len() as u64),
+            finished: false,
+        })
+    }
+
+    pub fn next_chunk(&mut self) -> Result<Option<Chunk>, String> {
+        if self.finished {
+            return Ok(None);
+        }
+
+        let size = read_u32_be(&mut self.reader)
+            .map_err(|e| format!("Failed to read chunk size: {}", e))? as usize;
+
+        let mut tag = [0u8; 4];
+        self.reader
+            .read_exact(&mut tag)
+            .map_err(|e| format!("Failed to read chunk type: {}", e))?;
+
+        // Checked before the body is allocated, the size comes straight from the file
+        let chunk_len = size as u64 + CHUNK_OVERHEAD;
+        if chunk_len > self.remaining {
+            return Err(format!(
+                "{} chunk of {} bytes runs past the end of the patch",
+                String::from_utf8_lossy(&tag),
+                size
+            ));
+        }
+        self.remaining -= chunk_len;
+
+        let mut body = vec![0u8; size];
+        self.reader
+            .read_exact(&mut body)
+            .map_err(|e| format!("Failed to read chunk body: {}", e))?;
+
+        let expected_crc = read_u32_be(&mut self.reader)
+            .map_err(|e| format!("Failed to read chunk checksum: {}", e))?;
+
+        let mut hasher = crc32fast::Hasher::new();
+        hasher.update(&tag);
+        hasher.update(&body);
+        let crc = hasher.finalize();
+        if crc != expected_crc {
+            return Err(format!(
+                "Checksum mismatch in {} chunk: expected {:08X}, got {:08X}",
+                String::from_utf8_lossy(&tag),
+                expected_crc,
+                crc
+            ));
+        }
+
+        let chunk = parse_chunk(&tag, &body).map_err(|e| {
+            format!(
+                "Failed to parse {} chunk: {}",
+                String::from_utf8_lossy(&tag),
+                e
+            )
+        })?;
+
+        if matches!(chunk, Chunk::EndOfFile) {
+            self.finished = true;
+        }
+
+        Ok(Some(chunk))
+    }
+}
+
+fn parse_chunk(tag: &[u8; 4], body: &[u8]) -> io::Result<Chunk> {
+    let mut reader = Cursor::new(body);
+
+    let chunk = match tag {
+        b"FHDR" => {
+            // Version lives in the third byte of a little endian dword
+            let version = (read_u32_le(&mut reader)? >> 16) as u8;
+            let patch_type = read_tag(&mut reader)?;
+            Chunk::FileHeader {
+                version,
+                patch_type,
+            }
+        }
+        b"APLY" => {
+            let option = match read_u32_be(&mut reader)? {
+                1 => ApplyOption::IgnoreMissing,
+                2 => ApplyOption::IgnoreOldMismatch,
+                other => ApplyOption::Unknown(other),
+            };
+            let _reserved = read_u32_be(&mut reader)?;
+            let value = read_u32_be(&mut reader)? != 0;
+            Chunk::ApplyOption { option, value }
+        }
+        b"APFS" => Chunk::ApplyFreeSpace,
+        b"ADIR" => Chunk::AddDirectory(read_prefixed_string(&mut reader)?),
+        b"DELD" => Chunk::DeleteDirectory(read_prefixed_string(&mut reader)?),
+        b"SQPK" => Chunk::Sqpk(parse_sqpk(&mut reader)?),
+        b"EOF_" => Chunk::EndOfFile,
+        _ => Chunk::Unknown(String::from_utf8_lossy(tag).to_string()),
+    };
+
+    Ok(chunk)
+}
+
+fn parse_sqpk(reader: &mut Cursor<&[u8]>) -> io::Result<SqpkCommand> {
+    let _size = read_u32_be(reader)?;
+    let command = read_u8(reader)?;
+
+    let sqpk = match command {
+        b'A' => {
+            skip(reader, 3)?;
+            let target = SqpackFileId::read(reader)?;
+            let block_offset = (read_u32_be(reader)? as u64) << BLOCK_SHIFT;
+            let block_size = (read_u32_be(reader)? as u64) << BLOCK_SHIFT;
+            let block_delete_size = (read_u32_be(reader)? as u64) << BLOCK_SHIFT;
+            let data = read_bytes(reader, block_size as usize)?;
+            SqpkCommand::AddData {
+                target,
+                block_offset,
+                data,
+                block_delete_size,
+            }
+        }
+        b'D' | b'E' => {
+            skip(reader, 3)?;
+            let target = SqpackFileId::read(reader)?;
+            let block_offset = (read_u32_be(reader)? as u64) << BLOCK_SHIFT;
+            let block_count = read_u32_be(reader)?;
+            if command == b'D' {
+                SqpkCommand::DeleteData {
+                    target,
+                    block_offset,
+                    block_count,
+                }
+            } else {
+                SqpkCommand::ExpandData {
+                    target,
+                    block_offset,
+                    block_count,
+                }
+            }
+        }
+        b'H' => {
+            let file_kind = match read_u8(reader)? {
+                b'D' => SqpkHeaderFileKind::Dat,
+                b'I' => SqpkHeaderFileKind::Index,
+                other => return Err(invalid_data(format!("unknown header file kind {}", other))),
+            };
+            let header_kind = match read_u8(reader)? {
+                b'V' => SqpkHeaderKind::Version,
+                b'I' => SqpkHeaderKind::Index,
+                b'D' => SqpkHeaderKind::Data,
+                other => return Err(invalid_data(format!("unknown header kind {}", other))),
+            };
+            skip(reader, 1)?;
+            let target = SqpackFileId::read(reader)?;
+            let data = read_bytes(reader, SQPK_HEADER_SIZE)?;
+            SqpkCommand::Header {
+                file_kind,
+                header_kind,
+                target,
+                data,
+            }
+        }
+        b'F' => SqpkCommand::File(parse_sqpk_file(reader)?),
+        b'T' => {
+            skip(reader, 3)?;
+            let platform = Platform::from_id(read_u16_be(reader)?).map_err(invalid_data)?;
+            let region = read_u16_be(reader)? as i16;
+            let is_debug = read_u16_be(reader)? != 0;
+            let version = read_u16_be(reader)?;
+            SqpkCommand::TargetInfo {
+                platform,
+                region,
+                is_debug,
+                version,
+            }
+        }
+        b'I' => SqpkCommand::Index,
+        b'X' => SqpkCommand::PatchInfo,
+        other => {
+            return Err(invalid_data(format!(
+                "unknown SQPK command {}",
+                other as char
+            )))
+        }
+    };
+
+    Ok(sqpk)
+}
+
+fn parse_sqpk_file(reader: &mut Cursor<&[u8]>) -> io::Result<SqpkFile> {
+    let operation = match read_u8(reader)? {
+        b'A' => SqpkFileOperation::AddFile,
+        b'R' => SqpkFileOperation::RemoveAll,
+        b'D' => SqpkFileOperation::DeleteFile,
+        b'M' => SqpkFileOperation::MakeDirTree,
+        other => return Err(invalid_data(format!("unknown file operation {}", other))),
+    };
+    skip(reader, 2)?;
+
+    let file_offset = read_u64_be(reader)?;
+    let _file_size = read_u64_be(reader)?;
+    let path_len = read_u32_be(reader)? as usize;
+    let expansion_id = read_u16_be(reader)?;
+    skip(reader, 2)?;
+
+    let path_bytes = read_bytes(reader, path_len)?;
+    let path = String::from_utf8_lossy(&path_bytes)
+        .trim_end_matches('\0')
+        .to_string();
+
+    let mut blocks = Vec::new();
+    if operation == SqpkFileOperation::AddFile {
+        let end = reader.get_ref().len() as u64;
+        while reader.position() < end {
+            blocks.push(read_compressed_block(reader)?);
+        }
+    }
+
+    Ok(SqpkFile {
+        operation,
+        file_offset,
+        expansion_id,
+        path,
+        blocks,
+    })
+}
+
+fn read_compressed_block(reader: &mut Cursor<&[u8]>) -> io::Result<Vec<u8>> {
+    let start = reader.position();
+    let header_size = read_u32_le(reader)?;
+    let _pad = read_u32_le(reader)?;
+    let compressed_size = read_u32_le(reader)?;
+    let decompressed_size = read_u32_le(reader)? as usize;
+    reader.seek(SeekFrom::Start(start + header_size as u64))?;
+
+    let is_compressed = compressed_size != UNCOMPRESSED_BLOCK_MARKER;
+    let data = if is_compressed {
+        let compressed = read_bytes(reader, compressed_size as usize)?;
+        // One byte past the stated size is enough to tell it's wrong
+        let mut decompressed = Vec::new();
+        DeflateDecoder::new(compressed.as_slice())
+            .take(decompressed_size as u64 + 1)
+            .read_to_end(&mut decompressed)?;
+        if decompressed.len() != decompressed_size {
+            return Err(invalid_data(format!(
+                "block decompressed to {} bytes, expected {}",
+                decompressed.len(),
+                decompressed_size
+            )));
+        }
+        decompressed
+    } else {
+        read_bytes(reader, decompressed_size)?
+    };
+
+    // Blocks are padded out to the next 128 byte boundary
+    let data_size = if is_compressed {
+        compressed_size as u64
+    } else {
+        decompressed_size as u64
+    };
+    let block_size = (data_size + 143) & !0x7F;
+    reader.seek(SeekFrom::Start(start + block_size))?;
+
+    Ok(data)
+}
+
+#[derive(Debug, Clone)]
+pub struct ZiPatchConfig {
+    pub game_path: PathBuf,
+    pub platform: Platform,
+    pub ignore_missing: bool,
+    pub ignore_old_mismatch: bool,
+}
+
+impl ZiPatchConfig {
+    pub fn new(game_path: impl Into<PathBuf>) -> Self {
+        Self {
+            game_path: game_path.into(),
+            platform: Platform::Win32,
+            ignore_missing: false,
+            ignore_old_mismatch: false,
+        }
+    }
+}
+
+pub struct ZiPatchApplier {
+    config: ZiPatchConfig,
+    open_files: HashMap<PathBuf, File>,
+}
+
+impl ZiPatchApplier {
+    pub fn new(config: ZiPatchConfig) -> Self {
+        Self {
+            config,
+            open_files: HashMap::new(),
+        }
+    }
+
+    pub fn apply_file(&mut self, patch_path: &Path) -> Result<(), String> {
+        info!("Applying patch {}", patch_path.display());
+        let mut patch = ZiPatchFile::open(patch_path)?;
+        let mut chunk_count = 0;
+
+        while let Some(chunk) = patch.next_chunk()? {
+            self.apply_chunk(chunk)?;
+            chunk_count += 1;
+        }
+
+        self.flush()?;
+        info!(
+            "Applied {} chunks from {}",
+            chunk_count,
+            patch_path.display()
+        );
+        Ok(())
+    }
+
+    pub fn apply_chunk(&mut self, chunk: Chunk) -> Result<(), String> {
+        match chunk {
+            Chunk::FileHeader {
+                version,
+                patch_type,
+            } => {
+                debug!("Patch header: version {}, type {}", version, patch_type);
+            }
+            Chunk::ApplyOption { option, value } => match option {
+                ApplyOption::IgnoreMissing => self.config.ignore_missing = value,
+                ApplyOption::IgnoreOldMismatch => self.config.ignore_old_mismatch = value,
+                ApplyOption::Unknown(id) => warn!("Ignoring unknown apply option {}", id),
+            },
+            Chunk::AddDirectory(path) => {
+                let dir = self.config.game_path.join(patch_path(&path)?);
+                fs::create_dir_all(&dir)
+                    .map_err(|e| format!("Failed to create directory {}: {}", dir.display(), e))?;
+            }
+            Chunk::DeleteDirectory(path) => {
+                let dir = self.config.game_path.join(patch_path(&path)?);
+                if let Err(e) = fs::remove_dir(&dir) {
+                    if !self.config.ignore_missing {
+                        return Err(format!(
+                            "Failed to delete directory {}: {}",
+                            dir.display(),
+                            e
+                        ));
+                    }
+                    warn!("Failed to delete directory {}: {}", dir.display(), e);
+                }
+            }
+            Chunk::Sqpk(command) => self.apply_sqpk(command)?,
+            Chunk::ApplyFreeSpace | Chunk::EndOfFile => {}
+            Chunk::Unknown(tag) => warn!("Skipping unknown chunk type {}", tag),
+        }
+
+        Ok(())
+    }
+
+    fn apply_sqpk(&mut self, command: SqpkCommand) -> Result<(), String> {
+        match command {
+            SqpkCommand::AddData {
+                target,
+                block_offset,
+                data,
+                block_delete_size,
+            } => {
+                let path = target.dat_path(self.config.platform);
+                let file = self.file(Path::new(&path))?;
+                write_at(file, block_offset, &data)
+                    .and_then(|_| write_zeros(file, block_delete_size))
+                    .map_err(|e| format!("Failed to add data to {}: {}", path, e))?;
+            }
+            SqpkCommand::DeleteData {
+                target,
+                block_offset,
+                block_count,
+            }
+            | SqpkCommand::ExpandData {
+                target,
+                block_offset,
+                block_count,
+            } => {
+                let path = target.dat_path(self.config.platform);
+                let file = self.file(Path::new(&path))?;
+                write_empty_file_block(file, block_offset, block_count)
+                    .map_err(|e| format!("Failed to write empty block to {}: {}", path, e))?;
+            }
+            SqpkCommand::Header {
+                file_kind,
+                header_kind,
+                target,
+                data,
+            } => {
+                let path = match file_kind {
+                    SqpkHeaderFileKind::Dat => target.dat_path(self.config.platform),
+                    SqpkHeaderFileKind::Index => target.index_path(self.config.platform),
+                };
+                let offset = if header_kind == SqpkHeaderKind::Version {
+                    0
+                } else {
+                    SQPK_HEADER_SIZE as u64
+                };
+                let file = self.file(Path::new(&path))?;
+                write_at(file, offset, &data)
+                    .map_err(|e| format!("Failed to write header to {}: {}", path, e))?;
+            }
+            SqpkCommand::File(file) => self.apply_sqpk_file(file)?,
+            SqpkCommand::TargetInfo {
+                platform,
+                region,
+                is_debug,
+                version,
+            } => {
+                debug!(
+                    "Patch targets platform {:?}, region {}, version {}, debug {}",
+                    platform, region, version, is_debug
+                );
+                self.config.platform = platform;
+            }
+            // Index and patch info commands carry nothing the game files need
+            SqpkCommand::Index | SqpkCommand::PatchInfo => {}
+        }
+
+        Ok(())
+    }
+
+    fn apply_sqpk_file(&mut self, command: SqpkFile) -> Result<(), String> {
+        match command.operation {
+            SqpkFileOperation::AddFile => {
+                let relative = patch_path(&command.path)?;
+                let target = self.config.game_path.join(&relative);
+
+                // A write at offset zero starts the file from scratch
+                if command.file_offset == 0 {
+                    self.open_files.remove(&target);
+                    if let Some(parent) = target.parent() {
+                        fs::create_dir_all(parent).map_err(|e| {
+                            format!("Failed to create directory {}: {}", parent.display(), e)
+                        })?;
+                    }
+                    File::create(&target).map_err(|e| {
+                        format!("Failed to create file {}: {}", target.display(), e)
+                    })?;
+                }
+
+                let file = self.file(&relative)?;
+                file.seek(SeekFrom::Start(command.file_offset))
+                    .map_err(|e| format!("Failed to seek in {}: {}", command.path, e))?;
+                for block in &command.blocks {
+                    file.write_all(block)
+                        .map_err(|e| format!("Failed to write to {}: {}", command.path, e))?;
+                }
+            }
+            SqpkFileOperation::DeleteFile => {
+                let target = self.config.game_path.join(patch_path(&command.path)?);
+                self.open_files.remove(&target);
+                if let Err(e) = fs::remove_file(&target) {
+                    if !self.config.ignore_missing {
+                        return Err(format!("Failed to delete file {}: {}", target.display(), e));
+                    }
+                    warn!("Failed to delete file {}: {}", target.display(), e);
+                }
+            }
+            SqpkFileOperation::MakeDirTree => {
+                let dir = self.config.game_path.join(patch_path(&command.path)?);
+                fs::create_dir_all(&dir)
+                    .map_err(|e| format!("Failed to create directory {}: {}", dir.display(), e))?;
+            }
+            SqpkFileOperation::RemoveAll => {
+                let folder = expansion_folder(command.expansion_id as u8);
+                self.open_files.clear();
+                for root in ["sqpack", "movie"] {
+                    let dir = self.config.game_path.join(root).join(&folder);
+                    remove_expansion_files(&dir)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn file(&mut self, relative_path: &Path) -> Result<&mut File, String> {
+        let path = self.config.game_path.join(relative_path);
+
+        if !self.open_files.contains_key(&path) {
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent).map_err(|e| {
+                    format!("Failed to create directory {}: {}", parent.display(), e)
+                })?;
+            }
+            let file = OpenOptions::new()
+                .read(true)
+                .write(true)
+                .create(true)
+                .truncate(false)
+                .open(&path)
+                .map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+            self.open_files.insert(path.clone(), file);
+        }
+
+        Ok(self.open_files.get_mut(&path).unwrap())
+    }
+
+    pub fn flush(&mut self) -> Result<(), String> {
+        for (path, file) in self.open_files.iter_mut() {
+            file.flush()
+                .map_err(|e| format!("Failed to flush {}: {}", path.display(), e))?;
+        }
+        self.open_files.clear();
+        Ok(())
+    }
+}
+
+// Paths in a patch are relative to the game folder, one leaving it is refused
+fn patch_path(path: &str) -> Result<PathBuf, String> {
+    safe_relative_path(path).map_err(|_| format!("Unsafe path in patch: {}", path))
+}
+
+fn remove_expansion_files(dir: &Path) -> Result<(), String> {
+    if !dir.exists() {
+        return Ok(());
+    }
+
+    let entries =
+        fs::read_dir(dir).map_err(|e| format!("Failed to read {}: {}", dir.display(), e))?;
+
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if !path.is_file() {
+            continue;
+        }
+
+        // Version files and the intro movies survive a reset patch
+        let name = entry.file_name().to_string_lossy().to_lowercase();
+        let keep = name.ends_with(".var")
+            || matches!(
+                name.as_str(),
+                "00000.bk2" | "00001.bk2" | "00002.bk2" | "00003.bk2"
+            );
+        if keep {
+            continue;
+        }
+
+        fs::remove_file(&path)
+            .map_err(|e| format!("Failed to delete {}: {}", path.display(), e))?;
+    }
+
+    Ok(())
+}
+
+fn write_at(file: &mut File, offset: u64, data: &[u8]) -> io::Result<()> {
+    file.seek(SeekFrom::Start(offset))?;
+    file.write_all(data)
+}
+
+fn write_zeros(file: &mut File, len: u64) -> io::Result<()> {
+    io::copy(&mut io::repeat(0).take(len), file)?;
+    Ok(())
+}
+
+fn write_empty_file_block(file: &mut File, offset: u64, block_count: u32) -> io::Result<()> {
+    file.seek(SeekFrom::Start(offset))?;
+    write_zeros(file, (block_count as u64) << BLOCK_SHIFT)?;
+
+    // Empty block header so the game sees the region as unused
+    let mut header = Vec::with_capacity(20);
+    header.extend_from_slice(&(1u32 << BLOCK_SHIFT).to_le_bytes());
+    header.extend_from_slice(&0u32.to_le_bytes());
+    header.extend_from_slice(&0u32.to_le_bytes());
+    header.extend_from_slice(&block_count.wrapping_sub(1).to_le_bytes());
+    header.extend_from_slice(&0u32.to_le_bytes());
+    write_at(file, offset, &header)
+}
+
+fn expansion_folder(expansion_id: u8) -> String {
+    if expansion_id == 0 {
+        "ffxiv".to_string()
+    } else {
+        format!("ex{}", expansion_id)
+    }
+}
+
+fn invalid_data(message: impl Into<String>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.into())
+}
+
+fn skip(reader: &mut impl Read, count: u64) -> io::Result<()> {
+    io::copy(&mut reader.take(count), &mut io::sink())?;
+    Ok(())
+}
+
+// Grows with what is actually there, a bogus length doesn't allocate up front
+fn read_bytes(reader: &mut impl Read, len: usize) -> io::Result<Vec<u8>> {
+    let mut buf = Vec::new();
+    reader.take(len as u64).read_to_end(&mut buf)?;
+    if buf.len() < len {
+        return Err(io::ErrorKind::UnexpectedEof.into());
+    }
+    Ok(buf)
+}
+
+fn read_tag(reader: &mut impl Read) -> io::Result<String> {
+    let mut tag = [0u8; 4];
+    reader.read_exact(&mut tag)?;
+    Ok(String::from_utf8_lossy(&tag).to_string())
+}
+
+fn read_prefixed_string(reader: &mut impl Read) -> io::Result<String> {
+    let len = read_u32_be(reader)? as usize;
+    let bytes = read_bytes(reader, len)?;
+    Ok(String::from_utf8_lossy(&bytes)
+        .trim_end_matches('\0')
+        .to_string())
+}
+
+fn read_u8(reader: &mut impl Read) -> io::Result<u8> {
+    let mut buf = [0u8; 1];
+    reader.read_exact(&mut buf)?;
+    Ok(buf[0])
+}
+
+fn read_u16_be(reader: &mut impl Read) -> io::Result<u16> {
+    let mut buf = [0u8; 2];
+    reader.read_exact(&mut buf)?;
+    Ok(u16::from_be_bytes(buf))
+}
+
+fn read_u32_be(reader: &mut impl Read) -> io::Result<u32> {
+    let mut buf = [0u8; 4];
+    reader.read_exact(&mut buf)?;
+    Ok(u32::from_be_bytes(buf))
+}
+
+fn read_u32_le(reader: &mut impl Read) -> io::Result<u32> {
+    let mut buf = [0u8; 4];
+    reader.read_exact(&mut buf)?;
+    Ok(u32::from_le_bytes(buf))
+}
+
+fn read_u64_be(reader: &mut impl Read) -> io::Result<u64> {
+    let mut buf = [0u8; 8];
+    reader.read_exact(&mut buf)?;
+    Ok(u64::from_be_bytes(buf))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn patch(chunks: &[(&[u8; 4], Vec<u8>)]) -> Vec<u8> {
+        let mut data = ZIPATCH_MAGIC.to_vec();
+        for (tag, body) in chunks {
+            let mut hasher = crc32fast::Hasher::new();
+            hasher.update(*tag);
+            hasher.update(body);
+            data.extend_from_slice(&(body.len() as u32).to_be_bytes());
+            data.extend_from_slice(*tag);
+            data.extend_from_slice(body);
+            data.extend_from_slice(&hasher.finalize().to_be_bytes());
+        }
+        data
+    }
+
+    fn prefixed(value: &str) -> Vec<u8> {
+        let mut body = (value.len() as u32).to_be_bytes().to_vec();
+        body.extend_from_slice(value.as_bytes());
+        body
+    }
+
+    fn open(data: Vec<u8>) -> ZiPatchFile<Cursor<Vec<u8>>> {
+        let len = data.len() as u64;
+        ZiPatchFile::from_reader(Cursor::new(data), len).unwrap()
+    }
+
+    #[test]
+    fn reads_chunks_up_to_end_of_file() {
+        let mut patch = open(patch(&[
+            (b"ADIR", prefixed("sqpack/ex1")),
+            (b"EOF_", Vec::new()),
+        ]));
+
+        assert!(matches!(
+            patch.next_chunk().unwrap(),
+            Some(Chunk::AddDirectory(path)) if path == "sqpack/ex1"
+        ));
+        assert!(matches!(
+            patch.next_chunk().unwrap(),
+            Some(Chunk::EndOfFile)
+        ));
+        assert!(patch.next_chunk().unwrap().is_none());
+    }
+
+    #[test]
+    fn chunk_larger_than_the_patch_is_rejected() {
+        let mut data = ZIPATCH_MAGIC.to_vec();
+        data.extend_from_slice(&u32::MAX.to_be_bytes());
+        data.extend_from_slice(b"SQPK");
+
+        let error = open(data).next_chunk().unwrap_err();
+        assert!(
+            error.contains("runs past the end of the patch"),
+            "{}",
+            error
+        );
+    }
+
+    #[test]
+    fn string_length_past_the_chunk_is_rejected() {
+        let mut body = u32::MAX.to_be_bytes().to_vec();
+        body.extend_from_slice(b"sqpack");
+
+        let error = open(patch(&[(b"ADIR", body)])).next_chunk().unwrap_err();
+        assert!(error.contains("Failed to parse ADIR chunk"), "{}", error);
+    }
+
+    #[test]
+    fn paths_leaving_the_game_folder_are_refused() {
+        let root = tempfile::tempdir().unwrap();
+        let game = root.path().join("game");
+        fs::create_dir(&game).unwrap();
+        let mut applier = ZiPatchApplier::new(ZiPatchConfig::new(&game));
+
+        for path in [
+            "../escaped",
+            "sqpack/../../escaped",
+            "/escaped",
+            "C:\\escaped",
+        ] {
+            let error = applier
+                .apply_chunk(Chunk::AddDirectory(path.to_string()))
+                .unwrap_err();
+            assert!(error.starts_with("Unsafe path in patch"), "{}", error);
+        }
+        let error = applier
+            .apply_sqpk_file(SqpkFile {
+                operation: SqpkFileOperation::AddFile,
+                file_offset: 0,
+                expansion_id: 0,
+                path: "../escaped.exe".to_string(),
+                blocks: vec![b"data".to_vec()],
+            })
+            .unwrap_err();
+        assert!(error.starts_with("Unsafe path in patch"), "{}", error);
+        assert!(!root.path().join("escaped").exists());
+        assert!(!root.path().join("escaped.exe").exists());
+
+        applier
+            .apply_chunk(Chunk::AddDirectory("sqpack/ex1".to_string()))
+            .unwrap();
+        assert!(game.join("sqpack/ex1").is_dir());
+    }
+}
@@ -0,0 +1,212 @@
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::thread::JoinHandle;
+
+use tracing::{info, warn};
+
+use super::{GamescopeConfig, WineConfig, WineRunnerKind};
+
+const STEAM_DECK_WIDTH: u32 = 1280;
+const STEAM_DECK_HEIGHT: u32 = 800;
+
+// The runner process, which lives exactly as long as the game does
+#[derive(Debug)]
+pub struct SpawnedGame {
+    pub pid: u32,
+    // The reaper thread, joins with the runner's exit code. None when it was killed by a
+    // signal or couldn't be waited for
+    pub exit: JoinHandle<Option<u32>>,
+}
+
+// Starts the game through Wine or Proton
+pub fn spawn_game(
+    wine: &WineConfig,
+    gamescope: &GamescopeConfig,
+    gamemode: Option<bool>,
+    game_path: &str,
+    args: &str,
+    environment: &[(&str, &str)],
+) -> Result<SpawnedGame, String> {
+    let steam_deck = is_steam_deck();
+    let mut wrappers = Vec::new();
+    if gamemode.unwrap_or(steam_deck) {
+        if is_in_path("gamemoderun") {
+            wrappers.push("gamemoderun".to_string());
+        } else {
+            warn!("gamemoderun was not found, starting without gamemode");
+        }
+    }
+    // The Deck's game mode already runs everything inside gamescope
+    let in_gamescope = std::env::var_os("GAMESCOPE_WAYLAND_DISPLAY").is_some();
+    if gamescope.enabled.unwrap_or(steam_deck && !in_gamescope) {
+        if is_in_path("gamescope") {
+            wrappers.extend(gamescope_arguments(gamescope, steam_deck));
+        } else {
+            warn!("gamescope was not found, starting without it");
+        }
+    }
+
+    let mut command = runner_command(wine, &wrappers)?;
+    command
+        .arg(game_path)
+        // Wine quotes argv back into a command line, so this gives the game the same string
+        .args(args.split_whitespace())
+        .envs(environment.iter().copied())
+        .stdin(Stdio::null());
+    if let Some(game_dir) = Path::new(game_path).parent() {
+        command.current_dir(game_dir);
+    }
+
+    info!(
+        "Starting game through {:?} at {}",
+        wine.runner, wine.runner_path
+    );
+    let mut child = command
+        .spawn()
+        .map_err(|e| format!("Failed to start {}: {}", wine.runner_path, e))?;
+    let pid = child.id();
+
+    // Reap the runner when it exits, a zombie would keep the game monitor waiting. Wine
+    // exits with the game's exit code
+    let exit = std::thread::spawn(move || match child.wait() {
+        Ok(status) => {
+            info!("Wine runner {} exited with {}", pid, status);
+            status.code().map(|code| code as u32)
+        }
+        Err(e) => {
+            warn!("Failed to wait for wine runner {}: {}", pid, e);
+            None
+        }
+    });
+
+    Ok(SpawnedGame { pid, exit })
+}
+
+// The runner, preceded by wrapper commands like gamemoderun that take it as their arguments
+fn wrapped_command(wrappers: &[String], program: &str) -> Command {
+    match wrappers.split_first() {
+        Some((first, rest)) => {
+            let mut command = Command::new(first);
+            command.args(rest).arg(program);
+            command
+        }
+        None => Command::new(program),
+    }
+}
+
+fn runner_command(wine: &WineConfig, wrappers: &[String]) -> Result<Command, String> {
+    if wine.runner_path.is_empty() {
+        return Err("No Wine or Proton runner is configured".to_string());
+    }
+    if !wrappers.is_empty() {
+        info!("Wrapping the game with: {}", wrappers.join(" "));
+    }
+
+    let mut command = match wine.runner {
+        WineRunnerKind::Wine => {
+            let mut command = wrapped_command(wrappers, &wine.runner_path);
+            if !wine.prefix.is_empty() {
+                command.env("WINEPREFIX", &wine.prefix);
+            }
+            if wine.esync {
+                command.env("WINEESYNC", "1");
+            }
+            if wine.fsync {
+                command.env("WINEFSYNC", "1");
+            }
+            // DXVK has to be installed into the prefix, this only makes wine prefer it
+            if wine.dxvk {
+                command.env("WINEDLLOVERRIDES", "d3d11,dxgi=n,b");
+            }
+            command
+        }
+        WineRunnerKind::Proton => {
+            let script = Path::new(&wine.runner_path).join("proton");
+            if !script.is_file() {
+                return Err(format!("No proton script found at {}", script.display()));
+            }
+            if wine.prefix.is_empty() {
+                return Err("Proton needs a compatdata folder to use as prefix".to_string());
+            }
+
+            let mut command = wrapped_command(wrappers, &script.to_string_lossy());
+            command
+                .arg("run")
+                .env("STEAM_COMPAT_DATA_PATH", &wine.prefix)
+                .env("STEAM_COMPAT_CLIENT_INSTALL_PATH", steam_client_path());
+            // Proton ships DXVK and both sync modes, the switches only turn them off
+            if !wine.dxvk {
+                command.env("PROTON_USE_WINED3D", "1");
+            }
+            if !wine.esync {
+                command.env("PROTON_NO_ESYNC", "1");
+            }
+            if !wine.fsync {
+                command.env("PROTON_NO_FSYNC", "1");
+            }
+            command
+        }
+    };
+
+    if !wine.dxvk_hud.is_empty() {
+        command.env("DXVK_HUD", &wine.dxvk_hud);
+    }
+    // Wine's debug channels are very chatty, keep them quiet unless asked for
+    if std::env::var_os("WINEDEBUG").is_none() {
+        command.env("WINEDEBUG", "-all");
+    }
+
+    Ok(command)
+}
+
+fn steam_client_path() -> String {
+    std::env::var("HOME")
+        .map(|home| format!("{}/.steam/steam", home))
+        .unwrap_or_default()
+}
+
+fn gamescope_arguments(gamescope: &GamescopeConfig, steam_deck: bool) -> Vec<String> {
+    let (width, height) = match (gamescope.width, gamescope.height) {
+        (0, _) | (_, 0) if steam_deck => (STEAM_DECK_WIDTH, STEAM_DECK_HEIGHT),
+        size => size,
+    };
+
+    let mut args = vec!["gamescope".to_string()];
+    if width > 0 && height > 0 {
+        args.extend([
+            "-W".to_string(),
+            width.to_string(),
+            "-H".to_string(),
+            height.to_string(),
+        ]);
+    }
+    if gamescope.fsr {
+        args.extend(["-F".to_string(), "fsr".to_string()]);
+    }
+    if let Some(limit) = gamescope.frame_limit.filter(|l| *l > 0) {
+        args.extend(["-r".to_string(), limit.to_string()]);
+    }
+    if gamescope.fullscreen {
+        args.push("-f".to_string());
+    }
+    args.push("--".to_string());
+    args
+}
+
+fn is_steam_deck() -> bool {
+    // Steam sets this in game mode, the board name covers desktop mode too
+    if std::env::var("SteamDeck").is_ok_and(|v| v == "1") {
+        return true;
+    }
+    let read = |name: &str| {
+        std::fs::read_to_string(format!("/sys/devices/virtual/dmi/id/{}", name))
+            .map(|v| v.trim().to_string())
+            .unwrap_or_default()
+    };
+    read("board_vendor") == "Valve" && matches!(read("board_name").as_str(), "Jupiter" | "Galileo")
+}
+
+fn is_in_path(program: &str) -> bool {
+    std::env::var_os("PATH")
+        .is_some_and(|path| std::env::split_paths(&path).any(|dir| dir.join(program).is_file()))
+}
@@ -0,0 +1,58 @@
+use serde::{Deserialize, Serialize};
+
+#[cfg(target_os = "linux")]
+pub mod linux;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WineRunnerKind {
+    #[default]
+    Wine,
+    Proton,
+}
+
+// How the game is started outside Windows, ignored on Windows itself
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WineConfig {
+    pub runner: WineRunnerKind,
+    // The wine binary, or the Proton folder that holds the proton script
+    pub runner_path: String,
+    // WINEPREFIX for wine, the compatdata folder for Proton
+    pub prefix: String,
+    pub dxvk: bool,
+    // DXVK release installed into the prefix before launch, None leaves the prefix alone
+    pub dxvk_version: Option<String>,
+    pub dxvk_hud: String,
+    pub esync: bool,
+    pub fsync: bool,
+}
+
+impl Default for WineConfig {
+    fn default() -> Self {
+        Self {
+            runner: WineRunnerKind::Wine,
+            runner_path: "wine".to_string(),
+            prefix: String::new(),
+            dxvk: true,
+            dxvk_version: None,
+            dxvk_hud: String::new(),
+            esync: true,
+            fsync: false,
+        }
+    }
+}
+
+// Wraps the game in gamescope on Linux. None for enabled picks it automatically,
+// which means on a Steam Deck outside of its own gamescope session
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GamescopeConfig {
+    pub enabled: Option<bool>,
+    // Output resolution, the Steam Deck panel is used when left at 0
+    pub width: u32,
+    pub height: u32,
+    pub fsr: bool,
+    pub frame_limit: Option<u32>,
+    pub fullscreen: bool,
+}
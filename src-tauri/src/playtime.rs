@@ -0,0 +1,133 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::AppHandle;
+use tauri_plugin_store::StoreExt;
+use time::{Date, Duration, OffsetDateTime, UtcOffset};
+
+use tracing::{info, warn};
+
+// Kept out of settings.json, it only ever grows
+const PLAYTIME_STORE: &str = "playtime.json";
+const SESSIONS_KEY: &str = "sessions";
+
+// One run of the game from the launch until the monitor saw it exit
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PlaySession {
+    pub account: String,
+    // Unix seconds
+    pub started_at: u64,
+    pub ended_at: u64,
+}
+
+impl PlaySession {
+    fn seconds(&self) -> u64 {
+        self.ended_at.saturating_sub(self.started_at)
+    }
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct PlaytimeStats {
+    pub total_seconds: u64,
+    pub sessions: usize,
+    // Keyed by the first day of the period as YYYY-MM-DD in the caller's time zone, weeks
+    // start on Monday. A session counts towards the day it started on
+    pub daily: BTreeMap<String, u64>,
+    pub weekly: BTreeMap<String, u64>,
+    pub monthly: BTreeMap<String, u64>,
+    pub accounts: BTreeMap<String, u64>,
+}
+
+pub fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+}
+
+fn load_sessions(app: &AppHandle) -> Result<Vec<PlaySession>, String> {
+    let store = app
+        .store(PLAYTIME_STORE)
+        .map_err(|e| format!("Failed to open playtime store: {}", e))?;
+
+    match store.get(SESSIONS_KEY) {
+        Some(value) => serde_json::from_value(value)
+            .map_err(|e| format!("Failed to parse play sessions: {}", e)),
+        None => Ok(Vec::new()),
+    }
+}
+
+// Called by the game monitor once the game has exited
+pub fn record_session(app: &AppHandle, account: &str, started_at: u64, ended_at: u64) {
+    let session = PlaySession {
+        account: account.to_string(),
+        started_at,
+        ended_at,
+    };
+    let result = load_sessions(app).and_then(|mut sessions| {
+        sessions.push(session);
+        let store = app
+            .store(PLAYTIME_STORE)
+            .map_err(|e| format!("Failed to open playtime store: {}", e))?;
+        let value = serde_json::to_value(&sessions)
+            .map_err(|e| format!("Failed to serialize play sessions: {}", e))?;
+        store.set(SESSIONS_KEY, value);
+        store
+            .save()
+            .map_err(|e| format!("Failed to save play sessions: {}", e))
+    });
+
+    match result {
+        Ok(()) => info!(
+            "Recorded {}s of playtime for {}",
+            ended_at.saturating_sub(started_at),
+            account
+        ),
+        Err(e) => warn!("Failed to record playtime: {}", e),
+    }
+}
+
+fn day_of(timestamp: u64, offset: UtcOffset) -> Option<Date> {
+    OffsetDateTime::from_unix_timestamp(timestamp as i64)
+        .ok()
+        .map(|time| time.to_offset(offset).date())
+}
+
+// The frontend passes -Date.getTimezoneOffset(), the launcher can't reliably read the
+// local offset itself on every platform
+#[tauri::command]
+pub fn get_playtime_stats(
+    app: AppHandle,
+    account: Option<String>,
+    utc_offset_minutes: i32,
+) -> Result<PlaytimeStats, String> {
+    let offset = UtcOffset::from_whole_seconds(utc_offset_minutes * 60)
+        .map_err(|e| format!("Invalid UTC offset: {}", e))?;
+
+    let mut stats = PlaytimeStats::default();
+    let sessions = load_sessions(&app)?.into_iter().filter(|session| {
+        account
+            .as_deref()
+            .is_none_or(|account| session.account.eq_ignore_ascii_case(account))
+    });
+    for session in sessions {
+        let Some(day) = day_of(session.started_at, offset) else {
+            continue;
+        };
+        let seconds = session.seconds();
+        let week = day - Duration::days(day.weekday().number_days_from_monday() as i64);
+        let month = day.replace_day(1).unwrap_or(day);
+
+        stats.total_seconds += seconds;
+        stats.sessions += 1;
+        *stats.daily.entry(day.to_string()).or_default() += seconds;
+        *stats.weekly.entry(week.to_string()).or_default() += seconds;
+        *stats.monthly.entry(month.to_string()).or_default() += seconds;
+        *stats
+            .accounts
+            .entry(session.account.to_lowercase())
+            .or_default() += seconds;
+    }
+    Ok(stats)
+}
@@ -0,0 +1,751 @@
+use futures::future::join_all;
+use futures::stream::{self, StreamExt};
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::Path;
+use tauri::{AppHandle, Manager};
+use tauri_plugin_store::StoreExt;
+use tokio_util::sync::CancellationToken;
+
+use tracing::{info, warn};
+
+use crate::archive::extract_zip;
+use crate::ffxiv::{download_file, run_blocking};
+use crate::game_monitor;
+use crate::http::{HttpClient, REQUEST_TIMEOUT};
+use crate::launch_progress::LaunchProgress;
+use crate::retry::RetryPolicy;
+
+const OFFICIAL_PLUGIN_MASTER: &str = "https://kamori.goats.dev/Plugin/PluginMaster";
+// Shared with the frontend settings store
+const SETTINGS_STORE: &str = "settings.json";
+const REPOSITORIES_KEY: &str = "pluginRepositories";
+// Downloads running at once in update_all_plugins
+const PARALLEL_UPDATES: usize = 4;
+// Relative to the Dalamud folder. The config file is what Dalamud is started with as its
+// configuration path
+pub(crate) const DALAMUD_CONFIG_FILE: &str = "config";
+pub(crate) const PLUGIN_FOLDER: &str = "installedPlugins";
+// Dalamud keeps which plugins load in its profiles, the default one is used unless the user
+// set up others in game
+const DEFAULT_PROFILE_KEY: &str = "DefaultProfile";
+const PROFILE_TYPE: &str = "Dalamud.Plugin.Internal.Profiles.ProfileModelV1, Dalamud";
+// Dalamud's own id for an installed plugin, written into its manifest. Older profiles match
+// plugins by internal name instead
+const WORKING_ID_KEY: &str = "WorkingPluginId";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PluginRepository {
+    pub url: String,
+    pub enabled: bool,
+}
+
+// Only the fields we act on are typed, the rest is kept so the installed manifest
+// Dalamud reads matches what the repo published
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+pub struct PluginManifest {
+    pub internal_name: String,
+    pub name: String,
+    pub assembly_version: String,
+    pub download_link_install: String,
+    pub download_link_update: Option<String>,
+    #[serde(default)]
+    pub is_hide: bool,
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AvailablePlugin {
+    pub repository: String,
+    pub is_third_party: bool,
+    pub installed_version: Option<String>,
+    pub manifest: PluginManifest,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PluginUpdate {
+    pub internal_name: String,
+    pub name: String,
+    pub from_version: String,
+    pub to_version: String,
+    // None when the update went through
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InstalledPlugin {
+    pub internal_name: String,
+    pub name: String,
+    pub version: String,
+    pub enabled: bool,
+}
+
+fn load_repositories(app: &AppHandle) -> Result<Vec<PluginRepository>, String> {
+    let store = app
+        .store(SETTINGS_STORE)
+        .map_err(|e| format!("Failed to open settings store: {}", e))?;
+
+    match store.get(REPOSITORIES_KEY) {
+        Some(value) => serde_json::from_value(value)
+            .map_err(|e| format!("Failed to parse plugin repositories: {}", e)),
+        None => Ok(Vec::new()),
+    }
+}
+
+fn save_repositories(app: &AppHandle, repositories: &[PluginRepository]) -> Result<(), String> {
+    let store = app
+        .store(SETTINGS_STORE)
+        .map_err(|e| format!("Failed to open settings store: {}", e))?;
+
+    let value = serde_json::to_value(repositories)
+        .map_err(|e| format!("Failed to serialize plugin repositories: {}", e))?;
+    store.set(REPOSITORIES_KEY, value);
+    store
+        .save()
+        .map_err(|e| format!("Failed to save plugin repositories: {}", e))
+}
+
+#[tauri::command]
+pub fn get_plugin_repositories(app: AppHandle) -> Result<Vec<PluginRepository>, String> {
+    load_repositories(&app)
+}
+
+#[tauri::command]
+pub fn add_plugin_repository(app: AppHandle, url: String) -> Result<Vec<PluginRepository>, String> {
+    let url = url.trim().to_string();
+    if !url.starts_with("https://") && !url.starts_with("http://") {
+        return Err("Repository URL must start with http:// or https://".to_string());
+    }
+    if url == OFFICIAL_PLUGIN_MASTER {
+        return Err("The official repository is always included".to_string());
+    }
+
+    let mut repositories = load_repositories(&app)?;
+    if repositories.iter().any(|repo| repo.url == url) {
+        return Err("Repository is already added".to_string());
+    }
+
+    info!("Adding plugin repository {}", url);
+    repositories.push(PluginRepository { url, enabled: true });
+    save_repositories(&app, &repositories)?;
+    Ok(repositories)
+}
+
+#[tauri::command]
+pub fn remove_plugin_repository(
+    app: AppHandle,
+    url: String,
+) -> Result<Vec<PluginRepository>, String> {
+    let mut repositories = load_repositories(&app)?;
+    repositories.retain(|repo| repo.url != url);
+
+    info!("Removed plugin repository {}", url);
+    save_repositories(&app, &repositories)?;
+    Ok(repositories)
+}
+
+#[tauri::command]
+pub fn set_plugin_repository_enabled(
+    app: AppHandle,
+    url: String,
+    enabled: bool,
+) -> Result<Vec<PluginRepository>, String> {
+    let mut repositories = load_repositories(&app)?;
+    let repo = repositories
+        .iter_mut()
+        .find(|repo| repo.url == url)
+        .ok_or_else(|| format!("Unknown plugin repository {}", url))?;
+    repo.enabled = enabled;
+
+    save_repositories(&app, &repositories)?;
+    Ok(repositories)
+}
+
+async fn fetch_manifests(client: &Client, url: &str) -> Result<Vec<PluginManifest>, String> {
+    let response = client
+        .get(url)
+        .timeout(REQUEST_TIMEOUT)
+        .send()
+        .await
+        .map_err(|e| format!("Failed to fetch plugin repository {}: {}", url, e))?;
+
+    response
+        .json::<Vec<PluginManifest>>()
+        .await
+        .map_err(|e| format!("Failed to parse plugin repository {}: {}", url, e))
+}
+
+// Names and versions from repository manifests become folder names, so only plain names
+// are accepted: no separators, no drive letters and nothing made of dots alone
+fn check_path_part(kind: &str, value: &str) -> Result<(), String> {
+    let allowed = |c: char| c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-');
+    if value.is_empty() || !value.chars().all(allowed) || value.chars().all(|c| c == '.') {
+        return Err(format!("Invalid plugin {}: {:?}", kind, value));
+    }
+    Ok(())
+}
+
+fn check_manifest_paths(manifest: &PluginManifest) -> Result<(), String> {
+    check_path_part("name", &manifest.internal_name)?;
+    check_path_part("version", &manifest.assembly_version)
+}
+
+// Resolves links too, a plugin folder that points elsewhere is never written to or cleaned up
+fn ensure_inside(plugin_directory: &str, path: &str) -> Result<(), String> {
+    let resolve = |path: &str| {
+        Path::new(path)
+            .canonicalize()
+            .map_err(|e| format!("Failed to resolve {}: {}", path, e))
+    };
+    if resolve(path)?.starts_with(resolve(plugin_directory)?) {
+        Ok(())
+    } else {
+        Err(format!("{} is outside of {}", path, plugin_directory))
+    }
+}
+
+pub(crate) fn installed_version(plugin_directory: &str, internal_name: &str) -> Option<String> {
+    let entries = fs::read_dir(format!("{}/{}", plugin_directory, internal_name)).ok()?;
+
+    entries
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.path().is_dir())
+        .map(|entry| entry.file_name().to_string_lossy().to_string())
+        .max_by(|a, b| compare_versions(a, b))
+}
+
+pub(crate) fn compare_versions(a: &str, b: &str) -> std::cmp::Ordering {
+    let parse = |v: &str| -> Vec<u64> { v.split('.').map(|p| p.parse().unwrap_or(0)).collect() };
+    parse(a).cmp(&parse(b))
+}
+
+async fn collect_plugins(
+    app: &AppHandle,
+    plugin_directory: &str,
+) -> Result<Vec<AvailablePlugin>, String> {
+    let client = app.state::<HttpClient>().client();
+    let mut sources = vec![OFFICIAL_PLUGIN_MASTER.to_string()];
+    sources.extend(
+        load_repositories(app)?
+            .into_iter()
+            .filter(|repo| repo.enabled)
+            .map(|repo| repo.url),
+    );
+
+    let results = join_all(sources.iter().map(|url| fetch_manifests(&client, url))).await;
+
+    // Official entries come first, so a third-party repo can't shadow an official plugin
+    let mut seen = HashSet::new();
+    let mut plugins = Vec::new();
+    for (url, result) in sources.iter().zip(results) {
+        let manifests = match result {
+            Ok(manifests) => manifests,
+            Err(e) if url == OFFICIAL_PLUGIN_MASTER => return Err(e),
+            Err(e) => {
+                warn!("Skipping plugin repository: {}", e);
+                continue;
+            }
+        };
+
+        for manifest in manifests {
+            if manifest.is_hide || !seen.insert(manifest.internal_name.clone()) {
+                continue;
+            }
+            if let Err(e) = check_manifest_paths(&manifest) {
+                warn!("Skipping plugin from {}: {}", url, e);
+                continue;
+            }
+
+            plugins.push(AvailablePlugin {
+                repository: url.clone(),
+                is_third_party: url != OFFICIAL_PLUGIN_MASTER,
+                installed_version: installed_version(plugin_directory, &manifest.internal_name),
+                manifest,
+            });
+        }
+    }
+
+    Ok(plugins)
+}
+
+#[tauri::command]
+pub async fn list_available_plugins(
+    app: AppHandle,
+    plugin_directory: String,
+) -> Result<Vec<AvailablePlugin>, String> {
+    let plugins = collect_plugins(&app, &plugin_directory).await?;
+    info!("Found {} available plugins", plugins.len());
+    Ok(plugins)
+}
+
+// Installs the latest version, or updates an existing install in place
+#[tauri::command]
+pub async fn install_plugin(
+    app: AppHandle,
+    plugin_directory: String,
+    internal_name: String,
+) -> Result<String, String> {
+    let plugin = collect_plugins(&app, &plugin_directory)
+        .await?
+        .into_iter()
+        .find(|plugin| plugin.manifest.internal_name == internal_name)
+        .ok_or_else(|| format!("Plugin {} was not found in any repository", internal_name))?;
+
+    let client = app.state::<HttpClient>().client();
+    install(&client, None, &plugin_directory, plugin).await
+}
+
+// Updates every installed plugin a repository has a newer version of, a few at a time.
+// Download progress goes out as download events labelled with the plugin's name, and one
+// failed update doesn't stop the others
+#[tauri::command]
+pub async fn update_all_plugins(
+    app: AppHandle,
+    plugin_directory: String,
+) -> Result<Vec<PluginUpdate>, String> {
+    let outdated: Vec<AvailablePlugin> = collect_plugins(&app, &plugin_directory)
+        .await?
+        .into_iter()
+        .filter(|plugin| {
+            plugin
+                .installed_version
+                .as_deref()
+                .is_some_and(|installed| {
+                    compare_versions(installed, &plugin.manifest.assembly_version).is_lt()
+                })
+        })
+        .collect();
+    if outdated.is_empty() {
+        info!("All installed plugins are up to date");
+        return Ok(Vec::new());
+    }
+    info!("Updating {} plugins", outdated.len());
+
+    let client = app.state::<HttpClient>().client();
+    let progress = LaunchProgress::new(Some(app.clone()));
+    let updates: Vec<PluginUpdate> = stream::iter(outdated)
+        .map(|plugin| {
+            let (client, progress, plugin_directory) = (&client, &progress, &plugin_directory);
+            async move {
+                let mut update = PluginUpdate {
+                    internal_name: plugin.manifest.internal_name.clone(),
+                    name: plugin.manifest.name.clone(),
+                    from_version: plugin.installed_version.clone().unwrap_or_default(),
+                    to_version: plugin.manifest.assembly_version.clone(),
+                    error: None,
+                };
+                if let Err(e) = install(client, Some(progress), plugin_directory, plugin).await {
+                    warn!("Failed to update {}: {}", update.internal_name, e);
+                    update.error = Some(e);
+                }
+                update
+            }
+        })
+        .buffer_unordered(PARALLEL_UPDATES)
+        .collect()
+        .await;
+
+    let failed = updates
+        .iter()
+        .filter(|update| update.error.is_some())
+        .count();
+    info!(
+        "Updated {} plugins, {} failed",
+        updates.len() - failed,
+        failed
+    );
+    Ok(updates)
+}
+
+async fn install(
+    client: &Client,
+    progress: Option<&LaunchProgress>,
+    plugin_directory: &str,
+    plugin: AvailablePlugin,
+) -> Result<String, String> {
+    check_manifest_paths(&plugin.manifest)?;
+    let internal_name = plugin.manifest.internal_name.clone();
+    let manifest = plugin.manifest;
+    let version = manifest.assembly_version.clone();
+    if plugin.installed_version.as_deref() == Some(version.as_str()) {
+        info!("{} {} is already installed", internal_name, version);
+        return Ok(version);
+    }
+
+    let download_url = match (&plugin.installed_version, &manifest.download_link_update) {
+        (Some(_), Some(update_url)) => update_url.clone(),
+        _ => manifest.download_link_install.clone(),
+    };
+
+    info!(
+        "Installing {} {} from {}",
+        internal_name, version, plugin.repository
+    );
+
+    // Profiles refer to the plugin by this id, so it has to survive the update
+    let working_id = installed_manifest(plugin_directory, &internal_name)
+        .and_then(|manifest| manifest.get(WORKING_ID_KEY).cloned());
+
+    let plugin_root = format!("{}/{}", plugin_directory, internal_name);
+    let version_path = format!("{}/{}", plugin_root, version);
+    fs::create_dir_all(&version_path)
+        .map_err(|e| format!("Failed to create plugin directory: {}", e))?;
+    ensure_inside(plugin_directory, &plugin_root)?;
+
+    let temp_path = format!("{}/plugin_temp.zip", plugin_root);
+    let mut tracker = progress.map(|progress| progress.download(manifest.name.clone()));
+    download_file(
+        client,
+        &download_url,
+        &temp_path,
+        tracker.as_mut(),
+        &RetryPolicy::default(),
+        &CancellationToken::new(),
+    )
+    .await?;
+    let (zip_path, extract_path) = (temp_path.clone(), version_path.clone());
+    run_blocking(move || extract_zip(&zip_path, &extract_path, None, &CancellationToken::new()))
+        .await?;
+    fs::remove_file(&temp_path).map_err(|e| format!("Failed to remove temp file: {}", e))?;
+
+    // Dalamud loads plugins from the manifest next to the assembly
+    let mut local_manifest = manifest.clone();
+    local_manifest.extra.insert(
+        "InstalledFromUrl".to_string(),
+        serde_json::Value::String(plugin.repository.clone()),
+    );
+    if let Some(working_id) = working_id {
+        local_manifest
+            .extra
+            .insert(WORKING_ID_KEY.to_string(), working_id);
+    }
+    fs::write(
+        format!("{}/{}.json", version_path, internal_name),
+        serde_json::to_string_pretty(&local_manifest)
+            .map_err(|e| format!("Failed to serialize plugin manifest: {}", e))?,
+    )
+    .map_err(|e| format!("Failed to write plugin manifest: {}", e))?;
+
+    // Drop older versions now that the new one is in place
+    if let Ok(entries) = fs::read_dir(&plugin_root) {
+        for entry in entries.filter_map(|entry| entry.ok()) {
+            let path = entry.path();
+            if path.is_dir() && entry.file_name().to_string_lossy() != version {
+                if let Err(e) = fs::remove_dir_all(&path) {
+                    warn!("Failed to remove old plugin version {:?}: {}", path, e);
+                }
+            }
+        }
+    }
+
+    info!("Installed {} {}", internal_name, version);
+    Ok(version)
+}
+
+// The manifest of the newest installed version, kept as plain JSON for the fields Dalamud
+// adds to it
+fn installed_manifest(plugin_directory: &str, internal_name: &str) -> Option<serde_json::Value> {
+    let version = installed_version(plugin_directory, internal_name)?;
+    let path = Path::new(plugin_directory)
+        .join(internal_name)
+        .join(version)
+        .join(format!("{}.json", internal_name));
+    fs::read_to_string(&path)
+        .ok()
+        .and_then(|contents| serde_json::from_str(&contents).ok())
+}
+
+// An empty configuration when Dalamud hasn't written one yet, Dalamud fills in the rest
+fn read_dalamud_config(path: &Path) -> Result<serde_json::Value, String> {
+    match fs::read_to_string(path) {
+        Ok(contents) => serde_json::from_str(&contents)
+            .map_err(|e| format!("Failed to parse {}: {}", path.display(), e)),
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok(serde_json::json!({})),
+        Err(e) => Err(format!("Failed to read {}: {}", path.display(), e)),
+    }
+}
+
+// Json.NET writes lists of typed objects as {"$type": ..., "$values": [...]}
+fn profile_plugins(profile: &serde_json::Value) -> Option<&Vec<serde_json::Value>> {
+    let plugins = profile.get("Plugins")?;
+    plugins.get("$values").unwrap_or(plugins).as_array()
+}
+
+fn profile_plugins_mut(profile: &mut serde_json::Value) -> Option<&mut Vec<serde_json::Value>> {
+    let plugins = profile.get_mut("Plugins")?;
+    if plugins.get("$values").is_some() {
+        plugins.get_mut("$values")?.as_array_mut()
+    } else {
+        plugins.as_array_mut()
+    }
+}
+
+fn is_profile_entry(
+    entry: &serde_json::Value,
+    working_id: Option<&serde_json::Value>,
+    internal_name: &str,
+) -> bool {
+    match (working_id, entry.get(WORKING_ID_KEY)) {
+        (Some(id), Some(entry_id)) => id == entry_id,
+        _ => entry.get("InternalName").and_then(|name| name.as_str()) == Some(internal_name),
+    }
+}
+
+// Plugins missing from the default profile load, Dalamud adds them to it enabled
+fn profile_enabled(
+    config: &serde_json::Value,
+    working_id: Option<&serde_json::Value>,
+    internal_name: &str,
+) -> bool {
+    config
+        .get(DEFAULT_PROFILE_KEY)
+        .and_then(profile_plugins)
+        .and_then(|plugins| {
+            plugins
+                .iter()
+                .find(|entry| is_profile_entry(entry, working_id, internal_name))
+        })
+        .and_then(|entry| entry.get("IsEnabled"))
+        .and_then(|enabled| enabled.as_bool())
+        .unwrap_or(true)
+}
+
+fn set_profile_enabled(
+    config: &mut serde_json::Value,
+    working_id: Option<&serde_json::Value>,
+    internal_name: &str,
+    enabled: bool,
+) -> Result<(), String> {
+    let config = config
+        .as_object_mut()
+        .ok_or_else(|| "Dalamud configuration is not a JSON object".to_string())?;
+    let profile = config
+        .entry(DEFAULT_PROFILE_KEY)
+        .or_insert(serde_json::Value::Null);
+    if !profile.is_object() {
+        *profile = serde_json::json!({
+            "$type": PROFILE_TYPE,
+            "Name": "DEFAULT",
+            "IsEnabled": true,
+            "Plugins": [],
+        });
+    }
+    if profile_plugins(profile).is_none() {
+        profile["Plugins"] = serde_json::json!([]);
+    }
+    let plugins = profile_plugins_mut(profile)
+        .ok_or_else(|| "Default Dalamud profile has no plugin list".to_string())?;
+
+    match plugins
+        .iter_mut()
+        .find(|entry| is_profile_entry(entry, working_id, internal_name))
+    {
+        Some(entry) => entry["IsEnabled"] = serde_json::Value::Bool(enabled),
+        None => {
+            let mut entry = serde_json::json!({
+                "InternalName": internal_name,
+                "IsEnabled": enabled,
+            });
+            if let Some(id) = working_id {
+                entry[WORKING_ID_KEY] = id.clone();
+            }
+            plugins.push(entry);
+        }
+    }
+    Ok(())
+}
+
+fn describe_installed(
+    dalamud_config: &serde_json::Value,
+    manifest: &serde_json::Value,
+    internal_name: &str,
+) -> InstalledPlugin {
+    let text = |key: &str| manifest.get(key).and_then(|value| value.as_str());
+    InstalledPlugin {
+        internal_name: internal_name.to_string(),
+        name: text("Name").unwrap_or(internal_name).to_string(),
+        version: text("AssemblyVersion").unwrap_or_default().to_string(),
+        enabled: profile_enabled(dalamud_config, manifest.get(WORKING_ID_KEY), internal_name),
+    }
+}
+
+#[tauri::command]
+pub fn list_installed_plugins(dalamud_path: String) -> Result<Vec<InstalledPlugin>, String> {
+    let plugin_directory = format!("{}/{}", dalamud_path, PLUGIN_FOLDER);
+    let dalamud_config = read_dalamud_config(&Path::new(&dalamud_path).join(DALAMUD_CONFIG_FILE))?;
+    let entries = match fs::read_dir(&plugin_directory) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(format!("Failed to read {}: {}", plugin_directory, e)),
+    };
+
+    let mut plugins: Vec<InstalledPlugin> = entries
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.path().is_dir())
+        .filter_map(|entry| {
+            let internal_name = entry.file_name().to_string_lossy().to_string();
+            let manifest = installed_manifest(&plugin_directory, &internal_name)?;
+            Some(describe_installed(
+                &dalamud_config,
+                &manifest,
+                &internal_name,
+            ))
+        })
+        .collect();
+    plugins.sort_by_key(|plugin| plugin.name.to_lowercase());
+    Ok(plugins)
+}
+
+// Sets the plugin's state in Dalamud's default profile. Takes effect the next time Dalamud
+// starts, it only reads its configuration while loading
+#[tauri::command]
+pub fn set_plugin_enabled(
+    dalamud_path: String,
+    internal_name: String,
+    enabled: bool,
+) -> Result<InstalledPlugin, String> {
+    if game_monitor::get_game_status().running {
+        return Err("Close the game before enabling or disabling plugins".to_string());
+    }
+    check_path_part("name", &internal_name)?;
+    let plugin_directory = format!("{}/{}", dalamud_path, PLUGIN_FOLDER);
+    let manifest = installed_manifest(&plugin_directory, &internal_name)
+        .ok_or_else(|| format!("Plugin {} is not installed", internal_name))?;
+
+    let config_path = Path::new(&dalamud_path).join(DALAMUD_CONFIG_FILE);
+    let mut dalamud_config = read_dalamud_config(&config_path)?;
+    set_profile_enabled(
+        &mut dalamud_config,
+        manifest.get(WORKING_ID_KEY),
+        &internal_name,
+        enabled,
+    )?;
+    let contents = serde_json::to_string_pretty(&dalamud_config)
+        .map_err(|e| format!("Failed to serialize Dalamud configuration: {}", e))?;
+    fs::write(&config_path, contents)
+        .map_err(|e| format!("Failed to write Dalamud configuration: {}", e))?;
+
+    info!(
+        "{} {}",
+        if enabled { "Enabled" } else { "Disabled" },
+        internal_name
+    );
+    Ok(describe_installed(
+        &dalamud_config,
+        &manifest,
+        &internal_name,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dalamud_dir(manifest: serde_json::Value) -> tempfile::TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        let version = dir
+            .path()
+            .join(PLUGIN_FOLDER)
+            .join("Sample")
+            .join("1.2.0.0");
+        fs::create_dir_all(&version).unwrap();
+        fs::write(version.join("Sample.json"), manifest.to_string()).unwrap();
+        dir
+    }
+
+    fn dalamud_path(dir: &tempfile::TempDir) -> String {
+        dir.path().to_string_lossy().to_string()
+    }
+
+    fn read_config(dir: &tempfile::TempDir) -> serde_json::Value {
+        let contents = fs::read_to_string(dir.path().join(DALAMUD_CONFIG_FILE)).unwrap();
+        serde_json::from_str(&contents).unwrap()
+    }
+
+    #[test]
+    fn disabling_writes_the_default_profile() {
+        let dir = dalamud_dir(serde_json::json!({
+            "InternalName": "Sample",
+            "Name": "Sample Plugin",
+            "AssemblyVersion": "1.2.0.0",
+            "WorkingPluginId": "7d1c4e52-0f3a-4b8e-9a61-2f5c8d9e0b13",
+        }));
+        // As Dalamud writes it, other settings and profile entries have to survive
+        let existing = serde_json::json!({
+            "$type": "Dalamud.Configuration.Internal.DalamudConfiguration, Dalamud",
+            "LogLevel": 2,
+            "DefaultProfile": {
+                "$type": PROFILE_TYPE,
+                "Name": "DEFAULT",
+                "Plugins": {
+                    "$type": "System.Collections.Generic.List`1[[Dalamud.Plugin.Internal.Profiles.ProfileModelV1+ProfileModelV1Plugin, Dalamud]], System.Private.CoreLib",
+                    "$values": [
+                        { "InternalName": "Other", "WorkingPluginId": "1b7f0c2a-5d4e-4f3b-8c9a-6e2d1f0a3b4c", "IsEnabled": false },
+                        { "InternalName": "Sample", "WorkingPluginId": "7d1c4e52-0f3a-4b8e-9a61-2f5c8d9e0b13", "IsEnabled": true },
+                    ],
+                },
+            },
+        });
+        fs::write(dir.path().join(DALAMUD_CONFIG_FILE), existing.to_string()).unwrap();
+
+        let plugin = set_plugin_enabled(dalamud_path(&dir), "Sample".to_string(), false).unwrap();
+        assert!(!plugin.enabled);
+
+        let config = read_config(&dir);
+        assert_eq!(config["LogLevel"], 2);
+        let plugins = &config["DefaultProfile"]["Plugins"]["$values"];
+        assert_eq!(plugins[0]["IsEnabled"], false);
+        assert_eq!(plugins[1]["IsEnabled"], false);
+        assert_eq!(plugins.as_array().unwrap().len(), 2);
+
+        let listed = list_installed_plugins(dalamud_path(&dir)).unwrap();
+        assert_eq!(listed.len(), 1);
+        assert_eq!(listed[0].name, "Sample Plugin");
+        assert!(!listed[0].enabled);
+
+        set_plugin_enabled(dalamud_path(&dir), "Sample".to_string(), true).unwrap();
+        assert_eq!(
+            read_config(&dir)["DefaultProfile"]["Plugins"]["$values"][1]["IsEnabled"],
+            true
+        );
+    }
+
+    #[test]
+    fn a_missing_configuration_gets_a_default_profile() {
+        let dir = dalamud_dir(serde_json::json!({
+            "InternalName": "Sample",
+            "Name": "Sample Plugin",
+            "AssemblyVersion": "1.2.0.0",
+        }));
+        // Nothing in the profile yet, Dalamud loads the plugin
+        assert!(list_installed_plugins(dalamud_path(&dir)).unwrap()[0].enabled);
+
+        set_plugin_enabled(dalamud_path(&dir), "Sample".to_string(), false).unwrap();
+
+        let config = read_config(&dir);
+        let profile = &config[DEFAULT_PROFILE_KEY];
+        assert_eq!(profile["$type"], PROFILE_TYPE);
+        assert_eq!(
+            profile["Plugins"],
+            serde_json::json!([{ "InternalName": "Sample", "IsEnabled": false }])
+        );
+        assert!(!list_installed_plugins(dalamud_path(&dir)).unwrap()[0].enabled);
+    }
+
+    #[test]
+    fn only_installed_plugins_can_be_toggled() {
+        let dir = tempfile::tempdir().unwrap();
+        assert!(set_plugin_enabled(dalamud_path(&dir), "Missing".to_string(), false).is_err());
+        assert!(set_plugin_enabled(dalamud_path(&dir), "../Sample".to_string(), false).is_err());
+        assert!(!dir.path().join(DALAMUD_CONFIG_FILE).exists());
+    }
+}
@@ -0,0 +1,3 @@
+// Safe wrappers around the OS calls that create and control the game process and its windows
+#[cfg(windows)]
+pub mod windows;
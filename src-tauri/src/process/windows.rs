@@ -0,0 +1,360 @@
+use std::ffi::{OsStr, OsString};
+use std::io::Error as IoError;
+use std::iter::once;
+use std::os::windows::ffi::OsStrExt;
+use std::os::windows::io::{AsHandle, AsRawHandle, BorrowedHandle, FromRawHandle, OwnedHandle};
+use thiserror::Error;
+use windows::core::{Error as WindowsError, PCWSTR, PWSTR};
+use windows::Win32::Foundation::{
+    BOOL, FALSE, FILETIME, HANDLE, HWND, LPARAM, TRUE, WAIT_OBJECT_0, WAIT_TIMEOUT, WPARAM,
+};
+use windows::Win32::Security::{
+    InitializeSecurityDescriptor, SetSecurityDescriptorDacl, PSECURITY_DESCRIPTOR,
+    SECURITY_ATTRIBUTES, SECURITY_DESCRIPTOR,
+};
+use windows::Win32::System::Diagnostics::ToolHelp::{
+    CreateToolhelp32Snapshot, Process32FirstW, Process32NextW, PROCESSENTRY32W, TH32CS_SNAPPROCESS,
+};
+use windows::Win32::System::SystemServices::SECURITY_DESCRIPTOR_REVISION;
+use windows::Win32::System::Threading::{
+    CreateProcessW, GetExitCodeProcess, GetProcessId, GetProcessTimes, OpenProcess, ResumeThread,
+    TerminateProcess, WaitForSingleObject, CREATE_SUSPENDED, CREATE_UNICODE_ENVIRONMENT, INFINITE,
+    PROCESS_ACCESS_RIGHTS, PROCESS_INFORMATION, PROCESS_QUERY_LIMITED_INFORMATION,
+    PROCESS_SYNCHRONIZE, PROCESS_TERMINATE, STARTUPINFOW,
+};
+use windows::Win32::UI::WindowsAndMessaging::{
+    EnumWindows, GetClassNameW, GetWindowTextW, GetWindowThreadProcessId, IsWindowVisible,
+    PostMessageW, WM_CLOSE,
+};
+
+use tracing::warn;
+
+#[derive(Debug, Error)]
+pub enum ProcessError {
+    #[error("Failed to set up the process security descriptor: {0}")]
+    Security(WindowsError),
+    #[error("Failed to create process: {0}")]
+    Create(WindowsError),
+    #[error("Failed to resume process: {0}")]
+    Resume(IoError),
+    #[error("Failed to open process: {0}")]
+    Open(WindowsError),
+    #[error("Failed to wait for process: {0}")]
+    Wait(IoError),
+    #[error("Failed to read the process exit code: {0}")]
+    ExitCode(WindowsError),
+    #[error("Failed to terminate process: {0}")]
+    Terminate(WindowsError),
+    #[error("Failed to list processes: {0}")]
+    Snapshot(WindowsError),
+}
+
+// A process created suspended. Nothing of it has run until resume(), and one dropped
+// without being resumed is terminated instead of left hanging. The handles close on drop
+#[derive(Debug)]
+pub struct SuspendedProcess {
+    pid: u32,
+    process: OwnedHandle,
+    thread: OwnedHandle,
+    resumed: bool,
+}
+
+impl SuspendedProcess {
+    // Starts `application` with `command_line` and exactly the given environment. The
+    // handles are inheritable and open to everyone, the way the game's own launcher
+    // creates it, so Dalamud and other tools can attach
+    pub fn spawn(
+        application: &str,
+        command_line: &str,
+        environment: &[(OsString, OsString)],
+    ) -> Result<Self, ProcessError> {
+        let application = wide(OsStr::new(application));
+        // CreateProcessW may write to the command line, so it gets its own buffer
+        let mut command_line = wide(OsStr::new(command_line));
+        let environment = environment_block(environment);
+
+        let mut descriptor = SECURITY_DESCRIPTOR::default();
+        let descriptor_ptr = PSECURITY_DESCRIPTOR(&mut descriptor as *mut _ as *mut _);
+        let startup_info = STARTUPINFOW {
+            cb: std::mem::size_of::<STARTUPINFOW>() as u32,
+            ..Default::default()
+        };
+        let mut process_info = PROCESS_INFORMATION::default();
+
+        unsafe {
+            InitializeSecurityDescriptor(descriptor_ptr, SECURITY_DESCRIPTOR_REVISION)
+                .map_err(ProcessError::Security)?;
+            // A NULL DACL allows access to everyone
+            SetSecurityDescriptorDacl(descriptor_ptr, TRUE, None, FALSE)
+                .map_err(ProcessError::Security)?;
+            let attributes = SECURITY_ATTRIBUTES {
+                nLength: std::mem::size_of::<SECURITY_ATTRIBUTES>() as u32,
+                lpSecurityDescriptor: descriptor_ptr.0,
+                bInheritHandle: TRUE,
+            };
+
+            CreateProcessW(
+                PCWSTR(application.as_ptr()),
+                PWSTR(command_line.as_mut_ptr()),
+                Some(&attributes as *const _),
+                Some(&attributes as *const _),
+                TRUE,
+                CREATE_SUSPENDED | CREATE_UNICODE_ENVIRONMENT,
+                Some(environment.as_ptr() as *const _),
+                PCWSTR::null(),
+                &startup_info,
+                &mut process_info,
+            )
+            .map_err(ProcessError::Create)?;
+
+            // Owned right away, so every path from here closes them
+            let process = OwnedHandle::from_raw_handle(process_info.hProcess.0);
+            let thread = OwnedHandle::from_raw_handle(process_info.hThread.0);
+            Ok(Self {
+                pid: GetProcessId(process_info.hProcess),
+                process,
+                thread,
+                resumed: false,
+            })
+        }
+    }
+
+    pub fn pid(&self) -> u32 {
+        self.pid
+    }
+
+    // Hands back the process handle, so whatever comes after acts on this process
+    // instead of whichever one has the pid by then
+    pub fn resume(mut self) -> Result<ProcessHandle, ProcessError> {
+        // Duplicated first, a failure here still terminates the suspended process
+        let process = self.process.try_clone().map_err(ProcessError::Resume)?;
+        if unsafe { ResumeThread(raw(&self.thread)) } == u32::MAX {
+            return Err(ProcessError::Resume(IoError::last_os_error()));
+        }
+        self.resumed = true;
+        Ok(ProcessHandle {
+            pid: self.pid,
+            process,
+        })
+    }
+}
+
+// The process handle, for calls that act on the suspended game
+impl AsHandle for SuspendedProcess {
+    fn as_handle(&self) -> BorrowedHandle<'_> {
+        self.process.as_handle()
+    }
+}
+
+impl Drop for SuspendedProcess {
+    fn drop(&mut self) {
+        if self.resumed {
+            return;
+        }
+        warn!("Terminating process {} that was never resumed", self.pid);
+        if let Err(e) = unsafe { TerminateProcess(raw(&self.process), 1) } {
+            warn!("Failed to terminate process {}: {}", self.pid, e);
+        }
+    }
+}
+
+// A running process, closed on drop
+#[derive(Debug)]
+pub struct ProcessHandle {
+    pid: u32,
+    process: OwnedHandle,
+}
+
+impl ProcessHandle {
+    // For processes something else started, e.g. Dalamud.Injector. Only good for waiting on
+    pub fn open(pid: u32) -> Result<Self, ProcessError> {
+        Self::open_with(pid, PROCESS_SYNCHRONIZE | PROCESS_QUERY_LIMITED_INFORMATION)
+    }
+
+    // Like open(), but the handle can also terminate the process
+    pub fn open_terminable(pid: u32) -> Result<Self, ProcessError> {
+        Self::open_with(
+            pid,
+            PROCESS_SYNCHRONIZE | PROCESS_QUERY_LIMITED_INFORMATION | PROCESS_TERMINATE,
+        )
+    }
+
+    fn open_with(pid: u32, access: PROCESS_ACCESS_RIGHTS) -> Result<Self, ProcessError> {
+        unsafe {
+            let handle = OpenProcess(access, false, pid).map_err(ProcessError::Open)?;
+            Ok(Self {
+                pid,
+                process: OwnedHandle::from_raw_handle(handle.0),
+            })
+        }
+    }
+
+    pub fn pid(&self) -> u32 {
+        self.pid
+    }
+
+    // Blocks until the process exits and returns its exit code
+    pub fn wait(&self) -> Result<u32, ProcessError> {
+        unsafe {
+            if WaitForSingleObject(raw(&self.process), INFINITE) != WAIT_OBJECT_0 {
+                return Err(ProcessError::Wait(IoError::last_os_error()));
+            }
+            let mut code = 0;
+            GetExitCodeProcess(raw(&self.process), &mut code).map_err(ProcessError::ExitCode)?;
+            Ok(code)
+        }
+    }
+
+    // Doesn't wait, false once the process has exited
+    pub fn is_running(&self) -> bool {
+        unsafe { WaitForSingleObject(raw(&self.process), 0) == WAIT_TIMEOUT }
+    }
+
+    // Needs a handle from resume() or open_terminable(), the ones open() returns can't terminate
+    pub fn terminate(&self) -> Result<(), ProcessError> {
+        unsafe { TerminateProcess(raw(&self.process), 1) }.map_err(ProcessError::Terminate)
+    }
+
+    // In 100ns ticks since 1601, None if the process can't be queried
+    fn creation_time(&self) -> Option<u64> {
+        let mut created = FILETIME::default();
+        let mut exited = FILETIME::default();
+        let mut kernel = FILETIME::default();
+        let mut user = FILETIME::default();
+        unsafe {
+            GetProcessTimes(
+                raw(&self.process),
+                &mut created,
+                &mut exited,
+                &mut kernel,
+                &mut user,
+            )
+        }
+        .ok()?;
+        Some(((created.dwHighDateTime as u64) << 32) | created.dwLowDateTime as u64)
+    }
+}
+
+impl AsHandle for ProcessHandle {
+    fn as_handle(&self) -> BorrowedHandle<'_> {
+        self.process.as_handle()
+    }
+}
+
+// A top level window, the handle is only good for passing back to close_window()
+#[derive(Debug, Clone)]
+pub struct WindowInfo {
+    pub handle: usize,
+    pub class: String,
+    pub title: String,
+    pub visible: bool,
+}
+
+// Top level windows owned by the process
+pub fn process_windows(pid: u32) -> Vec<WindowInfo> {
+    unsafe extern "system" fn collect(hwnd: HWND, lparam: LPARAM) -> BOOL {
+        let (pid, found) = &mut *(lparam.0 as *mut (u32, Vec<WindowInfo>));
+        let mut owner = 0;
+        GetWindowThreadProcessId(hwnd, Some(&mut owner));
+        if owner == *pid {
+            let mut class = [0u16; 64];
+            let class_len = GetClassNameW(hwnd, &mut class).max(0) as usize;
+            let mut title = [0u16; 256];
+            let title_len = GetWindowTextW(hwnd, &mut title).max(0) as usize;
+            found.push(WindowInfo {
+                handle: hwnd.0 as usize,
+                class: String::from_utf16_lossy(&class[..class_len]),
+                title: String::from_utf16_lossy(&title[..title_len]),
+                visible: IsWindowVisible(hwnd).as_bool(),
+            });
+        }
+        TRUE
+    }
+
+    let mut found = (pid, Vec::new());
+    // Only fails when the callback stops early, which this one never does
+    let result = unsafe { EnumWindows(Some(collect), LPARAM(&mut found as *mut _ as isize)) };
+    if let Err(e) = result {
+        warn!("Failed to list the windows of process {}: {}", pid, e);
+    }
+    found.1
+}
+
+// Asks the window to close, like its close button does
+pub fn close_window(handle: usize) {
+    let result = unsafe { PostMessageW(HWND(handle as *mut _), WM_CLOSE, WPARAM(0), LPARAM(0)) };
+    if let Err(e) = result {
+        warn!("Failed to close window {:#x}: {}", handle, e);
+    }
+}
+
+// Children of the process, their children and so on, deepest last. Parent pids outlive
+// their processes, so a child that's older than its parent is left out as one of a pid reuse
+pub fn descendants(process: &ProcessHandle) -> Result<Vec<u32>, ProcessError> {
+    // (pid, parent pid) of everything running
+    let mut processes = Vec::new();
+    unsafe {
+        let snapshot =
+            CreateToolhelp32Snapshot(TH32CS_SNAPPROCESS, 0).map_err(ProcessError::Snapshot)?;
+        let snapshot = OwnedHandle::from_raw_handle(snapshot.0);
+
+        let mut entry = PROCESSENTRY32W {
+            dwSize: std::mem::size_of::<PROCESSENTRY32W>() as u32,
+            ..Default::default()
+        };
+        let mut found = Process32FirstW(raw(&snapshot), &mut entry).is_ok();
+        while found {
+            processes.push((entry.th32ProcessID, entry.th32ParentProcessID));
+            found = Process32NextW(raw(&snapshot), &mut entry).is_ok();
+        }
+    }
+
+    let mut found = Vec::new();
+    let mut parents = vec![(process.pid, process.creation_time())];
+    while let Some((parent, parent_created)) = parents.pop() {
+        for (child, _) in processes.iter().filter(|(_, p)| *p == parent) {
+            if found.contains(child) || *child == process.pid {
+                continue;
+            }
+            let child_created = ProcessHandle::open(*child)
+                .ok()
+                .and_then(|child| child.creation_time());
+            let reused = matches!(
+                (parent_created, child_created),
+                (Some(parent_at), Some(child_at)) if child_at < parent_at
+            );
+            if !reused {
+                found.push(*child);
+                parents.push((*child, child_created));
+            }
+        }
+    }
+    Ok(found)
+}
+
+fn raw(handle: &OwnedHandle) -> HANDLE {
+    HANDLE(handle.as_raw_handle())
+}
+
+fn wide(value: &OsStr) -> Vec<u16> {
+    value.encode_wide().chain(once(0)).collect()
+}
+
+// KEY=value entries, each NUL terminated, with one more NUL at the end
+fn environment_block(environment: &[(OsString, OsString)]) -> Vec<u16> {
+    let mut block: Vec<u16> = environment
+        .iter()
+        .flat_map(|(key, value)| {
+            let mut entry = key.clone();
+            entry.push("=");
+            entry.push(value);
+            wide(&entry)
+        })
+        .collect();
+    // An empty block still needs both terminators
+    if block.is_empty() {
+        block.push(0);
+    }
+    block.push(0);
+    block
+}
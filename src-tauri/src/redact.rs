@@ -0,0 +1,145 @@
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::fmt;
+use std::sync::OnceLock;
+use tauri_plugin_log::fern::FormatCallback;
+use time::OffsetDateTime;
+
+const REDACTED: &str = "<redacted>";
+
+// String that never shows up in Debug output, for passwords, OTPs and the like
+#[derive(Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Secret(String);
+
+impl Secret {
+    pub fn expose(&self) -> &str {
+        &self.0
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl From<String> for Secret {
+    fn from(value: String) -> Self {
+        Self(value)
+    }
+}
+
+impl fmt::Debug for Secret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.0.is_empty() {
+            f.write_str("\"\"")
+        } else {
+            f.write_str(REDACTED)
+        }
+    }
+}
+
+// Last line of defence for secrets that end up inside formatted strings, like
+// the SID in launch arguments or the stored value in an oauth response
+fn patterns() -> &'static [(Regex, &'static str)] {
+    static PATTERNS: OnceLock<Vec<(Regex, &'static str)>> = OnceLock::new();
+
+    PATTERNS.get_or_init(|| {
+        [
+            (r"//\*\*sqex0003[^*]*\*\*//", "//**sqex0003<redacted>**//"),
+            (r"(?i)(DEV\.TestSID\s*=\s*)\S+", "${1}<redacted>"),
+            (r"(?i)(\bsid\s*[,=:]\s*)[0-9a-f]{16,}", "${1}<redacted>"),
+            (r"(?i)(X-Patch-Unique-Id\W+)[0-9a-f]{16,}", "${1}<redacted>"),
+            (r#"(?i)(_STORED_"?\s*value=")[^"]*"#, "${1}<redacted>"),
+            (
+                r#"(?i)("?(?:password|otppw|otp_secret|_STORED_)"?\s*[:=]\s*")[^"]*"#,
+                "${1}<redacted>",
+            ),
+        ]
+        .into_iter()
+        .map(|(pattern, replacement)| (Regex::new(pattern).unwrap(), replacement))
+        .collect()
+    })
+}
+
+pub fn scrub(message: &str) -> Cow<'_, str> {
+    let mut scrubbed = Cow::Borrowed(message);
+    for (pattern, replacement) in patterns() {
+        if let Cow::Owned(replaced) = pattern.replace_all(&scrubbed, *replacement) {
+            scrubbed = Cow::Owned(replaced);
+        }
+    }
+    scrubbed
+}
+
+// Same layout as the log plugin's default format, with the message scrubbed
+pub fn format_log(out: FormatCallback, message: &fmt::Arguments, record: &log::Record) {
+    let now = OffsetDateTime::now_utc();
+    let message = message.to_string();
+
+    out.finish(format_args!(
+        "[{}-{:02}-{:02}][{:02}:{:02}:{:02}][{}][{}] {}",
+        now.year(),
+        now.month() as u8,
+        now.day(),
+        now.hour(),
+        now.minute(),
+        now.second(),
+        record.level(),
+        record.target(),
+        scrub(&message)
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+    use tauri_plugin_log::fern;
+
+    // Lines the global test logger wrote, it outlives the test that installs it
+    static LOGGED: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+    #[test]
+    fn scrubs_sid_in_launch_arguments() {
+        let sid = "0123456789abcdef0123456789abcdef";
+        let arguments = format!("DEV.TestSID={} SYS.Region=3", sid);
+        assert_eq!(scrub(&arguments), "DEV.TestSID=<redacted> SYS.Region=3");
+    }
+
+    #[test]
+    fn scrubs_stored_value() {
+        let body = r#"<input type="hidden" name="_STORED_" value="abc123def">"#;
+        assert!(!scrub(body).contains("abc123def"));
+    }
+
+    // The GUI installs no tracing subscriber, so tracing events reach the log plugin's
+    // format through the log crate. This is the only test that sets the global logger
+    #[test]
+    fn tracing_events_are_scrubbed_by_the_log_format() {
+        fern::Dispatch::new()
+            .level(log::LevelFilter::Trace)
+            .format(format_log)
+            .chain(fern::Output::call(|record| {
+                LOGGED.lock().unwrap().push(record.args().to_string())
+            }))
+            .apply()
+            .unwrap();
+
+        let sid = "fedcba9876543210fedcba9876543210";
+        tracing::info!("Launching with DEV.TestSID={} SYS.Region=3", sid);
+
+        let logged = LOGGED.lock().unwrap();
+        let line = logged
+            .iter()
+            .find(|line| line.contains("Launching with"))
+            .expect("the event reached the log plugin's format");
+        assert!(line.contains("DEV.TestSID=<redacted>"), "{}", line);
+        assert!(!line.contains(sid), "{}", line);
+        assert!(
+            line.contains("[INFO][rust_xivloader_lib::redact::tests]"),
+            "{}",
+            line
+        );
+    }
+}
@@ -0,0 +1,320 @@
+use regex::Regex;
+use reqwest::header::USER_AGENT;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use tauri::State;
+use tokio_util::sync::CancellationToken;
+
+use tracing::{info, warn};
+
+use crate::ffxiv::{download_file, get_user_agent, sha1_file};
+use crate::http::{HttpClient, REQUEST_TIMEOUT};
+use crate::retry::RetryPolicy;
+
+const DOWNLOADS_PAGE: &str = "https://reshade.me";
+const DOWNLOAD_BASE: &str = "https://reshade.me/downloads";
+// The DX11 client loads dxgi.dll from its own folder first
+const INJECTION_DLL: &str = "dxgi.dll";
+const RESHADE_DLL: &str = "ReShade64.dll";
+const RESHADE_INI: &str = "ReShade.ini";
+const GSHADE_INI: &str = "GShade.ini";
+const MANAGER_FOLDER: &str = "xivloader-reshade";
+const STATE_FILE: &str = "state.json";
+const PRESETS_FOLDER: &str = "reshade-presets";
+const PRESET_FILE: &str = "ReShadePreset.ini";
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ShaderTool {
+    ReShade,
+    GShade,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ReShadeConfig {
+    pub enabled: bool,
+    // Folder under reshade-presets this profile reads its presets from, empty keeps ReShade's default
+    pub preset_folder: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ReShadeStatus {
+    pub tool: Option<ShaderTool>,
+    // Set when the DLL in the game folder is the one downloaded here
+    pub managed: bool,
+    pub version: Option<String>,
+    pub dll_present: bool,
+    pub preset_folders: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct ManagerState {
+    version: String,
+    sha1: String,
+}
+
+#[tauri::command]
+pub fn get_reshade_status(game_path: String) -> Result<ReShadeStatus, String> {
+    detect(&game_dir(&game_path))
+}
+
+// Downloads the newest ReShade and puts it into the game folder
+#[tauri::command]
+pub async fn update_reshade(
+    http: State<'_, HttpClient>,
+    game_path: String,
+) -> Result<ReShadeStatus, String> {
+    let game_dir = game_dir(&game_path);
+    let status = detect(&game_dir)?;
+    if status.tool == Some(ShaderTool::GShade) {
+        return Err("GShade is installed, it has to be updated through its own installer".into());
+    }
+    let target = game_dir.join(INJECTION_DLL);
+    if target.is_file() && status.tool.is_none() && !status.managed {
+        return Err(format!(
+            "{} in the game folder does not belong to ReShade, not replacing it",
+            INJECTION_DLL
+        ));
+    }
+
+    let client = &http.client();
+    let version = latest_version(client).await?;
+    let state = load_state(&game_dir);
+    if state.as_ref().is_some_and(|s| s.version == version) && status.managed {
+        info!("ReShade {} is already installed", version);
+        return Ok(status);
+    }
+
+    let manager_dir = game_dir.join(MANAGER_FOLDER);
+    fs::create_dir_all(&manager_dir)
+        .map_err(|e| format!("Failed to create ReShade directory: {}", e))?;
+    let setup_path = manager_dir.join("ReShade_Setup.exe");
+    let setup = setup_path.to_string_lossy().to_string();
+
+    info!("Downloading ReShade {}", version);
+    let url = format!("{}/ReShade_Setup_{}.exe", DOWNLOAD_BASE, version);
+    download_file(
+        client,
+        &url,
+        &setup,
+        None,
+        &RetryPolicy::default(),
+        &CancellationToken::new(),
+    )
+    .await?;
+    let result = extract_dll(&setup_path, &manager_dir.join(RESHADE_DLL));
+    let _ = fs::remove_file(&setup_path);
+    result?;
+
+    let sha1 = sha1_file(&manager_dir.join(RESHADE_DLL).to_string_lossy())
+        .map_err(|e| format!("Failed to hash ReShade: {}", e))?;
+    save_state(&game_dir, &ManagerState { version, sha1 })?;
+
+    fs::copy(manager_dir.join(RESHADE_DLL), &target)
+        .map_err(|e| format!("Failed to install ReShade: {}", e))?;
+    // ReShade creates the rest of its configuration on first start
+    if !game_dir.join(RESHADE_INI).exists() {
+        fs::write(game_dir.join(RESHADE_INI), "[GENERAL]\n")
+            .map_err(|e| format!("Failed to create {}: {}", RESHADE_INI, e))?;
+    }
+
+    detect(&game_dir)
+}
+
+#[tauri::command]
+pub fn create_reshade_preset_folder(game_path: String, name: String) -> Result<(), String> {
+    let dir = preset_dir(&game_dir(&game_path), &name)?;
+    fs::create_dir_all(&dir).map_err(|e| format!("Failed to create preset folder: {}", e))
+}
+
+// Called before the game starts: puts our DLL back if something removed it, or takes it
+// out for profiles that have ReShade turned off
+pub fn prepare(game_path: &str, config: &ReShadeConfig) -> Result<(), String> {
+    let game_dir = game_dir(game_path);
+    let status = detect(&game_dir)?;
+    if status.tool == Some(ShaderTool::GShade) {
+        info!("GShade is installed, leaving its files alone");
+        return Ok(());
+    }
+
+    let target = game_dir.join(INJECTION_DLL);
+    if !config.enabled {
+        // A ReShade the user installed by hand isn't ours to remove
+        if status.managed {
+            info!("ReShade is off for this profile, removing it from the game folder");
+            fs::remove_file(&target).map_err(|e| format!("Failed to remove ReShade: {}", e))?;
+        }
+        return Ok(());
+    }
+
+    if !status.dll_present {
+        let managed_dll = game_dir.join(MANAGER_FOLDER).join(RESHADE_DLL);
+        if !managed_dll.is_file() {
+            return Err("ReShade has not been downloaded yet".to_string());
+        }
+        info!("Putting ReShade back into the game folder");
+        fs::copy(&managed_dll, &target).map_err(|e| format!("Failed to install ReShade: {}", e))?;
+    }
+
+    if !config.preset_folder.is_empty() {
+        let dir = preset_dir(&game_dir, &config.preset_folder)?;
+        fs::create_dir_all(&dir).map_err(|e| format!("Failed to create preset folder: {}", e))?;
+        let preset = format!(
+            ".\\{}\\{}\\{}",
+            PRESETS_FOLDER, config.preset_folder, PRESET_FILE
+        );
+        set_ini_value(
+            &game_dir.join(RESHADE_INI),
+            "GENERAL",
+            "PresetPath",
+            &preset,
+        )?;
+        info!("Using ReShade presets from {}", dir.display());
+    }
+    Ok(())
+}
+
+fn game_dir(game_path: &str) -> PathBuf {
+    Path::new(game_path).join("game")
+}
+
+fn detect(game_dir: &Path) -> Result<ReShadeStatus, String> {
+    if !game_dir.is_dir() {
+        return Err(format!("Game folder not found at {}", game_dir.display()));
+    }
+
+    let target = game_dir.join(INJECTION_DLL);
+    let dll_present = target.is_file();
+    let tool = if game_dir.join(GSHADE_INI).exists() {
+        Some(ShaderTool::GShade)
+    } else if game_dir.join(RESHADE_INI).exists() {
+        Some(ShaderTool::ReShade)
+    } else {
+        None
+    };
+
+    let state = load_state(game_dir);
+    let managed = match &state {
+        Some(state) if dll_present => {
+            sha1_file(&target.to_string_lossy()).is_ok_and(|sha1| sha1 == state.sha1)
+        }
+        _ => false,
+    };
+
+    let mut preset_folders: Vec<String> = fs::read_dir(game_dir.join(PRESETS_FOLDER))
+        .map(|entries| {
+            entries
+                .filter_map(|entry| entry.ok())
+                .filter(|entry| entry.path().is_dir())
+                .map(|entry| entry.file_name().to_string_lossy().to_string())
+                .collect()
+        })
+        .unwrap_or_default();
+    preset_folders.sort();
+
+    Ok(ReShadeStatus {
+        tool,
+        managed,
+        version: state.filter(|_| managed).map(|s| s.version),
+        dll_present,
+        preset_folders,
+    })
+}
+
+fn preset_dir(game_dir: &Path, name: &str) -> Result<PathBuf, String> {
+    let valid = !name.is_empty() && name != "." && name != ".." && !name.contains(['/', '\\', ':']);
+    if !valid {
+        return Err(format!("Invalid preset folder name: {}", name));
+    }
+    Ok(game_dir.join(PRESETS_FOLDER).join(name))
+}
+
+async fn latest_version(client: &reqwest::Client) -> Result<String, String> {
+    let page = client
+        .get(DOWNLOADS_PAGE)
+        .header(USER_AGENT, get_user_agent())
+        .timeout(REQUEST_TIMEOUT)
+        .send()
+        .await
+        .map_err(|e| format!("Failed to check for ReShade updates: {}", e))?
+        .text()
+        .await
+        .map_err(|e| format!("Failed to read ReShade download page: {}", e))?;
+
+    // The addon build is linked next to the normal one and isn't allowed with online games
+    let re = Regex::new(r"/downloads/ReShade_Setup_(\d+(?:\.\d+)+)\.exe").unwrap();
+    re.captures(&page)
+        .map(|caps| caps[1].to_string())
+        .ok_or_else(|| "No ReShade download found on reshade.me".to_string())
+}
+
+// The setup executable carries its DLLs in a zip archive appended to the program
+fn extract_dll(setup_path: &Path, target: &Path) -> Result<(), String> {
+    let file =
+        fs::File::open(setup_path).map_err(|e| format!("Failed to open ReShade setup: {}", e))?;
+    let mut archive =
+        zip::ZipArchive::new(file).map_err(|e| format!("Failed to read ReShade setup: {}", e))?;
+    let mut dll = archive
+        .by_name(RESHADE_DLL)
+        .map_err(|e| format!("No {} in ReShade setup: {}", RESHADE_DLL, e))?;
+
+    let mut contents = Vec::new();
+    dll.read_to_end(&mut contents)
+        .map_err(|e| format!("Failed to extract {}: {}", RESHADE_DLL, e))?;
+    fs::write(target, contents).map_err(|e| format!("Failed to save {}: {}", RESHADE_DLL, e))
+}
+
+// Sets one key in an ini file, keeping everything else as ReShade wrote it
+fn set_ini_value(path: &Path, section: &str, key: &str, value: &str) -> Result<(), String> {
+    let contents = fs::read_to_string(path).unwrap_or_default();
+    let header = format!("[{}]", section);
+    let entry = format!("{}={}", key, value);
+
+    let mut lines: Vec<String> = contents.lines().map(str::to_string).collect();
+    let section_start = lines.iter().position(|l| l.trim() == header);
+    match section_start {
+        Some(start) => {
+            let end = lines[start + 1..]
+                .iter()
+                .position(|l| l.trim_start().starts_with('['))
+                .map_or(lines.len(), |i| start + 1 + i);
+            let existing = lines[start + 1..end].iter().position(|l| {
+                l.split_once('=')
+                    .is_some_and(|(k, _)| k.trim().eq_ignore_ascii_case(key))
+            });
+            match existing {
+                Some(i) => lines[start + 1 + i] = entry,
+                None => lines.insert(start + 1, entry),
+            }
+        }
+        None => {
+            lines.push(header);
+            lines.push(entry);
+        }
+    }
+
+    let mut contents = lines.join("\n");
+    contents.push('\n');
+    fs::write(path, contents).map_err(|e| format!("Failed to update {}: {}", path.display(), e))
+}
+
+fn load_state(game_dir: &Path) -> Option<ManagerState> {
+    let contents = fs::read_to_string(game_dir.join(MANAGER_FOLDER).join(STATE_FILE)).ok()?;
+    match serde_json::from_str(&contents) {
+        Ok(state) => Some(state),
+        Err(e) => {
+            warn!("Ignoring unreadable ReShade state: {}", e);
+            None
+        }
+    }
+}
+
+fn save_state(game_dir: &Path, state: &ManagerState) -> Result<(), String> {
+    let contents = serde_json::to_string_pretty(state)
+        .map_err(|e| format!("Failed to serialize ReShade state: {}", e))?;
+    fs::write(game_dir.join(MANAGER_FOLDER).join(STATE_FILE), contents)
+        .map_err(|e| format!("Failed to save ReShade state: {}", e))
+}
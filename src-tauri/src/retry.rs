@@ -0,0 +1,130 @@
+use serde::{Deserialize, Serialize};
+use std::error::Error as StdError;
+use std::future::Future;
+use std::io::ErrorKind;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use tracing::warn;
+
+// How often a request that failed for a passing reason is tried again
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RetryPolicy {
+    // Total tries including the first, 1 turns retrying off
+    pub max_attempts: u32,
+    pub base_delay_ms: u64,
+    pub max_delay_ms: u64,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 4,
+            base_delay_ms: 500,
+            max_delay_ms: 8000,
+        }
+    }
+}
+
+impl RetryPolicy {
+    pub fn none() -> Self {
+        Self {
+            max_attempts: 1,
+            ..Self::default()
+        }
+    }
+
+    // Doubles with every attempt, then picks a point in the upper half so clients that failed
+    // together don't all come back at the same moment
+    pub(crate) fn delay(&self, attempt: u32) -> Duration {
+        let exponential = self
+            .base_delay_ms
+            .saturating_mul(1 << attempt.min(16))
+            .min(self.max_delay_ms);
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.subsec_nanos())
+            .unwrap_or(0);
+        let jitter = exponential / 2 * (nanos % 1000) as u64 / 1000;
+        Duration::from_millis(exponential / 2 + jitter)
+    }
+}
+
+// Runs the request again after server errors, timeouts and dropped connections.
+// Anything else, like a 4xx, is returned straight away
+pub async fn retry<T, F, Fut>(
+    policy: &RetryPolicy,
+    what: &str,
+    mut op: F,
+) -> Result<T, reqwest::Error>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, reqwest::Error>>,
+{
+    let mut attempt = 1;
+    loop {
+        match op().await {
+            Ok(value) => return Ok(value),
+            Err(e) if attempt < policy.max_attempts && is_transient(&e) => {
+                let delay = policy.delay(attempt - 1);
+                warn!(
+                    "{} failed (attempt {} of {}), retrying in {:?}: {}",
+                    what, attempt, policy.max_attempts, delay, e
+                );
+                tokio::time::sleep(delay).await;
+                attempt += 1;
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+// Sends the request built by the closure under the policy, also retrying 5xx responses.
+// 4xx responses come back as they are for the caller to handle
+pub async fn send<F>(
+    policy: &RetryPolicy,
+    what: &str,
+    mut request: F,
+) -> Result<reqwest::Response, reqwest::Error>
+where
+    F: FnMut() -> reqwest::RequestBuilder,
+{
+    retry(policy, what, || {
+        let pending = request().send();
+        async move { pending.await.and_then(server_errors) }
+    })
+    .await
+}
+
+fn server_errors(response: reqwest::Response) -> Result<reqwest::Response, reqwest::Error> {
+    if response.status().is_server_error() {
+        response.error_for_status()
+    } else {
+        Ok(response)
+    }
+}
+
+fn is_transient(error: &reqwest::Error) -> bool {
+    if error.is_timeout() || error.is_connect() {
+        return true;
+    }
+    if let Some(status) = error.status() {
+        return status.is_server_error();
+    }
+
+    // Resets surface as a plain request or body error with the io error further down
+    let mut source = error.source();
+    while let Some(inner) = source {
+        if let Some(io) = inner.downcast_ref::<std::io::Error>() {
+            return matches!(
+                io.kind(),
+                ErrorKind::ConnectionReset
+                    | ErrorKind::ConnectionAborted
+                    | ErrorKind::BrokenPipe
+                    | ErrorKind::UnexpectedEof
+            );
+        }
+        source = inner.source();
+    }
+    false
+}
@@ -0,0 +1,105 @@
+// Values stored through the store plugin end up as plain JSON on disk,
+// so secret fields go through DPAPI first and only the ciphertext is persisted
+const PROTECTED_PREFIX: &str = "dpapi:";
+// Keeps other applications running as the same user from decrypting our blobs by accident
+#[cfg(windows)]
+const ENTROPY: &[u8] = b"rust-xivloader-secrets";
+
+#[tauri::command]
+pub fn protect_secret(value: String) -> Result<String, String> {
+    let protected = dpapi_protect(value.as_bytes())?;
+    Ok(format!("{}{}", PROTECTED_PREFIX, base64::encode(protected)))
+}
+
+#[tauri::command]
+pub fn unprotect_secret(value: String) -> Result<String, String> {
+    let encoded = value
+        .strip_prefix(PROTECTED_PREFIX)
+        .ok_or_else(|| "Value is not a protected secret".to_string())?;
+    let protected =
+        base64::decode(encoded).map_err(|e| format!("Failed to decode protected secret: {}", e))?;
+
+    let plain = dpapi_unprotect(&protected)?;
+    String::from_utf8(plain).map_err(|e| format!("Protected secret is not valid UTF-8: {}", e))
+}
+
+#[cfg(windows)]
+fn dpapi_protect(data: &[u8]) -> Result<Vec<u8>, String> {
+    use windows::core::PCWSTR;
+    use windows::Win32::Security::Cryptography::{
+        CryptProtectData, CRYPTPROTECT_UI_FORBIDDEN, CRYPT_INTEGER_BLOB,
+    };
+
+    let input = blob(data);
+    let entropy = blob(ENTROPY);
+    let mut output = CRYPT_INTEGER_BLOB::default();
+
+    unsafe {
+        CryptProtectData(
+            &input,
+            PCWSTR::null(),
+            Some(&entropy),
+            None,
+            None,
+            CRYPTPROTECT_UI_FORBIDDEN,
+            &mut output,
+        )
+        .map_err(|e| format!("Failed to encrypt secret: {}", e))?;
+
+        Ok(take_blob(output))
+    }
+}
+
+#[cfg(windows)]
+fn dpapi_unprotect(data: &[u8]) -> Result<Vec<u8>, String> {
+    use windows::Win32::Security::Cryptography::{
+        CryptUnprotectData, CRYPTPROTECT_UI_FORBIDDEN, CRYPT_INTEGER_BLOB,
+    };
+
+    let input = blob(data);
+    let entropy = blob(ENTROPY);
+    let mut output = CRYPT_INTEGER_BLOB::default();
+
+    unsafe {
+        CryptUnprotectData(
+            &input,
+            None,
+            Some(&entropy),
+            None,
+            None,
+            CRYPTPROTECT_UI_FORBIDDEN,
+            &mut output,
+        )
+        .map_err(|e| format!("Failed to decrypt secret: {}", e))?;
+
+        Ok(take_blob(output))
+    }
+}
+
+#[cfg(windows)]
+fn blob(data: &[u8]) -> windows::Win32::Security::Cryptography::CRYPT_INTEGER_BLOB {
+    windows::Win32::Security::Cryptography::CRYPT_INTEGER_BLOB {
+        cbData: data.len() as u32,
+        pbData: data.as_ptr() as *mut u8,
+    }
+}
+
+// Copies the DPAPI output and releases the buffer Windows allocated for it
+#[cfg(windows)]
+unsafe fn take_blob(blob: windows::Win32::Security::Cryptography::CRYPT_INTEGER_BLOB) -> Vec<u8> {
+    use windows::Win32::Foundation::{LocalFree, HLOCAL};
+
+    let data = std::slice::from_raw_parts(blob.pbData, blob.cbData as usize).to_vec();
+    LocalFree(HLOCAL(blob.pbData as *mut _));
+    data
+}
+
+#[cfg(not(windows))]
+fn dpapi_protect(_data: &[u8]) -> Result<Vec<u8>, String> {
+    Err("Secret encryption uses DPAPI and is only available on Windows".to_string())
+}
+
+#[cfg(not(windows))]
+fn dpapi_unprotect(_data: &[u8]) -> Result<Vec<u8>, String> {
+    Err("Secret encryption uses DPAPI and is only available on Windows".to_string())
+}
@@ -0,0 +1,185 @@
+use reqwest::header::{ACCEPT, USER_AGENT};
+use semver::Version;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, State, Url};
+use tauri_plugin_updater::UpdaterExt;
+
+use tracing::{info, warn};
+
+use crate::http::{HttpClient, REQUEST_TIMEOUT};
+
+const RELEASES_URL: &str = "https://api.github.com/repos/pieckenst/rust-xivloader/releases";
+// Written by the release workflow next to the installers, describes the signed bundles
+const UPDATE_MANIFEST: &str = "latest.json";
+// Release builds are made with the key, the updater refuses bundles not signed by it
+const UPDATER_PUBKEY: Option<&str> = option_env!("XIVLOADER_UPDATER_PUBKEY");
+pub const LAUNCHER_UPDATE_PROGRESS_EVENT: &str = "launcher-update://progress";
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ReleaseChannel {
+    #[default]
+    Stable,
+    // Pre-releases as well as stable releases, whichever is newer
+    Beta,
+}
+
+#[derive(Debug, Deserialize)]
+struct GithubRelease {
+    tag_name: String,
+    #[serde(default)]
+    body: Option<String>,
+    html_url: String,
+    draft: bool,
+    prerelease: bool,
+    #[serde(default)]
+    published_at: Option<String>,
+    #[serde(default)]
+    assets: Vec<GithubAsset>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GithubAsset {
+    name: String,
+    browser_download_url: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct LauncherUpdate {
+    pub current_version: String,
+    pub version: String,
+    pub channel: ReleaseChannel,
+    pub notes: String,
+    pub published_at: Option<String>,
+    pub release_url: String,
+    // False when the release has no signed bundles or this build can't verify them,
+    // the frontend links to the release page instead
+    pub installable: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct UpdateProgressEvent {
+    downloaded: u64,
+    total: Option<u64>,
+}
+
+// Tags are v1.2.3 or 1.2.3-beta.1
+fn release_version(release: &GithubRelease) -> Option<Version> {
+    Version::parse(release.tag_name.trim_start_matches('v')).ok()
+}
+
+async fn newest_release(
+    client: &reqwest::Client,
+    channel: ReleaseChannel,
+) -> Result<Option<(Version, GithubRelease)>, String> {
+    let releases: Vec<GithubRelease> = client
+        .get(RELEASES_URL)
+        // GitHub rejects API requests without a user agent
+        .header(USER_AGENT, "rust-xivloader")
+        .header(ACCEPT, "application/vnd.github+json")
+        .timeout(REQUEST_TIMEOUT)
+        .send()
+        .await
+        .map_err(|e| format!("Failed to fetch launcher releases: {}", e))?
+        .error_for_status()
+        .map_err(|e| format!("Failed to fetch launcher releases: {}", e))?
+        .json()
+        .await
+        .map_err(|e| format!("Failed to parse launcher releases: {}", e))?;
+
+    Ok(releases
+        .into_iter()
+        .filter(|release| !release.draft)
+        .filter(|release| channel == ReleaseChannel::Beta || !release.prerelease)
+        .filter_map(|release| release_version(&release).map(|version| (version, release)))
+        .max_by(|(a, _), (b, _)| a.cmp(b)))
+}
+
+fn manifest_url(release: &GithubRelease) -> Option<&str> {
+    release
+        .assets
+        .iter()
+        .find(|asset| asset.name == UPDATE_MANIFEST)
+        .map(|asset| asset.browser_download_url.as_str())
+}
+
+#[tauri::command]
+pub async fn check_launcher_update(
+    app: AppHandle,
+    http: State<'_, HttpClient>,
+    channel: ReleaseChannel,
+) -> Result<Option<LauncherUpdate>, String> {
+    let current = app.package_info().version.clone();
+    let Some((version, release)) = newest_release(&http.client(), channel).await? else {
+        info!("No {:?} launcher releases published", channel);
+        return Ok(None);
+    };
+    if version <= current {
+        info!("Launcher {} is up to date on {:?}", current, channel);
+        return Ok(None);
+    }
+
+    info!("Launcher update {} available on {:?}", version, channel);
+    let installable = UPDATER_PUBKEY.is_some() && manifest_url(&release).is_some();
+    Ok(Some(LauncherUpdate {
+        current_version: current.to_string(),
+        version: version.to_string(),
+        channel,
+        notes: release.body.clone().unwrap_or_default(),
+        published_at: release.published_at.clone(),
+        release_url: release.html_url.clone(),
+        installable,
+    }))
+}
+
+// Called once the user has confirmed the update, restarts into the new version
+#[tauri::command]
+pub async fn install_launcher_update(
+    app: AppHandle,
+    http: State<'_, HttpClient>,
+    channel: ReleaseChannel,
+) -> Result<(), String> {
+    let pubkey = UPDATER_PUBKEY.ok_or_else(|| {
+        "This build has no update signing key, download the new version from the release page"
+            .to_string()
+    })?;
+    let (version, release) = newest_release(&http.client(), channel)
+        .await?
+        .ok_or_else(|| "No launcher release found".to_string())?;
+    let manifest = manifest_url(&release)
+        .ok_or_else(|| format!("Release {} has no signed update bundles", version))?;
+    let endpoint =
+        Url::parse(manifest).map_err(|e| format!("Invalid update manifest URL: {}", e))?;
+
+    let update = app
+        .updater_builder()
+        .pubkey(pubkey)
+        .endpoints(vec![endpoint])
+        .map_err(|e| format!("Failed to set up updater: {}", e))?
+        .build()
+        .map_err(|e| format!("Failed to set up updater: {}", e))?
+        .check()
+        .await
+        .map_err(|e| format!("Failed to check launcher update: {}", e))?
+        .ok_or_else(|| format!("Release {} has no bundle for this platform", version))?;
+
+    info!("Installing launcher {}", update.version);
+    let progress_app = app.clone();
+    let mut downloaded = 0u64;
+    update
+        .download_and_install(
+            |chunk, total| {
+                downloaded += chunk as u64;
+                let event = UpdateProgressEvent { downloaded, total };
+                if let Err(e) = progress_app.emit(LAUNCHER_UPDATE_PROGRESS_EVENT, event) {
+                    warn!("Failed to emit launcher update progress: {}", e);
+                }
+            },
+            || info!("Launcher update downloaded"),
+        )
+        .await
+        .map_err(|e| format!("Failed to install launcher update: {}", e))?;
+
+    info!("Launcher update installed, restarting");
+    app.restart();
+}
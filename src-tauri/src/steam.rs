@@ -0,0 +1,124 @@
+#[cfg(feature = "steam")]
+use std::time::{SystemTime, UNIX_EPOCH};
+
+#[cfg(feature = "steam")]
+use crate::encryption::{blowfish_encrypt, to_mangled_se_base64};
+
+#[cfg(feature = "steam")]
+const FFXIV_STEAM_APP_ID: u32 = 39210;
+
+#[cfg(feature = "steam")]
+const TICKET_GARBAGE_TABLE: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+// SE's oauth page rejects ticket strings with parts longer than this
+#[cfg(feature = "steam")]
+const TICKET_SPLIT_SIZE: usize = 300;
+
+#[derive(Debug, Clone)]
+pub struct SteamTicket {
+    pub text: String,
+    // Length without the comma separators, as the oauth endpoint expects it
+    pub length: usize,
+}
+
+#[cfg(feature = "steam")]
+pub fn get_encrypted_ticket() -> Result<SteamTicket, String> {
+    use std::sync::OnceLock;
+
+    // Steam has to stay initialized for the ticket to remain valid through login
+    static CLIENT: OnceLock<steamworks::Client> = OnceLock::new();
+
+    let client = match CLIENT.get() {
+        Some(client) => client,
+        None => {
+            let (client, _single) = steamworks::Client::init_app(FFXIV_STEAM_APP_ID)
+                .map_err(|e| format!("Failed to initialize Steam, is Steam running? {}", e))?;
+            CLIENT.get_or_init(|| client)
+        }
+    };
+
+    let (_auth_ticket, ticket) = client.user().authentication_session_ticket();
+    tracing::info!(
+        "Obtained Steam auth session ticket ({} bytes)",
+        ticket.len()
+    );
+
+    Ok(encrypt_auth_session_ticket(&ticket, server_time()))
+}
+
+#[cfg(not(feature = "steam"))]
+pub fn get_encrypted_ticket() -> Result<SteamTicket, String> {
+    Err("This build of xivloader was compiled without Steam support".to_string())
+}
+
+#[cfg(feature = "steam")]
+fn server_time() -> u32 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs() as u32)
+        .unwrap_or(0)
+}
+
+// Port of the ticket encryption the official launcher applies before sending it to oauth
+#[cfg(feature = "steam")]
+pub fn encrypt_auth_session_ticket(ticket: &[u8], time: u32) -> SteamTicket {
+    // Key time is rounded down to the minute, with a few seconds of slack
+    let time = time.wrapping_sub(5);
+    let time = time - time % 60;
+
+    let mut raw_ticket = hex::encode(ticket).into_bytes();
+    raw_ticket.push(0);
+
+    let ticket_sum = raw_ticket
+        .iter()
+        .fold(0u16, |sum, b| sum.wrapping_add(*b as u16));
+
+    let mut buffer = Vec::with_capacity(raw_ticket.len() + 16);
+    buffer.extend_from_slice(&ticket_sum.to_le_bytes());
+    buffer.extend_from_slice(&raw_ticket);
+
+    // Pad with pseudo random characters up to the blowfish block size
+    let garbage_len = ((raw_ticket.len() + 9) & !7) - 2 - raw_ticket.len();
+    let mut rand = CrtRand::new(time ^ (ticket_sum as i16 as i32 as u32));
+    let mut checksum = u32::from_le_bytes([buffer[0], buffer[1], buffer[2], buffer[3]]);
+
+    for _ in 0..garbage_len {
+        let c = TICKET_GARBAGE_TABLE[(checksum.wrapping_add(rand.next()) & 0x3F) as usize];
+        buffer.push(c);
+        checksum = checksum.wrapping_add(c as u32);
+    }
+
+    buffer[0..4].copy_from_slice(&checksum.to_le_bytes());
+    buffer.swap(0, 1);
+
+    let key = format!("{:08x}#un@e=x>", time);
+    let encrypted = to_mangled_se_base64(&blowfish_encrypt(key.as_bytes(), &buffer));
+
+    let parts: Vec<&str> = encrypted
+        .as_bytes()
+        .chunks(TICKET_SPLIT_SIZE)
+        .map(|part| std::str::from_utf8(part).unwrap_or_default())
+        .collect();
+    let text = parts.join(",");
+    let length = text.len() - (parts.len() - 1);
+
+    SteamTicket { text, length }
+}
+
+// MSVC rand(), which the official launcher seeds to generate the padding
+#[cfg(feature = "steam")]
+struct CrtRand {
+    seed: u32,
+}
+
+#[cfg(feature = "steam")]
+impl CrtRand {
+    fn new(seed: u32) -> Self {
+        Self { seed }
+    }
+
+    fn next(&mut self) -> u32 {
+        self.seed = self.seed.wrapping_mul(0x343FD).wrapping_add(0x269EC3);
+        (self.seed >> 16) & 0x7FFF
+    }
+}
@@ -0,0 +1,96 @@
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+
+use tracing::{debug, info};
+
+use crate::error::LauncherError;
+use crate::ffxiv::{GameLaunchMetrics, LaunchResult};
+use crate::http::REQUEST_TIMEOUT;
+use crate::launch_history::{LaunchOutcome, StepDuration};
+use crate::launch_pipeline::LaunchStep;
+
+// Off unless the user turns it on and points it at a collector, there is no default one
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TelemetryConfig {
+    pub enabled: bool,
+    pub endpoint: String,
+}
+
+impl TelemetryConfig {
+    fn endpoint(&self) -> Option<&str> {
+        let endpoint = self.endpoint.trim();
+        (self.enabled && !endpoint.is_empty()).then_some(endpoint)
+    }
+}
+
+// Nothing that identifies the user or the machine: no account, ids, paths or error text
+#[derive(Debug, Serialize)]
+pub struct LaunchReport {
+    pub launcher_version: &'static str,
+    pub os: &'static str,
+    pub game_version: Option<String>,
+    pub outcome: LaunchOutcome,
+    // Only the kind of error, the messages can contain names and paths
+    pub error_code: Option<&'static str>,
+    pub failed_step: Option<LaunchStep>,
+    pub used_dalamud: bool,
+    pub dalamud_version: Option<String>,
+    pub native_injection: bool,
+    pub steps: Vec<StepDuration>,
+    pub metrics: GameLaunchMetrics,
+}
+
+impl LaunchReport {
+    pub fn new(
+        result: &Result<LaunchResult, LauncherError>,
+        step: LaunchStep,
+        steps: Vec<StepDuration>,
+        metrics: GameLaunchMetrics,
+        game_version: Option<String>,
+        native_injection: bool,
+    ) -> Self {
+        let (outcome, error_code, failed_step) = match result {
+            Ok(_) => (LaunchOutcome::Success, None, None),
+            Err(LauncherError::Cancelled) => (LaunchOutcome::Cancelled, None, Some(step)),
+            Err(e) => (LaunchOutcome::Failed, Some(e.code()), Some(step)),
+        };
+        let launch = result.as_ref().ok();
+
+        Self {
+            launcher_version: env!("CARGO_PKG_VERSION"),
+            os: std::env::consts::OS,
+            game_version,
+            outcome,
+            error_code,
+            failed_step,
+            used_dalamud: launch.is_some_and(|launch| launch.used_dalamud),
+            dalamud_version: launch.and_then(|launch| launch.dalamud_version.clone()),
+            native_injection,
+            steps,
+            metrics,
+        }
+    }
+}
+
+// In the background, the launch never waits on the collector and a failed report is dropped
+pub fn send(client: &Client, config: &TelemetryConfig, report: LaunchReport) {
+    let Some(endpoint) = config.endpoint() else {
+        return;
+    };
+    info!(
+        "Sending anonymous launch report ({:?}) to {}",
+        report.outcome, endpoint
+    );
+    let request = client.post(endpoint).timeout(REQUEST_TIMEOUT).json(&report);
+    tauri::async_runtime::spawn(async move {
+        match request
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+        {
+            Ok(_) => debug!("Launch report sent"),
+            Err(e) => debug!("Failed to send launch report: {}", e),
+        }
+    });
+}
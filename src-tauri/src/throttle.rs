@@ -0,0 +1,60 @@
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use tracing::info;
+
+// One bucket for every download in the app, so the cap holds for all of them together
+static BUCKET: Mutex<Option<TokenBucket>> = Mutex::new(None);
+
+// Refills at the limit and holds at most a second of it. Downloads take what they read
+// even when that runs the bucket into debt, and wait until the debt is paid back
+struct TokenBucket {
+    bytes_per_second: u64,
+    tokens: f64,
+    refilled: Instant,
+}
+
+impl TokenBucket {
+    fn take(&mut self, bytes: u64) -> Duration {
+        let rate = self.bytes_per_second as f64;
+        let now = Instant::now();
+        let refill = now.duration_since(self.refilled).as_secs_f64() * rate;
+        self.tokens = (self.tokens + refill).min(rate) - bytes as f64;
+        self.refilled = now;
+        if self.tokens >= 0.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64(-self.tokens / rate)
+        }
+    }
+}
+
+// KiB per second across all downloads, 0 lifts the limit
+pub fn set_limit(kib_per_second: u64) {
+    let mut bucket = BUCKET.lock().unwrap();
+    let bytes_per_second = kib_per_second.saturating_mul(1024);
+    if bucket.as_ref().map_or(0, |b| b.bytes_per_second) == bytes_per_second {
+        return;
+    }
+    *bucket = (bytes_per_second > 0).then(|| TokenBucket {
+        bytes_per_second,
+        tokens: bytes_per_second as f64,
+        refilled: Instant::now(),
+    });
+    if bytes_per_second > 0 {
+        info!("Limiting downloads to {} KiB/s", kib_per_second);
+    } else {
+        info!("Download speed is no longer limited");
+    }
+}
+
+// Called with each chunk a download read, sleeps off whatever went over the limit
+pub async fn consume(bytes: u64) {
+    let wait = match BUCKET.lock().unwrap().as_mut() {
+        Some(bucket) => bucket.take(bytes),
+        None => return,
+    };
+    if !wait.is_zero() {
+        tokio::time::sleep(wait).await;
+    }
+}
@@ -0,0 +1,301 @@
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::fs;
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Manager};
+use zip::write::FileOptions;
+use zip::ZipWriter;
+
+use tracing::{info, warn};
+
+use crate::error::LauncherError;
+use crate::ffxiv::{GameLaunchMetrics, LaunchConfig, LaunchResult};
+use crate::patching::version_check;
+use crate::redact;
+
+const MAX_RECORDED_LAUNCHES: usize = 10;
+// Only the end of a log matters for a bug report, and the pack has to fit in an attachment
+const MAX_LOG_BYTES: u64 = 2 * 1024 * 1024;
+const REDACTED_ACCOUNT: &str = "<account>";
+
+static RECENT_LAUNCHES: Mutex<VecDeque<LaunchRecord>> = Mutex::new(VecDeque::new());
+
+#[derive(Debug, Clone, Serialize)]
+struct LaunchRecord {
+    // Unix timestamp in seconds
+    at: u64,
+    error: Option<String>,
+    used_dalamud: bool,
+    dalamud_version: Option<String>,
+    metrics: GameLaunchMetrics,
+    warnings: Vec<String>,
+}
+
+// Summary of the setup, also handed to Dalamud for its crash reports
+#[derive(Debug, Serialize)]
+pub struct TroubleshootingInfo {
+    pub when: u64,
+    pub launcher_version: &'static str,
+    pub os: &'static str,
+    pub arch: &'static str,
+    pub boot_version: String,
+    pub game_version: String,
+    pub expansion_versions: Vec<String>,
+    pub dx11: bool,
+    pub dalamud_enabled: bool,
+    pub dalamud_installed: Vec<String>,
+    pub encrypt_arguments: bool,
+    pub is_steam: bool,
+    pub language: u32,
+    pub region: u32,
+}
+
+impl TroubleshootingInfo {
+    pub fn collect(config: &LaunchConfig) -> Self {
+        let versions =
+            version_check::read_installed_versions(&config.game_path, config.expansion_level());
+
+        let hooks = Path::new(&config.dalamud_path).join("addon/Hooks");
+        let mut dalamud_installed: Vec<String> = fs::read_dir(hooks)
+            .map(|entries| {
+                entries
+                    .filter_map(|entry| entry.ok())
+                    .filter(|entry| entry.path().is_dir())
+                    .map(|entry| entry.file_name().to_string_lossy().to_string())
+                    .collect()
+            })
+            .unwrap_or_default();
+        dalamud_installed.sort();
+
+        Self {
+            when: unix_now(),
+            launcher_version: env!("CARGO_PKG_VERSION"),
+            os: std::env::consts::OS,
+            arch: std::env::consts::ARCH,
+            boot_version: versions.boot,
+            game_version: versions.game,
+            expansion_versions: versions
+                .expansions
+                .into_iter()
+                .map(|ex| format!("ex{} {}", ex.expansion, ex.version))
+                .collect(),
+            dx11: config.dx11,
+            dalamud_enabled: config.enable_dalamud,
+            dalamud_installed,
+            encrypt_arguments: config.encrypt_arguments,
+            is_steam: config.is_steam,
+            language: config.language,
+            region: config.region(),
+        }
+    }
+}
+
+pub fn record_launch(result: &Result<LaunchResult, LauncherError>) {
+    let record = match result {
+        Ok(result) => LaunchRecord {
+            at: unix_now(),
+            error: None,
+            used_dalamud: result.used_dalamud,
+            dalamud_version: result.dalamud_version.clone(),
+            metrics: result.metrics.clone(),
+            warnings: result.warnings.clone(),
+        },
+        Err(e) => LaunchRecord {
+            at: unix_now(),
+            error: Some(format!("{}: {}", e.code(), redact::scrub(&e.to_string()))),
+            used_dalamud: false,
+            dalamud_version: None,
+            metrics: GameLaunchMetrics::default(),
+            warnings: Vec::new(),
+        },
+    };
+
+    let mut launches = RECENT_LAUNCHES.lock().unwrap();
+    if launches.len() == MAX_RECORDED_LAUNCHES {
+        launches.pop_front();
+    }
+    launches.push_back(record);
+}
+
+// Collects logs, the redacted config, versions and recent launches into one zip for bug reports
+#[tauri::command]
+pub fn export_tspack(
+    app: AppHandle,
+    config: LaunchConfig,
+    output_path: String,
+) -> Result<String, String> {
+    let file = fs::File::create(&output_path)
+        .map_err(|e| format!("Failed to create {}: {}", output_path, e))?;
+    let mut zip = ZipWriter::new(file);
+
+    let info = serde_json::to_string_pretty(&TroubleshootingInfo::collect(&config))
+        .map_err(|e| format!("Failed to serialize troubleshooting info: {}", e))?;
+    add_file(&mut zip, "troubleshooting.json", info.as_bytes())?;
+
+    // Secret fields already print as <redacted>, scrub catches whatever else looks like one
+    let mut dumped = config.clone();
+    if !dumped.username.is_empty() {
+        dumped.username = REDACTED_ACCOUNT.to_string();
+    }
+    let config_dump = format!("{:#?}", dumped);
+    add_file(
+        &mut zip,
+        "config.txt",
+        redact::scrub(&config_dump).as_bytes(),
+    )?;
+
+    let launches: Vec<LaunchRecord> = RECENT_LAUNCHES.lock().unwrap().iter().cloned().collect();
+    let launches = serde_json::to_string_pretty(&launches)
+        .map_err(|e| format!("Failed to serialize launch history: {}", e))?;
+    add_file(&mut zip, "launches.json", launches.as_bytes())?;
+
+    match app.path().app_log_dir() {
+        Ok(dir) => add_logs(&mut zip, &dir, "logs/launcher", &config.username)?,
+        Err(e) => warn!("No launcher log directory: {}", e),
+    }
+    add_logs(
+        &mut zip,
+        &Path::new(&config.dalamud_path).join("logs"),
+        "logs/dalamud",
+        &config.username,
+    )?;
+
+    zip.finish()
+        .map_err(|e| format!("Failed to write troubleshooting pack: {}", e))?;
+    info!("Exported troubleshooting pack to {}", output_path);
+    Ok(output_path)
+}
+
+// The launcher logs the account name when it saves or reads credentials, that goes too
+fn add_logs(
+    zip: &mut ZipWriter<fs::File>,
+    dir: &Path,
+    prefix: &str,
+    username: &str,
+) -> Result<(), String> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        warn!("No logs found in {}", dir.display());
+        return Ok(());
+    };
+
+    for entry in entries.filter_map(|entry| entry.ok()) {
+        let path = entry.path();
+        if path.extension().is_none_or(|ext| ext != "log") {
+            continue;
+        }
+        let contents = match read_tail(&path) {
+            Ok(contents) => contents,
+            Err(e) => {
+                warn!("Failed to read {}: {}", path.display(), e);
+                continue;
+            }
+        };
+        let name = format!("{}/{}", prefix, entry.file_name().to_string_lossy());
+        let mut contents = redact::scrub(&contents).into_owned();
+        if !username.is_empty() {
+            contents = contents.replace(username, REDACTED_ACCOUNT);
+        }
+        add_file(zip, &name, contents.as_bytes())?;
+    }
+    Ok(())
+}
+
+fn read_tail(path: &Path) -> std::io::Result<String> {
+    let mut file = fs::File::open(path)?;
+    let len = file.metadata()?.len();
+    if len > MAX_LOG_BYTES {
+        file.seek(SeekFrom::Start(len - MAX_LOG_BYTES))?;
+    }
+    let mut contents = Vec::new();
+    file.read_to_end(&mut contents)?;
+    Ok(String::from_utf8_lossy(&contents).into_owned())
+}
+
+fn add_file(zip: &mut ZipWriter<fs::File>, name: &str, contents: &[u8]) -> Result<(), String> {
+    zip.start_file(name, FileOptions::default())
+        .and_then(|_| zip.write_all(contents).map_err(Into::into))
+        .map_err(|e| format!("Failed to add {} to troubleshooting pack: {}", name, e))
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs::File;
+    use tauri_plugin_log::fern;
+    use zip::ZipArchive;
+
+    // Writes a line the way the log plugin does for a backend tracing event
+    fn write_backend_log(path: &Path, message: &str) {
+        let (_, logger) = fern::Dispatch::new()
+            .format(redact::format_log)
+            .chain(fern::log_file(path).unwrap())
+            .into_log();
+        logger.log(
+            &log::Record::builder()
+                .level(log::Level::Info)
+                .target("rust_xivloader_lib::ffxiv")
+                .args(format_args!("{}", message))
+                .build(),
+        );
+        logger.flush();
+    }
+
+    fn read_pack(path: &Path, name: &str) -> String {
+        let mut archive = ZipArchive::new(File::open(path).unwrap()).unwrap();
+        let mut contents = String::new();
+        archive
+            .by_name(name)
+            .unwrap()
+            .read_to_string(&mut contents)
+            .unwrap();
+        contents
+    }
+
+    #[test]
+    fn pack_contains_backend_log_lines() {
+        let dir = tempfile::tempdir().unwrap();
+        let logs = dir.path().join("logs");
+        fs::create_dir(&logs).unwrap();
+        write_backend_log(
+            &logs.join("rust-xivloader.log"),
+            "Saving credentials for someone DEV.TestSID=0123456789abcdef0123456789abcdef",
+        );
+
+        let pack = dir.path().join("pack.zip");
+        let mut zip = ZipWriter::new(File::create(&pack).unwrap());
+        add_logs(&mut zip, &logs, "logs/launcher", "someone").unwrap();
+        zip.finish().unwrap();
+
+        let contents = read_pack(&pack, "logs/launcher/rust-xivloader.log");
+        assert!(
+            contents.contains("[INFO][rust_xivloader_lib::ffxiv] Saving credentials for <account>"),
+            "{}",
+            contents
+        );
+        assert!(contents.contains("DEV.TestSID=<redacted>"), "{}", contents);
+        assert!(!contents.contains("someone"), "{}", contents);
+    }
+
+    #[test]
+    fn missing_log_directory_is_skipped() {
+        let dir = tempfile::tempdir().unwrap();
+        let pack = dir.path().join("pack.zip");
+        let mut zip = ZipWriter::new(File::create(&pack).unwrap());
+        add_logs(&mut zip, &dir.path().join("missing"), "logs/dalamud", "").unwrap();
+        zip.finish().unwrap();
+        assert_eq!(
+            ZipArchive::new(File::open(&pack).unwrap()).unwrap().len(),
+            0
+        );
+    }
+}
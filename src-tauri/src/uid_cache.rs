@@ -0,0 +1,89 @@
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+use tracing::{debug, info};
+
+use crate::redact::Secret;
+
+// Square Enix doesn't say how long an id stays usable, keep well inside a play session
+const VALIDITY: Duration = Duration::from_secs(3 * 60 * 60);
+
+// Kept in memory only, a session id on disk would let anyone reading the file log in as the account
+static CACHE: OnceLock<Mutex<HashMap<String, CachedUid>>> = OnceLock::new();
+
+struct CachedUid {
+    unique_id: Secret,
+    // Patching invalidates the id, the server issues a new one for the new build
+    game_version: String,
+    expansion_level: u32,
+    obtained: Instant,
+}
+
+fn cache() -> &'static Mutex<HashMap<String, CachedUid>> {
+    CACHE.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+fn key(username: &str, is_steam: bool) -> String {
+    format!("{}:{}", username.to_lowercase(), is_steam)
+}
+
+pub fn get(
+    username: &str,
+    is_steam: bool,
+    game_version: &str,
+    expansion_level: u32,
+) -> Option<String> {
+    let mut cache = cache().lock().unwrap();
+    let key = key(username, is_steam);
+    let entry = cache.get(&key)?;
+
+    if entry.obtained.elapsed() > VALIDITY {
+        debug!("Cached unique id for {} expired", username);
+    } else if entry.game_version != game_version || entry.expansion_level != expansion_level {
+        debug!(
+            "Cached unique id for {} was issued for another build",
+            username
+        );
+    } else {
+        return Some(entry.unique_id.expose().to_string());
+    }
+    cache.remove(&key);
+    None
+}
+
+pub fn store(
+    username: &str,
+    is_steam: bool,
+    unique_id: &str,
+    game_version: &str,
+    expansion_level: u32,
+) {
+    cache().lock().unwrap().insert(
+        key(username, is_steam),
+        CachedUid {
+            unique_id: Secret::from(unique_id.to_string()),
+            game_version: game_version.to_string(),
+            expansion_level,
+            obtained: Instant::now(),
+        },
+    );
+}
+
+// Drops an id the game turned out not to accept
+pub fn forget(username: &str, is_steam: bool) {
+    if cache()
+        .lock()
+        .unwrap()
+        .remove(&key(username, is_steam))
+        .is_some()
+    {
+        debug!("Dropped cached unique id for {}", username);
+    }
+}
+
+#[tauri::command]
+pub fn clear_uid_cache() {
+    cache().lock().unwrap().clear();
+    info!("Cleared cached unique ids");
+}
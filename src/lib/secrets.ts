@@ -0,0 +1,13 @@
+import { invoke } from '@tauri-apps/api/core';
+
+// Secret fields are run through DPAPI on the backend before they go into a store,
+// unset fields stay empty without a round trip
+export async function protectSecret(value: string): Promise<string> {
+  if (!value) return '';
+  return invoke<string>('protect_secret', { value });
+}
+
+export async function unprotectSecret(value: string): Promise<string> {
+  if (!value) return '';
+  return invoke<string>('unprotect_secret', { value });
+}
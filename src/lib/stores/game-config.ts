@@ -3,34 +3,140 @@ import { join } from '@tauri-apps/api/path';
 import { appDataDir, homeDir } from '@tauri-apps/api/path';
 
 export interface GameConfig {
+  // The password is kept in the OS credential store under this username
   username: string;
-  password: string;
   otp: string;
+  // Relaunches reuse the last login for a few hours instead of asking Square Enix again
+  uidCache: boolean;
   gamePath: string;
+  // Saved game install to launch, its path and settings win over the ones here
+  install: string | null;
+  // 'benchmark' runs the official benchmark from benchmarkPath instead of logging in
+  profileType: 'game' | 'benchmark';
+  benchmarkPath: string;
+  benchmarkScreenMode: 'windowed' | 'fullscreen' | 'borderless';
+  benchmarkScreenWidth: number;
+  benchmarkScreenHeight: number;
   isSteam: boolean;
   language: number;
   dx11: boolean;
-  expansionLevel: number;
-  region: number;
+  // null detects the expansion from the install and sends region 3
+  expansionLevel: number | null;
+  region: number | null;
   
   // Game settings
   isFreeTrial: boolean;
-  dpiAwareness: 'Aware' | 'Unaware';
+  dpiAwareness: 'Aware' | 'Unaware' | 'PerMonitorV2';
+  // Key=Value pairs, values can use {game_path}, {language} and {sid_present}
   additionalLaunchArgs: string;
   encryptArguments: boolean;
   savedLogin: boolean;
   autoLogin: boolean;
+  launcherBehavior: 'keep' | 'hide' | 'close';
+  relaunchOnCrash: boolean;
+  crashRelaunchWindowMinutes: number;
+  // Zip the FFXIV_CHR folders before every launch
+  backupCharacterData: boolean;
+  // Empty uses Documents/My Games/FINAL FANTASY XIV - A Realm Reborn
+  gameUserDir: string;
+  // Anonymous launch reports, nothing is sent without an endpoint
+  telemetryEnabled: boolean;
+  telemetryEndpoint: string;
+  // Base URL serving the patch server's paths, e.g. a LAN cache. Empty downloads from Square Enix
+  patchMirror: string;
   directXVersion: '11' | '9';
   clientLanguage: 'Japanese' | 'English' | 'German' | 'French';
+  // Frontier locale for news and banners (e.g. 'ja-jp'), empty to follow the game language
+  newsLocale: string;
 
   // Dalamud settings
   dalamudEnabled: boolean;
   dalamudPath: string;
   dalamudInjectDelay: number;
+  // Rewrite the game's entrypoint from the launcher instead of running Dalamud.Injector.exe
+  dalamudNativeInjection: boolean;
+  // Skip the Dalamud update check while the last one is younger than the TTL
+  dalamudFastLaunch: boolean;
+  dalamudFastLaunchTtlMinutes: number;
+  // When Dalamud isn't updated for the installed game version yet
+  dalamudGameVersionPolicy: 'vanilla' | 'warn' | 'block';
+  // Load Dalamud without plugins, to recover from a plugin crashing the game
+  dalamudSafeMode: boolean;
+  // When injecting Dalamud fails, abort or start the game without it
+  dalamudInjectionFailurePolicy: 'abort' | 'vanilla';
   dalamudConfigPath: string;
   dalamudPluginPath: string;
   dalamudDevPluginPath: string;
   dalamudAssetPath: string;
+  // Leave empty to use kamori.goats.dev
+  dalamudVersionInfoUrl: string;
+  dalamudAssetMetaUrl: string;
+  dalamudDownloadBase: string;
+  // Given to Penumbra when it starts for the first time
+  penumbraModDirectory: string;
+
+  // Backoff for requests that fail with a server error or a dropped connection, 1 attempt disables it
+  retryMaxAttempts: number;
+  retryBaseDelayMs: number;
+  retryMaxDelayMs: number;
+
+  // http://, https:// or socks5:// proxy for every request, empty connects directly
+  proxyUrl: string;
+  proxyUsername: string;
+  // Protected with protectSecret, only unprotected when it is handed to the backend
+  proxyPassword: string;
+  // Hosts or domains that skip the proxy
+  proxyBypass: string[];
+  // PEM or DER files with extra root certificates, for intercepting proxies and local mirrors
+  caCertificates: string[];
+  // KiB/s shared by patch, Dalamud and asset downloads, 0 for no limit
+  downloadLimitKib: number;
+
+  // Leave empty for the retail login and frontier servers
+  oauthUrl: string;
+  oauthReferer: string;
+  frontierUrl: string;
+
+  // Linux only, how the game is started through Wine or Proton
+  wineRunner: 'wine' | 'proton';
+  wineRunnerPath: string;
+  winePrefix: string;
+  wineDxvk: boolean;
+  // DXVK release kept in the prefix, empty leaves the prefix as it is
+  wineDxvkVersion: string;
+  wineEsync: boolean;
+  wineFsync: boolean;
+  // null picks these automatically, on for the Steam Deck
+  gamescope: boolean | null;
+  gamescopeWidth: number;
+  gamescopeHeight: number;
+  gamescopeFsr: boolean;
+  gamescopeFrameLimit: number | null;
+  gamescopeFullscreen: boolean;
+  gamemode: boolean | null;
+
+  // Advanced Combat Tracker started alongside the game
+  actEnabled: boolean;
+  actPath: string;
+  actTiming: 'before' | 'after';
+  actCloseOnExit: boolean;
+  actReadyTimeoutSecs: number;
+  // Other programs started around the game, like Teamcraft or overlay helpers
+  externalTools: ExternalTool[];
+
+  // DX11 only, folder under the game's reshade-presets this profile uses
+  reshadeEnabled: boolean;
+  reshadePresetFolder: string;
+}
+
+export interface ExternalTool {
+  enabled: boolean;
+  name: string;
+  path: string;
+  // Can use {game_path}, {language}, {sid_present}, and {pid} when started after the game
+  args: string[];
+  timing: 'before' | 'after';
+  killOnExit: boolean;
 }
 
 // Default paths based on platform
@@ -92,14 +198,20 @@ initializePaths();
 
 const initialConfig: GameConfig = {
   username: '',
-  password: '',
   otp: '',
+  uidCache: false,
   gamePath: '',
+  install: null,
+  profileType: 'game',
+  benchmarkPath: '',
+  benchmarkScreenMode: 'windowed',
+  benchmarkScreenWidth: 1920,
+  benchmarkScreenHeight: 1080,
   isSteam: false,
   language: 1,
   dx11: true,
-  expansionLevel: 4,
-  region: 3,
+  expansionLevel: null,
+  region: null,
   
   // Game settings
   isFreeTrial: false,
@@ -108,17 +220,76 @@ const initialConfig: GameConfig = {
   encryptArguments: true,
   savedLogin: false,
   autoLogin: false,
+  launcherBehavior: 'keep',
+  relaunchOnCrash: false,
+  crashRelaunchWindowMinutes: 5,
+  backupCharacterData: false,
+  gameUserDir: '',
+  telemetryEnabled: false,
+  telemetryEndpoint: '',
+  patchMirror: '',
   directXVersion: '11',
   clientLanguage: 'English',
+  newsLocale: '',
 
   // Dalamud settings
   dalamudEnabled: true,
   dalamudPath: PATHS.dalamudBase,
   dalamudInjectDelay: 0,
+  dalamudNativeInjection: false,
+  dalamudFastLaunch: false,
+  dalamudFastLaunchTtlMinutes: 60,
+  dalamudGameVersionPolicy: 'vanilla',
+  dalamudSafeMode: false,
+  dalamudInjectionFailurePolicy: 'abort',
   dalamudConfigPath: PATHS.configDir,
   dalamudPluginPath: PATHS.pluginsDir,
   dalamudDevPluginPath: PATHS.devPluginsDir,
   dalamudAssetPath: PATHS.assetsDir,
+  dalamudVersionInfoUrl: '',
+  dalamudAssetMetaUrl: '',
+  dalamudDownloadBase: '',
+  penumbraModDirectory: '',
+
+  retryMaxAttempts: 4,
+  retryBaseDelayMs: 500,
+  retryMaxDelayMs: 8000,
+
+  proxyUrl: '',
+  proxyUsername: '',
+  proxyPassword: '',
+  proxyBypass: [],
+  caCertificates: [],
+  downloadLimitKib: 0,
+
+  oauthUrl: '',
+  oauthReferer: '',
+  frontierUrl: '',
+
+  wineRunner: 'wine',
+  wineRunnerPath: 'wine',
+  winePrefix: '',
+  wineDxvk: true,
+  wineDxvkVersion: '',
+  wineEsync: true,
+  wineFsync: false,
+  gamescope: null,
+  gamescopeWidth: 0,
+  gamescopeHeight: 0,
+  gamescopeFsr: false,
+  gamescopeFrameLimit: null,
+  gamescopeFullscreen: false,
+  gamemode: null,
+
+  actEnabled: false,
+  actPath: '',
+  actTiming: 'before',
+  actCloseOnExit: false,
+  actReadyTimeoutSecs: 60,
+  externalTools: [],
+
+  reshadeEnabled: false,
+  reshadePresetFolder: '',
 };
 
 export const gameConfig = writable<GameConfig>(initialConfig);
@@ -7,6 +7,8 @@ interface AppSettings {
   centerTitle: boolean;
   showMinimize: boolean;
   showMaximize: boolean;
+  // Beta also offers pre-releases
+  updateChannel: 'stable' | 'beta';
 }
 
 const defaultSettings: AppSettings = {
@@ -14,7 +16,8 @@ const defaultSettings: AppSettings = {
   theme: 'system',
   centerTitle: true,
   showMinimize: true,
-  showMaximize: true
+  showMaximize: true,
+  updateChannel: 'stable'
 };
 
 let store: Store;
@@ -28,7 +31,7 @@ async function loadSettings() {
     store = await Store.load('settings.json');
     const storedSettings = await store.get<AppSettings>('settings');
     if (storedSettings) {
-      settings.set(storedSettings);
+      settings.set({ ...defaultSettings, ...storedSettings });
     } else {
       await store.set('settings', defaultSettings);
     }
@@ -4,3 +4,22 @@ import { twMerge } from "tailwind-merge";
 export function cn(...inputs: ClassValue[]) {
 	return twMerge(clsx(inputs));
 }
+
+// Launches and update checks reject with { code, message }, other commands with a plain string
+export interface LauncherError {
+	code: 'auth' | 'network' | 'terms' | 'maintenance' | 'patch' | 'dalamud' | 'process' | 'config' | 'cancelled';
+	message: string;
+	// Only on 'terms', where to read the updated terms of service
+	url?: string;
+}
+
+export function isLauncherError(error: unknown): error is LauncherError {
+	return typeof error === 'object' && error !== null && 'code' in error && 'message' in error;
+}
+
+export function describeError(error: unknown): string {
+	if (error instanceof Error || isLauncherError(error)) {
+		return error.message;
+	}
+	return String(error);
+}